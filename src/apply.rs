@@ -0,0 +1,1156 @@
+use crate::config::FixConfig;
+use crate::finding::{Finding, Severity};
+use crate::format::{self, Formatter};
+use crate::msrv;
+use crate::parser::{self, Anchor, ErrorType, Language, ParsedError};
+use crate::regexes;
+use crate::ui;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// A change to one line of a source file. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Edit {
+    /// Insert a new line before `line` (one past the end appends)
+    InsertLine { line: usize, text: String },
+    /// Insert text on `line` before the character at `column`
+    InsertAt {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// Replace the whole file, e.g. with a formatter's output. Other edits
+    /// in the same fix are ignored.
+    ReplaceAll { text: String },
+}
+
+/// An automatic fix for one error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fix {
+    pub file: PathBuf,
+    pub description: String,
+    pub edits: Vec<Edit>,
+}
+
+/// Error kinds `suggest_fix` can fix, for some errors at least: custom
+/// patterns need an `insert`, policy violations are only `use-strict`
+pub const AUTO_FIXABLE: &[&str] = &[
+    "MissingInclude",
+    "MissingSemicolon",
+    "MissingLicenseHeader",
+    "PolicyViolation",
+    "Custom",
+    "Unformatted",
+    "RustVersion",
+];
+
+/// Suggest an automatic fix for an error, if it has an obvious one.
+/// Relative error paths are resolved against `root`.
+pub fn suggest_fix(error: &ParsedError, root: &Path) -> Option<Fix> {
+    // The fix is in the manifest, not the file with the error
+    if let ErrorType::RustVersion(requirement) = &error.error_type {
+        return msrv::fix(&msrv::Manifest::find(root, &error.file)?, requirement);
+    }
+    let file = root.join(&error.file);
+    let content = read_source(&file).ok()?.text;
+
+    let (description, edit) = match &error.error_type {
+        ErrorType::MissingInclude(header) => (
+            format!("Add #include <{}>", header),
+            include_edit(&content, header)?,
+        ),
+        ErrorType::MissingSemicolon => {
+            let line = error.line? as usize;
+            let code = content.lines().nth(line.checked_sub(1)?)?;
+            let column = match error.language {
+                Language::Cpp => ui::gcc_column_to_char(code, error.column.unwrap_or(1)),
+                _ => error.column.unwrap_or(1),
+            };
+            (
+                "Add the missing semicolon".to_string(),
+                semicolon_edit(&content, line, column as usize)?,
+            )
+        }
+        ErrorType::MissingLicenseHeader(header) if find_header(&content, header).is_none() => (
+            "Add the license header".to_string(),
+            header_edit(&content, header),
+        ),
+        ErrorType::PolicyViolation(rule)
+            if rule == "use-strict" && find_use_strict(&content).is_none() =>
+        {
+            ("Add 'use strict'".to_string(), use_strict_edit(&content))
+        }
+        ErrorType::Custom(custom) => {
+            let (anchor, text) = custom.insert.as_ref()?;
+            if find_line(&content, text).is_some() {
+                return None;
+            }
+            (
+                format!("Insert '{}'", text.trim()),
+                anchor_edit(&content, *anchor, text, error.line)?,
+            )
+        }
+        ErrorType::Unformatted(_) => {
+            let formatter = format::formatter(&error.language, &file)?;
+            let formatted = formatter.format(&file)?;
+            return format_fix(&file, &content, &formatter, &formatted);
+        }
+        _ => return None,
+    };
+
+    Some(Fix {
+        file,
+        description,
+        edits: vec![edit],
+    })
+}
+
+/// Replace a file with its formatter's output, keeping its line endings.
+/// None when the formatter changes nothing.
+pub fn format_fix(
+    file: &Path,
+    content: &str,
+    formatter: &Formatter,
+    formatted: &str,
+) -> Option<Fix> {
+    let formatted = with_line_endings(formatted, line_ending(content));
+    if formatted == content {
+        return None;
+    }
+    Some(Fix {
+        file: file.to_path_buf(),
+        description: format!("Format with {}", formatter.name),
+        edits: vec![Edit::ReplaceAll { text: formatted }],
+    })
+}
+
+/// Why an error has no fix to apply when the change the fix would make is
+/// already in the file, e.g. an `#include` below the code that needs it.
+/// Re-running a fix is safe either way; this says what to do instead.
+pub fn already_applied(error: &ParsedError, root: &Path) -> Option<String> {
+    let file = root.join(&error.file);
+    let content = read_source(&file).ok()?.text;
+
+    match &error.error_type {
+        ErrorType::MissingInclude(header) => {
+            let line = find_include(&content, header)?;
+            let include = format!("#include <{}> is already on line {}", header, line);
+            Some(match enclosing_conditional(&content, line) {
+                Some(condition) => format!(
+                    "{}, inside '{}' - check that the condition holds",
+                    include, condition
+                ),
+                None if error.line.is_some_and(|used| (used as usize) < line) => {
+                    format!("{}, below the code that needs it - move it up", include)
+                }
+                None => include,
+            })
+        }
+        ErrorType::MissingLicenseHeader(header) => {
+            let line = find_header(&content, header)?;
+            Some(format!(
+                "The license header is already on line {}, but it has to be at the top",
+                line
+            ))
+        }
+        ErrorType::PolicyViolation(rule) if rule == "use-strict" => {
+            let line = find_use_strict(&content)?;
+            Some(format!(
+                "'use strict' is already on line {}, but it only counts as the first statement",
+                line
+            ))
+        }
+        ErrorType::Custom(custom) => {
+            let (_, text) = custom.insert.as_ref()?;
+            let line = find_line(&content, text)?;
+            Some(format!("'{}' is already on line {}", text.trim(), line))
+        }
+        ErrorType::Unformatted(_) => {
+            let formatter = format::formatter(&error.language, &file)?;
+            let formatted = formatter.format(&file)?;
+            (with_line_endings(&formatted, line_ending(&content)) == content).then(|| {
+                format!(
+                    "{} leaves the file as it is - shorten the long lines by hand",
+                    formatter.name
+                )
+            })
+        }
+        _ => None,
+    }
+}
+
+/// 1-based line of an include of `header`, however it is spelled
+fn find_include(content: &str, header: &str) -> Option<usize> {
+    let re = Regex::new(&format!(
+        r#"^\s*#\s*include\s*[<"]\s*{}\s*[>"]"#,
+        regex::escape(header)
+    ))
+    .unwrap();
+    content.lines().position(|l| re.is_match(l)).map(|i| i + 1)
+}
+
+/// The innermost `#if`, `#ifdef` or `#ifndef` that `line` is inside.
+/// Include guards don't count.
+fn enclosing_conditional(content: &str, line: usize) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    fn directive(line: &str) -> Option<&str> {
+        line.trim_start().strip_prefix('#').map(str::trim_start)
+    }
+
+    let mut open: Vec<Option<String>> = Vec::new();
+    for (i, l) in lines.iter().enumerate().take(line.saturating_sub(1)) {
+        let Some(d) = directive(l) else {
+            continue;
+        };
+        if d.starts_with("if") {
+            let guard = d.strip_prefix("ifndef").map(str::trim).is_some_and(|name| {
+                lines
+                    .get(i + 1)
+                    .and_then(|next| directive(next)?.strip_prefix("define"))
+                    .and_then(|defined| defined.split_whitespace().next())
+                    == Some(name)
+            });
+            open.push((!guard).then(|| l.trim().to_string()));
+        } else if d.starts_with("endif") {
+            open.pop();
+        }
+    }
+    open.into_iter().rev().flatten().next()
+}
+
+/// 1-based line that is `text`, ignoring indentation
+fn find_line(content: &str, text: &str) -> Option<usize> {
+    content
+        .lines()
+        .position(|l| l.trim() == text.trim())
+        .map(|i| i + 1)
+}
+
+/// Insert a `[[patterns]]` line at its anchor. At the error line it is
+/// indented like the code there.
+fn anchor_edit(content: &str, anchor: Anchor, text: &str, error_line: Option<u32>) -> Option<Edit> {
+    let top = if content.starts_with("#!") { 2 } else { 1 };
+    let lines: Vec<&str> = content.lines().collect();
+
+    let (line, indent) = match anchor {
+        Anchor::TopOfFile => (top, ""),
+        Anchor::AfterImports => {
+            let last = lines.iter().rposition(|l| regexes::IMPORT_LINE.is_match(l));
+            let line = match last {
+                // A parenthesized import list ends at its closing paren
+                Some(i) if lines[i].contains('(') && !lines[i].contains(')') => {
+                    lines[i..]
+                        .iter()
+                        .position(|l| l.contains(')'))
+                        .map(|j| i + j)?
+                        + 2
+                }
+                Some(i) => i + 2,
+                None => top,
+            };
+            (line, "")
+        }
+        Anchor::AtErrorLine => {
+            let line = error_line? as usize;
+            let code = lines.get(line.checked_sub(1)?)?;
+            (line, &code[..code.len() - code.trim_start().len()])
+        }
+    };
+
+    Some(Edit::InsertLine {
+        line,
+        text: format!("{}{}", indent, text.trim()),
+    })
+}
+
+/// 1-based line of the license header anywhere in the file
+fn find_header(content: &str, header: &str) -> Option<usize> {
+    let first = header.lines().next()?.trim_start_matches(['/', '#']).trim();
+    if first.is_empty() {
+        return None;
+    }
+    content
+        .lines()
+        .position(|l| l.contains(first))
+        .map(|i| i + 1)
+}
+
+/// 1-based line of a `'use strict'` directive anywhere in the file
+fn find_use_strict(content: &str) -> Option<usize> {
+    content
+        .lines()
+        .map(str::trim)
+        .position(|l| l.starts_with("'use strict'") || l.starts_with("\"use strict\""))
+        .map(|i| i + 1)
+}
+
+/// Insert the include after the last existing one, or at the top
+fn include_edit(content: &str, header: &str) -> Option<Edit> {
+    let text = format!("#include <{}>", header);
+    if find_include(content, header).is_some() {
+        return None;
+    }
+
+    let line = content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| l.trim_start().starts_with("#include"))
+        .map(|(i, _)| i + 2)
+        .last()
+        .unwrap_or(1);
+
+    Some(Edit::InsertLine { line, text })
+}
+
+/// Put the header at the top of the file, after a `#!` line
+fn header_edit(content: &str, header: &str) -> Edit {
+    let line = if content.starts_with("#!") { 2 } else { 1 };
+    Edit::InsertLine {
+        line,
+        text: header.to_string(),
+    }
+}
+
+/// Put the directive before the first statement, below the `#!` line and
+/// leading comments such as a license header
+fn use_strict_edit(content: &str) -> Edit {
+    let line = content
+        .lines()
+        .take_while(|l| {
+            let l = l.trim_start();
+            l.starts_with("#!") || l.starts_with("//")
+        })
+        .count()
+        + 1;
+    Edit::InsertLine {
+        line,
+        text: "'use strict';".to_string(),
+    }
+}
+
+/// Compilers report a missing `;` at the next token, which is often on the
+/// next line. Put it after the last code before that token.
+fn semicolon_edit(content: &str, line: usize, column: usize) -> Option<Edit> {
+    let lines: Vec<&str> = content.lines().collect();
+    let current = lines.get(line.checked_sub(1)?)?;
+
+    let before: String = current.chars().take(column.saturating_sub(1)).collect();
+    if !before.trim().is_empty() {
+        return Some(Edit::InsertAt {
+            line,
+            column: before.trim_end().chars().count() + 1,
+            text: ";".to_string(),
+        });
+    }
+
+    let (index, previous) = lines[..line - 1]
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, l)| !l.trim().is_empty())?;
+
+    Some(Edit::InsertAt {
+        line: index + 1,
+        column: previous.trim_end().chars().count() + 1,
+        text: ";".to_string(),
+    })
+}
+
+impl Edit {
+    /// Line the edit applies to (1 for a whole-file replacement)
+    fn line(&self) -> usize {
+        match self {
+            Edit::InsertLine { line, .. } | Edit::InsertAt { line, .. } => *line,
+            Edit::ReplaceAll { .. } => 1,
+        }
+    }
+
+    /// Whether two edits change the same text. Inserting whole lines never
+    /// does, so two fixes can add lines at the same place.
+    fn overlaps(&self, other: &Edit) -> bool {
+        match (self, other) {
+            (Edit::ReplaceAll { .. }, _) | (_, Edit::ReplaceAll { .. }) => true,
+            (Edit::InsertAt { line: a, .. }, Edit::InsertAt { line: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Fix {
+    fn conflicts_with(&self, other: &Fix) -> bool {
+        self.file == other.file
+            && self
+                .edits
+                .iter()
+                .any(|a| other.edits.iter().any(|b| a.overlaps(b)))
+    }
+}
+
+/// A fix left out because it changes the same lines as one that is kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub fix: Fix,
+    /// Description of the fix it conflicts with
+    pub with: String,
+}
+
+/// Fixes that can be applied together, ordered by file and line, and the
+/// ones that conflict with them
+#[derive(Debug, Default, PartialEq)]
+pub struct Plan {
+    pub fixes: Vec<Fix>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Decide which fixes to apply together. Earlier fixes win a conflict, so
+/// pass them most important first. Identical fixes are applied once.
+pub fn plan(fixes: Vec<Fix>) -> Plan {
+    let mut plan = Plan::default();
+    for fix in fixes {
+        if plan.fixes.contains(&fix) {
+            continue;
+        }
+        match plan.fixes.iter().find(|kept| kept.conflicts_with(&fix)) {
+            Some(kept) => plan.conflicts.push(Conflict {
+                with: kept.description.clone(),
+                fix,
+            }),
+            None => plan.fixes.push(fix),
+        }
+    }
+
+    let first_line = |fix: &Fix| fix.edits.iter().map(Edit::line).min().unwrap_or(0);
+    plan.fixes.sort_by(|a, b| {
+        (&a.file, first_line(a), &a.description).cmp(&(&b.file, first_line(b), &b.description))
+    });
+    plan
+}
+
+impl Plan {
+    /// The planned fixes combined into one per file
+    pub fn merged(&self) -> Vec<Fix> {
+        let mut merged: Vec<Fix> = Vec::new();
+        for fix in &self.fixes {
+            match merged.iter_mut().find(|m| m.file == fix.file) {
+                Some(m) => {
+                    m.description = format!("{}, {}", m.description, fix.description);
+                    m.edits.extend(fix.edits.iter().cloned());
+                }
+                None => merged.push(fix.clone()),
+            }
+        }
+        merged
+    }
+}
+
+/// How a source file's text is stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Utf8,
+    /// UTF-8 starting with a byte order mark, as some Windows editors save it
+    Utf8Bom,
+    /// Anything that isn't UTF-8, read one byte per character so it can be
+    /// written back byte for byte
+    Latin1,
+}
+
+const BOM: &str = "\u{feff}";
+
+/// A file's text and the encoding to write it back in
+struct Source {
+    text: String,
+    encoding: Encoding,
+}
+
+fn read_source(path: &Path) -> Result<Source> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Could not read {}", path.display()))?;
+    Ok(decode(bytes))
+}
+
+fn decode(bytes: Vec<u8>) -> Source {
+    match String::from_utf8(bytes) {
+        Ok(text) => match text.strip_prefix(BOM) {
+            Some(rest) => Source {
+                text: rest.to_string(),
+                encoding: Encoding::Utf8Bom,
+            },
+            None => Source {
+                text,
+                encoding: Encoding::Utf8,
+            },
+        },
+        Err(e) => Source {
+            text: e.into_bytes().iter().map(|&b| b as char).collect(),
+            encoding: Encoding::Latin1,
+        },
+    }
+}
+
+fn encode(text: &str, encoding: Encoding) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+        Encoding::Utf8Bom => Ok(format!("{}{}", BOM, text).into_bytes()),
+        Encoding::Latin1 => text
+            .chars()
+            .map(|c| u8::try_from(c).ok())
+            .collect::<Option<Vec<u8>>>()
+            .context("The fix adds characters the file's encoding can't store"),
+    }
+}
+
+/// The line ending most lines of `content` use
+fn line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    if crlf > 0 && crlf * 2 >= content.matches('\n').count() {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+fn with_line_endings(text: &str, newline: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    if newline == "\n" {
+        text
+    } else {
+        text.replace('\n', newline)
+    }
+}
+
+/// Apply edits to file content. Edits are applied bottom-up so earlier
+/// line numbers stay valid. On the same line, insertions within the line
+/// go first and new lines keep the order they were given in. The result
+/// keeps the content's line endings and trailing newline, or lack of one.
+pub fn apply_edits(content: &str, edits: &[Edit]) -> String {
+    let newline = line_ending(content);
+    if let Some(Edit::ReplaceAll { text }) =
+        edits.iter().find(|e| matches!(e, Edit::ReplaceAll { .. }))
+    {
+        return with_line_endings(text, newline);
+    }
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+
+    let mut edits: Vec<(usize, &Edit)> = edits.iter().enumerate().collect();
+    edits.sort_by_key(|(i, e)| {
+        (
+            std::cmp::Reverse(e.line()),
+            matches!(e, Edit::InsertLine { .. }),
+            std::cmp::Reverse(*i),
+        )
+    });
+
+    for (_, edit) in edits {
+        match edit {
+            Edit::InsertLine { line, text } => {
+                let index = line.saturating_sub(1).min(lines.len());
+                lines.insert(index, with_line_endings(text, newline));
+            }
+            Edit::InsertAt { line, column, text } => {
+                if let Some(target) = lines.get_mut(line.saturating_sub(1)) {
+                    let offset = target
+                        .char_indices()
+                        .nth(column.saturating_sub(1))
+                        .map(|(i, _)| i)
+                        .unwrap_or(target.len());
+                    target.insert_str(offset, text);
+                }
+            }
+            Edit::ReplaceAll { .. } => {}
+        }
+    }
+
+    let mut result = lines.join(newline);
+    if content.ends_with('\n') {
+        result.push_str(newline);
+    }
+    result
+}
+
+/// Current content of the fixed file and what it would look like after the fix
+pub fn preview(fix: &Fix) -> Result<(String, String)> {
+    let content = read_source(&fix.file)?.text;
+    let fixed = apply_edits(&content, &fix.edits);
+    Ok((content, fixed))
+}
+
+/// A file's bytes from before a fix, to roll the fix back
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub file: PathBuf,
+    bytes: Vec<u8>,
+}
+
+impl Backup {
+    /// Put the file back the way it was
+    pub fn restore(&self) -> Result<()> {
+        std::fs::write(&self.file, &self.bytes)
+            .with_context(|| format!("Could not restore {}", self.file.display()))
+    }
+}
+
+/// Write a fix to disk, returning a backup of the original. A fix that
+/// changes nothing leaves the file untouched. The file is rewritten in
+/// place, so its permissions (like the executable bit) stay as they are,
+/// and in its own encoding.
+pub fn apply_fix(fix: &Fix) -> Result<Backup> {
+    let bytes = std::fs::read(&fix.file)
+        .with_context(|| format!("Could not read {}", fix.file.display()))?;
+    let source = decode(bytes.clone());
+    let fixed = apply_edits(&source.text, &fix.edits);
+    if fixed != source.text {
+        std::fs::write(&fix.file, encode(&fixed, source.encoding)?)
+            .with_context(|| format!("Could not write {}", fix.file.display()))?;
+    }
+    Ok(Backup {
+        file: fix.file.clone(),
+        bytes,
+    })
+}
+
+/// How `[fix]` lets a rule's fixes be applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixPolicy {
+    /// Without asking, by `ess find-bug --walk` too
+    Auto,
+    /// The way each command does unless told otherwise
+    Default,
+    /// Only after a preview and a yes, `ess bug --apply` included
+    Confirm,
+    /// Never; the fix is only suggested
+    Never,
+}
+
+/// The policy for a rule's fixes, matching its name or stable ID
+/// case-insensitively. A rule listed more than once gets the most careful
+/// of its policies.
+pub fn fix_policy(config: &FixConfig, rule: &str) -> FixPolicy {
+    let id = parser::rule_id(rule);
+    let listed = |rules: &[String]| {
+        rules.iter().any(|r| {
+            r.eq_ignore_ascii_case(rule) || id.is_some_and(|id| r.eq_ignore_ascii_case(id))
+        })
+    };
+    if listed(&config.never) {
+        FixPolicy::Never
+    } else if listed(&config.confirm) {
+        FixPolicy::Confirm
+    } else if listed(&config.auto) {
+        FixPolicy::Auto
+    } else {
+        FixPolicy::Default
+    }
+}
+
+/// Whether `[fix]` allows a rule's fix to be applied at all; says so
+/// when it doesn't
+pub fn permitted(config: &FixConfig, rule: &str, fix: &Fix) -> bool {
+    if fix_policy(config, rule) != FixPolicy::Never {
+        return true;
+    }
+    ui::print_info(&format!(
+        "Not applying '{}': [fix] never lists {}",
+        fix.description, rule
+    ));
+    false
+}
+
+/// Show what a fix changes and ask on `input` whether to apply it
+pub fn confirm(fix: &Fix, input: &mut impl BufRead) -> Result<bool> {
+    if let Ok((before, after)) = preview(fix) {
+        ui::print_diff(&before, &after);
+    }
+    ui::print_prompt(&format!("Apply '{}'? [y/N]: ", fix.description));
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        println!();
+    }
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// What re-checking a file after a fix showed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verification {
+    /// The fixed error is gone and nothing new broke
+    Fixed,
+    /// The fixed error is still reported
+    StillFailing,
+    /// The fix caused errors that weren't there before
+    NewErrors(Vec<String>),
+}
+
+/// Compare the findings of a file before and after fixing `targets`.
+/// Findings are matched by rule and message, since a fix can move lines.
+/// Only new errors count, not warnings that changed wording.
+pub fn verify(before: &[Finding], targets: &[&Finding], after: &[Finding]) -> Verification {
+    let same = |a: &Finding, b: &Finding| a.rule() == b.rule() && a.message == b.message;
+    let count = |findings: &[Finding], finding: &Finding| {
+        findings.iter().filter(|f| same(f, finding)).count()
+    };
+
+    let mut new: Vec<String> = after
+        .iter()
+        .filter(|f| f.severity == Severity::Error && count(after, f) > count(before, f))
+        .map(|f| f.message.clone())
+        .collect();
+    new.dedup();
+
+    if !new.is_empty() {
+        Verification::NewErrors(new)
+    } else if targets
+        .iter()
+        .any(|target| count(after, target) >= count(before, target))
+    {
+        Verification::StillFailing
+    } else {
+        Verification::Fixed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpp_error(file: &str, line: u32, column: u32, error_type: ErrorType) -> ParsedError {
+        ParsedError {
+            file: file.to_string(),
+            line: Some(line),
+            column: Some(column),
+            end_line: None,
+            end_column: None,
+            message: String::new(),
+            error_type,
+            language: Language::Cpp,
+        }
+    }
+
+    #[test]
+    fn test_include_after_existing_includes() {
+        let content = "#include <iostream>\n\nint main() {}\n";
+        let edit = include_edit(content, "vector").unwrap();
+
+        assert_eq!(
+            apply_edits(content, &[edit]),
+            "#include <iostream>\n#include <vector>\n\nint main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_include_at_top_without_includes() {
+        let content = "int main() {}";
+        let edit = include_edit(content, "string").unwrap();
+
+        assert_eq!(
+            apply_edits(content, &[edit]),
+            "#include <string>\nint main() {}"
+        );
+    }
+
+    #[test]
+    fn test_include_already_present() {
+        assert!(include_edit("#include <vector>\n", "vector").is_none());
+        assert!(include_edit("#  include \"vector\"\n", "vector").is_none());
+        assert!(include_edit("#include <vector_utils>\n", "vector").is_some());
+    }
+
+    #[test]
+    fn test_enclosing_conditional_skips_include_guards() {
+        let content = "#ifndef APP_H\n#define APP_H\n#ifdef USE_VECTOR\n#include <vector>\n#endif\n#include <map>\n#endif\n";
+
+        assert_eq!(
+            enclosing_conditional(content, 4),
+            Some("#ifdef USE_VECTOR".to_string())
+        );
+        assert_eq!(enclosing_conditional(content, 6), None);
+    }
+
+    #[test]
+    fn test_already_applied_explains_misplaced_include() {
+        let temp_dir = std::env::temp_dir().join("ess_apply_already_applied");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        std::fs::write(
+            temp_dir.join("main.cpp"),
+            "int main() {\n    std::vector<int> v;\n}\n#include <vector>\n",
+        )
+        .unwrap();
+
+        let error = cpp_error(
+            "main.cpp",
+            2,
+            10,
+            ErrorType::MissingInclude("vector".to_string()),
+        );
+        let fix = suggest_fix(&error, &temp_dir);
+        let reason = already_applied(&error, &temp_dir);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert!(fix.is_none());
+        assert_eq!(
+            reason.unwrap(),
+            "#include <vector> is already on line 4, below the code that needs it - move it up"
+        );
+    }
+
+    #[test]
+    fn test_semicolon_on_previous_line() {
+        let content = "int main() {\n    int x = 5\n\n    return x;\n}\n";
+        let edit = semicolon_edit(content, 4, 5).unwrap();
+
+        assert_eq!(
+            apply_edits(content, &[edit]),
+            "int main() {\n    int x = 5;\n\n    return x;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_semicolon_on_same_line() {
+        let content = "int x = 5 \n";
+        let edit = semicolon_edit(content, 1, 11).unwrap();
+
+        assert_eq!(apply_edits(content, &[edit]), "int x = 5; \n");
+    }
+
+    #[test]
+    fn test_apply_edits_multibyte() {
+        let content = "s = \"żółw\"\n";
+        let edit = Edit::InsertAt {
+            line: 1,
+            column: 11,
+            text: ";".to_string(),
+        };
+
+        assert_eq!(apply_edits(content, &[edit]), "s = \"żółw\";\n");
+    }
+
+    #[test]
+    fn test_suggest_and_apply_fix() {
+        let temp_dir = std::env::temp_dir().join("ess_apply_fix");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        std::fs::write(
+            temp_dir.join("main.cpp"),
+            "int main() {\n    std::vector<int> v;\n}\n",
+        )
+        .unwrap();
+
+        let error = cpp_error(
+            "main.cpp",
+            2,
+            10,
+            ErrorType::MissingInclude("vector".to_string()),
+        );
+        let fix = suggest_fix(&error, &temp_dir).unwrap();
+        let backup = apply_fix(&fix).unwrap();
+        let content = std::fs::read_to_string(temp_dir.join("main.cpp")).unwrap();
+        backup.restore().unwrap();
+        let restored = std::fs::read_to_string(temp_dir.join("main.cpp")).unwrap();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(fix.description, "Add #include <vector>");
+        assert!(content.starts_with("#include <vector>\nint main()"));
+        assert_eq!(restored, "int main() {\n    std::vector<int> v;\n}\n");
+    }
+
+    #[test]
+    fn test_windows_file_keeps_crlf_bom_and_missing_final_newline() {
+        let temp_dir = std::env::temp_dir().join("ess_apply_windows_file");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        let file = temp_dir.join("main.cpp");
+        std::fs::write(
+            &file,
+            "\u{feff}#include <iostream>\r\nint main() {\r\n    int x = 5\r\n    return x;\r\n}",
+        )
+        .unwrap();
+
+        let fix = Fix {
+            file: file.clone(),
+            description: "Fix".to_string(),
+            edits: vec![
+                include_edit("#include <iostream>\n", "vector").unwrap(),
+                Edit::InsertAt {
+                    line: 3,
+                    column: 14,
+                    text: ";".to_string(),
+                },
+            ],
+        };
+        apply_fix(&fix).unwrap();
+        let content = std::fs::read(&file).unwrap();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "\u{feff}#include <iostream>\r\n#include <vector>\r\nint main() {\r\n    int x = 5;\r\n    return x;\r\n}"
+        );
+    }
+
+    #[test]
+    fn test_latin1_file_is_written_back_in_latin1() {
+        let temp_dir = std::env::temp_dir().join("ess_apply_latin1_file");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        let file = temp_dir.join("main.cpp");
+        // "// Größe" in Latin-1
+        std::fs::write(&file, b"// Gr\xf6\xdfe\nint x = 5\n").unwrap();
+
+        let fix = Fix {
+            file: file.clone(),
+            description: "Fix".to_string(),
+            edits: vec![Edit::InsertAt {
+                line: 2,
+                column: 10,
+                text: ";".to_string(),
+            }],
+        };
+        let backup = apply_fix(&fix).unwrap();
+        let fixed = std::fs::read(&file).unwrap();
+        backup.restore().unwrap();
+        let restored = std::fs::read(&file).unwrap();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(fixed, b"// Gr\xf6\xdfe\nint x = 5;\n");
+        assert_eq!(restored, b"// Gr\xf6\xdfe\nint x = 5\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fix_keeps_executable_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = std::env::temp_dir().join("ess_apply_executable");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        let file = temp_dir.join("run.js");
+        std::fs::write(&file, "#!/usr/bin/env node\nvar a;\n").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        let fix = Fix {
+            file: file.clone(),
+            description: "Add 'use strict'".to_string(),
+            edits: vec![use_strict_edit(&content)],
+        };
+        apply_fix(&fix).unwrap();
+        let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn test_replace_all_takes_the_file_line_endings() {
+        let edits = [Edit::ReplaceAll {
+            text: "a\nb\n".to_string(),
+        }];
+        assert_eq!(apply_edits("a\r\n b\r\n", &edits), "a\r\nb\r\n");
+        assert_eq!(apply_edits("a\n b\n", &edits), "a\nb\n");
+    }
+
+    fn finding(severity: Severity, message: &str) -> Finding {
+        Finding::new(Path::new("/p/main.cpp"), severity, message, "g++")
+    }
+
+    #[test]
+    fn test_verify() {
+        let semicolon = finding(Severity::Error, "expected ';' before '}' token");
+        let before = vec![semicolon.clone(), semicolon.clone()];
+
+        // One of two identical errors fixed
+        assert_eq!(
+            verify(&before, &[&semicolon], std::slice::from_ref(&semicolon)),
+            Verification::Fixed
+        );
+        assert_eq!(
+            verify(&before, &[&semicolon], &before),
+            Verification::StillFailing
+        );
+
+        let broken = finding(Severity::Error, "'x' was not declared in this scope");
+        assert_eq!(
+            verify(&before, &[&semicolon], &[broken.clone(), broken]),
+            Verification::NewErrors(vec!["'x' was not declared in this scope".to_string()])
+        );
+
+        // A warning with new wording isn't a new error
+        let long = finding(Severity::Warning, "Line is 130 characters long");
+        assert_eq!(verify(&before, &[&semicolon], &[long]), Verification::Fixed);
+    }
+
+    #[test]
+    fn test_header_goes_after_shebang() {
+        let content = "#!/usr/bin/env python3\nimport os\n";
+        let edit = header_edit(content, "# SPDX-License-Identifier: MIT");
+
+        assert_eq!(
+            apply_edits(content, &[edit]),
+            "#!/usr/bin/env python3\n# SPDX-License-Identifier: MIT\nimport os\n"
+        );
+    }
+
+    #[test]
+    fn test_use_strict_goes_below_header() {
+        let content = "// SPDX-License-Identifier: MIT\nvar a;\n";
+
+        assert_eq!(
+            apply_edits(content, &[use_strict_edit(content)]),
+            "// SPDX-License-Identifier: MIT\n'use strict';\nvar a;\n"
+        );
+        assert_eq!(
+            apply_edits("var a;\n", &[use_strict_edit("var a;\n")]),
+            "'use strict';\nvar a;\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_all_wins_over_line_edits() {
+        let edits = [
+            Edit::InsertLine {
+                line: 1,
+                text: "// top".to_string(),
+            },
+            Edit::ReplaceAll {
+                text: "fn main() {}\n".to_string(),
+            },
+        ];
+
+        assert_eq!(apply_edits("fn main(){}", &edits), "fn main() {}\n");
+    }
+
+    fn fix(description: &str, edits: Vec<Edit>) -> Fix {
+        Fix {
+            file: PathBuf::from("/p/main.cpp"),
+            description: description.to_string(),
+            edits,
+        }
+    }
+
+    fn semicolon(line: usize) -> Edit {
+        Edit::InsertAt {
+            line,
+            column: 10,
+            text: ";".to_string(),
+        }
+    }
+
+    fn include(header: &str) -> Edit {
+        Edit::InsertLine {
+            line: 1,
+            text: format!("#include <{}>", header),
+        }
+    }
+
+    #[test]
+    fn test_plan_skips_conflicts_and_duplicates() {
+        let plan = plan(vec![
+            fix("Semicolon on 5", vec![semicolon(5)]),
+            fix("Add <vector>", vec![include("vector")]),
+            fix("Add <vector>", vec![include("vector")]),
+            fix("Other semicolon on 5", vec![semicolon(5)]),
+            fix("Add <string>", vec![include("string")]),
+        ]);
+
+        let kept: Vec<&str> = plan.fixes.iter().map(|f| f.description.as_str()).collect();
+        assert_eq!(kept, vec!["Add <string>", "Add <vector>", "Semicolon on 5"]);
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].fix.description, "Other semicolon on 5");
+        assert_eq!(plan.conflicts[0].with, "Semicolon on 5");
+    }
+
+    #[test]
+    fn test_formatting_conflicts_with_everything_in_the_file() {
+        let format = fix(
+            "Format",
+            vec![Edit::ReplaceAll {
+                text: String::new(),
+            }],
+        );
+        let plan = plan(vec![format, fix("Add <vector>", vec![include("vector")])]);
+
+        assert_eq!(plan.fixes.len(), 1);
+        assert_eq!(plan.conflicts[0].with, "Format");
+    }
+
+    #[test]
+    fn test_merged_fixes_apply_in_order() {
+        let plan = plan(vec![
+            fix("Semicolon", vec![semicolon(1)]),
+            fix("Add <vector>", vec![include("vector")]),
+            fix("Add <string>", vec![include("string")]),
+        ]);
+        let merged = plan.merged();
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            merged[0].description,
+            "Add <string>, Add <vector>, Semicolon"
+        );
+        assert_eq!(
+            apply_edits("int x = 5\n", &merged[0].edits),
+            "#include <string>\n#include <vector>\nint x = 5;\n"
+        );
+    }
+
+    #[test]
+    fn test_anchor_edits() {
+        let python = "#!/usr/bin/env python3\nimport os\nfrom app import (\n    a,\n)\n\ndef f():\n    return a\n";
+        let at = |anchor, line| {
+            apply_edits(
+                python,
+                &[anchor_edit(python, anchor, "X = 1", line).unwrap()],
+            )
+        };
+
+        assert!(at(Anchor::TopOfFile, None).starts_with("#!/usr/bin/env python3\nX = 1\nimport os"));
+        assert!(at(Anchor::AfterImports, None).contains("    a,\n)\nX = 1\n\ndef f()"));
+        assert!(at(Anchor::AtErrorLine, Some(8)).contains("def f():\n    X = 1\n    return a"));
+        assert!(anchor_edit(python, Anchor::AtErrorLine, "X = 1", None).is_none());
+    }
+
+    #[test]
+    fn test_no_fix_for_other_errors() {
+        let error = cpp_error(
+            "/nonexistent/main.cpp",
+            1,
+            1,
+            ErrorType::Unknown("x".to_string()),
+        );
+        assert!(suggest_fix(&error, Path::new("/")).is_none());
+    }
+
+    #[test]
+    fn test_fix_policy() {
+        let config: FixConfig = toml::from_str(
+            r#"
+            auto = ["MissingSemicolon", "MissingInclude"]
+            confirm = ["missinginclude"]
+            never = ["ImportError"]
+            "#,
+        )
+        .unwrap();
+        let semicolon = fix("Semicolon on 5", vec![semicolon(5)]);
+
+        assert_eq!(fix_policy(&config, "MissingSemicolon"), FixPolicy::Auto);
+        assert_eq!(fix_policy(&config, "MissingInclude"), FixPolicy::Confirm);
+        assert_eq!(fix_policy(&config, "importerror"), FixPolicy::Never);
+        assert_eq!(fix_policy(&config, "Unformatted"), FixPolicy::Default);
+        let by_id: FixConfig = toml::from_str(r#"never = ["gen-fmt-001"]"#).unwrap();
+        assert_eq!(fix_policy(&by_id, "Unformatted"), FixPolicy::Never);
+        assert!(permitted(&config, "MissingSemicolon", &semicolon));
+        assert!(!permitted(&config, "ImportError", &semicolon));
+
+        assert!(confirm(&semicolon, &mut "y\n".as_bytes()).unwrap());
+        assert!(!confirm(&semicolon, &mut "\n".as_bytes()).unwrap());
+        assert!(!confirm(&semicolon, &mut "".as_bytes()).unwrap());
+    }
+}
@@ -0,0 +1,621 @@
+use crate::finding::{Finding, Severity};
+use crate::parser::{ErrorType, Language, ParsedError};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// `cargo audit --json` report
+#[derive(Deserialize)]
+struct CargoAudit {
+    vulnerabilities: CargoVulnerabilities,
+    /// Informational advisories by kind: "unmaintained", "unsound", "yanked"
+    #[serde(default)]
+    warnings: BTreeMap<String, Vec<CargoWarning>>,
+}
+
+#[derive(Deserialize)]
+struct CargoVulnerabilities {
+    #[serde(default)]
+    list: Vec<CargoVulnerability>,
+}
+
+#[derive(Deserialize)]
+struct CargoVulnerability {
+    advisory: Advisory,
+    versions: PatchedVersions,
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct CargoWarning {
+    kind: String,
+    package: Package,
+    advisory: Option<Advisory>,
+}
+
+#[derive(Deserialize)]
+struct Advisory {
+    id: String,
+    title: String,
+    /// CVSS vector, e.g. "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"
+    cvss: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PatchedVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+}
+
+/// `npm audit --json` report (npm 7 and later)
+#[derive(Deserialize)]
+struct NpmAudit {
+    #[serde(default)]
+    vulnerabilities: BTreeMap<String, NpmVulnerability>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmVulnerability {
+    name: String,
+    severity: String,
+    #[serde(default)]
+    is_direct: bool,
+    #[serde(default)]
+    via: Vec<NpmVia>,
+    fix_available: Option<NpmFix>,
+}
+
+/// An advisory, or the name of a vulnerable dependency it comes through
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NpmVia {
+    Advisory { title: String, url: Option<String> },
+    Dependency(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NpmFix {
+    /// `true` when `npm audit fix` can upgrade within the allowed range
+    Auto(bool),
+    Upgrade {
+        name: String,
+        version: String,
+        #[serde(default, rename = "isSemVerMajor")]
+        is_semver_major: bool,
+    },
+}
+
+/// Base score of a CVSS 3.x vector
+pub fn cvss_score(vector: &str) -> Option<f64> {
+    let metrics: BTreeMap<&str, &str> = vector
+        .split('/')
+        .filter_map(|part| part.split_once(':'))
+        .collect();
+    let changed = *metrics.get("S")? == "C";
+
+    let av = match *metrics.get("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        _ => 0.2,
+    };
+    let ac = if *metrics.get("AC")? == "L" {
+        0.77
+    } else {
+        0.44
+    };
+    let pr = match (*metrics.get("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        (_, false) => 0.27,
+        (_, true) => 0.5,
+    };
+    let ui = if *metrics.get("UI")? == "N" {
+        0.85
+    } else {
+        0.62
+    };
+    let cia = |key: &str| -> Option<f64> {
+        Some(match *metrics.get(key)? {
+            "H" => 0.56,
+            "L" => 0.22,
+            _ => 0.0,
+        })
+    };
+
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let score = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(round_up(score.min(10.0)))
+}
+
+/// Round up to one decimal, as the CVSS 3.1 spec does it
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    }
+}
+
+/// Qualitative rating of a CVSS score
+pub fn cvss_severity(score: f64) -> &'static str {
+    match score {
+        s if s >= 9.0 => "critical",
+        s if s >= 7.0 => "high",
+        s if s >= 4.0 => "medium",
+        s if s > 0.0 => "low",
+        _ => "none",
+    }
+}
+
+/// The patched version to move to: the oldest one semver-compatible with
+/// the current version if there is one, else the newest
+fn patched_version(current: &str, patched: &[String]) -> Option<(String, bool)> {
+    let versions: Vec<String> = patched
+        .iter()
+        .map(|req| {
+            req.trim_start_matches(['>', '<', '=', '^', '~', ' '])
+                .to_string()
+        })
+        .filter(|v| !v.is_empty())
+        .collect();
+    let compatible = |v: &str| semver_prefix(v) == semver_prefix(current);
+
+    match versions.iter().find(|v| compatible(v)) {
+        Some(version) => Some((version.clone(), true)),
+        None => versions.last().map(|v| (v.clone(), false)),
+    }
+}
+
+/// The part of a version that must not change in a compatible upgrade:
+/// the major version, or the minor one too for 0.x
+pub fn semver_prefix(version: &str) -> String {
+    let mut parts = version.split('.');
+    match parts.next() {
+        Some("0") => format!("0.{}", parts.next().unwrap_or("0")),
+        Some(major) => major.to_string(),
+        None => String::new(),
+    }
+}
+
+/// 1-based number of the first line where `is_match` holds
+pub(crate) fn line_where(
+    content: &str,
+    is_match: impl Fn(&str, Option<&str>) -> bool,
+) -> Option<u32> {
+    let lines: Vec<&str> = content.lines().collect();
+    (0..lines.len())
+        .find(|&i| is_match(lines[i].trim(), lines.get(i + 1).map(|l| l.trim())))
+        .map(|i| i as u32 + 1)
+}
+
+/// A vulnerability finding, explained with how to upgrade
+fn vulnerability(
+    root: &Path,
+    file: &Path,
+    line: Option<u32>,
+    message: String,
+    source: &str,
+    language: Language,
+    advice: String,
+) -> Finding {
+    let parsed = ParsedError {
+        file: file.to_string_lossy().to_string(),
+        line,
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: message.clone(),
+        error_type: ErrorType::Vulnerability(advice),
+        language,
+    };
+    Finding::new(file, Severity::Error, &message, source)
+        .with_parsed(Some(parsed), root)
+        .with_explanation(&message)
+}
+
+/// Findings in a `cargo audit --json` report, located in `Cargo.lock`
+pub fn cargo_audit_findings(root: &Path, output: &str) -> Vec<Finding> {
+    let Ok(report) = serde_json::from_str::<CargoAudit>(output) else {
+        return Vec::new();
+    };
+    let lock_file = root.join("Cargo.lock");
+    let lock = std::fs::read_to_string(&lock_file).unwrap_or_default();
+    let line_of = |package: &Package| {
+        let name = format!("name = \"{}\"", package.name);
+        let version = format!("version = \"{}\"", package.version);
+        line_where(&lock, |line, next| line == name && next == Some(&version))
+    };
+
+    let mut findings = Vec::new();
+    for vuln in &report.vulnerabilities.list {
+        let (advisory, package) = (&vuln.advisory, &vuln.package);
+        let rating = advisory
+            .cvss
+            .as_deref()
+            .and_then(cvss_score)
+            .map(|score| format!(" ({})", cvss_severity(score)))
+            .unwrap_or_default();
+        let advice = match patched_version(&package.version, &vuln.versions.patched) {
+            Some((version, true)) => format!(
+                "Run 'cargo update -p {}' to get {} or later",
+                package.name, version
+            ),
+            Some((version, false)) => format!(
+                "Upgrade {} to {} or later in Cargo.toml. If it isn't a direct dependency, upgrade the crate that pulls it in (see 'cargo tree -i {}').",
+                package.name, version, package.name
+            ),
+            None => format!(
+                "No fixed version of {} exists yet. Replace it, or check the advisory for workarounds{}.",
+                package.name,
+                advisory.url.as_deref().map(|u| format!(": {}", u)).unwrap_or_default()
+            ),
+        };
+        let message = format!(
+            "{}{}: {} - {} {}",
+            advisory.id, rating, advisory.title, package.name, package.version
+        );
+        findings.push(vulnerability(
+            root,
+            &lock_file,
+            line_of(package),
+            message,
+            "cargo audit",
+            Language::Rust,
+            advice,
+        ));
+    }
+
+    // Unmaintained, unsound and yanked crates are worth knowing about, but
+    // aren't vulnerabilities
+    for warning in report.warnings.values().flatten() {
+        let package = &warning.package;
+        let message = match &warning.advisory {
+            Some(advisory) => format!(
+                "{} ({}): {} - {} {}",
+                advisory.id, warning.kind, advisory.title, package.name, package.version
+            ),
+            None => format!("{} {} is {}", package.name, package.version, warning.kind),
+        };
+        findings.push(
+            Finding::new(&lock_file, Severity::Warning, &message, "cargo audit")
+                .at(line_of(package), None),
+        );
+    }
+
+    findings
+}
+
+/// Findings in an `npm audit --json` report. Each vulnerable package is
+/// reported once; packages that are only affected through a dependency are
+/// reported when they are direct dependencies, since those are the ones to
+/// upgrade.
+pub fn npm_audit_findings(root: &Path, output: &str) -> Vec<Finding> {
+    let Ok(report) = serde_json::from_str::<NpmAudit>(output) else {
+        return Vec::new();
+    };
+    let manifest = root.join("package.json");
+    let lock_file = root.join("package-lock.json");
+    let manifest_content = std::fs::read_to_string(&manifest).unwrap_or_default();
+    let lock = std::fs::read_to_string(&lock_file).unwrap_or_default();
+
+    let mut findings = Vec::new();
+    for vuln in report.vulnerabilities.values() {
+        let titles: Vec<&str> = vuln
+            .via
+            .iter()
+            .filter_map(|via| match via {
+                NpmVia::Advisory { title, .. } => Some(title.as_str()),
+                NpmVia::Dependency(_) => None,
+            })
+            .collect();
+        let through: Vec<&str> = vuln
+            .via
+            .iter()
+            .filter_map(|via| match via {
+                NpmVia::Dependency(name) => Some(name.as_str()),
+                NpmVia::Advisory { .. } => None,
+            })
+            .collect();
+        if titles.is_empty() && !vuln.is_direct {
+            continue;
+        }
+
+        let description = if titles.is_empty() {
+            format!("depends on vulnerable {}", through.join(", "))
+        } else {
+            titles.join("; ")
+        };
+        let message = format!("{} ({}): {}", vuln.name, vuln.severity, description);
+
+        let url = vuln.via.iter().find_map(|via| match via {
+            NpmVia::Advisory { url, .. } => url.clone(),
+            NpmVia::Dependency(_) => None,
+        });
+        let advice = match &vuln.fix_available {
+            Some(NpmFix::Upgrade {
+                name,
+                version,
+                is_semver_major,
+            }) => format!(
+                "Run 'npm install {}@{}'{}",
+                name,
+                version,
+                if *is_semver_major {
+                    " - a new major version, so check its changelog for breaking changes"
+                } else {
+                    ""
+                }
+            ),
+            Some(NpmFix::Auto(true)) => "Run 'npm audit fix'".to_string(),
+            _ => format!(
+                "No fixed version of {} exists yet. Replace it, or check the advisory for workarounds{}.",
+                vuln.name,
+                url.map(|u| format!(": {}", u)).unwrap_or_default()
+            ),
+        };
+
+        // Direct dependencies are declared in package.json
+        let key = format!("\"{}\":", vuln.name);
+        let (file, line) = match line_where(&manifest_content, |line, _| line.starts_with(&key)) {
+            Some(line) if vuln.is_direct => (&manifest, Some(line)),
+            _ => {
+                let key = format!("\"node_modules/{}\":", vuln.name);
+                (
+                    &lock_file,
+                    line_where(&lock, |line, _| line.starts_with(&key)),
+                )
+            }
+        };
+
+        findings.push(vulnerability(
+            root,
+            file,
+            line,
+            message,
+            "npm audit",
+            Language::JavaScript,
+            advice,
+        ));
+    }
+
+    findings
+}
+
+/// Why an npm command run with `--json` produced no report, e.g. no
+/// network or no lockfile
+pub fn npm_error(output: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(output).ok()?;
+    let error = value.get("error")?;
+    // Network failures leave the summary empty and put the reason in "message"
+    let reason = [
+        error.get("summary"),
+        value.get("message"),
+        error.get("code"),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|s| s.as_str())
+    .find(|s| !s.trim().is_empty())
+    .unwrap_or("no report");
+    Some(reason.lines().next().unwrap_or(reason).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_AUDIT: &str = r#"{
+        "database": {"advisory-count": 600},
+        "lockfile": {"dependency-count": 2},
+        "vulnerabilities": {
+            "found": true,
+            "count": 1,
+            "list": [{
+                "advisory": {
+                    "id": "RUSTSEC-2020-0071",
+                    "package": "time",
+                    "title": "Potential segfault in the time crate",
+                    "cvss": "CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H",
+                    "url": "https://github.com/time-rs/time/issues/293"
+                },
+                "versions": {"patched": [">=0.2.23"], "unaffected": ["=0.2.0"]},
+                "package": {"name": "time", "version": "0.1.45"}
+            }]
+        },
+        "warnings": {
+            "unmaintained": [{
+                "kind": "unmaintained",
+                "package": {"name": "ansi_term", "version": "0.12.1"},
+                "advisory": {
+                    "id": "RUSTSEC-2021-0139",
+                    "title": "ansi_term is Unmaintained",
+                    "cvss": null,
+                    "url": null
+                }
+            }]
+        }
+    }"#;
+
+    const NPM_AUDIT: &str = r#"{
+        "auditReportVersion": 2,
+        "vulnerabilities": {
+            "lodash": {
+                "name": "lodash",
+                "severity": "critical",
+                "isDirect": true,
+                "via": [{
+                    "source": 1096305,
+                    "name": "lodash",
+                    "title": "Prototype Pollution in lodash",
+                    "url": "https://github.com/advisories/GHSA-jf85-cpcp-j695",
+                    "severity": "critical"
+                }],
+                "fixAvailable": {"name": "lodash", "version": "4.17.21", "isSemVerMajor": false}
+            },
+            "minimist": {
+                "name": "minimist",
+                "severity": "critical",
+                "isDirect": false,
+                "via": [{"title": "Prototype Pollution in minimist", "url": null}],
+                "fixAvailable": true
+            },
+            "mkdirp": {
+                "name": "mkdirp",
+                "severity": "critical",
+                "isDirect": true,
+                "via": ["minimist"],
+                "fixAvailable": false
+            },
+            "optimist": {
+                "name": "optimist",
+                "severity": "critical",
+                "isDirect": false,
+                "via": ["minimist"],
+                "fixAvailable": true
+            }
+        }
+    }"#;
+
+    fn advice(finding: &Finding) -> &str {
+        match &finding.parsed.as_ref().unwrap().error_type {
+            ErrorType::Vulnerability(advice) => advice,
+            other => panic!("unexpected error type {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cvss_score() {
+        let score = |v| cvss_score(v).unwrap();
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), 9.8);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H"), 10.0);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"), 5.9);
+        assert_eq!(score("CVSS:3.1/AV:L/AC:L/PR:L/UI:N/S:U/C:N/I:N/A:N"), 0.0);
+        assert_eq!(cvss_score("not a vector"), None);
+        assert_eq!(cvss_severity(9.8), "critical");
+        assert_eq!(cvss_severity(5.9), "medium");
+    }
+
+    #[test]
+    fn test_patched_version_prefers_compatible_upgrade() {
+        let patched = vec!["^0.1.47".to_string(), ">=0.2.23".to_string()];
+        assert_eq!(
+            patched_version("0.1.45", &patched),
+            Some(("0.1.47".to_string(), true))
+        );
+        assert_eq!(
+            patched_version("0.2.1", &patched),
+            Some(("0.2.23".to_string(), true))
+        );
+        assert_eq!(
+            patched_version("0.0.9", &patched),
+            Some(("0.2.23".to_string(), false))
+        );
+        assert_eq!(patched_version("1.0.0", &[]), None);
+    }
+
+    #[test]
+    fn test_cargo_audit_findings() {
+        let temp_dir = std::env::temp_dir().join("ess_test_cargo_audit");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("Cargo.lock"),
+            "version = 3\n\n[[package]]\nname = \"time\"\nversion = \"0.2.27\"\n\n[[package]]\nname = \"time\"\nversion = \"0.1.45\"\n",
+        )
+        .unwrap();
+
+        let findings = cargo_audit_findings(&temp_dir, CARGO_AUDIT);
+
+        assert_eq!(findings.len(), 2);
+        let vuln = &findings[0];
+        assert_eq!(vuln.severity, Severity::Error);
+        assert_eq!(
+            vuln.message,
+            "RUSTSEC-2020-0071 (medium): Potential segfault in the time crate - time 0.1.45"
+        );
+        assert_eq!(vuln.line, Some(8));
+        assert_eq!(vuln.rule(), "Vulnerability");
+        assert!(advice(vuln).contains("Upgrade time to 0.2.23"));
+        assert!(advice(vuln).contains("cargo tree -i time"));
+        assert_eq!(findings[1].severity, Severity::Warning);
+        assert!(findings[1].message.contains("(unmaintained)"));
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_npm_audit_findings() {
+        let temp_dir = std::env::temp_dir().join("ess_test_npm_audit");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("package.json"),
+            "{\n  \"name\": \"app\",\n  \"dependencies\": {\n    \"lodash\": \"^4.17.0\",\n    \"mkdirp\": \"^0.5.0\"\n  }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("package-lock.json"),
+            "{\n  \"packages\": {\n    \"node_modules/minimist\": {\n      \"version\": \"0.0.8\"\n    }\n  }\n}\n",
+        )
+        .unwrap();
+
+        let findings = npm_audit_findings(&temp_dir, NPM_AUDIT);
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "lodash (critical): Prototype Pollution in lodash",
+                "minimist (critical): Prototype Pollution in minimist",
+                "mkdirp (critical): depends on vulnerable minimist",
+            ]
+        );
+        assert_eq!(findings[0].file, temp_dir.join("package.json"));
+        assert_eq!(findings[0].line, Some(4));
+        assert_eq!(advice(&findings[0]), "Run 'npm install lodash@4.17.21'");
+        assert_eq!(findings[1].file, temp_dir.join("package-lock.json"));
+        assert_eq!(findings[1].line, Some(3));
+        assert_eq!(advice(&findings[1]), "Run 'npm audit fix'");
+        assert!(advice(&findings[2]).starts_with("No fixed version of mkdirp"));
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_npm_error() {
+        let output = r#"{"error": {"code": "ENOLOCK", "summary": "This command requires an existing lockfile.\nTry creating one first"}}"#;
+        assert_eq!(
+            npm_error(output).as_deref(),
+            Some("This command requires an existing lockfile.")
+        );
+        let offline = r#"{"message": "request to https://registry.npmjs.org failed, reason: getaddrinfo ENOTFOUND", "error": {"summary": "", "detail": ""}}"#;
+        assert!(npm_error(offline).unwrap().contains("ENOTFOUND"));
+        assert_eq!(npm_error(NPM_AUDIT), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! `ess capabilities`: what this build of ess supports, as JSON for IDE
+//! plugins and CI templates, so they don't have to parse human output
+
+use crate::apply;
+use crate::config::{OutputView, ReportFormat};
+use crate::knowledge;
+use crate::parser::Language;
+use crate::scanner;
+use crate::ui;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+/// Version of the JSON layout, raised when a field changes meaning or
+/// goes away; new fields don't raise it
+const SCHEMA_VERSION: u32 = 1;
+
+const LANGUAGES: &[Language] = &[
+    Language::Cpp,
+    Language::Python,
+    Language::JavaScript,
+    Language::TypeScript,
+    Language::Rust,
+    Language::Java,
+    Language::CSharp,
+];
+
+/// Names clap accepts for a value enum
+fn names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|v| v.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+fn extensions(language: &Language) -> Vec<&'static str> {
+    scanner::EXTENSIONS
+        .iter()
+        .copied()
+        .filter(|ext| scanner::language_for_extension(ext).as_ref() == Some(language))
+        .collect()
+}
+
+/// Everything as one JSON object
+pub fn describe() -> Value {
+    let languages: Vec<Value> = LANGUAGES
+        .iter()
+        .map(|language| {
+            json!({
+                "name": language.to_string(),
+                "extensions": extensions(language),
+            })
+        })
+        .collect();
+    let rules: Vec<Value> = knowledge::entries()
+        .iter()
+        .map(|entry| {
+            json!({
+                "id": entry.kind,
+                "description": entry.meaning,
+                "auto_fixable": apply::AUTO_FIXABLE.contains(&entry.kind),
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "version": env!("CARGO_PKG_VERSION"),
+        "languages": languages,
+        "rules": rules,
+        "auto_fixable": apply::AUTO_FIXABLE,
+        "output_formats": names::<ReportFormat>(),
+        "output_views": names::<OutputView>(),
+    })
+}
+
+/// Print the capabilities for a person to read
+pub fn show() {
+    ui::print_section(&format!("ess {}", env!("CARGO_PKG_VERSION")));
+    ui::print_blank_line();
+    for language in LANGUAGES {
+        ui::print_info(&format!(
+            "{}: .{}",
+            language,
+            extensions(language).join(" .")
+        ));
+    }
+
+    ui::print_section("Rules");
+    ui::print_blank_line();
+    for entry in knowledge::entries() {
+        if apply::AUTO_FIXABLE.contains(&entry.kind) {
+            ui::print_success(&format!("{} (auto-fix)", entry.kind));
+        } else {
+            ui::print_info(entry.kind);
+        }
+    }
+
+    ui::print_blank_line();
+    ui::print_info(&format!(
+        "Output formats: {}",
+        names::<ReportFormat>().join(", ")
+    ));
+    ui::print_info(&format!("Views: {}", names::<OutputView>().join(", ")));
+    ui::print_hint("Use --json for a machine-readable description");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let capabilities = describe();
+
+        assert_eq!(capabilities["schema_version"], SCHEMA_VERSION);
+        assert_eq!(
+            capabilities["output_formats"],
+            json!(["text", "sarif", "json", "rdjson", "grep"])
+        );
+        let python = &capabilities["languages"][1];
+        assert_eq!(python["name"], "Python");
+        assert_eq!(python["extensions"], json!(["py"]));
+        // Every extension belongs to a listed language
+        let listed: usize = capabilities["languages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| l["extensions"].as_array().unwrap().len())
+            .sum();
+        assert_eq!(listed, scanner::EXTENSIONS.len());
+
+        let rules = capabilities["rules"].as_array().unwrap();
+        let ids: Vec<&str> = rules.iter().filter_map(|r| r["id"].as_str()).collect();
+        for kind in apply::AUTO_FIXABLE {
+            assert!(ids.contains(kind), "{} is not a rule", kind);
+        }
+        let semicolon = rules
+            .iter()
+            .find(|r| r["id"] == "MissingSemicolon")
+            .unwrap();
+        assert_eq!(semicolon["auto_fixable"], true);
+        let key_error = rules.iter().find(|r| r["id"] == "KeyError").unwrap();
+        assert_eq!(key_error["auto_fixable"], false);
+    }
+}
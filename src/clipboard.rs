@@ -0,0 +1,55 @@
+use crate::fixer;
+use anyhow::{bail, Result};
+use std::process::Command;
+
+/// Commands that print the clipboard on this OS ("linux", "macos" or
+/// "windows"), in the order they are tried
+pub fn paste_commands(os: &str) -> Vec<(&'static str, Vec<&'static str>)> {
+    match os {
+        "macos" => vec![("pbpaste", vec![])],
+        "windows" => vec![(
+            "powershell",
+            vec!["-NoProfile", "-Command", "Get-Clipboard -Raw"],
+        )],
+        _ => vec![
+            ("wl-paste", vec!["--no-newline"]),
+            ("xclip", vec!["-selection", "clipboard", "-o"]),
+            ("xsel", vec!["--clipboard", "--output"]),
+        ],
+    }
+}
+
+/// The text on the system clipboard, line breaks and all, which get lost
+/// when a traceback is pasted as shell arguments
+pub fn read() -> Result<String> {
+    let os = std::env::consts::OS;
+    let commands = paste_commands(os);
+    for (program, args) in &commands {
+        let Ok(output) = Command::new(program).args(args).output() else {
+            continue;
+        };
+        if output.status.success() {
+            return fixer::read_error_text(&mut output.stdout.as_slice());
+        }
+    }
+
+    let tried: Vec<&str> = commands.iter().map(|(program, _)| *program).collect();
+    bail!(
+        "Could not read the clipboard (tried {}); paste the error as an argument or \
+        save it to a file for --file instead",
+        tried.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paste_commands_per_os() {
+        assert_eq!(paste_commands("macos"), vec![("pbpaste", vec![])]);
+        assert_eq!(paste_commands("windows")[0].0, "powershell");
+        let linux: Vec<&str> = paste_commands("linux").iter().map(|c| c.0).collect();
+        assert_eq!(linux, vec!["wl-paste", "xclip", "xsel"]);
+    }
+}
@@ -0,0 +1,248 @@
+//! Findings with one cause between them, e.g. 38 import errors that all
+//! go away with one `pip install`, shown after a scan so the cause gets
+//! fixed instead of each symptom
+
+use crate::finding::Finding;
+use crate::parser::{ErrorType, Language};
+use crate::ui;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Findings it takes to call a cause systemic
+const MIN_FINDINGS: usize = 3;
+
+/// Clusters shown after a scan, the biggest first
+const MAX_SHOWN: usize = 5;
+
+/// Findings that stem from one cause
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// What they have in common, e.g. "a missing dependency 'requests'"
+    pub cause: String,
+    /// How to fix it once, when there is one way
+    pub fix: Option<String>,
+    pub findings: usize,
+    pub files: usize,
+}
+
+/// What one finding would share with others of the same cause
+fn cause(finding: &Finding) -> (String, Option<String>) {
+    let parsed = finding.parsed.as_ref();
+    let language = parsed.map_or(&Language::Unknown, |p| &p.language);
+    match parsed.map(|p| &p.error_type) {
+        Some(ErrorType::ImportError(module) | ErrorType::ModuleNotFound(module))
+            if !module.starts_with('.') && !module.is_empty() =>
+        {
+            let fix = match language {
+                Language::Python => {
+                    let package = module.split('.').next().unwrap_or(module);
+                    Some(format!("pip install {}", package))
+                }
+                Language::JavaScript | Language::TypeScript => {
+                    Some(format!("npm install {}", package_name(module)))
+                }
+                _ => None,
+            };
+            (format!("a missing dependency '{}'", module), fix)
+        }
+        Some(ErrorType::MissingInclude(header)) => (
+            format!("a missing #include <{}>", header),
+            Some(format!(
+                "include <{}> in a header these files share",
+                header
+            )),
+        ),
+        Some(ErrorType::MissingEnvVar(name)) => (
+            format!("the environment variable {} not being set", name),
+            Some(format!("set {} before running", name)),
+        ),
+        Some(ErrorType::UndeclaredVariable(name)) => (
+            format!("'{}' not being declared", name),
+            Some(format!(
+                "declare '{}' once, e.g. in a shared header or module",
+                name
+            )),
+        ),
+        _ => (
+            format!("the same problem: {}", template(&finding.message)),
+            None,
+        ),
+    }
+}
+
+/// npm package of an import path: `lodash/get` is in `lodash`,
+/// `@scope/pkg/sub` in `@scope/pkg`
+fn package_name(module: &str) -> String {
+    let parts: Vec<&str> = module.split('/').collect();
+    let take = if module.starts_with('@') { 2 } else { 1 };
+    parts[..take.min(parts.len())].join("/")
+}
+
+/// A message with what differs between its instances (quoted names and
+/// numbers) left out, so messages of one kind compare equal
+fn template(message: &str) -> String {
+    let mut out = String::new();
+    let mut quote = None;
+    for c in message.chars() {
+        match quote {
+            Some(q) if c == q => {
+                out.push('…');
+                out.push(c);
+                quote = None;
+            }
+            Some(_) => {}
+            None if matches!(c, '\'' | '"' | '`') => {
+                out.push(c);
+                quote = Some(c);
+            }
+            None if c.is_ascii_digit() => {
+                if !out.ends_with('N') {
+                    out.push('N');
+                }
+            }
+            None => out.push(c),
+        }
+    }
+    // An unclosed quote, e.g. an apostrophe, keeps the rest as it was
+    if quote.is_some() {
+        return message.to_string();
+    }
+    out
+}
+
+/// Causes shared by at least `MIN_FINDINGS` findings, the biggest first.
+/// A cause only the message tells must be shared across files.
+pub fn find(findings: &[Finding]) -> Vec<Cluster> {
+    let mut groups: BTreeMap<(String, Option<String>), Vec<&Path>> = BTreeMap::new();
+    for finding in findings {
+        groups
+            .entry(cause(finding))
+            .or_default()
+            .push(&finding.file);
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_iter()
+        .filter_map(|((cause, fix), files)| {
+            let findings = files.len();
+            let files = files.into_iter().collect::<BTreeSet<_>>().len();
+            let known = fix.is_some();
+            (findings >= MIN_FINDINGS && (known || files > 1)).then_some(Cluster {
+                cause,
+                fix,
+                findings,
+                files,
+            })
+        })
+        .collect();
+    clusters.sort_by_key(|c| Reverse(c.findings));
+    clusters
+}
+
+/// Show the biggest clusters
+pub fn print(clusters: &[Cluster]) {
+    if clusters.is_empty() {
+        return;
+    }
+    ui::print_blank_line();
+    for cluster in clusters.iter().take(MAX_SHOWN) {
+        let files = match cluster.files {
+            1 => "1 file".to_string(),
+            n => format!("{} files", n),
+        };
+        ui::print_warning(&format!(
+            "{} findings in {} stem from {}",
+            cluster.findings, files, cluster.cause
+        ));
+        if let Some(fix) = &cluster.fix {
+            ui::print_hint(&format!("Fix it once: {}", fix));
+        }
+    }
+    if clusters.len() > MAX_SHOWN {
+        ui::print_info(&format!(
+            "{} smaller groups of related findings not shown",
+            clusters.len() - MAX_SHOWN
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Severity;
+    use crate::parser::parse_error;
+
+    fn finding(file: &str, output: &str) -> Finding {
+        let root = Path::new("/p");
+        Finding::new(&root.join(file), Severity::Error, output, "python").with_parsed(
+            parse_error(&format!(
+                "Traceback (most recent call last):\n  File \"{}\", line 1, in <module>\n{}",
+                file, output
+            )),
+            root,
+        )
+    }
+
+    #[test]
+    fn test_find_clusters() {
+        let mut findings: Vec<Finding> = (0..4)
+            .map(|i| {
+                finding(
+                    &format!("app{}.py", i),
+                    "ModuleNotFoundError: No module named 'requests'",
+                )
+            })
+            .collect();
+        findings.push(finding(
+            "other.py",
+            "ModuleNotFoundError: No module named 'yaml'",
+        ));
+        for i in 0..3 {
+            findings.push(Finding::new(
+                Path::new(&format!("/p/lib{}.js", i)),
+                Severity::Warning,
+                &format!("Line {} is longer than 120 characters", 10 + i),
+                "policy/max-line-length",
+            ));
+        }
+        // Alike, but all in one file: nothing systemic
+        for i in 0..3 {
+            findings.push(Finding::new(
+                Path::new("/p/big.js"),
+                Severity::Warning,
+                &format!("'v{}' is assigned but never used", i),
+                "eslint",
+            ));
+        }
+
+        let clusters = find(&findings);
+
+        assert_eq!(clusters.len(), 2, "{:?}", clusters);
+        assert_eq!(
+            clusters[0],
+            Cluster {
+                cause: "a missing dependency 'requests'".to_string(),
+                fix: Some("pip install requests".to_string()),
+                findings: 4,
+                files: 4,
+            }
+        );
+        assert_eq!(
+            clusters[1].cause,
+            "the same problem: Line N is longer than N characters"
+        );
+        assert_eq!(clusters[1].fix, None);
+    }
+
+    #[test]
+    fn test_template() {
+        assert_eq!(
+            template("'x' was not declared in scope 42"),
+            "'…' was not declared in scope N"
+        );
+        assert_eq!(template("can't find it"), "can't find it");
+        assert_eq!(package_name("@scope/pkg/sub"), "@scope/pkg");
+        assert_eq!(package_name("lodash/get"), "lodash");
+    }
+}
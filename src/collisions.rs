@@ -0,0 +1,562 @@
+//! Names defined twice across a project, which compilers and interpreters
+//! only report as confusing errors somewhere downstream: two Python
+//! modules that `import` can't tell apart, a module that hides the
+//! standard library, and C++ functions or `main`s defined in more than
+//! one source file, which fail at link time.
+
+use crate::finding::{Finding, Related, Severity};
+use crate::regexes;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Rule IDs, the findings' source
+pub const PYTHON_MODULE: &str = "collision/python-module";
+pub const STDLIB_SHADOW: &str = "collision/stdlib-shadow";
+pub const CPP_SYMBOL: &str = "collision/cpp-symbol";
+pub const CPP_MAIN: &str = "collision/cpp-main";
+
+/// Directories under the root that Python projects put on `sys.path`
+const PYTHON_PATHS: &[&str] = &["", "src"];
+
+/// Standard library modules a script is likely to be named after
+const STDLIB_MODULES: &[&str] = &[
+    "abc",
+    "argparse",
+    "array",
+    "ast",
+    "asyncio",
+    "base64",
+    "bisect",
+    "calendar",
+    "collections",
+    "copy",
+    "csv",
+    "dataclasses",
+    "datetime",
+    "decimal",
+    "email",
+    "enum",
+    "fractions",
+    "functools",
+    "glob",
+    "hashlib",
+    "heapq",
+    "html",
+    "http",
+    "inspect",
+    "io",
+    "itertools",
+    "json",
+    "logging",
+    "math",
+    "operator",
+    "os",
+    "pathlib",
+    "pickle",
+    "platform",
+    "queue",
+    "random",
+    "re",
+    "secrets",
+    "shutil",
+    "signal",
+    "socket",
+    "sqlite3",
+    "statistics",
+    "string",
+    "struct",
+    "subprocess",
+    "sys",
+    "tempfile",
+    "threading",
+    "time",
+    "timeit",
+    "tokenize",
+    "turtle",
+    "types",
+    "typing",
+    "unittest",
+    "uuid",
+    "warnings",
+    "xml",
+    "zipfile",
+];
+
+/// Words before a function name that keep it out of the global symbols
+const LOCAL_SPECIFIERS: &[&str] = &[
+    "static",
+    "inline",
+    "constexpr",
+    "consteval",
+    "template",
+    "friend",
+    "typedef",
+    "using",
+];
+
+/// Words that look like a function name before `(...) {`
+const KEYWORDS: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "return", "sizeof", "decltype", "alignof",
+];
+
+/// Python modules defined twice on the project's import path, and scripts
+/// named after a standard library module. `files` are the project's
+/// Python files, wherever they are.
+pub fn python(root: &Path, files: &[PathBuf]) -> Vec<Finding> {
+    let files: BTreeSet<&Path> = files.iter().map(PathBuf::as_path).collect();
+    let mut findings = Vec::new();
+
+    let mut modules: BTreeMap<String, Vec<&Path>> = BTreeMap::new();
+    for file in &files {
+        for dir in PYTHON_PATHS {
+            let dir = root.join(dir);
+            if let Some(name) = module_name(&dir, file) {
+                modules.entry(name).or_default().push(file);
+            }
+        }
+    }
+    for (name, paths) in &modules {
+        if let [first, others @ ..] = paths.as_slice() {
+            if others.is_empty() {
+                continue;
+            }
+            let message = format!(
+                "Module '{}' is defined {} times on the import path; `import {}` only \
+                ever loads one of them",
+                name,
+                paths.len(),
+                name
+            );
+            findings.push(
+                Finding::new(first, Severity::Warning, &message, PYTHON_MODULE)
+                    .with_related(related(others, "also defined here")),
+            );
+        }
+    }
+
+    for file in &files {
+        let Some(stem) = file.file_stem().map(|s| s.to_string_lossy()) else {
+            continue;
+        };
+        let in_package = file
+            .parent()
+            .is_some_and(|dir| files.contains(dir.join("__init__.py").as_path()));
+        if !in_package && STDLIB_MODULES.contains(&stem.as_ref()) {
+            let message = format!(
+                "'{}.py' hides the standard library module '{}': `import {}` next to it \
+                loads this file instead; rename it",
+                stem, stem, stem
+            );
+            findings.push(Finding::new(
+                file,
+                Severity::Warning,
+                &message,
+                STDLIB_SHADOW,
+            ));
+        }
+    }
+    findings
+}
+
+/// Name `file` is imported as from `dir`: `name.py` in it, or the
+/// `__init__.py` of a package `name/`
+fn module_name(dir: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(dir).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    match parts.as_slice() {
+        [name] => name.strip_suffix(".py").map(str::to_string),
+        [package, init] if init == "__init__.py" => Some(package.clone()),
+        _ => None,
+    }
+}
+
+fn related(paths: &[&Path], message: &str) -> Vec<Related> {
+    paths
+        .iter()
+        .map(|path| Related {
+            file: path.to_path_buf(),
+            line: None,
+            column: None,
+            message: message.to_string(),
+        })
+        .collect()
+}
+
+/// A function defined where the linker sees it
+#[derive(Debug)]
+struct Definition {
+    /// Qualified name and parameter types, e.g. `util::parse(const char*)`
+    signature: String,
+    line: u32,
+}
+
+/// C++ functions with external linkage defined in more than one source
+/// file, and more than one `main` in a directory. `sources` are the
+/// source files (not headers) with their content.
+pub fn cpp(sources: &[(PathBuf, String)]) -> Vec<Finding> {
+    let mut symbols: BTreeMap<String, Vec<(&Path, u32)>> = BTreeMap::new();
+    let mut mains: BTreeMap<&Path, Vec<(&Path, u32)>> = BTreeMap::new();
+    for (path, content) in sources {
+        let mut seen = BTreeSet::new();
+        for definition in definitions(content) {
+            if definition.signature == "main" {
+                let dir = path.parent().unwrap_or(Path::new(""));
+                mains.entry(dir).or_default().push((path, definition.line));
+            } else if seen.insert(definition.signature.clone()) {
+                // Alternatives under #ifdef count once per file
+                symbols
+                    .entry(definition.signature)
+                    .or_default()
+                    .push((path, definition.line));
+            }
+        }
+    }
+    let has_main: BTreeSet<&Path> = mains.values().flatten().map(|(path, _)| *path).collect();
+
+    let mut findings = Vec::new();
+    for (signature, places) in &symbols {
+        // Files with a main of their own are separate programs
+        let programs = places.iter().filter(|(p, _)| has_main.contains(p)).count();
+        if places.len() < 2 || programs > 1 {
+            continue;
+        }
+        let message = format!(
+            "'{}' is defined in {} source files; linking them together fails with \
+            'multiple definition'. Define it in one and declare it in a header, or \
+            make it static or inline",
+            signature,
+            places.len()
+        );
+        findings.push(finding(&places[0], &places[1..], &message, CPP_SYMBOL));
+    }
+    for places in mains.values().filter(|places| places.len() > 1) {
+        let message = format!(
+            "{} files in this directory define main(); a program built from them \
+            fails to link. Keep one main per program",
+            places.len()
+        );
+        findings.push(finding(&places[0], &places[1..], &message, CPP_MAIN));
+    }
+    findings
+}
+
+fn finding(first: &(&Path, u32), others: &[(&Path, u32)], message: &str, rule: &str) -> Finding {
+    Finding::new(first.0, Severity::Warning, message, rule)
+        .at(Some(first.1), None)
+        .with_related(
+            others
+                .iter()
+                .map(|(file, line)| Related {
+                    file: file.to_path_buf(),
+                    line: Some(*line),
+                    column: None,
+                    message: "also defined here".to_string(),
+                })
+                .collect(),
+        )
+}
+
+/// What a `{` opens
+enum Scope {
+    Namespace(String),
+    /// `extern "C" { ... }`, whose contents are global as they are
+    Transparent,
+    /// Anything else: bodies, classes and anonymous namespaces
+    Hidden,
+}
+
+/// Functions defined at namespace scope with external linkage. Reads
+/// the code the way the preprocessor left it, without macros expanded.
+fn definitions(content: &str) -> Vec<Definition> {
+    let code = strip(content);
+    let mut scopes: Vec<Scope> = Vec::new();
+    let mut definitions = Vec::new();
+    // The statement since the last `;`, `{` or `}`, with each byte's line
+    let mut statement = String::new();
+    let mut lines: Vec<u32> = Vec::new();
+    let mut line = 1;
+
+    for c in code.chars() {
+        match c {
+            '{' => {
+                let text = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+                let visible = scopes.iter().all(|s| !matches!(s, Scope::Hidden));
+                let scope = if let Some(name) = text
+                    .strip_prefix("inline ")
+                    .unwrap_or(&text)
+                    .strip_prefix("namespace")
+                {
+                    match name.trim() {
+                        "" => Scope::Hidden,
+                        name => Scope::Namespace(name.to_string()),
+                    }
+                } else if text.starts_with("extern \"") {
+                    Scope::Transparent
+                } else {
+                    if visible {
+                        if let Some(definition) = definition(&statement, &lines, &scopes) {
+                            definitions.push(definition);
+                        }
+                    }
+                    Scope::Hidden
+                };
+                scopes.push(scope);
+                statement.clear();
+                lines.clear();
+            }
+            '}' => {
+                scopes.pop();
+                statement.clear();
+                lines.clear();
+            }
+            ';' => {
+                statement.clear();
+                lines.clear();
+            }
+            c => {
+                statement.push(c);
+                lines.extend(std::iter::repeat_n(line, c.len_utf8()));
+            }
+        }
+        if c == '\n' {
+            line += 1;
+        }
+    }
+    definitions
+}
+
+/// The function `statement` opens the body of, if it has external linkage
+fn definition(statement: &str, lines: &[u32], scopes: &[Scope]) -> Option<Definition> {
+    let text = statement.trim_end();
+    let cap = regexes::CPP_FUNCTION_HEAD.captures(text)?;
+    let prefix = cap.name("prefix")?.as_str();
+    let name = cap.name("name")?;
+    let words: Vec<&str> = prefix
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .collect();
+    if name.as_str().contains("::")
+        || KEYWORDS.contains(&name.as_str())
+        || words.iter().any(|w| LOCAL_SPECIFIERS.contains(w))
+        || words.iter().all(|w| w.is_empty() || *w == "else")
+    {
+        return None;
+    }
+
+    let namespaces: Vec<&str> = scopes
+        .iter()
+        .filter_map(|s| match s {
+            Scope::Namespace(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let line = lines.get(name.start()).copied().unwrap_or(1);
+    if namespaces.is_empty() && name.as_str() == "main" {
+        return Some(Definition {
+            signature: "main".to_string(),
+            line,
+        });
+    }
+
+    let mut qualified = namespaces.join("::");
+    if !qualified.is_empty() {
+        qualified.push_str("::");
+    }
+    qualified.push_str(name.as_str());
+    let params = cap.name("params").map_or("", |m| m.as_str());
+    Some(Definition {
+        signature: format!("{}({})", qualified, parameter_types(params)),
+        line,
+    })
+}
+
+/// Types of a parameter list, without names or default values, so the
+/// same function declared with other names still compares equal
+fn parameter_types(params: &str) -> String {
+    const TYPE_WORDS: &[&str] = &[
+        "int", "long", "short", "char", "double", "float", "bool", "unsigned", "signed", "void",
+        "auto", "const",
+    ];
+    params
+        .split(',')
+        .map(|param| {
+            let param = param.split('=').next().unwrap_or_default();
+            let mut words: Vec<&str> = param.split_whitespace().collect();
+            if let Some(last) = words.last() {
+                let name = last.trim_start_matches(['*', '&']);
+                if words.len() > 1
+                    && !TYPE_WORDS.contains(&name)
+                    && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                {
+                    let pointer = &last[..last.len() - name.len()];
+                    words.pop();
+                    if !pointer.is_empty() {
+                        words.push(pointer);
+                    }
+                }
+            }
+            words.join(" ").replace(" *", "*").replace(" &", "&")
+        })
+        .filter(|param| !param.is_empty() && param != "void")
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `content` with comments, string and character literals and
+/// preprocessor lines blanked out. Line breaks are kept, and quotes stay
+/// so `extern "C"` can be told apart.
+fn strip(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut line_start = true;
+    let mut directive = false;
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            // A directive continues past a line ending in a backslash
+            directive = directive && out.trim_end_matches([' ', '\r']).ends_with('\\');
+            line_start = true;
+            out.push('\n');
+            continue;
+        }
+        if line_start && !c.is_whitespace() {
+            line_start = false;
+            directive = c == '#';
+        }
+        if directive {
+            out.push(if c == '\\' { '\\' } else { ' ' });
+            continue;
+        }
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                out.push(' ');
+            }
+            '"' | '\'' => {
+                out.push(c);
+                while let Some(inner) = chars.next() {
+                    match inner {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '\n' => {
+                            out.push('\n');
+                            break;
+                        }
+                        _ if inner == c => break,
+                        _ => {}
+                    }
+                }
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_python_collisions() {
+        let root = Path::new("/p");
+        let files: Vec<PathBuf> = [
+            "/p/utils.py",
+            "/p/src/utils/__init__.py",
+            "/p/src/utils/helpers.py",
+            "/p/app.py",
+            "/p/random.py",
+            "/p/src/utils/json.py",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let findings = python(root, &files);
+        let rules: Vec<&str> = findings.iter().map(|f| f.source.as_str()).collect();
+
+        assert_eq!(rules, vec![PYTHON_MODULE, STDLIB_SHADOW]);
+        assert_eq!(findings[0].file, PathBuf::from("/p/src/utils/__init__.py"));
+        assert_eq!(findings[0].related[0].file, PathBuf::from("/p/utils.py"));
+        assert!(findings[0].message.contains("'utils' is defined 2 times"));
+        // json.py is inside a package, where `import json` is absolute
+        assert_eq!(findings[1].file, PathBuf::from("/p/random.py"));
+    }
+
+    #[test]
+    fn test_cpp_duplicate_symbols() {
+        let sources = vec![
+            (
+                PathBuf::from("/p/src/main.cpp"),
+                "#include \"util.h\"\n\
+                // int helper(int x) { return x; }\n\
+                int helper(int value) {\n    return value * 2;\n}\n\
+                static int local() { return 1; }\n\
+                namespace {\nint hidden() { return 2; }\n}\n\
+                int main() {\n    if (helper(1)) {\n        return 0;\n    }\n}\n"
+                    .to_string(),
+            ),
+            (
+                PathBuf::from("/p/src/util.cpp"),
+                "namespace util {\nvoid log(const char* msg) {}\n}\n\
+                int helper(int x)\n{\n    return x;\n}\n\
+                static int local() { return 3; }\n\
+                int hidden() { return 4; }\n\
+                inline int shared() { return 5; }\n\
+                struct Point {\n    int norm() const { return 0; }\n};\n"
+                    .to_string(),
+            ),
+            (
+                PathBuf::from("/p/src/other.cpp"),
+                "namespace util {\nvoid log(const char *text) {}\n}\n\
+                extern \"C\" {\nint main(int argc, char** argv) { return 0; }\n}\n"
+                    .to_string(),
+            ),
+        ];
+        let findings = cpp(&sources);
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+
+        assert_eq!(findings.len(), 3, "{:?}", messages);
+        assert_eq!(findings[0].source, CPP_SYMBOL);
+        assert!(messages[0].starts_with("'helper(int)' is defined in 2 source files"));
+        assert_eq!(findings[0].line, Some(3));
+        assert_eq!(
+            findings[0].related[0].file,
+            PathBuf::from("/p/src/util.cpp")
+        );
+        assert_eq!(findings[0].related[0].line, Some(4));
+        assert!(messages[1].starts_with("'util::log(const char*)'"));
+        assert_eq!(findings[2].source, CPP_MAIN);
+        assert_eq!(findings[2].file, PathBuf::from("/p/src/main.cpp"));
+        assert_eq!(findings[2].line, Some(10));
+        assert_eq!(findings[2].related[0].line, Some(5));
+    }
+
+    #[test]
+    fn test_separate_programs_share_names() {
+        let program = "int square(int x) { return x * x; }\nint main() { return square(2); }\n";
+        let sources = vec![
+            (PathBuf::from("/p/a/one.cpp"), program.to_string()),
+            (PathBuf::from("/p/b/two.cpp"), program.to_string()),
+        ];
+        assert!(cpp(&sources).is_empty());
+    }
+}
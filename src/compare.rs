@@ -0,0 +1,362 @@
+//! `ess compare <base> <head>`: scan two git revisions with the same
+//! settings, each checked out into a temporary worktree, and show which
+//! findings `head` adds and which it fixes. `ess diff <a> <b>` does the
+//! same for scans that already ran, or for two directories.
+
+use crate::config::Config;
+use crate::finding::Finding;
+use crate::git;
+use crate::replay::{Analysis, Session};
+use crate::scanner::{self, ScanOptions};
+use crate::scans;
+use crate::ui;
+use crate::usage::UsageError;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Findings one revision has and the other doesn't, with paths relative
+/// to the project
+#[derive(Debug, Default)]
+pub struct Comparison {
+    /// Only in `head`
+    pub added: Vec<Finding>,
+    /// Only in `base`
+    pub fixed: Vec<Finding>,
+    /// In both, as `head` has them
+    pub unchanged: Vec<Finding>,
+}
+
+/// A worktree that is removed when dropped, however the scan ends
+struct Worktree {
+    repo: PathBuf,
+    dir: PathBuf,
+}
+
+impl Worktree {
+    fn add(repo: &Path, rev: &str, n: usize) -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!("ess-compare-{}-{}", std::process::id(), n));
+        if git::add_worktree(repo, &dir, rev).is_none() {
+            bail!("git could not check out '{}' into {}", rev, dir.display());
+        }
+        Ok(Self {
+            repo: repo.to_path_buf(),
+            dir,
+        })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        git::remove_worktree(&self.repo, &self.dir);
+    }
+}
+
+/// Scan `base` and `head` of the repository `path` is in, and print what
+/// changed. Returns the comparison.
+pub fn run(path: &Path, config: &Config, base: &str, head: &str) -> Result<Comparison> {
+    ui::print_section(&format!("Comparing {} → {}", base, head));
+
+    let path = scanner::normalize_path(path);
+    let Some((repo, prefix)) = git::repository(&path) else {
+        bail!("{} is not in a git repository", path.display());
+    };
+    for rev in [base, head] {
+        if !git::is_commit(&repo, rev) {
+            bail!(
+                "'{}' is not a branch, tag or commit of {}",
+                rev,
+                repo.display()
+            );
+        }
+    }
+
+    let base_findings = scan_revision(&repo, &prefix, base, 0, config)?;
+    let head_findings = scan_revision(&repo, &prefix, head, 1, config)?;
+    let comparison = compare(base_findings, head_findings);
+    show(&comparison, head, false);
+    Ok(comparison)
+}
+
+/// `ess diff`: compare two scans, each an id from `ess history` of the
+/// project at `path`, a file saved with `--save`, or a directory to scan
+/// now. Returns the comparison.
+pub fn diff(
+    path: &Path,
+    config: &Config,
+    a: &str,
+    b: &str,
+    show_unchanged: bool,
+) -> Result<Comparison> {
+    let path = scanner::normalize_path(path);
+    let (a_label, a_findings) = load_scan(&path, config, a)?;
+    let (b_label, b_findings) = load_scan(&path, config, b)?;
+    ui::print_section(&format!("Comparing {} → {}", a_label, b_label));
+    let comparison = compare(a_findings, b_findings);
+    show(&comparison, &b_label, show_unchanged);
+    Ok(comparison)
+}
+
+/// The findings of one side of `ess diff`, with a label for it
+fn load_scan(project: &Path, config: &Config, spec: &str) -> Result<(String, Vec<Finding>)> {
+    let target = Path::new(spec);
+    if target.is_dir() {
+        ui::print_info(&format!("Scanning {}...", spec));
+        let root = scanner::normalize_path(target);
+        let summary = scanner::scan_project(
+            &root,
+            config,
+            &ScanOptions {
+                quiet: true,
+                ..Default::default()
+            },
+        )?;
+        return Ok((spec.to_string(), relative_findings(summary.findings, &root)));
+    }
+    let (label, session) = if target.is_file() {
+        (spec.to_string(), Session::load(target)?)
+    } else {
+        match spec.trim_start_matches('#').parse::<i64>() {
+            Ok(id) => (format!("scan #{}", id), scans::session(project, id)?),
+            Err(_) => {
+                return Err(UsageError(format!(
+                    "'{}' is not a scan id from 'ess history', a file saved with --save, \
+                    or a directory",
+                    spec
+                ))
+                .into())
+            }
+        }
+    };
+    match session.analysis {
+        Analysis::Scan { root, summary } => Ok((label, relative_findings(summary.findings, &root))),
+        Analysis::Bug { .. } => bail!("{} is an 'ess bug' analysis, not a scan", label),
+    }
+}
+
+/// Findings in the project at `prefix` as of `rev`
+fn scan_revision(
+    repo: &Path,
+    prefix: &str,
+    rev: &str,
+    n: usize,
+    config: &Config,
+) -> Result<Vec<Finding>> {
+    ui::print_info(&format!("Scanning {}...", rev));
+    let worktree = Worktree::add(repo, rev, n)?;
+    let root = scanner::normalize_path(&worktree.dir.join(prefix));
+    let summary = scanner::scan_project(
+        &root,
+        config,
+        &ScanOptions {
+            quiet: true,
+            ..Default::default()
+        },
+    )?;
+    Ok(relative_findings(summary.findings, &root))
+}
+
+/// Findings with paths relative to the project `root`, in messages too,
+/// so scans of copies of a project in other places compare equal
+fn relative_findings(findings: Vec<Finding>, root: &Path) -> Vec<Finding> {
+    let root_str = root.to_string_lossy().to_string();
+    findings
+        .into_iter()
+        .map(|mut finding| {
+            finding.file = finding
+                .file
+                .strip_prefix(root)
+                .unwrap_or(&finding.file)
+                .to_path_buf();
+            // Tools name files by where the project happened to be
+            finding.message = finding.message.replace(&root_str, ".");
+            finding
+        })
+        .collect()
+}
+
+/// Findings are the same when they are in the same file, from the same
+/// rule, with the same message: lines move when code is added above
+fn key(finding: &Finding) -> (PathBuf, String, String) {
+    (
+        finding.file.clone(),
+        finding.rule().to_string(),
+        finding.message.clone(),
+    )
+}
+
+pub fn compare(base: Vec<Finding>, head: Vec<Finding>) -> Comparison {
+    let counts = |findings: &[Finding]| {
+        let mut counts: HashMap<(PathBuf, String, String), usize> = HashMap::new();
+        for finding in findings {
+            *counts.entry(key(finding)).or_default() += 1;
+        }
+        counts
+    };
+    let mut in_base = counts(&base);
+    let mut in_head = counts(&head);
+
+    let mut comparison = Comparison::default();
+    for finding in head {
+        match in_base.get_mut(&key(&finding)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                comparison.unchanged.push(finding);
+            }
+            _ => comparison.added.push(finding),
+        }
+    }
+    for finding in base {
+        match in_head.get_mut(&key(&finding)) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => comparison.fixed.push(finding),
+        }
+    }
+    comparison
+}
+
+fn show(comparison: &Comparison, head: &str, show_unchanged: bool) {
+    let unchanged = if show_unchanged {
+        &comparison.unchanged[..]
+    } else {
+        &[]
+    };
+    for (title, findings) in [
+        (format!("New in {}", head), &comparison.added[..]),
+        (format!("Fixed in {}", head), &comparison.fixed[..]),
+        ("Unchanged".to_string(), unchanged),
+    ] {
+        if findings.is_empty() {
+            continue;
+        }
+        ui::print_section(&title);
+        for finding in findings {
+            let position = finding.position();
+            let location = if position.is_empty() {
+                finding.file.display().to_string()
+            } else {
+                format!("{}:{}", finding.file.display(), position)
+            };
+            ui::print_finding(
+                finding.severity,
+                &format!("{}:", location),
+                &finding.message,
+                &finding.label(),
+            );
+        }
+    }
+
+    ui::print_blank_line();
+    ui::print_info(&format!(
+        "{} new, {} fixed, {} in both",
+        comparison.added.len(),
+        comparison.fixed.len(),
+        comparison.unchanged.len()
+    ));
+    if comparison.added.is_empty() {
+        ui::print_success(&format!("{} adds no findings", head));
+    } else {
+        ui::print_warning(&format!(
+            "{} adds {} finding(s)",
+            head,
+            comparison.added.len()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Severity;
+    use crate::scanner::ScanSummary;
+    use crate::unparsed::ParseCoverage;
+
+    fn finding(file: &str, line: u32, message: &str) -> Finding {
+        Finding::new(Path::new(file), Severity::Error, message, "python").at(Some(line), None)
+    }
+
+    #[test]
+    fn test_compare_ignores_moved_lines() {
+        let base = vec![
+            finding("a.py", 3, "name 'x' is not defined"),
+            finding("a.py", 9, "name 'x' is not defined"),
+            finding("b.py", 1, "invalid syntax"),
+        ];
+        let head = vec![
+            finding("a.py", 5, "name 'x' is not defined"),
+            finding("a.py", 11, "name 'x' is not defined"),
+            finding("a.py", 12, "name 'x' is not defined"),
+            finding("c.py", 2, "invalid syntax"),
+        ];
+
+        let comparison = compare(base, head);
+
+        assert_eq!(comparison.unchanged.len(), 2);
+        let added: Vec<(String, Option<u32>)> = comparison
+            .added
+            .iter()
+            .map(|f| (f.file.display().to_string(), f.line))
+            .collect();
+        assert_eq!(
+            added,
+            vec![
+                ("a.py".to_string(), Some(12)),
+                ("c.py".to_string(), Some(2))
+            ]
+        );
+        assert_eq!(comparison.fixed.len(), 1);
+        assert_eq!(comparison.fixed[0].file, Path::new("b.py"));
+    }
+
+    #[test]
+    fn test_diff_saved_scans() {
+        let temp_dir = std::env::temp_dir().join("ess_test_compare_diff");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let save = |name: &str, root: &str, findings: Vec<Finding>| {
+            let summary = ScanSummary {
+                languages: Vec::new(),
+                lines_of_code: 0,
+                failing_files: Vec::new(),
+                findings,
+                skipped: Vec::new(),
+                parse_coverage: ParseCoverage::default(),
+            };
+            let path = temp_dir.join(name);
+            Session::scan(Path::new(root), &summary)
+                .save(&path)
+                .unwrap();
+            path.to_string_lossy().to_string()
+        };
+        // The same project, checked out in two places
+        let a = save(
+            "a.json",
+            "/a",
+            vec![finding("/a/x.py", 1, "No module named '/a/lib'")],
+        );
+        let b = save(
+            "b.json",
+            "/b",
+            vec![
+                finding("/b/x.py", 2, "No module named '/b/lib'"),
+                finding("/b/y.py", 1, "invalid syntax"),
+            ],
+        );
+
+        let comparison = diff(&temp_dir, &Config::default(), &a, &b, true).unwrap();
+        let unknown = diff(&temp_dir, &Config::default(), &a, "nope", false)
+            .err()
+            .unwrap()
+            .to_string();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(comparison.unchanged.len(), 1);
+        assert_eq!(comparison.unchanged[0].message, "No module named './lib'");
+        assert_eq!(comparison.added.len(), 1);
+        assert_eq!(comparison.added[0].file, Path::new("y.py"));
+        assert!(comparison.fixed.is_empty());
+        assert!(unknown.contains("is not a scan id"), "{}", unknown);
+    }
+}
@@ -1,5 +1,10 @@
+use crate::finding::Severity;
+use crate::parser::{Anchor, Language};
+use crate::scanner::detect_language_from_str;
 use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Configuration file name
@@ -8,6 +13,9 @@ const CONFIG_FILE_NAME: &str = ".essentialscode.toml";
 /// Global configuration file name (in home directory)
 const GLOBAL_CONFIG_FILE_NAME: &str = "essentialscode.toml";
 
+/// Data directory name (in ~/.config)
+const DATA_DIR_NAME: &str = "essentialscode";
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -17,8 +25,169 @@ pub struct Config {
     #[serde(default)]
     pub languages: LanguagesConfig,
 
+    #[serde(default)]
+    pub rust: RustConfig,
+
+    #[serde(default)]
+    pub cpp: CppConfig,
+
+    #[serde(default)]
+    pub run: RunConfig,
+
+    #[serde(default)]
+    pub policy: PolicyConfig,
+
     #[serde(default)]
     pub output: OutputConfig,
+
+    #[serde(default)]
+    pub redact: RedactConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub fix: FixConfig,
+
+    #[serde(default)]
+    pub rules: RulesConfig,
+
+    /// Language of files the extension doesn't tell, by path or glob
+    /// relative to the project root, e.g. "scripts/deploy" = "python"
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+
+    /// The project's own error patterns
+    #[serde(default)]
+    pub patterns: Vec<PatternConfig>,
+
+    /// Rules compiled to WebAssembly, tried after the patterns
+    #[serde(default)]
+    pub wasm_rules: Vec<WasmRuleConfig>,
+
+    /// Profile applied when `--profile` isn't given
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// Named bundles of settings, on top of or in place of the built-in
+    /// "quick", "full" and "ci"
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// Settings a profile changes. Anything it leaves out keeps the value
+/// from the rest of the config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    pub max_depth: Option<usize>,
+    pub run_linters: Option<bool>,
+    pub run_files: Option<bool>,
+    pub fail_on_missing_tools: Option<bool>,
+    pub check_headers: Option<bool>,
+    pub check_docs: Option<bool>,
+    pub audit: Option<bool>,
+    pub outdated: Option<bool>,
+    pub check_formatting: Option<bool>,
+    pub run_tests: Option<bool>,
+    pub colors: Option<bool>,
+    pub show_banner: Option<bool>,
+    pub view: Option<OutputView>,
+    pub format: Option<ReportFormat>,
+}
+
+/// Profiles every project has; `[profiles.<name>]` can change them
+const BUILTIN_PROFILES: &[&str] = &["quick", "full", "ci"];
+
+impl ProfileConfig {
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            // Syntax errors only
+            "quick" => Some(Self {
+                max_depth: Some(3),
+                run_linters: Some(false),
+                run_files: Some(false),
+                check_headers: Some(false),
+                check_docs: Some(false),
+                audit: Some(false),
+                outdated: Some(false),
+                check_formatting: Some(false),
+                run_tests: Some(false),
+                ..Default::default()
+            }),
+            "full" => Some(Self {
+                run_linters: Some(true),
+                run_files: Some(true),
+                check_headers: Some(true),
+                check_docs: Some(true),
+                audit: Some(true),
+                outdated: Some(true),
+                check_formatting: Some(true),
+                run_tests: Some(true),
+                ..Default::default()
+            }),
+            // Plain output for logs, and a failure when a check couldn't run
+            "ci" => Some(Self {
+                fail_on_missing_tools: Some(true),
+                colors: Some(false),
+                show_banner: Some(false),
+                format: Some(ReportFormat::Json),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// These settings, with the ones `other` sets taking their place
+    fn merged(self, other: &ProfileConfig) -> Self {
+        Self {
+            max_depth: other.max_depth.or(self.max_depth),
+            run_linters: other.run_linters.or(self.run_linters),
+            run_files: other.run_files.or(self.run_files),
+            fail_on_missing_tools: other.fail_on_missing_tools.or(self.fail_on_missing_tools),
+            check_headers: other.check_headers.or(self.check_headers),
+            check_docs: other.check_docs.or(self.check_docs),
+            audit: other.audit.or(self.audit),
+            outdated: other.outdated.or(self.outdated),
+            check_formatting: other.check_formatting.or(self.check_formatting),
+            run_tests: other.run_tests.or(self.run_tests),
+            colors: other.colors.or(self.colors),
+            show_banner: other.show_banner.or(self.show_banner),
+            view: other.view.or(self.view),
+            format: other.format.or(self.format),
+        }
+    }
+
+    fn apply(&self, config: &mut Config) {
+        let scan = &mut config.scan;
+        let output = &mut config.output;
+        let settings = [
+            (self.run_linters, &mut scan.run_linters),
+            (self.run_files, &mut scan.run_files),
+            (self.fail_on_missing_tools, &mut scan.fail_on_missing_tools),
+            (self.check_headers, &mut scan.check_headers),
+            (self.check_docs, &mut scan.check_docs),
+            (self.audit, &mut scan.audit),
+            (self.outdated, &mut scan.outdated),
+            (self.check_formatting, &mut scan.check_formatting),
+            (self.run_tests, &mut scan.run_tests),
+            (self.colors, &mut output.colors),
+            (self.show_banner, &mut output.show_banner),
+        ];
+        for (value, setting) in settings {
+            if let Some(value) = value {
+                *setting = value;
+            }
+        }
+        if let Some(depth) = self.max_depth {
+            scan.max_depth = depth;
+        }
+        if let Some(view) = self.view {
+            output.view = view;
+        }
+        if let Some(format) = self.format {
+            output.format = format;
+        }
+    }
 }
 
 /// Scanning configuration
@@ -28,10 +197,23 @@ pub struct ScanConfig {
     #[serde(default = "default_max_depth")]
     pub max_depth: usize,
 
-    /// Directories to ignore during scanning
+    /// Paths to ignore during scanning (plain names or globs)
     #[serde(default = "default_ignore")]
     pub ignore: Vec<String>,
 
+    /// Also skip what .gitignore, .ignore and the global git excludes list
+    #[serde(default = "default_true")]
+    pub respect_gitignore: bool,
+
+    /// Files checked at once by the per-file checks (0 = one per CPU)
+    #[serde(default)]
+    pub jobs: usize,
+
+    /// Compilers, interpreters and linters running at once across all
+    /// checks (0 = one per CPU)
+    #[serde(default)]
+    pub max_processes: usize,
+
     /// Whether to run language-specific linters
     #[serde(default = "default_true")]
     pub run_linters: bool,
@@ -39,6 +221,73 @@ pub struct ScanConfig {
     /// Whether to run files to check for runtime errors
     #[serde(default = "default_true")]
     pub run_files: bool,
+
+    /// Seconds a file run for runtime errors may take before it is
+    /// stopped (0 = no limit)
+    #[serde(default = "default_run_timeout")]
+    pub run_timeout: u64,
+
+    /// Seconds a compiler, linter or other check may take (0 = no limit)
+    #[serde(default)]
+    pub tool_timeout: u64,
+
+    /// Megabytes of stdout and of stderr kept from each tool run, its start
+    /// and end; the middle is dropped (0 = keep everything)
+    #[serde(default = "default_max_output_mb")]
+    pub max_output_mb: usize,
+
+    /// Ask compilers and linters for English messages (LC_ALL=C, VSLANG),
+    /// which the parser's patterns are written for
+    #[serde(default)]
+    pub english_output: bool,
+
+    /// Exit with an error when a check was skipped because its tool is missing
+    #[serde(default)]
+    pub fail_on_missing_tools: bool,
+
+    /// Also compile C++ headers on their own
+    #[serde(default)]
+    pub check_headers: bool,
+
+    /// Also syntax-check fenced code blocks in Markdown files
+    #[serde(default)]
+    pub check_docs: bool,
+
+    /// Also audit dependencies for known vulnerabilities
+    #[serde(default)]
+    pub audit: bool,
+
+    /// Also flag direct dependencies a major version behind their latest release
+    #[serde(default)]
+    pub outdated: bool,
+
+    /// Also flag files that look unformatted and offer their formatter's fix
+    #[serde(default)]
+    pub check_formatting: bool,
+
+    /// Longest line `check_formatting` accepts (0 = no limit)
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+
+    /// Also run the project's tests (pytest, cargo test, npm test) and
+    /// report the failures
+    #[serde(default)]
+    pub run_tests: bool,
+
+    /// Hand files no built-in check understands, and errors nothing built
+    /// in recognizes, to the `ess-plugin-*` executables on PATH
+    #[serde(default = "default_true")]
+    pub plugins: bool,
+
+    /// Container image to run the compilers, linters and files in, with
+    /// the project mounted read-only (default: run them on this machine)
+    #[serde(default)]
+    pub docker_image: Option<String>,
+
+    /// Environment variables set for the files run by `run_files` and the
+    /// tests run by `run_tests`, on top of the environment ess runs in
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
 }
 
 impl Default for ScanConfig {
@@ -46,12 +295,44 @@ impl Default for ScanConfig {
         Self {
             max_depth: default_max_depth(),
             ignore: default_ignore(),
+            respect_gitignore: true,
+            jobs: 0,
+            max_processes: 0,
             run_linters: true,
             run_files: true,
+            run_timeout: default_run_timeout(),
+            tool_timeout: 0,
+            max_output_mb: default_max_output_mb(),
+            english_output: false,
+            fail_on_missing_tools: false,
+            check_headers: false,
+            check_docs: false,
+            audit: false,
+            outdated: false,
+            check_formatting: false,
+            max_line_length: default_max_line_length(),
+            run_tests: false,
+            plugins: true,
+            docker_image: None,
+            env: BTreeMap::new(),
         }
     }
 }
 
+/// How runtime checks (`scan.run_files`) run the project's files
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RunConfig {
+    /// Files that are run, by path or glob relative to the project root.
+    /// The rest are only imported (Python) or syntax-checked (JavaScript).
+    /// Empty runs every file.
+    #[serde(default)]
+    pub entrypoints: Vec<String>,
+
+    /// Directory files run in, relative to the project root (default: the root)
+    #[serde(default)]
+    pub working_dir: Option<String>,
+}
+
 /// Languages configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct LanguagesConfig {
@@ -62,6 +343,219 @@ pub struct LanguagesConfig {
     /// Disabled languages
     #[serde(default)]
     pub disabled: Vec<String>,
+
+    /// Python interpreter to use, e.g. "python3.12" or "py -3"
+    /// (default: the first of python3, python and py -3 that works)
+    #[serde(default)]
+    pub python: Option<String>,
+}
+
+/// Rust checking configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RustConfig {
+    /// Feature sets to run `cargo check` with, one run each. An entry is a
+    /// comma-separated list of features, "all" (--all-features) and "none"
+    /// (--no-default-features), e.g. "none,serde". Empty = default features.
+    #[serde(default)]
+    pub features: Vec<String>,
+
+    /// Target triple to check for, e.g. "wasm32-unknown-unknown"
+    #[serde(default)]
+    pub target: Option<String>,
+
+    /// rustup toolchain to check with, e.g. "nightly" or "1.75"
+    #[serde(default)]
+    pub toolchain: Option<String>,
+}
+
+/// Command-line overrides for the Rust configuration
+#[derive(Debug, Clone, Default)]
+pub struct RustOverrides {
+    pub features: Vec<String>,
+    pub target: Option<String>,
+}
+
+impl RustConfig {
+    /// Apply command-line overrides on top of the loaded config
+    pub fn apply_overrides(&mut self, overrides: &RustOverrides) {
+        if !overrides.features.is_empty() {
+            self.features = overrides.features.clone();
+        }
+        if let Some(target) = &overrides.target {
+            self.target = Some(target.clone());
+        }
+    }
+
+    /// The feature sets to check, "default" when none are configured
+    pub fn feature_sets(&self) -> Vec<String> {
+        if self.features.is_empty() {
+            vec!["default".to_string()]
+        } else {
+            self.features.clone()
+        }
+    }
+}
+
+/// C++ checking configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CppConfig {
+    /// Compiler to use instead of the first of g++ and clang++ found
+    #[serde(default)]
+    pub compiler: Option<String>,
+
+    /// Target triple to check for, e.g. "aarch64-linux-gnu"
+    #[serde(default)]
+    pub target: Option<String>,
+
+    /// Extra compiler flags, e.g. "--sysroot=/opt/sysroot"
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+/// Command-line overrides for the C++ configuration
+#[derive(Debug, Clone, Default)]
+pub struct CppOverrides {
+    pub target: Option<String>,
+}
+
+impl CppConfig {
+    /// Apply command-line overrides on top of the loaded config
+    pub fn apply_overrides(&mut self, overrides: &CppOverrides) {
+        if let Some(target) = &overrides.target {
+            self.target = Some(target.clone());
+        }
+    }
+}
+
+/// Project policy rules, checked on every source file during scans
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PolicyConfig {
+    /// Text every source file must start with, e.g. an SPDX identifier
+    #[serde(default)]
+    pub license_header: Option<String>,
+
+    /// Paths (directories or globs) where debug output like console.log,
+    /// print() or dbg! is not allowed
+    #[serde(default)]
+    pub no_print: Vec<String>,
+
+    /// JavaScript scripts must start with 'use strict'
+    #[serde(default)]
+    pub require_use_strict: bool,
+
+    /// Longest a source file may be, in lines
+    #[serde(default)]
+    pub max_file_lines: Option<usize>,
+
+    /// Severity per rule ID; rules are warnings unless listed here
+    #[serde(default)]
+    pub severity: BTreeMap<String, Severity>,
+}
+
+/// What is taken out of reports and `--share` files, so they can be
+/// posted publicly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactConfig {
+    /// Take out tokens, passwords, the home directory, the user name and
+    /// IP addresses
+    #[serde(default = "default_true")]
+    pub defaults: bool,
+
+    /// More regexes whose matches are taken out, e.g. internal host names
+    #[serde(default)]
+    pub patterns: Vec<String>,
+
+    /// Redact what ess prints to the terminal too
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        Self {
+            defaults: true,
+            patterns: Vec::new(),
+            terminal: false,
+        }
+    }
+}
+
+/// Commands run after scans and fixes (see `hooks`)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HooksConfig {
+    /// Run after `ess find-bug`, e.g. "notify-send 'ess: {errors} errors'"
+    #[serde(default)]
+    pub post_scan: Option<String>,
+
+    /// Run after `ess find-bug --walk` applies a fix that holds
+    #[serde(default)]
+    pub post_fix: Option<String>,
+}
+
+/// Which rules' fixes are applied without asking, only after asking, or
+/// never (see `apply::fix_policy`). Rules are named by ID, e.g.
+/// "CPP-INC-001", or as scans show them, e.g. "MissingInclude".
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct FixConfig {
+    /// Applied by `ess find-bug --walk` without asking
+    #[serde(default)]
+    pub auto: Vec<String>,
+
+    /// Shown and confirmed before every apply, `ess bug --apply` included
+    #[serde(default)]
+    pub confirm: Vec<String>,
+
+    /// Never applied, only suggested
+    #[serde(default)]
+    pub never: Vec<String>,
+}
+
+/// Checks turned off for the project
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RulesConfig {
+    /// Rule IDs (e.g. "PY-ENV-001", as 'ess list' shows them) or rule
+    /// names whose findings scans drop
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+/// An error pattern of the project's own, from `[[patterns]]`. Templates
+/// can use `{name}` placeholders for the regex's capture groups.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PatternConfig {
+    /// Regex matched against error messages the built-in patterns don't know
+    pub regex: String,
+
+    /// Only match errors in this language
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Heading shown above the fix
+    pub title: String,
+
+    /// How to fix the error
+    pub fix: String,
+
+    /// Line to insert as an automatic fix
+    #[serde(default)]
+    pub insert: Option<String>,
+
+    /// Where `insert` goes: "top-of-file", "after-imports" or "at-error-line"
+    #[serde(default)]
+    pub anchor: Anchor,
+}
+
+/// A rule module from `[[wasm_rules]]`, run sandboxed: it can't touch
+/// files, the network or the environment, and gets a bounded amount of
+/// time and memory per error
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WasmRuleConfig {
+    /// The `.wasm` file, relative to the project root
+    pub path: String,
+
+    /// Only ask it about errors in this language
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 /// Output configuration
@@ -78,6 +572,108 @@ pub struct OutputConfig {
     /// Show diffs in fix suggestions
     #[serde(default = "default_true")]
     pub show_diffs: bool,
+
+    /// Show the banner at startup
+    #[serde(default = "default_true")]
+    pub show_banner: bool,
+
+    /// Show section headers
+    #[serde(default = "default_true")]
+    pub show_sections: bool,
+
+    /// Maximum lines of a fix instruction to show (0 = no limit)
+    #[serde(default)]
+    pub max_instruction_lines: usize,
+
+    /// How before/after diffs are laid out
+    #[serde(default)]
+    pub diff_style: DiffStyle,
+
+    /// Make file locations clickable in terminals that support it (OSC 8)
+    #[serde(default = "default_true")]
+    pub hyperlinks: bool,
+
+    /// Link target for file locations, with {path}, {line} and {column}
+    /// placeholders (default: "file://{path}")
+    #[serde(default)]
+    pub hyperlink_url: Option<String>,
+
+    /// How scan findings are laid out
+    #[serde(default)]
+    pub view: OutputView,
+
+    /// Order of scan findings
+    #[serde(default)]
+    pub sort: SortOrder,
+
+    /// What scans print: text for people or a report for other tools
+    #[serde(default)]
+    pub format: ReportFormat,
+
+    /// Text shown instead of the ASCII banner; may span several lines
+    #[serde(default)]
+    pub banner: Option<String>,
+
+    /// Repeated to draw the lines around section headers ("" = no lines)
+    #[serde(default = "default_section_separator")]
+    pub section_separator: String,
+
+    /// Width of the lines around section headers, in characters
+    #[serde(default = "default_section_width")]
+    pub section_width: usize,
+}
+
+/// Layout of before/after diffs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffStyle {
+    /// Removed lines above added lines
+    #[default]
+    Unified,
+    /// Before and after in two columns, in wide enough terminals
+    SideBySide,
+}
+
+/// Layout of scan findings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputView {
+    /// Each finding as soon as a check reports it, with the fix explained
+    #[default]
+    Stream,
+    /// All findings at the end, grouped under a header per file
+    Grouped,
+    /// No findings, only the totals at the end
+    Summary,
+}
+
+/// What a scan prints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportFormat {
+    /// Findings and fixes for reading in a terminal
+    #[default]
+    Text,
+    /// A SARIF 2.1.0 log, e.g. for GitHub code scanning
+    Sarif,
+    /// The findings and totals as JSON
+    Json,
+    /// Reviewdog Diagnostic JSON, for review comments through reviewdog
+    Rdjson,
+    /// One `path:line:column: RULE: message` line per finding, for grep,
+    /// awk or an editor's quickfix list
+    Grep,
+}
+
+/// Order of scan findings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// By path, then line, then rule, so results are the same on every machine
+    #[default]
+    Path,
+    /// As the checks report them, shown right away in the stream view
+    None,
 }
 
 impl Default for OutputConfig {
@@ -86,14 +682,313 @@ impl Default for OutputConfig {
             colors: true,
             show_hints: true,
             show_diffs: true,
+            show_banner: true,
+            show_sections: true,
+            max_instruction_lines: 0,
+            diff_style: DiffStyle::default(),
+            hyperlinks: true,
+            hyperlink_url: None,
+            view: OutputView::default(),
+            sort: SortOrder::default(),
+            format: ReportFormat::default(),
+            banner: None,
+            section_separator: default_section_separator(),
+            section_width: default_section_width(),
         }
     }
 }
 
+/// Command-line overrides of the output configuration for a single run
+#[derive(Debug, Clone, Default)]
+pub struct OutputOverrides {
+    pub no_banner: bool,
+    pub no_hints: bool,
+    pub no_diffs: bool,
+    pub no_sections: bool,
+    pub max_instruction_lines: Option<usize>,
+    pub diff_style: Option<DiffStyle>,
+    pub no_hyperlinks: bool,
+    pub view: Option<OutputView>,
+    pub sort: Option<SortOrder>,
+    pub format: Option<ReportFormat>,
+}
+
+impl OutputConfig {
+    /// Apply command-line overrides on top of the loaded config
+    pub fn apply_overrides(&mut self, overrides: &OutputOverrides) {
+        if overrides.no_banner {
+            self.show_banner = false;
+        }
+        if overrides.no_hints {
+            self.show_hints = false;
+        }
+        if overrides.no_diffs {
+            self.show_diffs = false;
+        }
+        if overrides.no_sections {
+            self.show_sections = false;
+        }
+        if let Some(lines) = overrides.max_instruction_lines {
+            self.max_instruction_lines = lines;
+        }
+        if let Some(style) = overrides.diff_style {
+            self.diff_style = style;
+        }
+        if overrides.no_hyperlinks {
+            self.hyperlinks = false;
+        }
+        if let Some(view) = overrides.view {
+            self.view = view;
+        }
+        if let Some(sort) = overrides.sort {
+            self.sort = sort;
+        }
+        if let Some(format) = overrides.format {
+            self.format = format;
+        }
+    }
+}
+
+/// Command-line overrides of the scan configuration for a single run
+#[derive(Debug, Clone, Default)]
+pub struct ScanOverrides {
+    pub max_depth: Option<usize>,
+    /// Extra ignore patterns, appended to the configured ones
+    pub ignore: Vec<String>,
+    pub no_gitignore: bool,
+    pub jobs: Option<usize>,
+    pub max_processes: Option<usize>,
+    pub no_linters: bool,
+    pub no_run: bool,
+    pub run_timeout: Option<u64>,
+    pub fail_on_missing_tools: bool,
+    pub check_headers: bool,
+    pub check_docs: bool,
+    pub audit: bool,
+    pub outdated: bool,
+    pub check_formatting: bool,
+    pub run_tests: bool,
+    pub docker_image: Option<String>,
+    /// `KEY=VALUE` pairs, added to (and replacing) the configured ones
+    pub env: Vec<(String, String)>,
+}
+
+impl ScanConfig {
+    /// Apply command-line overrides on top of the loaded config
+    pub fn apply_overrides(&mut self, overrides: &ScanOverrides) {
+        if let Some(depth) = overrides.max_depth {
+            self.max_depth = depth;
+        }
+        self.ignore.extend(overrides.ignore.iter().cloned());
+        if overrides.no_gitignore {
+            self.respect_gitignore = false;
+        }
+        if let Some(jobs) = overrides.jobs {
+            self.jobs = jobs;
+        }
+        if let Some(processes) = overrides.max_processes {
+            self.max_processes = processes;
+        }
+        if overrides.no_linters {
+            self.run_linters = false;
+        }
+        if overrides.no_run {
+            self.run_files = false;
+        }
+        if let Some(seconds) = overrides.run_timeout {
+            self.run_timeout = seconds;
+        }
+        if let Some(image) = &overrides.docker_image {
+            self.docker_image = Some(image.clone());
+        }
+        self.env.extend(overrides.env.iter().cloned());
+        if overrides.fail_on_missing_tools {
+            self.fail_on_missing_tools = true;
+        }
+        if overrides.check_headers {
+            self.check_headers = true;
+        }
+        if overrides.check_docs {
+            self.check_docs = true;
+        }
+        if overrides.audit {
+            self.audit = true;
+        }
+        if overrides.outdated {
+            self.outdated = true;
+        }
+        if overrides.check_formatting {
+            self.check_formatting = true;
+        }
+        if overrides.run_tests {
+            self.run_tests = true;
+        }
+    }
+}
+
+/// Compiled `scan.ignore` patterns, matched against paths relative to the
+/// scan root.
+///
+/// Plain entries ignore paths with them as whole components (e.g.
+/// "node_modules", or "src/generated"); entries with glob characters are
+/// matched as globs (e.g. "*.min.js"), anchored at the root when they
+/// start with '/'.
+#[derive(Clone)]
+pub struct IgnoreRules {
+    names: Vec<Vec<String>>,
+    globs: GlobSet,
+}
+
+impl IgnoreRules {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut names = Vec::new();
+        let mut builder = GlobSetBuilder::new();
+
+        for pattern in patterns {
+            if !pattern.contains(['*', '?', '[', '{']) {
+                let components: Vec<String> = pattern
+                    .split(['/', '\\'])
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if !components.is_empty() {
+                    names.push(components);
+                }
+                continue;
+            }
+
+            // Unanchored globs may match anywhere in the tree
+            let anchored = if let Some(rooted) = pattern.strip_prefix('/') {
+                rooted.to_string()
+            } else if pattern.starts_with("**") {
+                pattern.clone()
+            } else {
+                format!("**/{}", pattern)
+            };
+            let glob = GlobBuilder::new(&anchored)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid ignore pattern '{}': {}", pattern, e))?;
+            builder.add(glob);
+        }
+
+        Ok(Self {
+            names,
+            globs: builder.build()?,
+        })
+    }
+
+    /// Whether `path`, relative to the scan root, is ignored. What the
+    /// root itself is called never counts.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let components: Vec<String> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        self.names.iter().any(|name| {
+            components
+                .windows(name.len())
+                .any(|window| window == name.as_slice())
+        }) || self.globs.is_match(path)
+    }
+}
+
+/// Compiled `[files]` language overrides
+pub struct FileLanguages {
+    globs: GlobSet,
+    languages: Vec<String>,
+}
+
+impl FileLanguages {
+    pub fn new(files: &BTreeMap<String, String>) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let mut languages = Vec::new();
+
+        // Exact paths go first so they win over globs that also match
+        let is_glob = |p: &str| p.contains(['*', '?', '[', '{']);
+        let mut entries: Vec<(&String, &String)> = files.iter().collect();
+        entries.sort_by_key(|(pattern, _)| is_glob(pattern));
+
+        for (pattern, language) in entries {
+            let glob = GlobBuilder::new(pattern.trim_start_matches("./"))
+                .literal_separator(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Invalid [files] pattern '{}': {}", pattern, e))?;
+            builder.add(glob);
+            languages.push(language.clone());
+        }
+
+        Ok(Self {
+            globs: builder.build()?,
+            languages,
+        })
+    }
+
+    /// Language declared for a path relative to the project root. An exact
+    /// path wins over globs; among globs the first in key order wins.
+    pub fn language_for(&self, relative: &Path) -> Option<&str> {
+        self.globs
+            .matches(relative)
+            .into_iter()
+            .min()
+            .map(|i| self.languages[i].as_str())
+    }
+}
+
+/// Compiled `run.entrypoints`
+pub struct Entrypoints {
+    globs: GlobSet,
+    every_file: bool,
+}
+
+impl Entrypoints {
+    pub fn new(entrypoints: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in entrypoints {
+            let glob = GlobBuilder::new(pattern.trim_start_matches("./"))
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    anyhow::anyhow!("Invalid run.entrypoints pattern '{}': {}", pattern, e)
+                })?;
+            builder.add(glob);
+        }
+        Ok(Self {
+            globs: builder.build()?,
+            every_file: entrypoints.is_empty(),
+        })
+    }
+
+    /// Whether a path relative to the project root is run
+    pub fn contains(&self, relative: &Path) -> bool {
+        self.every_file || self.globs.is_match(relative)
+    }
+}
+
 fn default_max_depth() -> usize {
     5
 }
 
+fn default_run_timeout() -> u64 {
+    10
+}
+
+fn default_max_output_mb() -> usize {
+    16
+}
+
+fn default_max_line_length() -> usize {
+    120
+}
+
+fn default_section_separator() -> String {
+    "─".to_string()
+}
+
+fn default_section_width() -> usize {
+    60
+}
+
 fn default_ignore() -> Vec<String> {
     vec![
         "node_modules".to_string(),
@@ -105,6 +1000,7 @@ fn default_ignore() -> Vec<String> {
         "dist".to_string(),
         "build".to_string(),
         ".next".to_string(),
+        ".ess".to_string(),
     ]
 }
 
@@ -114,6 +1010,33 @@ fn default_true() -> bool {
 
 #[allow(dead_code)]
 impl Config {
+    /// Names of the profiles `apply_profile` accepts
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_PROFILES.iter().map(|n| n.to_string()).collect();
+        names.extend(self.profiles.keys().cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Apply a profile. `[profiles.<name>]` settings win over those of the
+    /// built-in profile with the same name.
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let builtin = ProfileConfig::builtin(name);
+        let profile = match (builtin, self.profiles.get(name)) {
+            (Some(builtin), Some(configured)) => builtin.merged(configured),
+            (Some(builtin), None) => builtin,
+            (None, Some(configured)) => configured.clone(),
+            (None, None) => anyhow::bail!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                self.profile_names().join(", ")
+            ),
+        };
+        profile.apply(self);
+        Ok(())
+    }
+
     /// Load configuration from project directory or global config
     pub fn load(project_path: Option<&Path>) -> Result<Self> {
         // Try loading from project directory first
@@ -160,26 +1083,28 @@ impl Config {
         dirs::home_dir().map(|h| h.join(".config").join(GLOBAL_CONFIG_FILE_NAME))
     }
 
-    /// Check if a path should be ignored based on config
-    pub fn should_ignore(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
-        self.scan
-            .ignore
-            .iter()
-            .any(|ignore| path_str.contains(ignore))
+    /// Get the directory for EssentialsCode's own data (history, caches)
+    pub fn data_dir() -> Option<PathBuf> {
+        dirs::home_dir().map(|h| h.join(".config").join(DATA_DIR_NAME))
+    }
+
+    /// Get the directory of `*.rhai` rule scripts
+    pub fn scripts_dir() -> Option<PathBuf> {
+        Self::data_dir().map(|d| d.join("rules"))
     }
 
-    /// Check if a language is enabled
+    /// Check if a language is enabled. Names may be aliases, e.g. "js"
+    /// or "c++".
     pub fn is_language_enabled(&self, lang: &str) -> bool {
         let lang_lower = lang.to_lowercase();
+        let language = detect_language_from_str(lang);
+        let same = |l: &String| match language {
+            Language::Unknown => l.to_lowercase() == lang_lower,
+            _ => detect_language_from_str(l) == language,
+        };
 
         // If disabled list contains this language, it's disabled
-        if self
-            .languages
-            .disabled
-            .iter()
-            .any(|l| l.to_lowercase() == lang_lower)
-        {
+        if self.languages.disabled.iter().any(same) {
             return false;
         }
 
@@ -189,10 +1114,7 @@ impl Config {
         }
 
         // Otherwise, check if it's in the enabled list
-        self.languages
-            .enabled
-            .iter()
-            .any(|l| l.to_lowercase() == lang_lower)
+        self.languages.enabled.iter().any(same)
     }
 
     /// Generate example configuration content
@@ -201,11 +1123,16 @@ impl Config {
 # Place this file in your project root as .essentialscode.toml
 # or in ~/.config/essentialscode.toml for global settings
 
+# Profile used when --profile isn't given: "quick", "full", "ci" or one of
+# your own from [profiles.<name>]
+# profile = "quick"
+
 [scan]
 # Maximum directory depth for scanning
 max_depth = 5
 
-# Directories to ignore during scanning
+# Paths to ignore during scanning. Plain names match anywhere in the path,
+# globs like "*.min.js" or "vendor/**" are matched as globs.
 ignore = [
     "node_modules",
     ".git",
@@ -216,14 +1143,92 @@ ignore = [
     "dist",
     "build",
     ".next",
+    ".ess",
 ]
 
+# Also skip everything .gitignore, .ignore and your global git excludes
+# list, e.g. vendored code the list above doesn't cover
+respect_gitignore = true
+
+# Files the compiler, interpreter and linter checks work on at once
+# (0 = one per CPU). Set 1 if the files you run interfere with each other.
+jobs = 0
+
+# Tools (compilers, interpreters, linters) running at once, however many
+# checks want one (0 = one per CPU). Lower it on small CI containers.
+max_processes = 0
+
 # Run language-specific linters (e.g., pylint for Python)
 run_linters = true
 
 # Run files to detect runtime errors
 run_files = true
 
+# Seconds a file may run before it's stopped and reported as timed out,
+# e.g. an endless loop or a server (0 = no limit)
+run_timeout = 10
+
+# Seconds a compiler, linter or other check may take (0 = no limit)
+tool_timeout = 0
+
+# Megabytes of output kept from each tool, half from its start and half
+# from its end, so a program printing in a loop can't use up the memory
+# (0 = keep everything)
+max_output_mb = 16
+
+# Make compilers and linters print English messages (LC_ALL=C, VSLANG=1033
+# for MSVC) when your system language isn't English and errors go
+# unrecognized. Files run for runtime errors keep your locale.
+english_output = false
+
+# Exit with code 3 when checks were skipped because a tool (g++, python,
+# node, npx, cargo) is not installed, instead of only warning about it
+fail_on_missing_tools = false
+
+# Compile C++ headers (.h, .hpp, ...) on their own too, with include paths
+# guessed from where the headers live
+check_headers = false
+
+# Syntax-check the ```python, ```js and ```rust blocks of Markdown files, so
+# README examples keep working
+check_docs = false
+
+# Audit dependencies with 'cargo audit' and 'npm audit' during scans, and
+# report known vulnerabilities as errors ('ess audit' runs only this)
+audit = false
+
+# Warn about direct dependencies a major version behind their latest release
+# (cargo outdated, npm outdated, pip list --outdated), with the command to
+# upgrade each
+outdated = false
+
+# Warn about files with mixed tab/space indentation or lines longer than
+# max_line_length, and let 'ess find-bug --walk' run the formatter (rustfmt,
+# black, prettier, clang-format) on them after showing its diff
+check_formatting = false
+max_line_length = 120
+
+# Run the project's tests (pytest, cargo test or npm test, whichever the
+# project has) and report each failing test with its assertion or error
+run_tests = false
+
+# Ask the ess-plugin-<name> executables on PATH about the files ess has
+# no checks for, and about errors it doesn't recognize
+plugins = true
+
+# Run compilers, linters and files inside this container image instead of
+# on this machine, with the project mounted read-only at the same path -
+# for machines without the toolchains, or to keep untrusted code away from
+# the host. The image needs the tools, e.g. "rust:1" or "node:20".
+# docker_image = "python:3.12"
+
+# Environment variables the files run by run_files and the tests run by
+# run_tests get, e.g. settings a module needs at import time. Only those
+# runs see them; ess's own environment is left alone.
+[scan.env]
+# DJANGO_SETTINGS_MODULE = "mysite.settings"
+# API_URL = "http://localhost:8000"
+
 [languages]
 # Languages to check (empty = all supported)
 # enabled = ["python", "rust", "typescript"]
@@ -231,6 +1236,71 @@ run_files = true
 # Languages to skip
 # disabled = ["cpp"]
 
+# Python interpreter; by default the first of python3, python and "py -3"
+# that runs Python 3 is used
+# python = "/usr/bin/python3.12"
+
+[rust]
+# Feature sets to run 'cargo check' with, one run each, so errors behind
+# feature gates are found. Each entry is a comma-separated list of features;
+# "all" means --all-features and "none" --no-default-features. Errors that
+# only some sets produce are labelled with those sets.
+# features = ["default", "all", "none", "none,serde"]
+
+# Target to check for, as in 'cargo check --target'. Install it first with
+# 'rustup target add <target>'.
+# target = "wasm32-unknown-unknown"
+
+# rustup toolchain to check with, as in 'cargo +nightly check'
+# toolchain = "nightly"
+
+[run]
+# Files scan.run_files runs, by path or glob. The rest are imported
+# (Python) or only syntax-checked (JavaScript), so modules meant to be
+# imported don't fail for being run on their own. Empty runs every file.
+# entrypoints = ["app.py", "src/index.js"]
+
+# Directory the files run in, relative to the project root
+# working_dir = "src"
+
+[cpp]
+# Compiler to use; by default the first of g++ and clang++ found
+# compiler = "clang++-17"
+
+# Target to check for. Uses the <target>-g++ cross compiler if it is
+# installed, otherwise clang++ --target=<target>.
+# target = "aarch64-linux-gnu"
+
+# Extra compiler flags, e.g. a sysroot for the target or defines
+# flags = ["--sysroot=/opt/aarch64-sysroot", "-DBOARD_REV=2"]
+
+[policy]
+# Header every source file must start with. 'ess find-bug --walk' can insert
+# it, commented for the file's language.
+# license_header = "SPDX-License-Identifier: MIT"
+
+# Directories or globs where debug output (console.log, print(), println!,
+# dbg!, std::cout) is not allowed
+# no_print = ["src", "lib/**/*.py"]
+
+# JavaScript scripts (not ES modules) must start with 'use strict'
+# require_use_strict = false
+
+# Longest a source file may be, in lines
+# max_file_lines = 1000
+
+# Rules are warnings; make some of them errors. Rule IDs: license-header,
+# no-print, use-strict, max-file-length
+# [policy.severity]
+# license-header = "error"
+
+[files]
+# Language of files without a telling extension, by path or glob relative
+# to the project root. Extensionless files with a shebang
+# (#!/usr/bin/env python3) are detected without this.
+# "scripts/deploy" = "python"
+# "bin/*" = "javascript"
+
 [output]
 # Use colors in terminal output
 colors = true
@@ -240,6 +1310,113 @@ show_hints = true
 
 # Show before/after diffs in fix suggestions
 show_diffs = true
+
+# Show the ASCII banner at startup
+show_banner = true
+
+# Show section headers like "How to Fix"
+show_sections = true
+
+# Cut fix instructions after this many lines (0 = no limit)
+max_instruction_lines = 0
+
+# Diff layout: "unified" or "side-by-side" (used in terminals 100+ columns wide)
+diff_style = "unified"
+
+# Clickable file locations in terminals that support OSC 8 hyperlinks
+hyperlinks = true
+
+# Where file links point; {path}, {line} and {column} are filled in.
+# Default is "file://{path}". For VS Code:
+# hyperlink_url = "vscode://file{path}:{line}:{column}"
+
+# Scan results: "stream" (as checks find them), "grouped" (by file, at the end)
+# or "summary" (only the totals)
+view = "stream"
+
+# Order of scan results: "path" (path, line, rule - stable across machines)
+# or "none" (as found, so the stream view shows them right away)
+sort = "path"
+
+# What scans print: "text", "json", "sarif" for code-scanning tools, or
+# "rdjson" for reviewdog (ess find-bug --format rdjson | reviewdog -f=rdjson),
+# or "grep" for one line per finding (ess find-bug --format grep | fzf).
+# Anything but text prints only the report, so it can be piped or saved.
+format = "text"
+
+# Text shown instead of the ASCII banner, e.g. your team's tool name
+# banner = "Acme code check"
+
+# Lines around section headers: the character(s) repeated and how wide the
+# line is. section_separator = "" leaves the lines out.
+section_separator = "─"
+section_width = 60
+
+[redact]
+# What JSON and SARIF reports and 'ess bug --share' files leave out, so they
+# can be posted publicly. The defaults take out tokens, passwords, your home
+# directory, your user name and IP addresses.
+defaults = true
+
+# More regexes to take out, e.g. internal host names
+# patterns = ["[a-z0-9-]+\\.corp\\.example\\.com"]
+
+# Redact terminal output too (or pass --redact)
+terminal = false
+
+[hooks]
+# Commands run through the shell after a scan or a fix, e.g. to send a
+# notification. Placeholders are filled in, quoted for the shell (so don't
+# quote them yourself), and also set as ESS_ERRORS etc.
+# post_scan: {errors}, {warnings}, {fixable}, {project}, and {results}, the
+#   scan saved for 'ess replay'
+# post_fix: {fixes}, {file}, and {errors} and {warnings} left in the file
+# post_scan = "notify-send 'ess: {errors} errors'"
+# post_fix = "git add {file}"
+
+[fix]
+# Rules, by ID (as 'ess list' shows them) or name, whose fixes
+# 'ess find-bug --walk' applies without asking, that are always previewed
+# and confirmed first ('ess bug --apply' included), or that are never
+# applied.
+# auto = ["GEN-SYN-001"]
+# confirm = ["MissingInclude"]
+# never = ["ImportError"]
+
+[rules]
+# Checks to turn off, by the ID shown next to each finding (or the rule
+# name). 'ess list' shows every ID.
+# disabled = ["PY-ENV-001", "PY-NONE-002"]
+
+# Error patterns of your own, for messages ess doesn't know (e.g. from an
+# in-house framework). {name} in fix and insert is replaced with the regex
+# group of that name. With insert, 'ess find-bug --walk' can add the line
+# at the anchor: "top-of-file", "after-imports" or "at-error-line".
+# [[patterns]]
+# regex = "Setting '(?P<var>\\w+)' is not configured"
+# language = "python"
+# title = "Missing setting"
+# fix = "Add {var} to settings.toml, or import its default"
+# insert = "from app.defaults import {var}"
+# anchor = "after-imports"
+
+# Detectors compiled to WebAssembly, for errors a regex can't describe.
+# Each module exports memory, alloc(len) -> ptr, and match_error(ptr, len),
+# which gets the error text and returns 0, or (ptr << 32 | len) of JSON
+# like {"title": "...", "fix": "..."}. Modules run without access to
+# files or the network. Needs ess built with the "wasm" feature.
+# [[wasm_rules]]
+# path = "tools/ess-rules/orm.wasm"
+# language = "python"
+
+# Bundles of settings for --profile. Built in are "quick" (syntax errors
+# only, depth 3), "full" (every check) and "ci" (no colors, JSON output,
+# fail when a check couldn't run). A profile of the same name changes the
+# built-in one; settings are those of [scan] plus colors, show_banner,
+# view and format.
+# [profiles.myteam]
+# max_depth = 8
+# check_formatting = true
 "#
         .to_string()
     }
@@ -260,11 +1437,164 @@ mod tests {
     }
 
     #[test]
-    fn test_should_ignore() {
-        let config = Config::default();
-        assert!(config.should_ignore(Path::new("/project/node_modules/package")));
-        assert!(config.should_ignore(Path::new("/project/.git/config")));
-        assert!(!config.should_ignore(Path::new("/project/src/main.rs")));
+    fn test_default_ignore_rules() {
+        let rules = IgnoreRules::new(&Config::default().scan.ignore).unwrap();
+        assert!(rules.is_ignored(Path::new("node_modules/package")));
+        assert!(rules.is_ignored(Path::new(".git/config")));
+        assert!(!rules.is_ignored(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn test_ignore_rules_globs() {
+        let rules = IgnoreRules::new(&[
+            "node_modules".to_string(),
+            "*.min.js".to_string(),
+            "vendor/**".to_string(),
+        ])
+        .unwrap();
+
+        assert!(rules.is_ignored(Path::new("node_modules/x.js")));
+        assert!(rules.is_ignored(Path::new("static/app.min.js")));
+        assert!(rules.is_ignored(Path::new("vendor/lib/a.py")));
+        assert!(!rules.is_ignored(Path::new("src/app.js")));
+        assert!(!rules.is_ignored(Path::new("src/vendor.py")));
+    }
+
+    #[test]
+    fn test_ignore_rules_match_whole_components() {
+        let rules = IgnoreRules::new(&[
+            "build".to_string(),
+            "dist".to_string(),
+            "src/generated".to_string(),
+            "/docs/*.py".to_string(),
+        ])
+        .unwrap();
+
+        assert!(rules.is_ignored(Path::new("build/a.py")));
+        assert!(rules.is_ignored(Path::new("app/dist/bundle.js")));
+        assert!(rules.is_ignored(Path::new("src/generated/api.py")));
+        assert!(rules.is_ignored(Path::new("docs/conf.py")));
+        // Only what's under the scan root counts, e.g. /x/build/proj/a.py
+        assert!(!rules.is_ignored(Path::new("a.py")));
+        assert!(!rules.is_ignored(Path::new("distance.py")));
+        assert!(!rules.is_ignored(Path::new("lib/rebuild/a.py")));
+        assert!(!rules.is_ignored(Path::new("generated/api.py")));
+        assert!(!rules.is_ignored(Path::new("site/docs/conf.py")));
+    }
+
+    #[test]
+    fn test_ignore_rules_invalid_glob() {
+        assert!(IgnoreRules::new(&["[unclosed".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut scan = ScanConfig::default();
+        scan.apply_overrides(&ScanOverrides {
+            max_depth: Some(2),
+            ignore: vec!["*.gen.py".to_string()],
+            no_gitignore: true,
+            jobs: Some(1),
+            max_processes: Some(2),
+            no_linters: true,
+            no_run: true,
+            run_timeout: Some(30),
+            docker_image: Some("python:3.12".to_string()),
+            env: vec![("DEBUG".to_string(), "1".to_string())],
+            fail_on_missing_tools: true,
+            check_headers: true,
+            check_docs: true,
+            audit: true,
+            outdated: true,
+            check_formatting: true,
+            run_tests: true,
+        });
+
+        assert_eq!(scan.max_depth, 2);
+        assert!(scan.ignore.contains(&"node_modules".to_string()));
+        assert!(scan.ignore.contains(&"*.gen.py".to_string()));
+        assert!(!scan.respect_gitignore);
+        assert_eq!(scan.jobs, 1);
+        assert_eq!(scan.max_processes, 2);
+        assert!(!scan.run_linters);
+        assert!(!scan.run_files);
+        assert_eq!(scan.run_timeout, 30);
+        assert_eq!(scan.docker_image.as_deref(), Some("python:3.12"));
+        assert_eq!(scan.env.get("DEBUG").map(String::as_str), Some("1"));
+        assert!(scan.fail_on_missing_tools);
+        assert!(scan.check_headers);
+        assert!(scan.check_docs);
+        assert!(scan.audit);
+        assert!(scan.outdated);
+        assert!(scan.check_formatting);
+        assert!(scan.run_tests);
+        assert_eq!(scan.max_line_length, 120);
+    }
+
+    #[test]
+    fn test_apply_empty_overrides_keeps_config() {
+        let mut scan = ScanConfig::default();
+        scan.apply_overrides(&ScanOverrides::default());
+
+        assert_eq!(scan.max_depth, 5);
+        assert!(scan.run_linters);
+        assert!(scan.run_files);
+    }
+
+    #[test]
+    fn test_output_overrides() {
+        let mut output = OutputConfig::default();
+        output.apply_overrides(&OutputOverrides {
+            no_banner: true,
+            no_hints: true,
+            max_instruction_lines: Some(3),
+            view: Some(OutputView::Grouped),
+            sort: Some(SortOrder::None),
+            ..Default::default()
+        });
+
+        assert!(!output.show_banner);
+        assert!(!output.show_hints);
+        assert!(output.show_diffs);
+        assert!(output.show_sections);
+        assert_eq!(output.max_instruction_lines, 3);
+        assert_eq!(output.view, OutputView::Grouped);
+        assert_eq!(output.sort, SortOrder::None);
+    }
+
+    #[test]
+    fn test_output_config_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[output]
+show_diffs = false
+show_banner = false
+max_instruction_lines = 5
+diff_style = "side-by-side"
+hyperlink_url = "vscode://file{path}:{line}"
+view = "grouped"
+banner = "Acme checks"
+section_separator = "="
+"#,
+        )
+        .unwrap();
+
+        assert!(!config.output.show_diffs);
+        assert!(!config.output.show_banner);
+        assert!(config.output.show_hints);
+        assert!(config.output.show_sections);
+        assert_eq!(config.output.max_instruction_lines, 5);
+        assert_eq!(config.output.diff_style, DiffStyle::SideBySide);
+        assert!(config.output.hyperlinks);
+        assert_eq!(
+            config.output.hyperlink_url.as_deref(),
+            Some("vscode://file{path}:{line}")
+        );
+        assert_eq!(config.output.view, OutputView::Grouped);
+        assert_eq!(config.output.sort, SortOrder::Path);
+        assert_eq!(config.output.banner.as_deref(), Some("Acme checks"));
+        assert_eq!(config.output.section_separator, "=");
+        assert_eq!(config.output.section_width, 60);
     }
 
     #[test]
@@ -292,6 +1622,7 @@ mod tests {
 
         assert!(config.is_language_enabled("python"));
         assert!(!config.is_language_enabled("cpp"));
+        assert!(!config.is_language_enabled("C++"));
     }
 
     #[test]
@@ -339,6 +1670,181 @@ enabled = ["python"]
         let _ = std::fs::remove_dir_all(&temp_dir);
     }
 
+    #[test]
+    fn test_patterns() {
+        let config: Config = toml::from_str(
+            r#"
+[[patterns]]
+regex = "Setting '(?P<var>\\w+)' is not configured"
+title = "Missing setting"
+fix = "Add {var} to settings.toml"
+insert = "from app.defaults import {var}"
+
+[[patterns]]
+regex = "E42"
+language = "rust"
+title = "E42"
+fix = "See the wiki"
+anchor = "at-error-line"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.patterns.len(), 2);
+        assert_eq!(config.patterns[0].anchor, Anchor::AfterImports);
+        assert_eq!(config.patterns[1].anchor, Anchor::AtErrorLine);
+        assert!(config.patterns[1].insert.is_none());
+        assert!(toml::from_str::<Config>(
+            "[[patterns]]\nregex = \"x\"\ntitle = \"x\"\nfix = \"x\"\nanchor = \"bottom\"\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_policy_severity() {
+        let config: Config = toml::from_str(
+            r#"
+[policy]
+max_file_lines = 500
+
+[policy.severity]
+max-file-length = "error"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.policy.max_file_lines, Some(500));
+        assert_eq!(
+            config.policy.severity.get("max-file-length"),
+            Some(&Severity::Error)
+        );
+        assert!(toml::from_str::<Config>(
+            "[policy.severity]
+no-print = \"fatal\"\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_file_languages() {
+        let config: Config = toml::from_str(
+            r#"
+[files]
+"scripts/deploy" = "python"
+"bin/*" = "javascript"
+"bin/legacy" = "bash"
+"#,
+        )
+        .unwrap();
+        let files = FileLanguages::new(&config.files).unwrap();
+
+        assert_eq!(
+            files.language_for(Path::new("scripts/deploy")),
+            Some("python")
+        );
+        assert_eq!(
+            files.language_for(Path::new("bin/serve")),
+            Some("javascript")
+        );
+        assert_eq!(files.language_for(Path::new("bin/legacy")), Some("bash"));
+        assert_eq!(files.language_for(Path::new("bin/sub/serve")), None);
+        assert_eq!(files.language_for(Path::new("deploy")), None);
+    }
+
+    #[test]
+    fn test_entrypoints() {
+        let config: Config = toml::from_str(
+            r#"
+[run]
+entrypoints = ["./app.py", "bin/*.js"]
+"#,
+        )
+        .unwrap();
+        let entrypoints = Entrypoints::new(&config.run.entrypoints).unwrap();
+
+        assert!(entrypoints.contains(Path::new("app.py")));
+        assert!(entrypoints.contains(Path::new("bin/serve.js")));
+        assert!(!entrypoints.contains(Path::new("pkg/app.py")));
+        assert!(!entrypoints.contains(Path::new("bin/lib/util.js")));
+
+        let every = Entrypoints::new(&[]).unwrap();
+        assert!(every.contains(Path::new("pkg/models.py")));
+        assert!(Entrypoints::new(&["src/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_rust_feature_sets() {
+        let mut rust = RustConfig::default();
+        assert_eq!(rust.feature_sets(), vec!["default"]);
+
+        let config: Config =
+            toml::from_str("[rust]\nfeatures = [\"all\", \"none,serde\"]\n").unwrap();
+        assert_eq!(config.rust.feature_sets(), vec!["all", "none,serde"]);
+
+        rust.apply_overrides(&RustOverrides {
+            features: vec!["none".to_string()],
+            target: Some("wasm32-unknown-unknown".to_string()),
+        });
+        assert_eq!(rust.feature_sets(), vec!["none"]);
+        assert_eq!(rust.target.as_deref(), Some("wasm32-unknown-unknown"));
+    }
+
+    #[test]
+    fn test_builtin_profiles() {
+        let mut quick = Config::default();
+        quick.scan.audit = true;
+        quick.apply_profile("quick").unwrap();
+        assert_eq!(quick.scan.max_depth, 3);
+        assert!(!quick.scan.run_linters);
+        assert!(!quick.scan.run_files);
+        assert!(!quick.scan.audit);
+
+        let mut ci = Config::default();
+        ci.apply_profile("ci").unwrap();
+        assert!(!ci.output.colors);
+        assert_eq!(ci.output.format, ReportFormat::Json);
+        assert!(ci.scan.fail_on_missing_tools);
+        // Settings the profile doesn't mention are kept
+        assert!(ci.scan.run_linters);
+    }
+
+    #[test]
+    fn test_configured_profiles() {
+        let mut config: Config = toml::from_str(
+            r#"
+profile = "myteam"
+
+[profiles.myteam]
+max_depth = 8
+check_formatting = true
+
+[profiles.ci]
+format = "sarif"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.profile.as_deref(), Some("myteam"));
+        assert_eq!(
+            config.profile_names(),
+            vec!["ci", "full", "myteam", "quick"]
+        );
+
+        config.apply_profile("ci").unwrap();
+        // Overridden, and the rest of the built-in "ci" kept
+        assert_eq!(config.output.format, ReportFormat::Sarif);
+        assert!(!config.output.colors);
+
+        config.apply_profile("myteam").unwrap();
+        assert_eq!(config.scan.max_depth, 8);
+        assert!(config.scan.check_formatting);
+
+        let error = config.apply_profile("nightly").unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Unknown profile 'nightly' (available: ci, full, myteam, quick)"
+        );
+    }
+
     #[test]
     fn test_example_config_is_valid_toml() {
         let example = Config::example_config();
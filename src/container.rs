@@ -0,0 +1,193 @@
+use crate::config::CppConfig;
+use crate::tools::{self, CppCompiler, CppToolchain, Interpreter};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::OnceLock;
+
+/// Where tools that write next to the sources write instead, as the
+/// project is mounted read-only
+const WRITABLE_ENV: &[(&str, &str)] = &[
+    ("CARGO_TARGET_DIR", "/tmp/ess-target"),
+    ("PYTHONPYCACHEPREFIX", "/tmp/ess-pycache"),
+];
+
+/// A container image the checks run in, for `scan.docker_image`. The
+/// project is mounted read-only at the same path, so file paths in tool
+/// output are the same as on the host.
+pub struct Container {
+    image: String,
+    root: PathBuf,
+    python: OnceLock<Option<Interpreter>>,
+    cpp: OnceLock<Option<CppToolchain>>,
+}
+
+impl Container {
+    pub fn new(image: &str, root: &Path) -> Self {
+        Self {
+            image: image.to_string(),
+            root: root.to_path_buf(),
+            python: OnceLock::new(),
+            cpp: OnceLock::new(),
+        }
+    }
+
+    pub fn image(&self) -> &str {
+        &self.image
+    }
+
+    /// Whether docker is installed and its daemon is running
+    pub fn available() -> bool {
+        Command::new("docker")
+            .args(["info", "--format", "{{.ServerVersion}}"])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// `cmd` as a `docker run` of the image, with its working directory,
+    /// environment and the files it names outside the project
+    pub fn wrap(&self, cmd: &Command) -> Command {
+        let mut docker = Command::new("docker");
+        // -i passes on what the tool reads from stdin
+        docker.args(["run", "--rm", "-i"]);
+        docker.arg("-v").arg(mount(&self.root));
+
+        // Files ess extracted to the temp directory, e.g. Markdown examples
+        let mut extra = BTreeSet::new();
+        for arg in cmd.get_args().map(Path::new) {
+            if arg.is_absolute() && !arg.starts_with(&self.root) && arg.exists() {
+                if let Some(dir) = arg.parent() {
+                    extra.insert(dir.to_path_buf());
+                }
+            }
+        }
+        for dir in extra {
+            docker.arg("-v").arg(mount(&dir));
+        }
+
+        let dir = cmd.get_current_dir().unwrap_or(&self.root);
+        docker.arg("-w").arg(dir);
+        for (key, value) in WRITABLE_ENV {
+            docker.arg("-e").arg(format!("{}={}", key, value));
+        }
+        for (key, value) in cmd.get_envs() {
+            if let Some(value) = value {
+                let mut pair = key.to_os_string();
+                pair.push("=");
+                pair.push(value);
+                docker.arg("-e").arg(pair);
+            }
+        }
+
+        docker.arg(&self.image);
+        docker.arg(cmd.get_program());
+        docker.args(cmd.get_args());
+        docker
+    }
+
+    /// Whether docker failed to start the program because the image
+    /// doesn't have it
+    pub fn is_not_found(output: &Output) -> bool {
+        output.status.code() == Some(127)
+            && String::from_utf8_lossy(&output.stderr).contains("executable file not found")
+    }
+
+    /// The first Python 3 candidate the image has
+    pub fn python(&self, configured: Option<&str>) -> Option<Interpreter> {
+        self.python
+            .get_or_init(|| {
+                tools::python_candidates(configured)
+                    .into_iter()
+                    .find(|python| {
+                        tools::version(&mut self.wrap(&python.command()))
+                            .is_some_and(|v| v.starts_with("Python 3"))
+                    })
+            })
+            .clone()
+    }
+
+    /// The first C++ compiler candidate the image has. Images are Linux,
+    /// so MSVC is never one.
+    pub fn cpp_compiler(&self, config: &CppConfig) -> Option<CppToolchain> {
+        self.cpp
+            .get_or_init(|| {
+                tools::cpp_candidates(config)
+                    .into_iter()
+                    .filter(|toolchain| toolchain.compiler != CppCompiler::Msvc)
+                    .find(|toolchain| {
+                        tools::version(&mut self.wrap(&Command::new(&toolchain.program))).is_some()
+                    })
+            })
+            .clone()
+    }
+}
+
+/// A read-only bind mount of `dir` at the same path
+fn mount(dir: &Path) -> String {
+    format!("{}:{}:ro", dir.display(), dir.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_wrap_runs_in_image() {
+        let container = Container::new("rust:1", Path::new("/work/app"));
+        let mut cmd = Command::new("cargo");
+        cmd.current_dir("/work/app/crate")
+            .env("RUSTFLAGS", "-D warnings")
+            .args(["check", "--message-format=json"]);
+        let wrapped = container.wrap(&cmd);
+
+        assert_eq!(wrapped.get_program(), "docker");
+        assert_eq!(
+            args(&wrapped),
+            vec![
+                "run",
+                "--rm",
+                "-i",
+                "-v",
+                "/work/app:/work/app:ro",
+                "-w",
+                "/work/app/crate",
+                "-e",
+                "CARGO_TARGET_DIR=/tmp/ess-target",
+                "-e",
+                "PYTHONPYCACHEPREFIX=/tmp/ess-pycache",
+                "-e",
+                "RUSTFLAGS=-D warnings",
+                "rust:1",
+                "cargo",
+                "check",
+                "--message-format=json",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wrap_mounts_files_outside_project() {
+        let temp_dir = std::env::temp_dir().join("ess_test_container");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let block = temp_dir.join("block_1.py");
+        std::fs::write(&block, "print(1)\n").unwrap();
+
+        let container = Container::new("python:3.12", Path::new("/work/app"));
+        let mut cmd = Command::new("python3");
+        cmd.args(["-m", "py_compile"]).arg(&block);
+        let wrapped = args(&container.wrap(&cmd));
+
+        assert!(wrapped.contains(&format!("{}:{}:ro", temp_dir.display(), temp_dir.display())));
+        assert_eq!(wrapped[wrapped.len() - 1], block.display().to_string());
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
@@ -0,0 +1,128 @@
+//! What happens when ess itself has a bug: instead of a raw Rust panic, a
+//! short message and a report file with what's needed to fix it
+
+use crate::config::RedactConfig;
+use crate::history;
+use crate::redact::Redactor;
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+const ISSUES_URL: &str = "https://github.com/Jakubeq33/EssentialsCode/issues";
+
+/// Exit status when stdout is closed early, as a shell gives a process
+/// killed by SIGPIPE (128 + 13)
+const BROKEN_PIPE: i32 = 141;
+
+/// Replace the panic message with a crash report, and exit with
+/// `exit_code` once one is written. With RUST_BACKTRACE set, the usual
+/// panic output is printed as well.
+pub fn install(exit_code: i32) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+
+        // `ess ... | head` closing the pipe early isn't a bug, nor a failure
+        if message.contains("failed printing to stdout") {
+            std::process::exit(BROKEN_PIPE);
+        }
+        if std::env::var_os("RUST_BACKTRACE").is_some() {
+            default_hook(info);
+        }
+
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string();
+        let report = report(
+            &message,
+            location.as_deref(),
+            &thread,
+            &Backtrace::force_capture().to_string(),
+        );
+
+        eprintln!();
+        eprintln!("Error: ess ran into a bug of its own and had to stop. Sorry!");
+        match save(&report) {
+            Some(path) => {
+                eprintln!("A crash report was saved to {}", path.display());
+                eprintln!(
+                    "Please attach it to a new issue at {} so it can be fixed",
+                    ISSUES_URL
+                );
+            }
+            None => eprintln!("{}", report),
+        }
+        std::process::exit(exit_code);
+    }));
+}
+
+/// The crash report: versions, the command line and where it happened.
+/// Secrets in the command line are redacted.
+pub fn report(message: &str, location: Option<&str>, thread: &str, backtrace: &str) -> String {
+    let args: Vec<String> = std::env::args().collect();
+    let command = args.join(" ");
+    let command = match Redactor::new(&RedactConfig::default()) {
+        Ok(redactor) => redactor.apply(&command),
+        Err(_) => command,
+    };
+    format!(
+        "EssentialsCode crash report\n\
+        \n\
+        Version:  {}\n\
+        OS:       {} {}\n\
+        Command:  {}\n\
+        Thread:   {}\n\
+        Panic:    {}\n\
+        Location: {}\n\
+        \n\
+        Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        command,
+        thread,
+        message,
+        location.unwrap_or("unknown"),
+        backtrace
+    )
+}
+
+/// Write the report to the temp dir. None when it can't be written.
+fn save(report: &str) -> Option<PathBuf> {
+    let path = std::env::temp_dir().join(format!(
+        "ess-crash-{}-{}.txt",
+        history::now(),
+        std::process::id()
+    ));
+    std::fs::write(&path, report).ok().map(|_| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let report = report(
+            "index out of bounds",
+            Some("src/scanner.rs:10:5"),
+            "main",
+            "   0: ess::main",
+        );
+
+        assert!(report.starts_with("EssentialsCode crash report\n"));
+        assert!(report.contains(&format!("Version:  {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains(&format!("OS:       {} ", std::env::consts::OS)));
+        assert!(report.contains("Panic:    index out of bounds\n"));
+        assert!(report.contains("Location: src/scanner.rs:10:5\n"));
+        assert!(report.ends_with("Backtrace:\n   0: ess::main\n"));
+    }
+}
@@ -0,0 +1,404 @@
+//! `ess daemon` keeps what scans learn between runs: file hashes, the
+//! tools found on this machine, and each project's last results. It
+//! listens on a Unix socket in the data directory, and
+//! `ess find-bug --daemon` asks it instead of scanning when it runs. It's
+//! opt-in: the daemon scans with its own environment, and only notices
+//! changes to project files, not to installed packages. A project with no file
+//! changed since its last scan gets those results right away; one with
+//! changes is scanned again, without looking for the tools again.
+
+use crate::config::{Config, IgnoreRules};
+use crate::scanner::{self, ScanOptions, ScanSummary};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const SOCKET_FILE_NAME: &str = "daemon.sock";
+
+/// Version of ess the daemon and its clients must both be
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Where the daemon listens
+pub fn socket_path() -> Option<PathBuf> {
+    Config::data_dir().map(|dir| dir.join(SOCKET_FILE_NAME))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "lowercase")]
+enum Request {
+    Scan {
+        version: String,
+        root: PathBuf,
+        config: Box<Config>,
+        lang: Option<String>,
+    },
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "lowercase")]
+enum Response {
+    Scanned {
+        summary: Box<ScanSummary>,
+        /// Whether no file changed since the scan the results are from
+        cached: bool,
+    },
+    Stopped,
+    Failed {
+        error: String,
+    },
+}
+
+/// A file's content hash, and the size and modification time it was
+/// taken at
+struct FileHash {
+    len: u64,
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+/// A project's last scan, and the files it saw
+struct LastResult {
+    files: BTreeMap<PathBuf, u64>,
+    summary: ScanSummary,
+}
+
+/// What the daemon remembers between requests
+#[derive(Default)]
+struct Cache {
+    hashes: HashMap<PathBuf, FileHash>,
+    /// By project and settings
+    scans: HashMap<String, LastResult>,
+}
+
+impl Cache {
+    /// Hash of a file's content, read again only when its size or
+    /// modification time changed
+    fn hash(&mut self, path: &Path) -> Option<u64> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let (len, modified) = (metadata.len(), metadata.modified().ok());
+        if let Some(known) = self.hashes.get(path) {
+            if known.len == len && known.modified == modified {
+                return Some(known.hash);
+            }
+        }
+        let content = std::fs::read(path).ok()?;
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+        self.hashes.insert(
+            path.to_path_buf(),
+            FileHash {
+                len,
+                modified,
+                hash,
+            },
+        );
+        Some(hash)
+    }
+
+    /// Hashes of the files a scan of `root` looks at
+    fn project_files(&mut self, root: &Path, config: &Config) -> Result<BTreeMap<PathBuf, u64>> {
+        let ignore = IgnoreRules::new(&config.scan.ignore)?;
+        Ok(scanner::walk(root, &config.scan, &ignore)
+            .filter_map(|path| {
+                let hash = self.hash(&path)?;
+                Some((path, hash))
+            })
+            .collect())
+    }
+
+    /// Results for `root`, and whether they are from the last scan
+    fn scan(
+        &mut self,
+        root: &Path,
+        config: &Config,
+        lang: Option<String>,
+    ) -> Result<(ScanSummary, bool)> {
+        let key = serde_json::to_string(&(root, config, &lang))?;
+        let files = self.project_files(root, config)?;
+        if let Some(last) = self.scans.get(&key) {
+            if last.files == files {
+                return Ok((last.summary.clone(), true));
+            }
+        }
+
+        let options = ScanOptions {
+            lang,
+            quiet: true,
+            ..Default::default()
+        };
+        let summary = scanner::scan_project(root, config, &options)?;
+        self.scans.insert(
+            key,
+            LastResult {
+                files,
+                summary: summary.clone(),
+            },
+        );
+        Ok((summary, false))
+    }
+
+    fn answer(&mut self, request: Request) -> Response {
+        match request {
+            Request::Stop => Response::Stopped,
+            Request::Scan { version, .. } if version != VERSION => Response::Failed {
+                error: format!(
+                    "the daemon is ess {} and this is ess {}; restart it",
+                    VERSION, version
+                ),
+            },
+            Request::Scan {
+                root, config, lang, ..
+            } => match self.scan(&root, &config, lang) {
+                Ok((summary, cached)) => Response::Scanned {
+                    summary: Box::new(summary),
+                    cached,
+                },
+                Err(e) => Response::Failed {
+                    error: format!("{:#}", e),
+                },
+            },
+        }
+    }
+}
+
+/// Scan results for `root` from the running daemon, and whether no file
+/// changed since it last scanned it. `None` when no daemon is running.
+pub fn scan(
+    root: &Path,
+    config: &Config,
+    lang: Option<String>,
+) -> Option<Result<(ScanSummary, bool)>> {
+    let request = Request::Scan {
+        version: VERSION.to_string(),
+        root: root.to_path_buf(),
+        config: Box::new(config.clone()),
+        lang,
+    };
+    let response = unix::request(&socket_path()?, &request)?;
+    Some(response.and_then(|response| match response {
+        Response::Scanned { summary, cached } => Ok((*summary, cached)),
+        Response::Failed { error } => Err(anyhow::anyhow!(error)),
+        Response::Stopped => Err(anyhow::anyhow!("the daemon stopped")),
+    }))
+}
+
+/// Stop the running daemon. False when none was running.
+pub fn stop() -> Result<bool> {
+    let Some(path) = socket_path() else {
+        return Ok(false);
+    };
+    match unix::request(&path, &Request::Stop) {
+        Some(response) => response.map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// Answer requests until asked to stop
+pub fn serve() -> Result<()> {
+    let path = socket_path()
+        .ok_or_else(|| anyhow::anyhow!("No home directory to put the daemon's socket in"))?;
+    unix::serve(&path)
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{Cache, Request, Response};
+    use crate::ui;
+    use anyhow::{bail, Result};
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::time::Instant;
+
+    fn send<T: serde::Serialize>(mut stream: &UnixStream, message: &T) -> Result<()> {
+        serde_json::to_writer(stream, message)?;
+        stream.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn receive<T: serde::de::DeserializeOwned>(stream: &UnixStream) -> Result<T> {
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// The daemon's answer, or `None` when nothing listens at `path`
+    pub(super) fn request(path: &Path, request: &Request) -> Option<Result<Response>> {
+        let stream = UnixStream::connect(path).ok()?;
+        Some(send(&stream, request).and_then(|_| receive(&stream)))
+    }
+
+    pub(super) fn serve(path: &Path) -> Result<()> {
+        if UnixStream::connect(path).is_ok() {
+            bail!("ess daemon is already running ({})", path.display());
+        }
+        // Left behind by a daemon that was killed
+        let _ = std::fs::remove_file(path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        ui::print_success(&format!("ess daemon listening on {}", path.display()));
+        ui::print_hint("find-bug asks it for results now; stop it with 'ess daemon --stop'");
+
+        let mut cache = Cache::default();
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let started = Instant::now();
+            let request = match receive::<Request>(&stream) {
+                Ok(request) => request,
+                // Connected to check whether the daemon runs
+                Err(e)
+                    if e.downcast_ref::<serde_json::Error>()
+                        .is_some_and(|e| e.is_eof()) =>
+                {
+                    continue
+                }
+                Err(e) => {
+                    ui::print_warning(&format!("Bad request: {}", e));
+                    continue;
+                }
+            };
+            let root = match &request {
+                Request::Scan { root, .. } => Some(root.clone()),
+                Request::Stop => None,
+            };
+            let response = cache.answer(request);
+            match (&response, root) {
+                (Response::Scanned { summary, cached }, Some(root)) => {
+                    ui::print_info(&format!(
+                        "{}: {} finding(s), {} in {:.1?}",
+                        root.display(),
+                        summary.findings.len(),
+                        if *cached { "unchanged" } else { "scanned" },
+                        started.elapsed()
+                    ));
+                }
+                (Response::Failed { error }, _) => ui::print_warning(error),
+                _ => {}
+            }
+            let stopped = matches!(response, Response::Stopped);
+            if let Err(e) = send(&stream, &response) {
+                ui::print_warning(&format!("Could not answer: {}", e));
+            }
+            if stopped {
+                break;
+            }
+        }
+        let _ = std::fs::remove_file(path);
+        ui::print_info("ess daemon stopped");
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod unix {
+    use super::{Request, Response};
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    pub(super) fn request(_path: &Path, _request: &Request) -> Option<Result<Response>> {
+        None
+    }
+
+    pub(super) fn serve(_path: &Path) -> Result<()> {
+        bail!("ess daemon needs Unix domain sockets, which this system doesn't have")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str) -> (PathBuf, Config) {
+        let temp_dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("big.js"), "var a;\nvar b;\nvar c;\n").unwrap();
+        let mut config = Config::default();
+        config.scan.run_linters = false;
+        config.scan.run_files = false;
+        config.scan.plugins = false;
+        config.policy.max_file_lines = Some(2);
+        (scanner::normalize_path(&temp_dir), config)
+    }
+
+    #[test]
+    fn test_cache_reuses_results_until_a_file_changes() {
+        let (root, config) = project("ess_test_daemon_cache");
+        let mut cache = Cache::default();
+
+        let (first, cached) = cache.scan(&root, &config, None).unwrap();
+        assert!(!cached);
+        assert_eq!(first.findings.len(), 1);
+        let (second, cached) = cache.scan(&root, &config, None).unwrap();
+        assert!(cached);
+        assert_eq!(second.findings.len(), 1);
+
+        // Other settings are another scan
+        let mut strict = config.clone();
+        strict.policy.require_use_strict = true;
+        let (strict_scan, cached) = cache.scan(&root, &strict, None).unwrap();
+        assert!(!cached);
+        assert_eq!(strict_scan.findings.len(), 2);
+
+        std::fs::write(root.join("big.js"), "var a;\n").unwrap();
+        let (fixed, cached) = cache.scan(&root, &config, None).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(!cached);
+        assert!(fixed.findings.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_daemon_answers_over_its_socket() {
+        let (root, config) = project("ess_test_daemon_socket");
+        let socket = root.join("test.sock");
+        let server = {
+            let socket = socket.clone();
+            std::thread::spawn(move || unix::serve(&socket))
+        };
+        while std::os::unix::net::UnixStream::connect(&socket).is_err() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let scan = |version: &str| {
+            let request = Request::Scan {
+                version: version.to_string(),
+                root: root.clone(),
+                config: Box::new(config.clone()),
+                lang: None,
+            };
+            unix::request(&socket, &request).unwrap().unwrap()
+        };
+        let first = scan(VERSION);
+        let second = scan(VERSION);
+        let old_client = scan("0.0.1");
+        let stopped = unix::request(&socket, &Request::Stop).unwrap().unwrap();
+        server.join().unwrap().unwrap();
+        let socket_left = socket.exists();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert!(matches!(first, Response::Scanned { cached: false, .. }));
+        assert!(matches!(
+            second,
+            Response::Scanned { cached: true, ref summary } if summary.findings.len() == 1
+        ));
+        assert!(matches!(old_client, Response::Failed { .. }));
+        assert!(matches!(stopped, Response::Stopped));
+        assert!(!socket_left);
+    }
+}
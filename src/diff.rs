@@ -0,0 +1,171 @@
+use similar::{ChangeTag, TextDiff};
+
+/// Unchanged lines kept around each change
+const CONTEXT_LINES: usize = 2;
+
+/// Part of a changed line; `emphasized` parts are the tokens that changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    pub emphasized: bool,
+}
+
+/// One line of a diff
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    Equal(String),
+    Removed(Vec<Segment>),
+    Added(Vec<Segment>),
+    /// Unchanged lines left out between two changes
+    Gap,
+}
+
+/// Line diff of two texts with intra-line changes marked
+pub fn unified(before: &str, after: &str) -> Vec<Line> {
+    let diff = TextDiff::from_lines(before, after);
+    let mut lines = Vec::new();
+
+    for (i, group) in diff.grouped_ops(CONTEXT_LINES).iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::Gap);
+        }
+
+        for op in group {
+            for change in diff.iter_inline_changes(op) {
+                let segments: Vec<Segment> = change
+                    .iter_strings_lossy()
+                    .map(|(emphasized, text)| Segment {
+                        text: text.trim_end_matches(['\r', '\n']).to_string(),
+                        emphasized,
+                    })
+                    .filter(|s| !s.text.is_empty())
+                    .collect();
+
+                lines.push(match change.tag() {
+                    ChangeTag::Equal => {
+                        Line::Equal(segments.into_iter().map(|s| s.text).collect::<String>())
+                    }
+                    ChangeTag::Delete => Line::Removed(segments),
+                    ChangeTag::Insert => Line::Added(segments),
+                });
+            }
+        }
+    }
+
+    lines
+}
+
+/// Pair diff lines into (before, after) rows: unchanged lines on both
+/// sides, removed lines next to the lines that replaced them
+pub fn side_by_side(lines: &[Line]) -> Vec<(Option<Line>, Option<Line>)> {
+    let mut rows = Vec::new();
+    let mut removed: Vec<Line> = Vec::new();
+    let mut added: Vec<Line> = Vec::new();
+
+    let flush = |rows: &mut Vec<_>, removed: &mut Vec<Line>, added: &mut Vec<Line>| {
+        let count = removed.len().max(added.len());
+        let mut removed = removed.drain(..);
+        let mut added = added.drain(..);
+        for _ in 0..count {
+            rows.push((removed.next(), added.next()));
+        }
+    };
+
+    for line in lines {
+        match line {
+            Line::Removed(_) => {
+                if !added.is_empty() {
+                    flush(&mut rows, &mut removed, &mut added);
+                }
+                removed.push(line.clone());
+            }
+            Line::Added(_) => added.push(line.clone()),
+            Line::Equal(_) | Line::Gap => {
+                flush(&mut rows, &mut removed, &mut added);
+                rows.push((Some(line.clone()), Some(line.clone())));
+            }
+        }
+    }
+    flush(&mut rows, &mut removed, &mut added);
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        match line {
+            Line::Equal(text) => text.clone(),
+            Line::Removed(segments) | Line::Added(segments) => {
+                segments.iter().map(|s| s.text.as_str()).collect()
+            }
+            Line::Gap => String::new(),
+        }
+    }
+
+    fn emphasized(line: &Line) -> Vec<String> {
+        match line {
+            Line::Removed(segments) | Line::Added(segments) => segments
+                .iter()
+                .filter(|s| s.emphasized)
+                .map(|s| s.text.clone())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_identical_texts_have_no_diff() {
+        assert!(unified("a\nb\n", "a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn test_intra_line_change() {
+        let lines = unified("value = data[\"key\"]\n", "value = data.get(\"key\")\n");
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(text(&lines[0]), "value = data[\"key\"]");
+        assert_eq!(text(&lines[1]), "value = data.get(\"key\")");
+        // Only the changed tokens are emphasized, not the whole line
+        assert!(!emphasized(&lines[0]).iter().any(|t| t.contains("value")));
+        assert!(!emphasized(&lines[1]).is_empty());
+    }
+
+    #[test]
+    fn test_insertion_keeps_context() {
+        let before = "#include <iostream>\nint main() {\n    return 0;\n}\n";
+        let after = "#include <iostream>\n#include <vector>\nint main() {\n    return 0;\n}\n";
+        let lines = unified(before, after);
+
+        assert_eq!(lines[0], Line::Equal("#include <iostream>".to_string()));
+        assert!(matches!(&lines[1], Line::Added(_)));
+        assert_eq!(text(&lines[1]), "#include <vector>");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_distant_changes_are_split_by_a_gap() {
+        let before: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let after = before
+            .replace("line 2\n", "line two\n")
+            .replace("line 19\n", "line nineteen\n");
+        let lines = unified(&before, &after);
+
+        assert_eq!(lines.iter().filter(|l| **l == Line::Gap).count(), 1);
+    }
+
+    #[test]
+    fn test_side_by_side_pairs_replacements() {
+        let lines = unified("a\nold\nz\n", "a\nnew\nextra\nz\n");
+        let rows = side_by_side(&lines);
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].0, Some(Line::Equal("a".to_string())));
+        assert_eq!(text(rows[1].0.as_ref().unwrap()), "old");
+        assert_eq!(text(rows[1].1.as_ref().unwrap()), "new");
+        assert!(rows[2].0.is_none());
+        assert_eq!(text(rows[2].1.as_ref().unwrap()), "extra");
+    }
+}
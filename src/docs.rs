@@ -0,0 +1,272 @@
+//! `ess docs <rule>`: the long-form write-up of an error kind from the
+//! knowledge base, in the terminal or (`--web`) as an HTML page
+
+use crate::highlight;
+use crate::knowledge::{self, Doc, Entry, Example};
+use crate::parser;
+use crate::ui;
+use anyhow::{anyhow, Result};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Names other tools use for a kind
+const ALIASES: &[(&str, &str)] = &[
+    ("nameerror", "UndeclaredVariable"),
+    ("referenceerror", "UndeclaredVariable"),
+    ("modulenotfounderror", "ImportError"),
+    ("nullpointerexception", "NullPointer"),
+];
+
+/// Only letters and digits, lowercased: "Key-Error" and "keyerror" match
+fn normalize(id: &str) -> String {
+    id.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The knowledge entry a rule ID names: a stable ID like `PY-KEY-001`, or
+/// a kind's name. Names are matched loosely: case and punctuation don't
+/// matter, a language prefix like `PY-` is dropped and the trailing
+/// "Error" may be left out.
+pub fn resolve(id: &str) -> Option<&'static Entry> {
+    if let Some(entry) = parser::rule_kind(id).and_then(knowledge::lookup) {
+        return Some(entry);
+    }
+    let unprefixed = id.split_once('-').map(|(_, rest)| rest);
+    [Some(id), unprefixed]
+        .into_iter()
+        .flatten()
+        .map(normalize)
+        .find_map(|wanted| {
+            let kind = ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == wanted)
+                .map(|(_, kind)| *kind);
+            knowledge::entries().iter().find(|entry| {
+                let name = normalize(entry.kind);
+                kind == Some(entry.kind) || name == wanted || name == format!("{}error", wanted)
+            })
+        })
+}
+
+/// The write-up's example first, then the snippets `ess bug` shows
+fn examples(doc: &'static Doc) -> Vec<&'static Example> {
+    doc.example
+        .iter()
+        .chain(knowledge::examples(doc.kind))
+        .collect()
+}
+
+/// Print the write-up for `entry`
+pub fn show(entry: &Entry) {
+    ui::print_section(&format!("{} — {}", entry.kind, entry.meaning));
+    let Some(doc) = knowledge::docs(entry.kind) else {
+        println!();
+        ui::print_fix_instruction(entry.fix);
+        return;
+    };
+
+    ui::print_section("Why It Happens");
+    println!();
+    println!("  {}", entry.explanation);
+    println!();
+    for cause in doc.causes {
+        println!("  • {}", cause);
+    }
+
+    ui::print_section("How to Fix");
+    println!();
+    println!("  {}", entry.fix);
+    println!();
+    for (i, strategy) in doc.strategies.iter().enumerate() {
+        println!("  {}. {}", i + 1, strategy);
+    }
+
+    if !doc.pitfalls.is_empty() {
+        ui::print_section("Pitfalls");
+        println!();
+        for pitfall in doc.pitfalls {
+            ui::print_warning(pitfall);
+        }
+    }
+
+    let examples = examples(doc);
+    if !examples.is_empty() {
+        ui::print_section("Examples");
+        println!();
+        for example in examples {
+            ui::print_snippet(
+                &format!("{} - fails:", example.language),
+                example.before,
+                &example.language,
+            );
+            ui::print_snippet("Fixed:", example.after, &example.language);
+        }
+    }
+    ui::print_hint(&format!(
+        "Open this page in a browser with 'ess docs {} --web'",
+        entry.kind
+    ));
+}
+
+/// Write the write-up as an HTML page to the temp dir and open it in the
+/// browser. Returns the page's path.
+pub fn open_web(entry: &Entry) -> Result<PathBuf> {
+    let page = std::env::temp_dir().join(format!("ess-docs-{}.html", entry.kind));
+    std::fs::write(&page, html(entry))
+        .map_err(|e| anyhow!("Could not write {}: {}", page.display(), e))?;
+
+    let mut opener = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        Command::new("xdg-open")
+    };
+    let opened = opener
+        .arg(&page)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if opened {
+        ui::print_success(&format!("Opened {}", page.display()));
+    } else {
+        ui::print_warning("Could not start a browser");
+        ui::print_info(&format!("Open {} by hand", page.display()));
+    }
+    Ok(page)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_list(out: &mut String, tag: &str, items: &[&str]) {
+    let _ = write!(out, "<{}>", tag);
+    for item in items {
+        let _ = write!(out, "<li>{}</li>", escape(item));
+    }
+    let _ = writeln!(out, "</{}>", tag);
+}
+
+fn html_code(out: &mut String, code: &str, example: &Example) {
+    out.push_str("<pre><code>");
+    for line in code.lines() {
+        for (token, text) in highlight::tokens(line, &example.language) {
+            match token.class() {
+                Some(class) => {
+                    let _ = write!(out, "<span class=\"{}\">{}</span>", class, escape(text));
+                }
+                None => out.push_str(&escape(text)),
+            }
+        }
+        out.push('\n');
+    }
+    out.push_str("</code></pre>\n");
+}
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; \
+padding: 0 1rem; line-height: 1.5; color: #1e293b; }
+h1 { color: #8645c7; margin-bottom: 0; }
+.meaning { color: #64748b; margin-top: 0.25rem; }
+pre { background: #0f172a; color: #e2e8f0; padding: 0.75rem 1rem; border-radius: 6px; overflow-x: auto; }
+.kw { color: #93c5fd; font-weight: bold; }
+.str { color: #86efac; }
+.com { color: #94a3b8; font-style: italic; }
+.num { color: #fbbf24; }
+.pitfalls li { color: #b45309; }
+";
+
+/// The write-up as a standalone HTML page
+pub fn html(entry: &Entry) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+        <title>{} - ess docs</title>\n<style>\n{}</style>\n</head>\n<body>",
+        escape(entry.kind),
+        STYLE
+    );
+    let _ = writeln!(
+        out,
+        "<h1>{}</h1>\n<p class=\"meaning\">{}</p>",
+        escape(entry.kind),
+        escape(entry.meaning)
+    );
+
+    let _ = writeln!(
+        out,
+        "<h2>Why it happens</h2>\n<p>{}</p>",
+        escape(entry.explanation)
+    );
+    let doc = knowledge::docs(entry.kind);
+    if let Some(doc) = doc {
+        html_list(&mut out, "ul", doc.causes);
+    }
+    let _ = writeln!(out, "<h2>How to fix it</h2>\n<p>{}</p>", escape(entry.fix));
+    if let Some(doc) = doc {
+        html_list(&mut out, "ol", doc.strategies);
+        if !doc.pitfalls.is_empty() {
+            out.push_str("<h2>Pitfalls</h2>\n<div class=\"pitfalls\">");
+            html_list(&mut out, "ul", doc.pitfalls);
+            out.push_str("</div>\n");
+        }
+
+        let examples = examples(doc);
+        if !examples.is_empty() {
+            out.push_str("<h2>Examples</h2>\n");
+        }
+        for example in examples {
+            let _ = writeln!(
+                out,
+                "<h3>{} - fails</h3>",
+                escape(&example.language.to_string())
+            );
+            html_code(&mut out, example.before, example);
+            out.push_str("<h3>Fixed</h3>\n");
+            html_code(&mut out, example.after, example);
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_rule_ids() {
+        let kind = |id: &str| resolve(id).map(|entry| entry.kind);
+        assert_eq!(kind("PY-KEYERROR"), Some("KeyError"));
+        assert_eq!(kind("keyerror"), Some("KeyError"));
+        assert_eq!(kind("RS-BORROW"), Some("BorrowError"));
+        assert_eq!(kind("missing-include"), Some("MissingInclude"));
+        assert_eq!(kind("NameError"), Some("UndeclaredVariable"));
+        assert_eq!(kind("JAVA-NullPointerException"), Some("NullPointer"));
+        assert_eq!(kind("PY-NOPE"), None);
+        // The stable IDs shown next to findings
+        assert_eq!(kind("PY-KEY-001"), Some("KeyError"));
+        assert_eq!(kind("gen-syn-001"), Some("MissingSemicolon"));
+    }
+
+    #[test]
+    fn test_html_page() {
+        let page = html(resolve("KeyError").unwrap());
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<h1>KeyError</h1>"));
+        assert!(page.contains("<h2>Pitfalls</h2>"));
+        // Code is escaped and highlighted
+        assert!(page.contains("<span class=\"str\">&quot;email&quot;</span>"));
+        assert!(page.ends_with("</html>\n"));
+    }
+}
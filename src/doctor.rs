@@ -0,0 +1,137 @@
+use crate::config::Config;
+use crate::regexes;
+use crate::tools;
+use crate::ui;
+use anyhow::Result;
+use std::process::Command;
+
+/// First of `programs` that runs, with its version
+fn first_available(programs: &[&str]) -> Option<(String, String)> {
+    programs.iter().find_map(|program| {
+        tools::version(&mut Command::new(program)).map(|v| (program.to_string(), v))
+    })
+}
+
+/// Report one tool; returns whether it was found
+fn report(label: &str, found: Option<(String, String)>, missing: &str, required: bool) -> bool {
+    match found {
+        Some((program, version)) => {
+            ui::print_success(&format!("{}: {} ({})", label, program, version));
+            true
+        }
+        None => {
+            let message = format!("{}: {} not found", label, missing);
+            if required {
+                ui::print_error(&message);
+            } else {
+                ui::print_warning(&message);
+            }
+            ui::print_hint(tools::install_hint(missing));
+            false
+        }
+    }
+}
+
+/// Check which tools the scanner can use on this machine
+pub fn run(config: &Config) -> Result<()> {
+    ui::print_section("Doctor");
+
+    let mut missing = 0;
+
+    let configured = config.languages.python.as_deref();
+    let python = tools::python(configured);
+    let found = python
+        .as_ref()
+        .and_then(|python| tools::version(&mut python.command()).map(|v| (python.to_string(), v)));
+    if !report("Python", found, "python", true) {
+        missing += 1;
+        let tried: Vec<String> = tools::python_candidates(configured)
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        ui::print_info(&format!("Tried: {}", tried.join(", ")));
+    }
+    if let Some(python) = &python {
+        let pylint = tools::version(python.command().args(["-m", "pylint"]))
+            .map(|v| (format!("{} -m pylint", python), v));
+        report("Pylint", pylint, "pylint", false);
+    }
+
+    let compiler =
+        tools::cpp_compiler(&config.cpp).and_then(|c| c.version().map(|v| (c.to_string(), v)));
+    if !report(
+        "C++",
+        compiler,
+        &tools::cpp_compiler_names(&config.cpp),
+        true,
+    ) {
+        missing += 1;
+    }
+
+    let checks: [(&str, &[&str], &str); 3] = [
+        ("JavaScript", &["node"], "node"),
+        ("TypeScript", &["npx"], "npx"),
+        ("Rust", &["cargo"], "cargo"),
+    ];
+    for (label, programs, name) in checks {
+        if !report(label, first_available(programs), name, true) {
+            missing += 1;
+        }
+    }
+
+    println!();
+    if missing == 0 {
+        ui::print_success("Every language can be checked");
+    } else {
+        ui::print_info(&format!(
+            "{} tool{} missing - 'ess find-bug' will skip {}",
+            missing,
+            if missing == 1 { "" } else { "s" },
+            if missing == 1 {
+                "that language"
+            } else {
+                "those languages"
+            }
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check every bundled pattern; returns how many are broken
+pub fn self_test() -> usize {
+    ui::print_section("Self-Test");
+
+    let failures = regexes::self_test();
+    for (name, reason) in &failures {
+        ui::print_error(&format!("Pattern '{}' {}", name, reason));
+    }
+
+    println!();
+    if failures.is_empty() {
+        ui::print_success(&format!(
+            "All {} bundled patterns compile and match their samples",
+            regexes::ALL.len()
+        ));
+    } else {
+        ui::print_hint("This is a bug in ess - please report it");
+    }
+
+    failures.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_available_skips_missing_programs() {
+        assert!(first_available(&["ess-test-no-such-tool"]).is_none());
+        assert!(first_available(&[]).is_none());
+    }
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(self_test(), 0);
+    }
+}
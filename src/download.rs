@@ -0,0 +1,171 @@
+use crate::regexes;
+use crate::tools;
+use anyhow::{bail, Result};
+use std::process::Command;
+use std::time::Duration;
+
+/// Largest log `ess bug --url` downloads
+pub const MAX_LOG_BYTES: usize = 50 << 20;
+
+/// How long a download may take
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// curl's exit code when the file is over `--max-filesize`
+const CURL_TOO_LARGE: i32 = 63;
+
+/// The link to the plain text behind a log page: GitHub file and gist
+/// pages, and pastebin pastes, are HTML around the log. Other links are
+/// taken to be raw already.
+pub fn raw_url(url: &str) -> Result<String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        bail!("'{}' is not a link; use --file for logs on disk", url);
+    };
+    if scheme != "http" && scheme != "https" {
+        bail!("Only http and https links can be downloaded, not '{}'", url);
+    }
+    let rest = rest.split(['#', '?']).next().unwrap_or_default();
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let raw = match (host, segments.as_slice()) {
+        ("github.com", [owner, repo, "blob", file @ ..]) if !file.is_empty() => format!(
+            "https://raw.githubusercontent.com/{}/{}/{}",
+            owner,
+            repo,
+            file.join("/")
+        ),
+        ("gist.github.com", [user, id]) => {
+            format!("https://gist.githubusercontent.com/{}/{}/raw", user, id)
+        }
+        ("pastebin.com", [id]) if *id != "raw" => format!("https://pastebin.com/raw/{}", id),
+        _ => return Ok(url.to_string()),
+    };
+    Ok(raw)
+}
+
+/// Why a download failed
+#[derive(Debug)]
+enum Failure {
+    NoCurl,
+    TimedOut,
+    TooLarge,
+    Failed(String),
+}
+
+/// Download `url` with curl, keeping at most `max` bytes
+fn curl(url: &str, max: usize) -> std::result::Result<Vec<u8>, Failure> {
+    let mut curl = Command::new("curl");
+    curl.args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--proto", "=http,https"])
+        .arg("--max-filesize")
+        .arg(max.to_string())
+        .arg(url);
+    let finished = match tools::output_within(&mut curl, &[], Some(TIMEOUT), Some(max)) {
+        Ok(finished) => finished,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Failure::NoCurl),
+        Err(e) => return Err(Failure::Failed(e.to_string())),
+    };
+
+    if finished.timed_out {
+        return Err(Failure::TimedOut);
+    }
+    if finished.truncated > 0 || finished.output.status.code() == Some(CURL_TOO_LARGE) {
+        return Err(Failure::TooLarge);
+    }
+    if !finished.output.status.success() {
+        return Err(Failure::Failed(
+            String::from_utf8_lossy(&finished.output.stderr)
+                .trim()
+                .to_string(),
+        ));
+    }
+    Ok(finished.output.stdout)
+}
+
+/// Download the log at `url`, as text ess can parse
+pub fn fetch(url: &str) -> Result<String> {
+    let raw = raw_url(url)?;
+    match curl(&raw, MAX_LOG_BYTES) {
+        Ok(log) => Ok(log_text(&String::from_utf8_lossy(&log))),
+        Err(Failure::NoCurl) => {
+            bail!("Downloading logs needs curl; save the log and use --file instead")
+        }
+        Err(Failure::TimedOut) => bail!(
+            "{} took over {} seconds to download",
+            raw,
+            TIMEOUT.as_secs()
+        ),
+        Err(Failure::TooLarge) => bail!(
+            "{} is over {} MB; save the part that failed and use --file instead",
+            raw,
+            MAX_LOG_BYTES >> 20
+        ),
+        Err(Failure::Failed(e)) => bail!("Could not download {}: {}", raw, e),
+    }
+}
+
+/// Download `url`, which may be at most `max` bytes
+pub fn bytes(url: &str, max: usize) -> Result<Vec<u8>> {
+    match curl(url, max) {
+        Ok(body) => Ok(body),
+        Err(Failure::NoCurl) => bail!("Downloading needs curl, which is not installed"),
+        Err(Failure::TimedOut) => bail!(
+            "{} took over {} seconds to download",
+            url,
+            TIMEOUT.as_secs()
+        ),
+        Err(Failure::TooLarge) => bail!("{} is over {} MB", url, max >> 20),
+        Err(Failure::Failed(e)) => bail!("Could not download {}: {}", url, e),
+    }
+}
+
+/// A CI log without the colors and per-line timestamps runners add
+pub fn log_text(log: &str) -> String {
+    let log = regexes::ANSI_COLOR.replace_all(log, "");
+    regexes::CI_TIMESTAMP.replace_all(&log, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_error, split_errors};
+
+    #[test]
+    fn test_raw_url() {
+        assert_eq!(
+            raw_url("https://github.com/o/r/blob/main/logs/build.log#L10").unwrap(),
+            "https://raw.githubusercontent.com/o/r/main/logs/build.log"
+        );
+        assert_eq!(
+            raw_url("https://gist.github.com/kuba/0a1b2c").unwrap(),
+            "https://gist.githubusercontent.com/kuba/0a1b2c/raw"
+        );
+        assert_eq!(
+            raw_url("https://pastebin.com/Xy12Ab").unwrap(),
+            "https://pastebin.com/raw/Xy12Ab"
+        );
+        let raw = "https://pastebin.com/raw/Xy12Ab";
+        assert_eq!(raw_url(raw).unwrap(), raw);
+        assert!(raw_url("build.log").is_err());
+        assert!(raw_url("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_log_text_parses() {
+        let log = "2024-05-01T12:00:01.0000000Z ##[group]Run cargo build\n\
+            2024-05-01T12:00:03.5184310Z \x1b[31merror[E0425]\x1b[0m: cannot find value `total` in this scope\n\
+            2024-05-01T12:00:03.5185000Z  --> src/main.rs:4:5\n\
+            2024-05-01T12:00:04.1000000Z Traceback (most recent call last):\n\
+            2024-05-01T12:00:04.1000100Z   File \"app.py\", line 2, in <module>\n\
+            2024-05-01T12:00:04.1000200Z KeyError: 'user'\n";
+        let text = log_text(log);
+        assert!(text.starts_with("##[group]Run cargo build\nerror[E0425]: cannot find"));
+
+        let kinds: Vec<&str> = split_errors(&text)
+            .iter()
+            .filter_map(|error| parse_error(error))
+            .map(|parsed| parsed.error_type.name())
+            .collect();
+        assert_eq!(kinds, vec!["UndeclaredVariable", "KeyError"]);
+    }
+}
@@ -0,0 +1,117 @@
+/// Characters Windows-1252 has at 0x80-0x9F, where Latin-1 has control
+/// codes. The five bytes it leaves undefined map to the control codes.
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Invalid sequences UTF-8 output may have and still be UTF-8: output cut
+/// to its start and end can split a character at each cut
+const SPLIT_CHARACTERS: usize = 2;
+
+/// Tool output as UTF-8, so the parser's patterns can match it. Output
+/// that isn't UTF-8 is taken as UTF-16 when it has a byte order mark or
+/// every other byte is NUL (PowerShell, some MSVC tools), and otherwise as
+/// Windows-1252, what compilers in Western European locales print.
+pub fn to_utf8(bytes: Vec<u8>) -> Vec<u8> {
+    if let Some(text) = utf16(&bytes) {
+        return text.into_bytes();
+    }
+
+    let mut invalid = 0;
+    let mut multibyte = false;
+    for chunk in bytes.utf8_chunks() {
+        multibyte |= !chunk.valid().is_ascii();
+        invalid += usize::from(!chunk.invalid().is_empty());
+    }
+    if invalid == 0 || (multibyte && invalid <= SPLIT_CHARACTERS) {
+        return match String::from_utf8(bytes) {
+            Ok(text) => text.into_bytes(),
+            Err(e) => String::from_utf8_lossy(e.as_bytes())
+                .into_owned()
+                .into_bytes(),
+        };
+    }
+    windows_1252(&bytes).into_bytes()
+}
+
+fn utf16(bytes: &[u8]) -> Option<String> {
+    let (little_endian, body) = match bytes {
+        [0xFF, 0xFE, body @ ..] => (true, body),
+        [0xFE, 0xFF, body @ ..] => (false, body),
+        _ if looks_like_utf16le(bytes) => (true, bytes),
+        _ => return None,
+    };
+    let units: Vec<u16> = body
+        .chunks_exact(2)
+        .map(|pair| {
+            if little_endian {
+                u16::from_le_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_be_bytes([pair[0], pair[1]])
+            }
+        })
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// ASCII text in UTF-16LE: a NUL after nearly every character
+fn looks_like_utf16le(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(256) & !1];
+    if sample.len() < 4 {
+        return false;
+    }
+    let nuls = sample.chunks_exact(2).filter(|pair| pair[1] == 0).count();
+    nuls * 10 >= sample.len() / 2 * 9
+}
+
+fn windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x80..=0x9F => CP1252_HIGH[usize::from(byte - 0x80)],
+            _ => char::from(byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(bytes: &[u8]) -> String {
+        String::from_utf8(to_utf8(bytes.to_vec())).unwrap()
+    }
+
+    #[test]
+    fn test_utf8_is_kept() {
+        assert_eq!(
+            decoded("error: ‘x’ was not declared".as_bytes()),
+            "error: ‘x’ was not declared"
+        );
+        // Cut at a character, as capped output can be
+        let cut = [&"café ".as_bytes()[..4], " ok é".as_bytes()].concat();
+        assert_eq!(decoded(&cut), "caf\u{FFFD} ok é");
+    }
+
+    #[test]
+    fn test_legacy_code_page() {
+        // gcc in a de_DE.ISO-8859-1 locale, and MSVC quotes in cp1252
+        assert_eq!(
+            decoded(b"main.cpp:3:5: Fehler: \xbbx\xab wurde nicht deklariert"),
+            "main.cpp:3:5: Fehler: »x« wurde nicht deklariert"
+        );
+        assert_eq!(decoded(b"\x93x\x94 \x80"), "“x” €");
+    }
+
+    #[test]
+    fn test_utf16() {
+        let text = "main.cpp(3): error C2065: 'x'";
+        let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(decoded(&le), text);
+        assert_eq!(decoded(&[&[0xFF, 0xFE][..], &le].concat()), text);
+
+        let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decoded(&[&[0xFE, 0xFF][..], &be].concat()), text);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::apply::Fix;
+use crate::parser::{ErrorType, ParsedError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Another place a tool points at for a finding, e.g. rustc's "first
+/// borrow occurs here" or a compiler note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Related {
+    pub file: PathBuf,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl Related {
+    /// `line:column`, `line` or nothing
+    pub fn position(&self) -> String {
+        position(self.line, self.column)
+    }
+}
+
+/// One problem reported by a check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    /// Absolute path of the file the problem is in
+    pub file: PathBuf,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// Where the offending code ends, when the tool gives a range
+    pub end_line: Option<u32>,
+    pub end_column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+    /// Tool or check that reported it, e.g. "pylint"
+    pub source: String,
+    /// Stable ID of the check, when it isn't the parsed error's (see
+    /// `rule_id`)
+    #[serde(default)]
+    pub id: Option<String>,
+    /// What the parser made of the tool output
+    pub parsed: Option<ParsedError>,
+    /// Tool output to explain in full when the finding is shown on its own
+    pub explain: Option<String>,
+    /// Automatic fixes, attached when the finding is reported
+    pub fixes: Vec<Fix>,
+    /// Secondary locations, in the order the tool gave them
+    pub related: Vec<Related>,
+}
+
+impl Finding {
+    pub fn new(file: &Path, severity: Severity, message: &str, source: &str) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            severity,
+            message: message.to_string(),
+            source: source.to_string(),
+            id: None,
+            parsed: None,
+            explain: None,
+            fixes: Vec::new(),
+            related: Vec::new(),
+        }
+    }
+
+    pub fn at(mut self, line: Option<u32>, column: Option<u32>) -> Self {
+        self.line = line;
+        self.column = column;
+        self
+    }
+
+    /// Last line and column (inclusive) of the code the finding covers
+    pub fn ending_at(mut self, line: Option<u32>, column: Option<u32>) -> Self {
+        self.end_line = line;
+        self.end_column = column;
+        self
+    }
+
+    /// Attach parsed tool output, taking its location if it points at this
+    /// file, and its range if it starts where the finding does
+    pub fn with_parsed(mut self, parsed: Option<ParsedError>, root: &Path) -> Self {
+        if let Some(error) = &parsed {
+            if same_file(&root.join(&error.file), &self.file) {
+                if self.line.is_none() {
+                    self.line = error.line;
+                    self.column = error.column;
+                }
+                if self.end_line.is_none() && (self.line, self.column) == (error.line, error.column)
+                {
+                    self.end_line = error.end_line;
+                    self.end_column = error.end_column;
+                }
+            }
+        }
+        self.parsed = parsed;
+        self
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_explanation(mut self, output: &str) -> Self {
+        self.explain = Some(output.to_string());
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Option<Fix>) -> Self {
+        self.fixes.extend(fix);
+        self
+    }
+
+    pub fn with_related(mut self, related: Vec<Related>) -> Self {
+        self.related.extend(related);
+        self
+    }
+
+    /// The parser recognized the error, so there is a fix to suggest
+    pub fn is_fixable(&self) -> bool {
+        self.parsed
+            .as_ref()
+            .is_some_and(|e| !matches!(e.error_type, ErrorType::Unknown(_)))
+    }
+
+    /// Kind of problem: the parsed error type, or the check that found it
+    pub fn rule(&self) -> &str {
+        match &self.parsed {
+            Some(error) if !matches!(error.error_type, ErrorType::Unknown(_)) => {
+                error.error_type.name()
+            }
+            _ => &self.source,
+        }
+    }
+
+    /// Stable ID of the check, e.g. "PY-ENV-001": its own, or the parsed
+    /// error kind's
+    pub fn rule_id(&self) -> Option<&str> {
+        self.id
+            .as_deref()
+            .or_else(|| self.parsed.as_ref()?.error_type.id())
+    }
+
+    /// The rule as machine-readable reports name it: its ID, else its name
+    pub fn reported_rule(&self) -> &str {
+        self.rule_id().unwrap_or(self.rule())
+    }
+
+    /// What reported it, as shown next to the message: the source and the
+    /// rule ID when there is one
+    pub fn label(&self) -> String {
+        match self.rule_id() {
+            Some(id) => format!("{} {}", self.source, id),
+            None => self.source.clone(),
+        }
+    }
+
+    /// Whether `[rules] disabled` lists its ID or rule name
+    pub fn is_disabled(&self, disabled: &[String]) -> bool {
+        disabled.iter().any(|rule| {
+            self.rule_id()
+                .is_some_and(|id| id.eq_ignore_ascii_case(rule))
+                || self.rule().eq_ignore_ascii_case(rule)
+        })
+    }
+
+    /// `line:column`, `line` or nothing
+    pub fn position(&self) -> String {
+        position(self.line, self.column)
+    }
+}
+
+fn position(line: Option<u32>, column: Option<u32>) -> String {
+    match (line, column) {
+        (Some(l), Some(c)) => format!("{}:{}", l, c),
+        (Some(l), None) => l.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Sort by path, then line and column, then rule (and message, so ties
+/// come out the same way every time)
+pub fn sort(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| {
+        (&a.file, a.line, a.column, a.rule(), &a.message).cmp(&(
+            &b.file,
+            b.line,
+            b.column,
+            b.rule(),
+            &b.message,
+        ))
+    });
+}
+
+/// How many findings of one rule and severity a scan reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleCount {
+    pub rule: String,
+    pub severity: Severity,
+    pub count: usize,
+}
+
+/// Findings counted per rule and severity: errors first, then the most
+/// common rules, then by name
+pub fn rule_counts(findings: &[Finding]) -> Vec<RuleCount> {
+    let mut counts: Vec<RuleCount> = Vec::new();
+    for finding in findings {
+        match counts
+            .iter_mut()
+            .find(|c| c.rule == finding.rule() && c.severity == finding.severity)
+        {
+            Some(count) => count.count += 1,
+            None => counts.push(RuleCount {
+                rule: finding.rule().to_string(),
+                severity: finding.severity,
+                count: 1,
+            }),
+        }
+    }
+    counts.sort_by(|a, b| {
+        (a.severity != Severity::Error, b.count, &a.rule).cmp(&(
+            b.severity != Severity::Error,
+            a.count,
+            &b.rule,
+        ))
+    });
+    counts
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_error;
+
+    #[test]
+    fn test_fixable_needs_known_error_type() {
+        let file = Path::new("/tmp/main.cpp");
+        let plain = Finding::new(file, Severity::Error, "oops", "g++");
+        let known = Finding::new(file, Severity::Error, "expected ';'", "g++").with_parsed(
+            parse_error("/tmp/main.cpp:10:5: error: expected ';' before 'return'"),
+            Path::new("/"),
+        );
+
+        assert!(!plain.is_fixable());
+        assert!(known.is_fixable());
+    }
+
+    #[test]
+    fn test_rule_id_and_disabled() {
+        let file = Path::new("/tmp/main.cpp");
+        let heuristic =
+            Finding::new(file, Severity::Warning, "x", "heuristics").with_id("PY-ENV-001");
+        let parsed = Finding::new(file, Severity::Error, "expected ';'", "g++").with_parsed(
+            parse_error("/tmp/main.cpp:10:5: error: expected ';' before 'return'"),
+            Path::new("/"),
+        );
+        let plain = Finding::new(file, Severity::Error, "oops", "lockfile/conflict");
+
+        assert_eq!(heuristic.rule_id(), Some("PY-ENV-001"));
+        assert_eq!(heuristic.label(), "heuristics PY-ENV-001");
+        assert_eq!(parsed.rule_id(), Some("GEN-SYN-001"));
+        assert_eq!(plain.rule_id(), None);
+        assert_eq!(plain.label(), "lockfile/conflict");
+
+        let disabled = ["py-env-001".to_string(), "lockfile/conflict".to_string()];
+        assert!(heuristic.is_disabled(&disabled));
+        assert!(plain.is_disabled(&disabled));
+        assert!(!parsed.is_disabled(&disabled));
+    }
+
+    #[test]
+    fn test_location_from_parsed_error_in_same_file() {
+        let parsed = parse_error("main.cpp:10:5: error: expected ';' before 'return'");
+
+        let same = Finding::new(Path::new("/src/main.cpp"), Severity::Error, "x", "g++")
+            .with_parsed(parsed.clone(), Path::new("/src"));
+        let other = Finding::new(Path::new("/src/other.cpp"), Severity::Error, "x", "g++")
+            .with_parsed(parsed, Path::new("/src"));
+
+        assert_eq!(same.position(), "10:5");
+        assert_eq!(other.position(), "");
+    }
+
+    #[test]
+    fn test_sort_by_path_line_and_rule() {
+        let finding = |file: &str, line: Option<u32>, source: &str| {
+            Finding::new(Path::new(file), Severity::Error, "x", source).at(line, None)
+        };
+        let mut findings = vec![
+            finding("/p/b.py", Some(2), "python"),
+            finding("/p/a.py", Some(10), "pylint"),
+            finding("/p/a.py", Some(10), "heuristics"),
+            finding("/p/a.py", Some(9), "pylint"),
+            finding("/p/a.py", None, "heuristics"),
+        ];
+        sort(&mut findings);
+
+        let order: Vec<(String, Option<u32>, &str)> = findings
+            .iter()
+            .map(|f| (f.file.to_string_lossy().to_string(), f.line, f.rule()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("/p/a.py".to_string(), None, "heuristics"),
+                ("/p/a.py".to_string(), Some(9), "pylint"),
+                ("/p/a.py".to_string(), Some(10), "heuristics"),
+                ("/p/a.py".to_string(), Some(10), "pylint"),
+                ("/p/b.py".to_string(), Some(2), "python"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rule_counts() {
+        let finding = |severity: Severity, source: &str| {
+            Finding::new(Path::new("/p/a.py"), severity, "x", source)
+        };
+        let findings = vec![
+            finding(Severity::Warning, "pylint"),
+            finding(Severity::Error, "python"),
+            finding(Severity::Warning, "pylint"),
+            finding(Severity::Error, "mypy"),
+            finding(Severity::Error, "python"),
+            finding(Severity::Warning, "python"),
+        ];
+
+        let counts = rule_counts(&findings);
+        assert_eq!(
+            counts
+                .iter()
+                .map(|c| (c.rule.as_str(), c.severity, c.count))
+                .collect::<Vec<_>>(),
+            vec![
+                ("python", Severity::Error, 2),
+                ("mypy", Severity::Error, 1),
+                ("pylint", Severity::Warning, 2),
+                ("python", Severity::Warning, 1),
+            ]
+        );
+    }
+}
@@ -1,87 +1,367 @@
-use crate::parser::{parse_error, ErrorType, Language, ParsedError};
+use crate::apply::{self, Fix};
+use crate::knowledge::{self, Entry, Example};
+use crate::msrv;
+use crate::parser::{
+    macro_origin, nuget_package, parse_error, split_errors, CustomFix, ErrorType, Language,
+    MacroOrigin, ParsedError,
+};
+use crate::ports;
+use crate::regexes;
+use crate::rules::{Builtin, Registry};
 use crate::ui;
 use anyhow::Result;
+use std::path::Path;
+
+/// What ess knows about fixing an error, as data rather than output
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// Error kind, as given by `ErrorType::name`
+    pub kind: &'static str,
+    /// What the error means and how to fix it in general
+    pub entry: Option<&'static Entry>,
+    /// Before/after code in the error's language
+    pub example: Option<&'static Example>,
+    /// Edits that fix the file, when ess can make them
+    pub fix: Option<Fix>,
+}
+
+/// The fix for a parsed error. `root` is the directory its file path is
+/// relative to.
+pub fn suggest(error: &ParsedError, root: &Path) -> Suggestion {
+    let kind = error.error_type.name();
+    Suggestion {
+        kind,
+        entry: knowledge::lookup(kind),
+        example: knowledge::example(kind, &error.language),
+        fix: apply::suggest_fix(error, root),
+    }
+}
+
+pub fn analyze_error(error_text: &str, rules: &Registry) -> Result<Option<ParsedError>> {
+    let parsed = rules.resolve(error_text, parse_error(error_text));
+    explain(error_text, parsed.as_ref(), rules);
+    Ok(parsed)
+}
+
+/// Error text from a build log or a pipe, without the color codes tools
+/// print. Logs aren't always UTF-8, so bytes that aren't are replaced.
+pub fn read_error_text(input: &mut impl std::io::Read) -> Result<String> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(regexes::ANSI_COLOR.replace_all(&text, "").to_string())
+}
 
-pub fn analyze_error(error_text: &str) -> Result<()> {
-    ui::print_section("Analyzing Error");
+/// Like `analyze_error`, but explains each error in output that has
+/// several, e.g. a whole build log. Each error comes with its own text.
+pub fn analyze_errors(error_text: &str, rules: &Registry) -> Result<Vec<(String, ParsedError)>> {
+    let mut errors: Vec<(String, ParsedError)> = split_errors(error_text)
+        .into_iter()
+        .filter_map(|text| {
+            let parsed = rules.resolve(&text, parse_error(&text))?;
+            Some((text, parsed))
+        })
+        .collect();
+    if errors.len() <= 1 {
+        errors = rules
+            .resolve(error_text, parse_error(error_text))
+            .map(|parsed| (error_text.to_string(), parsed))
+            .into_iter()
+            .collect();
+    }
+    show_errors(error_text, &errors, rules);
+    Ok(errors)
+}
+
+/// Explain errors `analyze_errors` found in `error_text`
+pub fn show_errors(error_text: &str, errors: &[(String, ParsedError)], rules: &Registry) {
+    match errors {
+        [] => explain(error_text, None, rules),
+        [(text, parsed)] => explain(text, Some(parsed), rules),
+        _ => {
+            for (i, (text, parsed)) in errors.iter().enumerate() {
+                explain_numbered(i + 1, errors.len(), text, parsed, rules);
+            }
+        }
+    }
+}
 
-    if let Some(error) = parse_error(error_text) {
-        show_parsed_error(&error);
-        show_fix_for_error(&error);
+/// Explain error `number` of `total`
+pub fn explain_numbered(
+    number: usize,
+    total: usize,
+    error_text: &str,
+    parsed: &ParsedError,
+    rules: &Registry,
+) {
+    explain_titled(
+        &format!("Analyzing Error {} of {}", number, total),
+        error_text,
+        Some(parsed),
+        rules,
+    );
+}
+
+/// Show an already parsed error and its fix, or fall back to matching
+/// common patterns in the raw error text
+pub fn explain(error_text: &str, parsed: Option<&ParsedError>, rules: &Registry) {
+    explain_titled("Analyzing Error", error_text, parsed, rules);
+}
+
+fn explain_titled(title: &str, error_text: &str, parsed: Option<&ParsedError>, rules: &Registry) {
+    ui::print_section(title);
+
+    if let Some(error) = parsed {
+        show_parsed_error(error);
+        let rule = rules.rule(error.error_type.name());
+        if let Some(entry) = rule.and_then(|rule| rule.explain()) {
+            ui::print_hint(entry.meaning);
+        }
+        if error.language == Language::Rust {
+            if let Some(origin) = macro_origin(error_text) {
+                show_macro_origin(error, &origin);
+            }
+        }
+        rules.fix(error);
     } else {
         ui::print_warning("Could not fully parse error format");
         ui::print_info("Attempting pattern matching...");
         println!();
 
-        if let Some(fix) = try_common_patterns(error_text) {
-            ui::print_fix_instruction(&fix);
+        if let Some((id, fix)) = try_common_patterns(error_text) {
+            ui::print_info(&format!("Matched common pattern {}", id));
+            ui::print_fix_instruction(fix);
         } else {
             ui::print_error("Unknown error pattern");
             ui::print_hint("Try 'ess list' to see supported error types");
         }
     }
-
-    Ok(())
 }
 
 fn show_parsed_error(error: &ParsedError) {
     println!();
-    ui::print_info(&format!("Language: {}", error.language));
-    ui::print_file_location(&error.file, error.line, error.column);
+    // Errors only a [[patterns]] entry recognized may not say more
+    if error.language != Language::Unknown {
+        ui::print_info(&format!("Language: {}", error.language));
+    }
+    if let Some(id) = error.error_type.id() {
+        ui::print_info(&format!("Rule: {} ({})", error.error_type.name(), id));
+    }
+    if !error.file.is_empty() {
+        ui::print_file_location(&error.file, error.line, error.column);
+        show_code_context(error);
+    }
     println!();
     ui::print_error(&error.message);
 }
 
-fn show_fix_for_error(error: &ParsedError) {
-    match &error.error_type {
-        ErrorType::MissingInclude(header) => {
-            fix_missing_include(header, &error.language);
-        }
-        ErrorType::MissingSemicolon => {
-            fix_missing_semicolon(&error.language);
+/// Show where the macro an error came from is invoked. The error itself is
+/// reported inside the macro, which is rarely the code to change.
+fn show_macro_origin(error: &ParsedError, origin: &MacroOrigin) {
+    println!();
+    ui::print_info(&format!(
+        "Expanded from {} at {}:{}",
+        origin.name, origin.file, origin.line
+    ));
+
+    if error.line != Some(origin.line) {
+        show_code_context(&ParsedError {
+            file: origin.file.clone(),
+            line: Some(origin.line),
+            column: None,
+            end_line: None,
+            end_column: None,
+            ..error.clone()
+        });
+    }
+}
+
+/// Lines shown before the error line
+const CONTEXT_BEFORE: usize = 2;
+
+/// Lines shown after the error line
+const CONTEXT_AFTER: usize = 1;
+
+/// Show the lines around the error, with the error column underlined
+fn show_code_context(error: &ParsedError) {
+    let line = match error.line {
+        Some(line) if line > 0 => line as usize,
+        _ => return,
+    };
+    let content = match std::fs::read_to_string(&error.file) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    if line > lines.len() {
+        return;
+    }
+
+    println!();
+    let first = line.saturating_sub(CONTEXT_BEFORE).max(1);
+    let last = (line + CONTEXT_AFTER).min(lines.len());
+    for num in first..=last {
+        let code = lines[num - 1];
+        ui::print_code_line(num as u32, code, num == line);
+        if num == line {
+            if let Some(column) = error.column {
+                let column = match error.language {
+                    Language::Cpp => ui::gcc_column_to_char(code, column),
+                    _ => column,
+                };
+                // Ranges over several lines are marked from the column on
+                let end_column = match error.end_line {
+                    Some(end_line) if end_line as usize != line => None,
+                    _ => error.end_column,
+                };
+                ui::print_caret(code, column, end_column);
+            }
         }
-        ErrorType::UndeclaredVariable(var) => {
-            fix_undeclared_variable(var, &error.language);
+    }
+}
+
+/// The built-in rules, one per error kind the parsers know
+pub const RULES: &[Builtin] = &[
+    Builtin::new("MissingInclude", |e| {
+        if let ErrorType::MissingInclude(header) = &e.error_type {
+            fix_missing_include(header, &e.language);
         }
-        ErrorType::SyntaxError(details) => {
-            fix_syntax_error(details, &error.language);
+    }),
+    Builtin::new("MissingSemicolon", |e| fix_missing_semicolon(&e.language)),
+    Builtin::new("UndeclaredVariable", |e| {
+        if let ErrorType::UndeclaredVariable(var) = &e.error_type {
+            fix_undeclared_variable(var, &e.language);
         }
-        ErrorType::IndentationError => {
-            fix_indentation_error();
+    }),
+    Builtin::new("SyntaxError", |e| {
+        if let ErrorType::SyntaxError(details) = &e.error_type {
+            fix_syntax_error(details, &e.language);
         }
-        ErrorType::ImportError(module) => {
-            fix_import_error(module, &error.language);
+    }),
+    Builtin::new("IndentationError", |_| fix_indentation_error()),
+    Builtin::new("ImportError", |e| {
+        if let ErrorType::ImportError(module) = &e.error_type {
+            fix_import_error(module, &e.language);
         }
-        ErrorType::ModuleNotFound(module) => {
-            fix_module_not_found(module, &error.language);
+    }),
+    Builtin::new("ModuleNotFound", |e| {
+        if let ErrorType::ModuleNotFound(module) = &e.error_type {
+            fix_module_not_found(module, &e.language);
         }
-        ErrorType::TypeError(details) => {
-            fix_type_error(details, &error.language);
+    }),
+    Builtin::new("TypeError", |e| {
+        if let ErrorType::TypeError(details) = &e.error_type {
+            fix_type_error(details, &e.language);
         }
-        ErrorType::BorrowError(details) => {
+    }),
+    Builtin::new("BorrowError", |e| {
+        if let ErrorType::BorrowError(details) = &e.error_type {
             fix_borrow_error(details);
         }
-        ErrorType::KeyError(key) => {
+    }),
+    Builtin::new("KeyError", |e| {
+        if let ErrorType::KeyError(key) = &e.error_type {
             fix_key_error(key);
         }
-        ErrorType::AttributeError(details) => {
+    }),
+    Builtin::new("AttributeError", |e| {
+        if let ErrorType::AttributeError(details) = &e.error_type {
             fix_attribute_error(details);
         }
-        ErrorType::ValueError(details) => {
+    }),
+    Builtin::new("ValueError", |e| {
+        if let ErrorType::ValueError(details) = &e.error_type {
             fix_value_error(details);
         }
-        ErrorType::MissingEnvVar(details) => {
+    }),
+    Builtin::new("MissingEnvVar", |e| {
+        if let ErrorType::MissingEnvVar(details) = &e.error_type {
             fix_missing_env_var(details);
         }
-        ErrorType::RequestsError(details) => {
+    }),
+    Builtin::new("RequestsError", |e| {
+        if let ErrorType::RequestsError(details) = &e.error_type {
             fix_requests_error(details);
         }
-        ErrorType::Unknown(msg) => {
+    }),
+    Builtin::new("UnclosedTemplateBlock", |e| {
+        if let ErrorType::UnclosedTemplateBlock(tag) = &e.error_type {
+            fix_unclosed_template_block(tag, &e.language);
+        }
+    }),
+    Builtin::new("UndefinedTemplateVariable", |e| {
+        if let ErrorType::UndefinedTemplateVariable(var) = &e.error_type {
+            fix_undefined_template_variable(var, &e.language);
+        }
+    }),
+    Builtin::new("DeriveError", |e| {
+        if let ErrorType::DeriveError(details) = &e.error_type {
+            fix_derive_error(details);
+        }
+    }),
+    Builtin::new("Vulnerability", |e| {
+        if let ErrorType::Vulnerability(advice) = &e.error_type {
+            fix_vulnerability(advice, &e.language);
+        }
+    }),
+    Builtin::new("MissingLicenseHeader", |e| {
+        if let ErrorType::MissingLicenseHeader(header) = &e.error_type {
+            fix_missing_license_header(header);
+        }
+    }),
+    Builtin::new("PolicyViolation", |e| {
+        if let ErrorType::PolicyViolation(rule) = &e.error_type {
+            fix_policy_violation(rule);
+        }
+    }),
+    Builtin::new("Unformatted", |e| {
+        if let ErrorType::Unformatted(command) = &e.error_type {
+            fix_unformatted(command);
+        }
+    }),
+    Builtin::new("AssertionFailed", |e| {
+        if let ErrorType::AssertionFailed(comparison) = &e.error_type {
+            fix_assertion_failed(comparison);
+        }
+    }),
+    Builtin::new("RustVersion", |e| {
+        if let ErrorType::RustVersion(requirement) = &e.error_type {
+            fix_rust_version(requirement, &e.file);
+        }
+    }),
+    Builtin::new("Custom", |e| {
+        if let ErrorType::Custom(fix) = &e.error_type {
+            fix_custom(fix);
+        }
+    }),
+    Builtin::new("FormatError", |e| {
+        if let ErrorType::FormatError(details) = &e.error_type {
+            fix_format_error(details);
+        }
+    }),
+    Builtin::new("MissingImport", |e| {
+        if let ErrorType::MissingImport(class) = &e.error_type {
+            fix_missing_import(class);
+        }
+    }),
+    Builtin::new("NullPointer", |e| {
+        if let ErrorType::NullPointer(details) = &e.error_type {
+            fix_null_pointer(details);
+        }
+    }),
+    Builtin::new("PortInUse", |e| {
+        if let ErrorType::PortInUse(port) = &e.error_type {
+            ports::diagnose(*port);
+        }
+    }),
+    Builtin::new("Unknown", |e| {
+        if let ErrorType::Unknown(msg) = &e.error_type {
             ui::print_warning(&format!("No automatic fix for: {}", msg));
             ui::print_hint("Check the error message and fix manually");
         }
-    }
-}
+    }),
+];
 
 fn fix_missing_include(header: &str, lang: &Language) {
     if lang == &Language::Cpp {
@@ -98,7 +378,11 @@ fn fix_missing_include(header: &str, lang: &Language) {
 
 fn fix_missing_semicolon(lang: &Language) {
     match lang {
-        Language::Cpp | Language::JavaScript | Language::TypeScript => {
+        Language::Cpp
+        | Language::JavaScript
+        | Language::TypeScript
+        | Language::Java
+        | Language::CSharp => {
             ui::print_diff("statement  // missing semicolon", "statement;");
             ui::print_fix_instruction(
                 "Add a semicolon at the end of the line indicated in the error.\n\n\
@@ -165,10 +449,70 @@ fn fix_undeclared_variable(var: &str, lang: &Language) {
                 var, var, var
             ));
         }
+        Language::Java => {
+            ui::print_fix_instruction(&format!(
+                "Options:\n\n\
+                1. Check spelling of '{}' (Java is case-sensitive)\n\
+                2. Declare it before using it:\n   int {} = 0;\n\
+                3. If it's a field of another class, qualify it:\n   Other.{}",
+                var, var, var
+            ));
+        }
+        Language::CSharp => {
+            ui::print_fix_instruction(&format!(
+                "Options:\n\n\
+                1. Check spelling of '{}' (C# is case-sensitive)\n\
+                2. Declare it before using it:\n   var {} = 0;\n\
+                3. If it's declared inside a block (if, for, using), move the declaration out\n\
+                4. If it's a static member of another type, qualify it:\n   Other.{}",
+                var, var, var
+            ));
+        }
         _ => {}
     }
 }
 
+fn fix_missing_import(class: &str) {
+    match class.rsplit_once('.') {
+        Some((_, name)) => {
+            ui::print_diff(
+                &format!("{} items = ...;", name),
+                &format!("import {};\n\n{} items = ...;", class, name),
+            );
+            ui::print_fix_instruction(&format!(
+                "'{}' lives in another package. Add this line below the package line:\n\n  import {};",
+                name, class
+            ));
+        }
+        None => ui::print_fix_instruction(&format!(
+            "Java doesn't know the class '{}'.\n\n\
+            Options:\n\n\
+            1. Check the spelling - class names start with a capital letter\n\
+            2. Import it from its package:\n   import com.example.{};\n\
+            3. If it's your own class, check its file is named {}.java",
+            class, class, class
+        )),
+    }
+}
+
+fn fix_null_pointer(details: &str) {
+    ui::print_section("Null Pointer");
+    println!();
+
+    if !details.is_empty() {
+        ui::print_error(details);
+        println!();
+    }
+    show_example("NullPointer", &Language::Java);
+    ui::print_fix_instruction(
+        "A variable or field was null when it was used.\n\n\
+        Options:\n\n\
+        1. Find where it should have been set - a missing 'new', or a method that returned null\n\
+        2. Check for null before using it:\n   if (name != null) { ... }\n\
+        3. Fail early with a clear message:\n   Objects.requireNonNull(name, \"name\");",
+    );
+}
+
 fn fix_syntax_error(details: &str, _lang: &Language) {
     ui::print_section("Syntax Error");
     println!();
@@ -246,7 +590,15 @@ fn fix_import_error(module: &str, lang: &Language) {
     }
 }
 
+/// The before/after snippet for an error kind in the error's language
+fn show_example(kind: &str, lang: &Language) {
+    if let Some(example) = knowledge::example(kind, lang) {
+        ui::print_diff(example.before, example.after);
+    }
+}
+
 fn fix_module_not_found(module: &str, lang: &Language) {
+    show_example("ModuleNotFound", lang);
     match lang {
         Language::JavaScript | Language::TypeScript => {
             ui::print_fix_instruction(&format!(
@@ -258,6 +610,7 @@ fn fix_module_not_found(module: &str, lang: &Language) {
                 module, module, module
             ));
         }
+        Language::CSharp => fix_missing_using(module),
         _ => {
             ui::print_fix_instruction(&format!(
                 "Module '{}' not found. Check installation and import path.",
@@ -267,6 +620,51 @@ fn fix_module_not_found(module: &str, lang: &Language) {
     }
 }
 
+/// A C# type the compiler can't find: a missing `using`, or a package
+/// the project doesn't reference
+fn fix_missing_using(name: &str) {
+    // CS0234 names a missing namespace, which only a package brings
+    let parent = name.rsplit_once('.').map_or("", |(parent, _)| parent);
+    if let Some(package) = nuget_package(name).filter(|_| nuget_package(parent).is_none()) {
+        ui::print_fix_instruction(&format!(
+            "The namespace '{}' comes from the {} NuGet package. Add it to the project:\n\n  dotnet add package {}",
+            name, package, package
+        ));
+        return;
+    }
+    let Some((namespace, type_name)) = name.rsplit_once('.') else {
+        ui::print_fix_instruction(&format!(
+            "The compiler can't find the type '{}'.\n\n\
+            Options:\n\n\
+            1. Check the spelling - type names are case-sensitive\n\
+            2. Add the using directive for its namespace at the top of the file:\n   using Some.Namespace;\n\
+            3. If it comes from a library, add its NuGet package:\n   dotnet add package <PackageName>",
+            name
+        ));
+        return;
+    };
+
+    ui::print_diff(
+        &format!("{} value = ...;", type_name),
+        &format!("using {};\n\n{} value = ...;", namespace, type_name),
+    );
+    let package = match nuget_package(namespace) {
+        Some(package) => format!(
+            "\n\nIf the project doesn't reference it yet, add the package:\n  dotnet add package {}",
+            package
+        ),
+        None if !namespace.starts_with("System") => format!(
+            "\n\nIf '{}' comes from a library, add its NuGet package:\n  dotnet add package <PackageName>",
+            namespace
+        ),
+        None => String::new(),
+    };
+    ui::print_fix_instruction(&format!(
+        "'{}' is in the {} namespace. Add this line at the top of the file:\n\n  using {};{}",
+        type_name, namespace, namespace, package
+    ));
+}
+
 fn fix_type_error(details: &str, lang: &Language) {
     ui::print_section("Type Error");
     println!();
@@ -274,6 +672,7 @@ fn fix_type_error(details: &str, lang: &Language) {
     ui::print_error(details);
     println!();
 
+    show_example("TypeError", lang);
     match lang {
         Language::TypeScript => {
             ui::print_fix_instruction(
@@ -292,6 +691,13 @@ fn fix_type_error(details: &str, lang: &Language) {
                 Then ensure the operation is valid for that type.",
             );
         }
+        Language::JavaScript => {
+            ui::print_fix_instruction(
+                "A value isn't what the code expects - usually undefined or null.\n\n\
+                Log it right before the failing line:\n  console.log(typeof value, value)\n\n\
+                Then guard against the missing case with ?. and ??, or fix where it comes from.",
+            );
+        }
         _ => {
             ui::print_fix_instruction(
                 "Type mismatch. Check that your variables have the expected types.",
@@ -317,40 +723,43 @@ fn fix_borrow_error(details: &str) {
     );
 }
 
-fn try_common_patterns(error_text: &str) -> Option<String> {
+/// Fixes for error text the parsers don't recognize: rule ID, what the
+/// lowercased text contains (any of), and the fix
+pub const COMMON_PATTERNS: &[(&str, &[&str], &str)] = &[
+    (
+        "COM-SYN-001",
+        &["expected ';'", "missing semicolon"],
+        "Add a semicolon (;) at the end of the line.",
+    ),
+    (
+        "COM-INC-001",
+        &["is not a member of", "was not declared"],
+        "You're using something that hasn't been imported/included.\n\
+        Add the appropriate #include or import statement at the top of your file.",
+    ),
+    (
+        "COM-NAME-001",
+        &["is not defined", "undeclared"],
+        "Variable is not defined.\n\
+        Either declare it before using, or check for typos in the name.",
+    ),
+    (
+        "COM-SYN-002",
+        &["unexpected token", "was never closed"],
+        "Syntax error - check for:\n\
+        • Missing or extra brackets { } [ ] ( )\n\
+        • Unclosed strings\n\
+        • Missing semicolons or commas",
+    ),
+];
+
+/// The rule ID and fix of the first common pattern in the error text
+fn try_common_patterns(error_text: &str) -> Option<(&'static str, &'static str)> {
     let lower = error_text.to_lowercase();
-
-    if lower.contains("expected ';'") || lower.contains("missing semicolon") {
-        return Some("Add a semicolon (;) at the end of the line.".to_string());
-    }
-
-    if lower.contains("is not a member of") || lower.contains("was not declared") {
-        return Some(
-            "You're using something that hasn't been imported/included.\n\
-            Add the appropriate #include or import statement at the top of your file."
-                .to_string(),
-        );
-    }
-
-    if lower.contains("is not defined") || lower.contains("undeclared") {
-        return Some(
-            "Variable is not defined.\n\
-            Either declare it before using, or check for typos in the name."
-                .to_string(),
-        );
-    }
-
-    if lower.contains("unexpected token") || lower.contains("was never closed") {
-        return Some(
-            "Syntax error - check for:\n\
-            • Missing or extra brackets { } [ ] ( )\n\
-            • Unclosed strings\n\
-            • Missing semicolons or commas"
-                .to_string(),
-        );
-    }
-
-    None
+    COMMON_PATTERNS
+        .iter()
+        .find(|(_, needles, _)| needles.iter().any(|needle| lower.contains(needle)))
+        .map(|(id, _, fix)| (*id, *fix))
 }
 
 fn is_std_type(name: &str) -> bool {
@@ -490,6 +899,277 @@ fn fix_missing_env_var(_details: &str) {
         3. Use a default value:\n\
            API_URL = os.getenv(\"API_URL\", \"https://default-api.com\")",
     );
+    ui::print_hint("For scans, give the files a value with [scan.env] or --env API_URL=...");
+}
+
+fn fix_unclosed_template_block(tag: &str, lang: &Language) {
+    ui::print_section("Unclosed Template Block");
+    println!();
+
+    if lang == &Language::JavaScript {
+        ui::print_diff(
+            "<% users.forEach(user => { %>\n  <li><%= user.name %></li>\n",
+            "<% users.forEach(user => { %>\n  <li><%= user.name %></li>\n<% }) %>",
+        );
+
+        ui::print_fix_instruction(
+            "An EJS block was opened but never closed.\n\n\
+            Every <% if (...) { %> needs a matching <% } %>, and every\n\
+            <% items.forEach(item => { %> needs a matching <% }) %>.\n\n\
+            Count the opening and closing braces in the template's <% %> tags.",
+        );
+    } else {
+        ui::print_diff(
+            &format!("{{% {} ... %}}\n  ...\n", tag),
+            &format!("{{% {} ... %}}\n  ...\n{{% end{} %}}", tag, tag),
+        );
+
+        ui::print_fix_instruction(&format!(
+            "The '{}' block is never closed.\n\n\
+            Add {{% end{} %}} where the block should end. If it's already there,\n\
+            check for a typo in it or a block nested inside that is left open.",
+            tag, tag
+        ));
+    }
+}
+
+fn fix_undefined_template_variable(var: &str, lang: &Language) {
+    ui::print_section("Undefined Template Variable");
+    println!();
+
+    if lang == &Language::JavaScript {
+        ui::print_diff(
+            "res.render(\"index\")",
+            &format!("res.render(\"index\", {{ {} }})", var),
+        );
+
+        ui::print_fix_instruction(&format!(
+            "The template uses '{}', but it wasn't passed to res.render().\n\n\
+            Fix:\n\n\
+            1. Pass it when rendering:\n\
+               res.render(\"index\", {{ {} }})\n\n\
+            2. Or make it optional in the template:\n\
+               <% if (locals.{}) {{ %> ... <% }} %>",
+            var, var, var
+        ));
+    } else {
+        ui::print_diff(
+            "render_template(\"page.html\")",
+            &format!("render_template(\"page.html\", {}={})", var, var),
+        );
+
+        ui::print_fix_instruction(&format!(
+            "The template uses '{}', but it wasn't passed when rendering.\n\n\
+            Fix:\n\n\
+            1. Pass it to the template:\n\
+               render_template(\"page.html\", {}={})  # Flask / Jinja2\n\
+               render(request, \"page.html\", {{\"{}\": {}}})  # Django\n\n\
+            2. Or make it optional in the template:\n\
+               {{{{ {} | default(\"\") }}}}  # Jinja2\n\
+               {{{{ {}|default:\"\" }}}}  # Django",
+            var, var, var, var, var, var, var
+        ));
+    }
+}
+
+fn fix_derive_error(details: &str) {
+    ui::print_section("Derive Error");
+    println!();
+
+    let quoted = regexes::BACKTICK_NAME
+        .captures(details)
+        .map(|c| c[1].to_string());
+
+    if details.contains("cannot find derive macro") {
+        let name = quoted.unwrap_or_default();
+        if name == "Serialize" || name == "Deserialize" {
+            ui::print_diff(
+                "#[derive(Serialize)]",
+                "use serde::Serialize;\n\n#[derive(Serialize)]",
+            );
+            ui::print_fix_instruction(
+                "Serialize and Deserialize come from serde's derive feature.\n\n\
+                Fix:\n\n\
+                1. Add serde with the feature:\n\
+                   cargo add serde --features derive\n\n\
+                2. Import the macro:\n\
+                   use serde::{Serialize, Deserialize};",
+            );
+        } else {
+            ui::print_fix_instruction(&format!(
+                "The derive macro '{}' is not in scope.\n\n\
+                Import it (use some_crate::{};) and make sure the crate that\n\
+                provides it is in Cargo.toml, with any feature it needs enabled.",
+                name, name
+            ));
+        }
+        return;
+    }
+
+    // "the trait bound `Bar: Clone` is not satisfied"
+    let (field_type, trait_name) = quoted
+        .as_deref()
+        .and_then(|bound| bound.split_once(": "))
+        .map(|(t, b)| (t.to_string(), b.to_string()))
+        .unwrap_or_else(|| ("FieldType".to_string(), "Trait".to_string()));
+
+    ui::print_diff(
+        &format!("struct {} {{ ... }}", field_type),
+        &format!("#[derive({})]\nstruct {} {{ ... }}", trait_name, field_type),
+    );
+    ui::print_fix_instruction(&format!(
+        "#[derive({})] only works when every field implements {} too,\n\
+        and '{}' doesn't.\n\n\
+        Fix:\n\n\
+        1. Derive {} on '{}' as well\n\n\
+        2. Or implement {} by hand for the type with the derive",
+        trait_name, trait_name, field_type, trait_name, field_type, trait_name
+    ));
+}
+
+fn fix_format_error(details: &str) {
+    ui::print_section("Format String Error");
+    println!();
+
+    if details.contains("doesn't implement") {
+        ui::print_diff("println!(\"{}\", value);", "println!(\"{:?}\", value);");
+        ui::print_fix_instruction(
+            "{} formats with Display, which the type doesn't implement.\n\n\
+            Fix:\n\n\
+            1. Print it for debugging with {:?} and #[derive(Debug)] on the type\n\n\
+            2. Or implement std::fmt::Display for the type to control its output",
+        );
+    } else if details.contains("never used") || details.contains("unused formatting") {
+        ui::print_diff("println!(\"{}\", a, b);", "println!(\"{} {}\", a, b);");
+        ui::print_fix_instruction(
+            "More arguments were passed than the format string uses.\n\n\
+            Add a {} for each argument, or remove the arguments you don't need.",
+        );
+    } else if details.contains("positional argument") {
+        ui::print_diff("println!(\"{} {}\", a);", "println!(\"{} {}\", a, b);");
+        ui::print_fix_instruction(
+            "The format string has more {} placeholders than arguments.\n\n\
+            Pass one argument per {}, or name them inline: println!(\"{a} {b}\").",
+        );
+    } else {
+        ui::print_fix_instruction(&format!(
+            "{}\n\n\
+            Check that the format string's {{}} placeholders match the arguments.\n\
+            Write a literal brace as {{{{ or }}}}.",
+            details
+        ));
+    }
+}
+
+fn fix_vulnerability(advice: &str, language: &Language) {
+    ui::print_section("Vulnerable Dependency");
+    println!();
+
+    let (check, why) = match language {
+        Language::Rust => ("cargo audit", "cargo tree -i <crate>"),
+        _ => ("npm audit", "npm ls <package>"),
+    };
+    ui::print_fix_instruction(&format!(
+        "{}\n\n\
+        Run '{}' afterwards to confirm the advisory is gone.\n\
+        '{}' shows which of your dependencies pulls the package in.",
+        advice, check, why
+    ));
+}
+
+fn fix_missing_license_header(header: &str) {
+    ui::print_section("Missing License Header");
+    println!();
+
+    ui::print_diff("", &format!("{}\n", header));
+    ui::print_fix_instruction(
+        "The project requires every source file to start with its license header\n\
+        (policy.license_header). Put it at the very top, after any #! line.\n\n\
+        'ess find-bug --walk' can insert it for you.",
+    );
+}
+
+fn fix_policy_violation(rule: &str) {
+    ui::print_section("Policy Violation");
+    println!();
+
+    match rule {
+        "no-print" => ui::print_fix_instruction(
+            "Debug output is not allowed in this part of the project (policy.no_print).\n\n\
+            Remove it, or use the project's logger so the output can be turned off.",
+        ),
+        "use-strict" => {
+            ui::print_diff(
+                "const app = express();\n",
+                "'use strict';\n\nconst app = express();\n",
+            );
+            ui::print_fix_instruction(
+                "Scripts must opt into strict mode (policy.require_use_strict).\n\n\
+                Add 'use strict'; as the first statement. 'ess find-bug --walk' can add it.",
+            );
+        }
+        "max-file-length" => ui::print_fix_instruction(
+            "The file is longer than policy.max_file_lines allows.\n\n\
+            Move self-contained parts (helpers, types, constants) into their own modules.",
+        ),
+        _ => ui::print_fix_instruction(&format!(
+            "The file breaks the '{}' rule in the [policy] section of .essentialscode.toml.",
+            rule
+        )),
+    }
+}
+
+fn fix_unformatted(command: &str) {
+    ui::print_section("Formatting");
+    println!();
+
+    ui::print_fix_instruction(&format!(
+        "The file mixes tabs and spaces or has very long lines.\n\n\
+        Run '{}', or let 'ess find-bug --walk' run the formatter and show the diff first. \
+        Long strings and comments may still need to be split by hand.",
+        command
+    ));
+}
+
+fn fix_assertion_failed(comparison: &str) {
+    ui::print_section("Failed Assertion");
+    println!();
+
+    ui::print_fix_instruction(&format!(
+        "The test got a different value than it expected:\n\n  {}\n\n\
+        If the code is right and the expectation is out of date, update the test. \
+        Otherwise fix the code the test calls - run the test on its own to step through it.",
+        comparison.replace('\n', "\n  ")
+    ));
+}
+
+/// The manifest or toolchain change for a `RustVersion` error, with the
+/// project's Cargo.toml read from the working directory on
+fn fix_rust_version(requirement: &str, file: &str) {
+    ui::print_section("Rust Edition and Version");
+    println!();
+
+    let manifest = msrv::Manifest::find(Path::new("."), file);
+    if let Some(manifest) = &manifest {
+        ui::print_info(&format!(
+            "{}: edition {}, rust-version {}",
+            manifest.path.display(),
+            manifest.edition(),
+            manifest.rust_version.as_deref().unwrap_or("not set")
+        ));
+        println!();
+    }
+    ui::print_fix_instruction(&msrv::advice(requirement, manifest.as_ref()));
+}
+
+pub fn fix_custom(fix: &CustomFix) {
+    ui::print_section(&fix.title);
+    println!();
+
+    if let Some((_, line)) = &fix.insert {
+        ui::print_diff("", &format!("{}\n", line));
+    }
+    ui::print_fix_instruction(&fix.fix);
 }
 
 fn fix_requests_error(details: &str) {
@@ -535,27 +1215,43 @@ fn fix_requests_error(details: &str) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_read_error_text() {
+        let mut log: &[u8] =
+            b"\x1b[1mmain.cpp:3:5: \x1b[31merror:\x1b[0m expected ';'\r\nbuilt by \xff\n";
+        let text = read_error_text(&mut log).unwrap();
+        assert_eq!(
+            text,
+            "main.cpp:3:5: error: expected ';'\r\nbuilt by \u{FFFD}\n"
+        );
+        assert_eq!(
+            parse_error(&text).unwrap().error_type,
+            ErrorType::MissingSemicolon
+        );
+    }
+
     // ==================== try_common_patterns Tests ====================
 
     #[test]
     fn test_pattern_missing_semicolon() {
         let result = try_common_patterns("expected ';' before return");
         assert!(result.is_some());
-        assert!(result.unwrap().contains("semicolon"));
+        assert_eq!(result.unwrap().0, "COM-SYN-001");
+        assert!(result.unwrap().1.contains("semicolon"));
     }
 
     #[test]
     fn test_pattern_missing_semicolon_variant() {
         let result = try_common_patterns("missing semicolon at end of line");
         assert!(result.is_some());
-        assert!(result.unwrap().contains("semicolon"));
+        assert!(result.unwrap().1.contains("semicolon"));
     }
 
     #[test]
     fn test_pattern_not_a_member() {
         let result = try_common_patterns("'vector' is not a member of 'std'");
         assert!(result.is_some());
-        let msg = result.unwrap();
+        let (_, msg) = result.unwrap();
         assert!(msg.contains("import") || msg.contains("include"));
     }
 
@@ -563,7 +1259,7 @@ mod tests {
     fn test_pattern_was_not_declared() {
         let result = try_common_patterns("'myVar' was not declared in this scope");
         assert!(result.is_some());
-        let msg = result.unwrap();
+        let (_, msg) = result.unwrap();
         assert!(msg.contains("import") || msg.contains("include"));
     }
 
@@ -571,7 +1267,7 @@ mod tests {
     fn test_pattern_is_not_defined() {
         let result = try_common_patterns("ReferenceError: x is not defined");
         assert!(result.is_some());
-        let msg = result.unwrap();
+        let (_, msg) = result.unwrap();
         assert!(msg.contains("define") || msg.contains("declare"));
     }
 
@@ -585,7 +1281,7 @@ mod tests {
     fn test_pattern_unexpected_token() {
         let result = try_common_patterns("SyntaxError: unexpected token '}'");
         assert!(result.is_some());
-        let msg = result.unwrap();
+        let (_, msg) = result.unwrap();
         assert!(msg.contains("bracket") || msg.contains("Syntax"));
     }
 
@@ -663,8 +1359,7 @@ mod tests {
     // ==================== ErrorType Handling Tests ====================
 
     #[test]
-    fn test_error_type_variants_exist() {
-        // Verify all error types can be matched
+    fn test_every_error_type_has_rule() {
         let types = vec![
             ErrorType::MissingInclude("test".to_string()),
             ErrorType::MissingSemicolon,
@@ -680,10 +1375,35 @@ mod tests {
             ErrorType::ValueError("val".to_string()),
             ErrorType::MissingEnvVar("VAR".to_string()),
             ErrorType::RequestsError("req".to_string()),
+            ErrorType::UnclosedTemplateBlock("for".to_string()),
+            ErrorType::UndefinedTemplateVariable("user".to_string()),
+            ErrorType::DeriveError("derive".to_string()),
+            ErrorType::FormatError("format".to_string()),
+            ErrorType::MissingImport("java.util.List".to_string()),
+            ErrorType::NullPointer("\"name\" is null".to_string()),
+            ErrorType::PortInUse(Some(3000)),
+            ErrorType::Vulnerability("upgrade".to_string()),
+            ErrorType::MissingLicenseHeader("// SPDX".to_string()),
+            ErrorType::PolicyViolation("no-print".to_string()),
+            ErrorType::Unformatted("rustfmt src/main.rs".to_string()),
+            ErrorType::AssertionFailed("left: 3\nright: 4".to_string()),
+            ErrorType::Custom(CustomFix {
+                title: "title".to_string(),
+                fix: "fix".to_string(),
+                insert: None,
+            }),
             ErrorType::Unknown("unknown".to_string()),
         ];
 
-        assert_eq!(types.len(), 15);
+        assert_eq!(types.len(), 28);
+        let rules = Registry::new();
+        for error_type in &types {
+            assert!(
+                rules.rule(error_type.name()).is_some(),
+                "no rule for {}",
+                error_type.name()
+            );
+        }
     }
 
     // ==================== Integration-style Tests ====================
@@ -700,20 +1420,50 @@ SyntaxError: invalid syntax"#,
         ];
 
         for case in test_cases {
-            let result = analyze_error(case);
+            let result = analyze_error(case, &Registry::new());
             assert!(result.is_ok());
         }
     }
 
+    #[test]
+    fn test_analyze_errors_explains_each_error() {
+        let output = "\
+a.cpp:3:5: error: 'cout' is not a member of 'std'
+a.cpp:4:1: warning: unused variable 'x'
+a.cpp:9:2: error: expected ';' before 'return'
+";
+        let errors = analyze_errors(output, &Registry::new()).unwrap();
+        let kinds: Vec<&str> = errors.iter().map(|(_, e)| e.error_type.name()).collect();
+        assert_eq!(kinds, vec!["MissingInclude", "MissingSemicolon"]);
+
+        let single = analyze_errors("main.cpp:10:5: error: expected ';'", &Registry::new());
+        assert_eq!(single.unwrap().len(), 1);
+        assert!(analyze_errors("random text", &Registry::new())
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_analyze_error_handles_unknown_format() {
-        let result = analyze_error("completely random text");
+        let result = analyze_error("completely random text", &Registry::new());
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_suggest() {
+        let error = parse_error("main.cpp:3:5: error: 'cout' is not a member of 'std'").unwrap();
+        let suggestion = suggest(&error, Path::new("/nonexistent"));
+
+        assert_eq!(suggestion.kind, "MissingInclude");
+        assert_eq!(suggestion.entry.unwrap().kind, "MissingInclude");
+        assert!(suggestion.example.is_none());
+        // There is no file to edit
+        assert!(suggestion.fix.is_none());
+    }
+
     #[test]
     fn test_analyze_error_handles_empty_input() {
-        let result = analyze_error("");
+        let result = analyze_error("", &Registry::new());
         assert!(result.is_ok());
     }
 }
@@ -0,0 +1,233 @@
+use crate::diff::{self, Line};
+use crate::parser::Language;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A formatter that reads a file on stdin and prints it formatted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formatter {
+    pub name: &'static str,
+    program: &'static str,
+    args: Vec<String>,
+}
+
+/// The usual formatter for a file's language. It runs in the file's
+/// directory, so the file is passed by name.
+pub fn formatter(language: &Language, file: &Path) -> Option<Formatter> {
+    let file = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (name, program, args) = match language {
+        Language::Rust => ("rustfmt", "rustfmt", vec!["--edition", "2021"]),
+        Language::Python => ("black", "black", vec!["-q", "--stdin-filename", &file, "-"]),
+        Language::JavaScript | Language::TypeScript => (
+            "prettier",
+            "npx",
+            vec!["--no-install", "prettier", "--stdin-filepath", &file],
+        ),
+        Language::Cpp => (
+            "clang-format",
+            "clang-format",
+            vec!["--assume-filename", &file],
+        ),
+        Language::Java => ("google-java-format", "google-java-format", vec!["-"]),
+        // dotnet format only rewrites files in place
+        Language::CSharp | Language::Unknown => return None,
+    };
+    Some(Formatter {
+        name,
+        program,
+        args: args.into_iter().map(String::from).collect(),
+    })
+}
+
+impl Formatter {
+    /// The command that formats `file` in place, for messages
+    pub fn command_line(&self, file: &Path) -> String {
+        match self.name {
+            "prettier" => format!("npx prettier --write {}", file.display()),
+            "clang-format" => format!("clang-format -i {}", file.display()),
+            "google-java-format" => format!("google-java-format -i {}", file.display()),
+            _ => format!("{} {}", self.name, file.display()),
+        }
+    }
+
+    /// The formatted content of `file`, or None when the formatter isn't
+    /// installed or can't parse the file. Runs next to the file so the
+    /// project's formatter config is picked up.
+    pub fn format(&self, file: &Path) -> Option<String> {
+        let input = std::fs::File::open(file).ok()?;
+        let mut cmd = Command::new(self.program);
+        cmd.args(&self.args)
+            .stdin(input)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        if let Some(dir) = file.parent().filter(|d| !d.as_os_str().is_empty()) {
+            cmd.current_dir(dir);
+        }
+
+        let output = cmd.output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    }
+}
+
+/// A sign that a file isn't formatted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub line: u32,
+    pub message: String,
+}
+
+/// Mixed tab/space indentation and lines over `max_line_length` characters
+/// (0 turns the length check off)
+pub fn issues(content: &str, max_line_length: usize) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let mut tabs = Vec::new();
+    let mut spaces = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let indent: String = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        let rest = &line[indent.len()..];
+        // " * text" continues a block comment and is aligned, not indented
+        if indent.is_empty() || rest.is_empty() || (indent == " " && rest.starts_with('*')) {
+            continue;
+        }
+        if indent.contains('\t') {
+            tabs.push(i as u32 + 1);
+        } else {
+            spaces.push(i as u32 + 1);
+        }
+    }
+    if !tabs.is_empty() && !spaces.is_empty() {
+        // Point at the first line of the less common style
+        let first = if tabs.len() <= spaces.len() {
+            tabs[0]
+        } else {
+            spaces[0]
+        };
+        issues.push(Issue {
+            line: first,
+            message: format!(
+                "Indentation mixes tabs and spaces ({} {} tabs, {} spaces)",
+                tabs.len(),
+                if tabs.len() == 1 {
+                    "line uses"
+                } else {
+                    "lines use"
+                },
+                spaces.len()
+            ),
+        });
+    }
+
+    if max_line_length > 0 {
+        let long: Vec<(usize, usize)> = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i, line.chars().count()))
+            .filter(|(_, length)| *length > max_line_length)
+            .collect();
+        if let Some((first, length)) = long.first() {
+            let message = if long.len() == 1 {
+                format!(
+                    "Line is {} characters long, over the {} limit",
+                    length, max_line_length
+                )
+            } else {
+                format!(
+                    "{} lines are longer than {} characters",
+                    long.len(),
+                    max_line_length
+                )
+            };
+            issues.push(Issue {
+                line: *first as u32 + 1,
+                message,
+            });
+        }
+    }
+
+    issues
+}
+
+/// Number of lines a reformat touches, counting each replaced line once
+pub fn changed_lines(before: &str, after: &str) -> usize {
+    let (mut removed, mut added) = (0, 0);
+    for line in diff::unified(before, after) {
+        match line {
+            Line::Removed(_) => removed += 1,
+            Line::Added(_) => added += 1,
+            Line::Equal(_) | Line::Gap => {}
+        }
+    }
+    removed.max(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_indentation() {
+        let content = "def f():\n    a = 1\n\tb = 2\n    return a\n";
+        let issues = issues(content, 0);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 3);
+        assert_eq!(
+            issues[0].message,
+            "Indentation mixes tabs and spaces (1 line uses tabs, 2 spaces)"
+        );
+    }
+
+    #[test]
+    fn test_consistent_indentation_and_comment_blocks() {
+        let content = "/**\n * Docs\n */\nint main() {\n\treturn 0;\n}\n";
+        assert!(issues(content, 120).is_empty());
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let long = "x".repeat(130);
+        let one = format!("a\n{}\n", long);
+        assert_eq!(
+            issues(&one, 120),
+            vec![Issue {
+                line: 2,
+                message: "Line is 130 characters long, over the 120 limit".to_string(),
+            }]
+        );
+
+        let two = format!("{}\nb\n{}\n", long, long);
+        let found = issues(&two, 120);
+        assert_eq!(found[0].line, 1);
+        assert_eq!(found[0].message, "2 lines are longer than 120 characters");
+
+        assert!(issues(&two, 0).is_empty());
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        assert_eq!(changed_lines("a\nb\n", "a\nb\n"), 0);
+        assert_eq!(changed_lines("a\n\tb\nc\n", "a\n    b\nc\n"), 1);
+        assert_eq!(changed_lines("f(a,\n  b)\n", "f(a, b)\n"), 2);
+    }
+
+    #[test]
+    fn test_formatter_per_language() {
+        let file = Path::new("src/app.ts");
+        assert_eq!(
+            formatter(&Language::TypeScript, file).unwrap().name,
+            "prettier"
+        );
+        assert_eq!(formatter(&Language::Rust, file).unwrap().name, "rustfmt");
+        assert!(formatter(&Language::Unknown, file).is_none());
+    }
+}
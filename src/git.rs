@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run git in `path` and return trimmed stdout, or None if git failed
+fn git(path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(args)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Commit hash of HEAD, if `path` is inside a git repository
+pub fn head_commit(path: &Path) -> Option<String> {
+    git(path, &["rev-parse", "HEAD"]).filter(|s| !s.is_empty())
+}
+
+/// Number of commits between `commit` and HEAD
+pub fn commits_since(path: &Path, commit: &str) -> Option<usize> {
+    git(path, &["rev-list", "--count", &format!("{}..HEAD", commit)])?
+        .parse()
+        .ok()
+}
+
+/// Files changed since `commit`, including uncommitted and untracked files,
+/// relative to `path`. Before the first commit, HEAD has nothing in it, so
+/// every file is changed since.
+pub fn changed_files_since(path: &Path, commit: &str) -> Option<Vec<String>> {
+    let unborn = commit == "HEAD" && head_commit(path).is_none();
+    let mut files = if unborn {
+        git_paths(path, &["ls-files", "-z", "--cached"])?
+    } else {
+        git_paths(
+            path,
+            &[
+                "diff",
+                "--name-only",
+                "--relative",
+                "-z",
+                "--end-of-options",
+                commit,
+            ],
+        )?
+    };
+
+    if let Some(untracked) = git_paths(path, &["ls-files", "-z", "--others", "--exclude-standard"])
+    {
+        files.extend(untracked);
+    }
+
+    files.sort();
+    files.dedup();
+    Some(files)
+}
+
+/// Paths a `-z` git command lists, exactly as named (no quoting of
+/// unusual characters, no trimming)
+fn git_paths(path: &Path, args: &[&str]) -> Option<Vec<String>> {
+    let output = Command::new("git")
+        .current_dir(path)
+        .args(args)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(
+        output
+            .stdout
+            .split(|&b| b == 0)
+            .filter(|p| !p.is_empty())
+            .map(|p| String::from_utf8_lossy(p).to_string())
+            .collect(),
+    )
+}
+
+/// Root of the repository `path` is in, and where `path` is inside it,
+/// e.g. "app/" (empty at the root)
+pub fn repository(path: &Path) -> Option<(PathBuf, String)> {
+    let root = git(path, &["rev-parse", "--show-toplevel"])?;
+    let prefix = git(path, &["rev-parse", "--show-prefix"])?;
+    Some((PathBuf::from(root), prefix))
+}
+
+/// Directory git runs hooks from, following `core.hooksPath`
+pub fn hooks_dir(path: &Path) -> Option<PathBuf> {
+    let dir = PathBuf::from(git(path, &["rev-parse", "--git-path", "hooks"])?);
+    Some(if dir.is_absolute() {
+        dir
+    } else {
+        path.join(dir)
+    })
+}
+
+/// Whether `rev` names a commit in the repository at `path`
+pub fn is_commit(path: &Path, rev: &str) -> bool {
+    git(
+        path,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .is_some()
+}
+
+/// Check `rev` out into a new worktree at `dir`, detached from any branch
+pub fn add_worktree(repo: &Path, dir: &Path, rev: &str) -> Option<()> {
+    let dir = dir.to_string_lossy();
+    git(repo, &["worktree", "add", "--detach", "--quiet", &dir, rev]).map(|_| ())
+}
+
+/// Remove a worktree `add_worktree` made, even with changes in it
+pub fn remove_worktree(repo: &Path, dir: &Path) {
+    let dir_str = dir.to_string_lossy();
+    if git(repo, &["worktree", "remove", "--force", &dir_str]).is_none() {
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = git(repo, &["worktree", "prune"]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_head_commit_outside_repo() {
+        let temp_dir = std::env::temp_dir().join("ess_git_no_repo");
+        let _ = std::fs::create_dir_all(&temp_dir);
+
+        // temp dir is normally not inside a repository
+        if git(&temp_dir, &["rev-parse", "--is-inside-work-tree"]).is_none() {
+            assert!(head_commit(&temp_dir).is_none());
+        }
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_changed_files_since() {
+        let temp_dir = std::env::temp_dir().join("ess_test_git_changed");
+        let sub = temp_dir.join("app");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(sub.join("kept.py"), "x = 1\n").unwrap();
+        std::fs::write(sub.join("edited.py"), "x = 1\n").unwrap();
+        std::fs::write(temp_dir.join("outside.py"), "x = 1\n").unwrap();
+        let committed = git(&temp_dir, &["init", "-q"]).is_some()
+            && git(&temp_dir, &["add", "."]).is_some()
+            && git(
+                &temp_dir,
+                &[
+                    "-c",
+                    "user.name=ess",
+                    "-c",
+                    "user.email=ess@example.com",
+                    "commit",
+                    "-q",
+                    "-m",
+                    "start",
+                ],
+            )
+            .is_some();
+        std::fs::write(sub.join("edited.py"), "x = 2\n").unwrap();
+        std::fs::write(sub.join("new.py"), "y = 1\n").unwrap();
+        std::fs::write(temp_dir.join("outside.py"), "x = 2\n").unwrap();
+        let changed = changed_files_since(&sub, "HEAD");
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        // Only where git is installed
+        if committed {
+            assert_eq!(
+                changed,
+                Some(vec!["edited.py".to_string(), "new.py".to_string()])
+            );
+        }
+    }
+
+    #[test]
+    fn test_changed_files_before_first_commit() {
+        let temp_dir = std::env::temp_dir().join("ess_test_git_unborn");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("staged.py"), "x = 1\n").unwrap();
+        std::fs::write(temp_dir.join("new.py"), "y = 1\n").unwrap();
+        let staged = git(&temp_dir, &["init", "-q"]).is_some()
+            && git(&temp_dir, &["add", "staged.py"]).is_some();
+        let changed = changed_files_since(&temp_dir, "HEAD");
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        // Only where git is installed
+        if staged {
+            assert_eq!(
+                changed,
+                Some(vec!["new.py".to_string(), "staged.py".to_string()])
+            );
+        }
+    }
+
+    #[test]
+    fn test_changed_files_with_unusual_names() {
+        let temp_dir = std::env::temp_dir().join("ess_test_git_names");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("kept.py"), "x = 1\n").unwrap();
+        let committed = git(&temp_dir, &["init", "-q"]).is_some()
+            && git(&temp_dir, &["add", "."]).is_some()
+            && git(
+                &temp_dir,
+                &[
+                    "-c",
+                    "user.name=ess",
+                    "-c",
+                    "user.email=ess@example.com",
+                    "commit",
+                    "-q",
+                    "-m",
+                    "start",
+                ],
+            )
+            .is_some();
+        std::fs::write(temp_dir.join("zażółć.py"), "y = 1\n").unwrap();
+        std::fs::write(temp_dir.join(" spaced .py"), "y = 1\n").unwrap();
+        let changed = changed_files_since(&temp_dir, "HEAD");
+        let option = changed_files_since(&temp_dir, "--output=stolen");
+        let written = temp_dir.join("stolen").exists();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        // Only where git is installed
+        if committed {
+            assert_eq!(
+                changed,
+                Some(vec![" spaced .py".to_string(), "zażółć.py".to_string()])
+            );
+            assert!(option.is_none());
+            assert!(!written);
+        }
+    }
+
+    #[test]
+    fn test_git_missing_directory() {
+        let fake_path = Path::new("/nonexistent/path/for/git");
+        assert!(head_commit(fake_path).is_none());
+        assert!(changed_files_since(fake_path, "HEAD").is_none());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::scanner;
+use crate::state::LastScan;
+use crate::ui;
+use anyhow::Result;
+use std::path::Path;
+
+/// Maximum points lost for error density
+const DENSITY_PENALTY_MAX: f64 = 60.0;
+
+/// Points lost per error per 1000 lines of code
+const DENSITY_PENALTY_PER_ERROR: f64 = 10.0;
+
+/// Maximum points lost when no errors have an automatic fix
+const UNFIXABLE_PENALTY_MAX: f64 = 20.0;
+
+/// Points lost (or gained) when errors went up (or down) since the last scan
+const TREND_POINTS: f64 = 10.0;
+
+/// Project health derived from scan results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthScore {
+    /// 0 (worst) to 100 (best)
+    pub score: u8,
+    pub grade: char,
+}
+
+impl HealthScore {
+    /// Score a scan. `previous_errors` is the error count of the last scan, if any.
+    pub fn compute(
+        errors: usize,
+        fixable: usize,
+        lines_of_code: usize,
+        previous_errors: Option<usize>,
+    ) -> Self {
+        let mut score = 100.0;
+
+        if errors > 0 {
+            let kloc = (lines_of_code as f64 / 1000.0).max(0.1);
+            let density = errors as f64 / kloc;
+            score -= (density * DENSITY_PENALTY_PER_ERROR).min(DENSITY_PENALTY_MAX);
+
+            let fixable_ratio = fixable.min(errors) as f64 / errors as f64;
+            score -= (1.0 - fixable_ratio) * UNFIXABLE_PENALTY_MAX;
+        }
+
+        match previous_errors {
+            Some(prev) if errors > prev => score -= TREND_POINTS,
+            Some(prev) if errors < prev => score += TREND_POINTS / 2.0,
+            _ => {}
+        }
+
+        let score = score.round().clamp(0.0, 100.0) as u8;
+        Self {
+            score,
+            grade: grade_for(score),
+        }
+    }
+
+    /// Badge color matching the grade
+    pub fn color(&self) -> &'static str {
+        match self.grade {
+            'A' => "#4c1",
+            'B' => "#97ca00",
+            'C' => "#dfb317",
+            'D' => "#fe7d37",
+            _ => "#e05d44",
+        }
+    }
+
+    /// Shields-style flat SVG badge
+    pub fn badge_svg(&self) -> String {
+        let label = "ess health";
+        let value = format!("{} {}", self.score, self.grade);
+
+        // Rough width of Verdana 11px text
+        let label_width = label.len() * 7 + 10;
+        let value_width = value.len() * 7 + 10;
+        let width = label_width + value_width;
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+  <title>{label}: {value}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{value_x}" y="14">{value}</text>
+  </g>
+</svg>
+"##,
+            width = width,
+            label = label,
+            value = value,
+            label_width = label_width,
+            value_width = value_width,
+            color = self.color(),
+            label_x = label_width / 2,
+            value_x = label_width + value_width / 2,
+        )
+    }
+}
+
+fn grade_for(score: u8) -> char {
+    match score {
+        90..=100 => 'A',
+        75..=89 => 'B',
+        60..=74 => 'C',
+        40..=59 => 'D',
+        _ => 'F',
+    }
+}
+
+/// Write a badge for the last scan of a project
+pub fn write_badge(path: &Path, output: &Path) -> Result<()> {
+    ui::print_section("Health Badge");
+
+    let path = scanner::normalize_path(path);
+    let last = match LastScan::load(&path)? {
+        Some(last) => last,
+        None => {
+            ui::print_warning("This project has not been scanned yet");
+            ui::print_hint("Run 'ess find-bug' first, then generate the badge");
+            return Ok(());
+        }
+    };
+
+    let health = last.health();
+    std::fs::write(output, health.badge_svg())?;
+
+    ui::print_info(&format!(
+        "Health: {}/100 (grade {})",
+        health.score, health.grade
+    ));
+    ui::print_success(&format!("Badge written to {}", output.display()));
+    ui::print_hint(&format!(
+        "Add it to your README: ![ess health]({})",
+        output.display()
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_project_is_perfect() {
+        let health = HealthScore::compute(0, 0, 5000, None);
+        assert_eq!(health.score, 100);
+        assert_eq!(health.grade, 'A');
+    }
+
+    #[test]
+    fn test_density_penalty_is_capped() {
+        let health = HealthScore::compute(1000, 1000, 100, None);
+        assert_eq!(health.score, 40);
+    }
+
+    #[test]
+    fn test_unfixable_errors_cost_more() {
+        let fixable = HealthScore::compute(2, 2, 10_000, None);
+        let unfixable = HealthScore::compute(2, 0, 10_000, None);
+        assert!(fixable.score > unfixable.score);
+    }
+
+    #[test]
+    fn test_trend_vs_baseline() {
+        let base = HealthScore::compute(3, 3, 10_000, None);
+        let worse = HealthScore::compute(3, 3, 10_000, Some(1));
+        let better = HealthScore::compute(3, 3, 10_000, Some(8));
+        assert!(worse.score < base.score);
+        assert!(better.score > base.score);
+    }
+
+    #[test]
+    fn test_grades() {
+        assert_eq!(grade_for(95), 'A');
+        assert_eq!(grade_for(80), 'B');
+        assert_eq!(grade_for(65), 'C');
+        assert_eq!(grade_for(45), 'D');
+        assert_eq!(grade_for(10), 'F');
+    }
+
+    #[test]
+    fn test_badge_svg() {
+        let health = HealthScore::compute(0, 0, 100, None);
+        let svg = health.badge_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("100 A"));
+        assert!(svg.contains("#4c1"));
+    }
+}
@@ -0,0 +1,170 @@
+//! Just enough syntax highlighting for the snippets in `ess docs`:
+//! keywords, strings, comments and numbers, one line at a time
+
+use crate::parser::Language;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token {
+    Plain,
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+impl Token {
+    /// CSS class for the HTML docs
+    pub fn class(self) -> Option<&'static str> {
+        match self {
+            Token::Plain => None,
+            Token::Keyword => Some("kw"),
+            Token::String => Some("str"),
+            Token::Comment => Some("com"),
+            Token::Number => Some("num"),
+        }
+    }
+}
+
+fn keywords(language: &Language) -> &'static [&'static str] {
+    match language {
+        Language::Python => &[
+            "and", "as", "def", "elif", "else", "except", "False", "for", "from", "if", "import",
+            "in", "is", "None", "not", "or", "raise", "return", "True", "try", "while", "with",
+        ],
+        Language::Rust => &[
+            "as", "enum", "fn", "for", "if", "impl", "in", "let", "match", "mut", "pub", "return",
+            "Self", "self", "struct", "use", "where",
+        ],
+        Language::JavaScript | Language::TypeScript => &[
+            "async",
+            "await",
+            "const",
+            "else",
+            "export",
+            "false",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "let",
+            "new",
+            "null",
+            "return",
+            "true",
+            "undefined",
+            "var",
+        ],
+        Language::Java | Language::CSharp => &[
+            "class", "else", "false", "for", "if", "import", "int", "new", "null", "private",
+            "public", "return", "static", "String", "true", "using", "var", "void",
+        ],
+        Language::Cpp => &[
+            "auto",
+            "class",
+            "const",
+            "else",
+            "for",
+            "if",
+            "include",
+            "int",
+            "namespace",
+            "return",
+            "std",
+            "struct",
+            "void",
+        ],
+        Language::Unknown => &[],
+    }
+}
+
+fn comment_start(language: &Language) -> &'static str {
+    match language {
+        Language::Python => "#",
+        _ => "//",
+    }
+}
+
+/// `line` split into tokens, in order, covering all of it
+pub fn tokens<'a>(line: &'a str, language: &Language) -> Vec<(Token, &'a str)> {
+    let keywords = keywords(language);
+    let comment = comment_start(language);
+    let mut tokens: Vec<(Token, &str)> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(c) = line[pos..].chars().next() {
+        let rest = &line[pos..];
+        let (token, len) = if rest.starts_with(comment) {
+            (Token::Comment, rest.len())
+        } else if c == '"' || c == '\'' || c == '`' {
+            // To the closing quote, skipping escaped ones
+            let mut escaped = false;
+            let end = rest[1..]
+                .char_indices()
+                .find(|&(_, d)| {
+                    let closes = d == c && !escaped;
+                    escaped = d == '\\' && !escaped;
+                    closes
+                })
+                .map_or(rest.len(), |(i, _)| i + 2);
+            (Token::String, end)
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|d: char| !d.is_alphanumeric() && d != '_')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let token = if c.is_ascii_digit() {
+                Token::Number
+            } else if keywords.contains(&word) {
+                Token::Keyword
+            } else {
+                Token::Plain
+            };
+            (token, end)
+        } else {
+            (Token::Plain, c.len_utf8())
+        };
+
+        let end = pos + len;
+        match tokens.last_mut() {
+            // Runs of plain text stay one token
+            Some((Token::Plain, text)) if token == Token::Plain => {
+                *text = &line[pos - text.len()..end];
+            }
+            _ => tokens.push((token, &line[pos..end])),
+        }
+        pos = end;
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let python = tokens(
+            "value = data.get(\"it's\", 0)  # default",
+            &Language::Python,
+        );
+        assert_eq!(
+            python,
+            vec![
+                (Token::Plain, "value = data.get("),
+                (Token::String, "\"it's\""),
+                (Token::Plain, ", "),
+                (Token::Number, "0"),
+                (Token::Plain, ")  "),
+                (Token::Comment, "# default"),
+            ]
+        );
+
+        let rust = tokens("let s = \"a\\\"b\"; // x", &Language::Rust);
+        assert_eq!(rust[0], (Token::Keyword, "let"));
+        assert_eq!(rust[2], (Token::String, "\"a\\\"b\""));
+        assert_eq!(rust.last(), Some(&(Token::Comment, "// x")));
+        let joined: String = rust.iter().map(|(_, text)| *text).collect();
+        assert_eq!(joined, "let s = \"a\\\"b\"; // x");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::config::Config;
+use crate::parser::ParsedError;
+use crate::regexes;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// History file name (in the data directory)
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// One analyzed error, stored without paths or user names
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub language: String,
+    /// `ErrorType::name()` of the parsed error
+    pub kind: String,
+    pub message: String,
+}
+
+impl HistoryEntry {
+    pub fn from_parsed(error: &ParsedError) -> Self {
+        Self {
+            timestamp: now(),
+            language: error.language.to_string(),
+            kind: error.error_type.name().to_string(),
+            message: anonymize(&error.message),
+        }
+    }
+}
+
+/// Append-only store of past errors (JSON lines)
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// History stored in the user's data directory
+    pub fn open_default() -> Option<Self> {
+        Config::data_dir().map(|dir| Self::new(&dir.join(HISTORY_FILE_NAME)))
+    }
+
+    pub fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// All stored entries, oldest first. Corrupt lines are skipped.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&self.path)?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// Record a parsed error in the default history, ignoring failures
+pub fn record_error(error: &ParsedError) {
+    if let Some(history) = History::open_default() {
+        let _ = history.record(&HistoryEntry::from_parsed(error));
+    }
+}
+
+/// Strip directories (and with them user names) from paths in a message
+pub fn anonymize(message: &str) -> String {
+    regexes::PATH.replace_all(message, "$1").to_string()
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ErrorType, Language};
+
+    fn sample_error() -> ParsedError {
+        ParsedError {
+            file: "/home/alice/project/app.py".to_string(),
+            line: Some(3),
+            column: None,
+            end_line: None,
+            end_column: None,
+            message: "KeyError: 'token'".to_string(),
+            error_type: ErrorType::KeyError("'token'".to_string()),
+            language: Language::Python,
+        }
+    }
+
+    #[test]
+    fn test_anonymize_unix_path() {
+        let msg = anonymize("No such file: /home/alice/project/config.json");
+        assert_eq!(msg, "No such file: config.json");
+    }
+
+    #[test]
+    fn test_anonymize_windows_path() {
+        let msg = anonymize(r"cannot open C:\Users\KUBA\proj\main.cpp now");
+        assert_eq!(msg, "cannot open main.cpp now");
+    }
+
+    #[test]
+    fn test_anonymize_keeps_plain_text() {
+        let msg = anonymize("NameError: name 'foo' is not defined");
+        assert_eq!(msg, "NameError: name 'foo' is not defined");
+    }
+
+    #[test]
+    fn test_entry_from_parsed() {
+        let entry = HistoryEntry::from_parsed(&sample_error());
+        assert_eq!(entry.kind, "KeyError");
+        assert_eq!(entry.language, "Python");
+        assert!(!entry.message.contains("alice"));
+    }
+
+    #[test]
+    fn test_record_and_read_back() {
+        let temp_dir = std::env::temp_dir().join("ess_history_test");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let history = History::new(&temp_dir.join("history.jsonl"));
+        assert!(history.entries().unwrap().is_empty());
+
+        let entry = HistoryEntry::from_parsed(&sample_error());
+        history.record(&entry).unwrap();
+        history.record(&entry).unwrap();
+
+        let entries = history.entries().unwrap();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], entry);
+    }
+}
@@ -0,0 +1,197 @@
+//! `[hooks]`: commands of the user's own, run through the shell after a
+//! scan or a fix, e.g. to send a notification or start the next step of
+//! a pipeline. `{name}` placeholders in the command are replaced with the
+//! values below, shell-quoted, which are also in `ESS_<NAME>` environment
+//! variables.
+//!
+//! - `post_scan`: `{errors}`, `{warnings}`, `{fixable}`, `{project}` and
+//!   `{results}`, the scan saved for `ess replay`
+//! - `post_fix`: `{fixes}` applied, `{file}` fixed, `{errors}` and
+//!   `{warnings}` still in it, and `{project}`
+
+use crate::config::HooksConfig;
+use crate::finding::{Finding, Severity};
+use crate::replay::Session;
+use crate::scanner::ScanSummary;
+use crate::state;
+use crate::ui;
+use anyhow::Result;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Where a scan is saved for `post_scan` when `--save` isn't given
+const RESULTS_FILE_NAME: &str = "results.json";
+
+/// Run `post_scan` after a scan of `project`. `saved` is where `--save`
+/// put the results, if it was given.
+pub fn post_scan(
+    config: &HooksConfig,
+    project: &Path,
+    summary: &ScanSummary,
+    saved: Option<&Path>,
+) -> Result<()> {
+    let Some(command) = &config.post_scan else {
+        return Ok(());
+    };
+    let results = match saved {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let path = state::create_state_dir(project)?.join(RESULTS_FILE_NAME);
+            Session::scan(project, summary).save(&path)?;
+            path
+        }
+    };
+    let mut values = counts(&summary.findings);
+    values.push(("fixable", summary.fixable_errors().to_string()));
+    values.push(("project", project.display().to_string()));
+    values.push(("results", results.display().to_string()));
+    run("post_scan", command, &values);
+    Ok(())
+}
+
+/// Run `post_fix` after `fixes` fixes to `file` held. `check` is the
+/// re-check of the file.
+pub fn post_fix(
+    config: &HooksConfig,
+    project: &Path,
+    file: &Path,
+    fixes: usize,
+    check: &ScanSummary,
+) {
+    let Some(command) = &config.post_fix else {
+        return;
+    };
+    let mut values = counts(&check.findings);
+    values.push(("fixes", fixes.to_string()));
+    values.push(("file", file.display().to_string()));
+    values.push(("project", project.display().to_string()));
+    run("post_fix", command, &values);
+}
+
+fn counts(findings: &[Finding]) -> Vec<(&'static str, String)> {
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    vec![
+        ("errors", errors.to_string()),
+        ("warnings", (findings.len() - errors).to_string()),
+    ]
+}
+
+/// `command` with its `{name}` placeholders filled in. Values are quoted
+/// for the shell, so a file named e.g. `$(rm -rf ~).py` stays a name.
+pub fn expand(command: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(command.to_string(), |command, (name, value)| {
+            command.replace(&format!("{{{}}}", name), &quote(value))
+        })
+}
+
+/// A value as one shell word. Plain words, e.g. counts, are left as they
+/// are.
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | '=' | ',')
+        });
+    if plain {
+        value.to_string()
+    } else if cfg!(windows) {
+        // Windows paths can't hold quotes
+        format!("\"{}\"", value.replace('"', ""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Run a hook, warning when it fails. Its output and the warning go to
+/// stderr, so they can't end up in a report printed to stdout.
+fn run(name: &str, command: &str, values: &[(&str, String)]) -> bool {
+    let expanded = expand(command, values);
+    let mut shell = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    shell
+        .arg(&expanded)
+        .envs(
+            values
+                .iter()
+                .map(|(key, value)| (format!("ESS_{}", key.to_uppercase()), value)),
+        )
+        .stdin(Stdio::null())
+        .stdout(std::io::stderr());
+
+    match shell.status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            ui::eprint_warning(&format!("The {} hook failed ({})", name, status));
+            false
+        }
+        Err(e) => {
+            ui::eprint_warning(&format!("Could not run the {} hook: {}", name, e));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_placeholders() {
+        let values = vec![("errors", "3".to_string()), ("warnings", "1".to_string())];
+        assert_eq!(
+            expand(
+                "notify-send 'ess: {errors} errors, {warnings} {other}'",
+                &values
+            ),
+            "notify-send 'ess: 3 errors, 1 {other}'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_placeholders_are_quoted() {
+        let values = vec![
+            ("file", "src/$(touch pwned) it's.py".to_string()),
+            ("project", "/home/me/app".to_string()),
+        ];
+        assert_eq!(
+            expand("git add {file} && echo {project}", &values),
+            "git add 'src/$(touch pwned) it'\\''s.py' && echo /home/me/app"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_gets_values_in_environment() {
+        let temp_dir = std::env::temp_dir().join("ess_test_hooks_run");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let out = temp_dir.join("out.txt");
+        let values = vec![("errors", "2".to_string()), ("project", "demo".to_string())];
+
+        let ran = run(
+            "post_scan",
+            &format!("echo \"{{errors}} $ESS_PROJECT\" > '{}'", out.display()),
+            &values,
+        );
+        let written = std::fs::read_to_string(&out).unwrap_or_default();
+        let failed = run("post_scan", "exit 4", &values);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert!(ran);
+        assert_eq!(written, "2 demo\n");
+        assert!(!failed);
+    }
+}
@@ -0,0 +1,943 @@
+use crate::parser::Language;
+
+/// Plain-language knowledge about each error kind
+#[derive(Debug)]
+pub struct Entry {
+    /// Matches `ErrorType::name()`
+    pub kind: &'static str,
+    /// What the error means
+    pub meaning: &'static str,
+    /// The usual fix, in one or two sentences
+    pub fix: &'static str,
+    /// Longer background shown with `ess learn --explain`
+    pub explanation: &'static str,
+}
+
+const ENTRIES: &[Entry] = &[
+    Entry {
+        kind: "MissingInclude",
+        meaning: "A C++ name from the standard library is used, but the header that declares it was never included.",
+        fix: "Add the matching #include line (e.g. #include <vector>) at the top of the file.",
+        explanation: "C++ only knows about names that were declared before they are used. Standard types like \
+            std::vector or std::string live in headers, and each header has to be included explicitly - \
+            even if it compiles on one machine because another header happened to include it.",
+    },
+    Entry {
+        kind: "MissingSemicolon",
+        meaning: "A statement was not terminated with ';', so the compiler ran into the next statement.",
+        fix: "Add ';' at the end of the line the error points to (or the line just above it).",
+        explanation: "The compiler reports the error where it noticed the problem, which is often the start \
+            of the NEXT statement. Look one line up from the reported location first.",
+    },
+    Entry {
+        kind: "UndeclaredVariable",
+        meaning: "A name is used that was never defined in the current scope.",
+        fix: "Check the spelling, declare the variable before using it, or import it from its module.",
+        explanation: "Names are resolved by scope: a variable defined inside a function, loop or block is \
+            not visible outside of it. Typos and missing imports are the most common causes.",
+    },
+    Entry {
+        kind: "SyntaxError",
+        meaning: "The code is not valid for the language's grammar - the parser could not understand it.",
+        fix: "Look at the reported line for unbalanced brackets, unclosed strings or missing punctuation.",
+        explanation: "Syntax errors are found before the program runs at all. The real mistake is frequently \
+            earlier than the reported line, e.g. a bracket opened a few lines above and never closed.",
+    },
+    Entry {
+        kind: "IndentationError",
+        meaning: "Python found inconsistent or unexpected indentation.",
+        fix: "Use 4 spaces per level everywhere and never mix tabs with spaces.",
+        explanation: "In Python, indentation defines blocks. Mixing tabs and spaces can look fine in an editor \
+            while meaning something different to the interpreter.",
+    },
+    Entry {
+        kind: "ImportError",
+        meaning: "Python could not find or load the module you tried to import.",
+        fix: "Install the package (pip install <name>) or check the module path and spelling.",
+        explanation: "Modules are searched on sys.path. A package installed for a different interpreter or \
+            virtual environment is invisible to the one running your code.",
+    },
+    Entry {
+        kind: "TypeError",
+        meaning: "An operation was applied to a value of the wrong type.",
+        fix: "Check what type the value really has and convert it, or fix where the wrong type comes from.",
+        explanation: "Type errors usually point at the place where a value is used, not where it was created. \
+            Trace the value back to find why it has an unexpected type (often None, undefined or a string).",
+    },
+    Entry {
+        kind: "ModuleNotFound",
+        meaning: "An import refers to a package or file that cannot be resolved.",
+        fix: "Run npm install <package>, or fix the relative path of the import.",
+        explanation: "Bare imports (\"react\") are resolved from node_modules, relative imports (\"./util\") \
+            from the importing file. A missing npm install or a wrong path are the usual causes.",
+    },
+    Entry {
+        kind: "BorrowError",
+        meaning: "Rust's borrow checker found a reference that could lead to a data race or dangling pointer.",
+        fix: "Shorten the borrow's scope, clone the data, or restructure so only one mutable borrow exists.",
+        explanation: "Rust allows either many shared references or exactly one mutable reference at a time. \
+            The error notes show where each conflicting borrow starts and ends.",
+    },
+    Entry {
+        kind: "KeyError",
+        meaning: "A dictionary was accessed with a key that does not exist.",
+        fix: "Use dict.get(key, default) or check 'key in dict' before indexing.",
+        explanation: "data[\"key\"] raises when the key is missing, while data.get(\"key\") returns None. \
+            API responses and config files are the classic source of missing keys.",
+    },
+    Entry {
+        kind: "AttributeError",
+        meaning: "An attribute or method was accessed on an object that does not have it - very often None.",
+        fix: "Check the object's type and handle the None case before calling methods on it.",
+        explanation: "'NoneType' object has no attribute ... means a function returned None where you \
+            expected a real object. Find the call that produced the None.",
+    },
+    Entry {
+        kind: "ValueError",
+        meaning: "A value has the right type but invalid content (e.g. int('abc')).",
+        fix: "Validate the input before converting it, or wrap the conversion in try/except.",
+        explanation: "Parsing user input, dates and numbers from text are the usual sources. Validate early \
+            and give the user a clear message instead of crashing.",
+    },
+    Entry {
+        kind: "MissingEnvVar",
+        meaning: "An environment variable was not set, so the code used None in its place.",
+        fix: "Set the variable (.env file or shell export) and validate it at startup.",
+        explanation: "os.getenv() silently returns None for missing variables. Inside f-strings this turns \
+            into the text 'None', producing broken URLs far away from the real cause.",
+    },
+    Entry {
+        kind: "RequestsError",
+        meaning: "An HTTP request made with the requests library failed.",
+        fix: "Check the URL and connectivity, add a timeout and handle RequestException.",
+        explanation: "Network calls can fail for reasons outside your code. Always pass a timeout and catch \
+            requests.exceptions.RequestException around the call.",
+    },
+    Entry {
+        kind: "UnclosedTemplateBlock",
+        meaning: "A template block such as {% for %} or <% if (...) { %> was opened but never closed.",
+        fix: "Add the matching end tag ({% endfor %}, {% endif %}, or <% } %> in EJS) where the block should end.",
+        explanation: "Template engines report this at the end of the file, where they gave up looking for \
+            the end tag. The block that is really left open is often much earlier, or nested inside another.",
+    },
+    Entry {
+        kind: "UndefinedTemplateVariable",
+        meaning: "The template uses a variable that was not passed to it when rendering.",
+        fix: "Pass the variable to render_template()/render()/res.render(), or give it a default in the template.",
+        explanation: "Templates only see the variables they are rendered with. A view that renders the same \
+            template as another, or a renamed variable, is the usual cause.",
+    },
+    Entry {
+        kind: "DeriveError",
+        meaning: "A #[derive(...)] could not be used: the derive macro isn't in scope, or a field doesn't implement the trait.",
+        fix: "Import the derive macro (e.g. serde's derive feature), or derive the trait on the field's type too.",
+        explanation: "Derives generate code that calls the trait on every field. The error points at the field, \
+            but the fix is usually on the field's type - or on the derive list of the outer type.",
+    },
+    Entry {
+        kind: "FormatError",
+        meaning: "The arguments of format!, println! or a similar macro don't match its format string.",
+        fix: "Use one argument per {} placeholder, and {:?} for types that only implement Debug.",
+        explanation: "Format strings are checked at compile time. {} needs Display, {:?} needs Debug, and every \
+            placeholder needs exactly one argument.",
+    },
+    Entry {
+        kind: "MissingImport",
+        meaning: "A Java class is used without an import, so the compiler can't find it.",
+        fix: "Add the import (e.g. import java.util.ArrayList;) below the package line, or fix the class name.",
+        explanation: "Only classes in java.lang and in the same package are visible without an import. \
+            Everything else - even standard classes like List or Scanner - has to be imported by its \
+            full name, or written out in full where it is used.",
+    },
+    Entry {
+        kind: "NullPointer",
+        meaning: "Java code used a reference that was null: called a method on it, read a field or unboxed it.",
+        fix: "Make sure the variable is assigned before use, or check for null where it can legitimately be missing.",
+        explanation: "Object variables hold null until they are assigned, and methods like Map.get return null \
+            when there is nothing to return. Since Java 14 the message names what was null, e.g. \
+            'because \"name\" is null'; look for where that value should have been set.",
+    },
+    Entry {
+        kind: "PortInUse",
+        meaning: "The program tried to listen on a network port that another process already holds.",
+        fix: "Stop the other process (often an earlier copy of the same server) or use a different port.",
+        explanation: "Only one process can listen on a port at a time. A dev server left running in \
+            another terminal, or one that didn't shut down cleanly, keeps the port until it exits.",
+    },
+    Entry {
+        kind: "Vulnerability",
+        meaning: "A dependency has a published security advisory (found by cargo audit or npm audit).",
+        fix: "Upgrade the dependency to a patched version, or upgrade the package that pulls it in.",
+        explanation: "Vulnerable code is in your build even if you never call it directly. Most advisories \
+            are fixed in a patch release, so 'cargo update -p <crate>' or 'npm audit fix' is often enough.",
+    },
+    Entry {
+        kind: "MissingLicenseHeader",
+        meaning: "A source file doesn't start with the license header the project requires.",
+        fix: "Add the header as a comment at the top of the file, after any shebang line.",
+        explanation: "License headers tell readers of a single file under which terms it may be used. \
+            Tools that check them only look at the first lines, so the header has to come first.",
+    },
+    Entry {
+        kind: "PolicyViolation",
+        meaning: "The code breaks one of the project's [policy] rules, like no debug output in src/.",
+        fix: "Follow the rule named in the finding, or change the rule in .essentialscode.toml.",
+        explanation: "Policy rules aren't bugs the compiler sees, but conventions the project agreed on. \
+            Breaking them is usually harmless today and confusing later.",
+    },
+    Entry {
+        kind: "Unformatted",
+        meaning: "The file mixes tabs and spaces for indentation, or has lines longer than scan.max_line_length.",
+        fix: "Run the project's formatter (rustfmt, black, prettier, clang-format) on the file.",
+        explanation: "Mixed indentation renders differently in every editor, and in Python it can change \
+            which block a line belongs to. A formatter settles both in one pass.",
+    },
+    Entry {
+        kind: "AssertionFailed",
+        meaning: "A test checked a value and it wasn't the one the test expected.",
+        fix: "Decide which side is wrong: fix the code under test, or update the expected value in the test.",
+        explanation: "Assertions compare what the code returned with what the test author expected. A \
+            failure after changing the code usually means the behaviour changed; a failure in code you \
+            didn't touch often means the test depends on order, time or shared state.",
+    },
+    Entry {
+        kind: "RustVersion",
+        meaning: "The code or a dependency needs a newer Rust edition or toolchain than the project \
+            is set up for, or a feature only nightly Rust has.",
+        fix: "Set the edition or rust-version in Cargo.toml, or update the toolchain with rustup update.",
+        explanation: "Editions change what Rust code may say, and each needs a minimum rustc; \
+            dependencies declare the rustc they need too. Cargo.toml says which edition and \
+            rust-version the crate targets, rustup which toolchain builds it.",
+    },
+    Entry {
+        kind: "Custom",
+        meaning: "The error matched one of the project's own [[patterns]] or [[wasm_rules]] in \
+            .essentialscode.toml, a rule script in ~/.config/essentialscode/rules, or an \
+            ess-plugin-* executable recognized it.",
+        fix: "Follow the fix text; it was written for this error by your team or the rule's authors.",
+        explanation: "Custom rules cover errors ess can't know about, like messages from in-house \
+            frameworks. Edit the rule if its advice is out of date.",
+    },
+];
+
+/// An error kind shown in one language: code that fails and the fixed code
+#[derive(Debug)]
+pub struct Example {
+    pub kind: &'static str,
+    pub language: Language,
+    pub before: &'static str,
+    pub after: &'static str,
+}
+
+/// Snippets for kinds that look different in every language
+const EXAMPLES: &[Example] = &[
+    Example {
+        kind: "TypeError",
+        language: Language::Python,
+        before: "total = \"Items: \" + len(items)",
+        after: "total = \"Items: \" + str(len(items))",
+    },
+    Example {
+        kind: "TypeError",
+        language: Language::JavaScript,
+        before: "const count = user.orders.length;",
+        after: "const count = user.orders?.length ?? 0;",
+    },
+    Example {
+        kind: "TypeError",
+        language: Language::TypeScript,
+        before: "const id: number = params.get(\"id\");",
+        after: "const id: number = Number(params.get(\"id\"));",
+    },
+    Example {
+        kind: "NullPointer",
+        language: Language::Java,
+        before: "String name = users.get(id);\nint length = name.length();",
+        after: "String name = users.getOrDefault(id, \"\");\nint length = name.length();",
+    },
+    Example {
+        kind: "ModuleNotFound",
+        language: Language::JavaScript,
+        before: "const helpers = require(\"./helper\");",
+        after: "const helpers = require(\"./helpers\");",
+    },
+    Example {
+        kind: "ModuleNotFound",
+        language: Language::TypeScript,
+        before: "import express from \"express\";\n// npm install express",
+        after: "import express from \"express\";\n// npm install express @types/express",
+    },
+];
+
+/// The longer write-up `ess docs` shows for an error kind
+#[derive(Debug)]
+pub struct Doc {
+    pub kind: &'static str,
+    /// What usually leads to the error
+    pub causes: &'static [&'static str],
+    /// Ways to fix it, most common first
+    pub strategies: &'static [&'static str],
+    /// Fixes that look right but cause trouble later
+    pub pitfalls: &'static [&'static str],
+    /// Failing and fixed code
+    pub example: Option<Example>,
+}
+
+const DOCS: &[Doc] = &[
+    Doc {
+        kind: "MissingInclude",
+        causes: &[
+            "A standard type or function is used without including its header",
+            "The code compiled before because another header included it indirectly",
+        ],
+        strategies: &[
+            "Include the header that declares the name, e.g. <vector> for std::vector",
+            "Look the name up on cppreference.com, which lists its header at the top",
+        ],
+        pitfalls: &[
+            "Including <bits/stdc++.h> works with GCC only and slows every build down",
+            "Relying on indirect includes breaks when a compiler or library version changes",
+        ],
+        example: Some(Example {
+            kind: "MissingInclude",
+            language: Language::Cpp,
+            before: "int main() {\n    std::vector<int> v;\n}",
+            after: "#include <vector>\n\nint main() {\n    std::vector<int> v;\n}",
+        }),
+    },
+    Doc {
+        kind: "MissingSemicolon",
+        causes: &[
+            "A statement, class or struct definition was left without its closing ';'",
+            "A line was split or joined while editing",
+        ],
+        strategies: &[
+            "Add ';' at the end of the statement before the reported position",
+            "Check the line above the reported one first: the compiler notices the next token",
+        ],
+        pitfalls: &[
+            "A missing ';' after a class definition produces errors in the next declaration, \
+            often in another file that includes the header",
+        ],
+        example: Some(Example {
+            kind: "MissingSemicolon",
+            language: Language::Cpp,
+            before: "struct Point {\n    int x, y;\n}\nint main() {}",
+            after: "struct Point {\n    int x, y;\n};\nint main() {}",
+        }),
+    },
+    Doc {
+        kind: "UndeclaredVariable",
+        causes: &[
+            "A typo in the name, or a different capitalization",
+            "The name is defined in another scope, e.g. inside an if block or a function",
+            "An import or declaration is missing",
+        ],
+        strategies: &[
+            "Fix the spelling to match the definition",
+            "Define the variable before it is used, in a scope that covers the use",
+            "Import the name from the module that defines it",
+        ],
+        pitfalls: &[
+            "Making the variable global to silence the error hides the real data flow",
+            "Defining it with a placeholder value (x = None) moves the crash somewhere else",
+        ],
+        example: Some(Example {
+            kind: "UndeclaredVariable",
+            language: Language::Python,
+            before: "if ready:\n    total = 10\nprint(total)",
+            after: "total = 0\nif ready:\n    total = 10\nprint(total)",
+        }),
+    },
+    Doc {
+        kind: "SyntaxError",
+        causes: &[
+            "Unbalanced brackets, parentheses or braces",
+            "An unclosed string literal",
+            "A missing ':' after if, for, def or class in Python",
+        ],
+        strategies: &[
+            "Look at the reported line and the few lines above it",
+            "Let the editor match brackets to find the one left open",
+            "Comment out half the code to narrow down where the parser gets lost",
+        ],
+        pitfalls: &[
+            "The reported line is where parsing failed, not always where the mistake is",
+            "Code that is valid in a newer language version is a syntax error in an older one",
+        ],
+        example: Some(Example {
+            kind: "SyntaxError",
+            language: Language::Python,
+            before: "def greet(name)\n    print(f\"Hello {name}\")",
+            after: "def greet(name):\n    print(f\"Hello {name}\")",
+        }),
+    },
+    Doc {
+        kind: "IndentationError",
+        causes: &[
+            "Tabs and spaces mixed in the same block",
+            "Code pasted from another file with a different indentation width",
+            "A block (if, def, for) with no indented body",
+        ],
+        strategies: &[
+            "Convert the file to spaces only, 4 per level",
+            "Use 'pass' as the body of a block that should be empty for now",
+            "Run a formatter like black to settle the indentation",
+        ],
+        pitfalls: &[
+            "Editors may show tabs and spaces identically, so the code looks fine",
+            "Re-indenting by hand can move a line into a different block without an error",
+        ],
+        example: Some(Example {
+            kind: "IndentationError",
+            language: Language::Python,
+            before: "def todo():\n# later\n\nprint(\"done\")",
+            after: "def todo():\n    pass  # later\n\nprint(\"done\")",
+        }),
+    },
+    Doc {
+        kind: "ImportError",
+        causes: &[
+            "The package isn't installed for the interpreter that runs the code",
+            "A virtual environment isn't activated",
+            "A local file has the same name as the module, e.g. random.py",
+        ],
+        strategies: &[
+            "Install the package with the same interpreter: python -m pip install <name>",
+            "Activate the project's virtual environment before running",
+            "Rename local files that shadow standard or installed modules",
+        ],
+        pitfalls: &[
+            "pip and python can belong to different installations; python -m pip avoids that",
+            "Adding paths to sys.path at runtime works locally and breaks when deployed",
+        ],
+        example: Some(Example {
+            kind: "ImportError",
+            language: Language::Python,
+            before: "# pip install requests  (for another Python)\nimport requests",
+            after: "# python -m pip install requests\nimport requests",
+        }),
+    },
+    Doc {
+        kind: "TypeError",
+        causes: &[
+            "A value is None or undefined where an object was expected",
+            "Numbers and strings are combined without converting",
+            "A function is called with the wrong number or kind of arguments",
+        ],
+        strategies: &[
+            "Print or inspect the value's type right before the failing line",
+            "Convert explicitly: str(), int(), Number(), String()",
+            "Trace the value back to where it was created and fix it there",
+        ],
+        pitfalls: &[
+            "Wrapping the line in try/except hides where the wrong type comes from",
+            "Optional chaining (?.) silences the error but may leave an undefined result",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "ModuleNotFound",
+        causes: &[
+            "npm install hasn't been run, or the package isn't in package.json",
+            "A relative import has a wrong path or file name",
+            "File names differ in case, which matters on Linux but not macOS or Windows",
+        ],
+        strategies: &[
+            "Run npm install, or npm install <package> for a new dependency",
+            "Fix the relative path; it is resolved from the importing file",
+            "Match the exact case of the file name",
+        ],
+        pitfalls: &[
+            "Installing globally (-g) makes it work on one machine only",
+            "TypeScript may need @types/<package> even when the package itself is installed",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "BorrowError",
+        causes: &[
+            "A value is borrowed mutably while a shared borrow of it is still in use",
+            "A reference outlives the value it points to",
+            "Iterating over a collection while modifying it",
+        ],
+        strategies: &[
+            "End the first borrow earlier: copy out the value you need before mutating",
+            "Collect the changes first and apply them after the loop",
+            "Clone the data when it is small and the copy is cheap",
+            "Use indices instead of references into a collection",
+        ],
+        pitfalls: &[
+            "Reaching for Rc<RefCell<_>> moves the check to runtime, where it panics instead",
+            "Cloning large data in a hot loop fixes the error but costs performance",
+        ],
+        example: Some(Example {
+            kind: "BorrowError",
+            language: Language::Rust,
+            before: "let first = &items[0];\nitems.push(4);\nprintln!(\"{}\", first);",
+            after: "let first = items[0];\nitems.push(4);\nprintln!(\"{}\", first);",
+        }),
+    },
+    Doc {
+        kind: "KeyError",
+        causes: &[
+            "An API response or config file lacks a field the code expects",
+            "The key is spelled or cased differently",
+            "The dictionary was built from data that varies between runs",
+        ],
+        strategies: &[
+            "Use data.get(\"key\") or data.get(\"key\", default) when the key is optional",
+            "Check 'key in data' before indexing and handle the missing case",
+            "Validate input data at the boundary, e.g. with a schema",
+        ],
+        pitfalls: &[
+            "A default value can hide a real bug when the key must be there",
+            "collections.defaultdict creates missing keys on every read, growing the dict",
+        ],
+        example: Some(Example {
+            kind: "KeyError",
+            language: Language::Python,
+            before: "email = user[\"email\"]",
+            after: "email = user.get(\"email\", \"\")",
+        }),
+    },
+    Doc {
+        kind: "AttributeError",
+        causes: &[
+            "A function returned None, e.g. a regex search that found nothing",
+            "A typo in the attribute or method name",
+            "An object of a different type than expected was passed in",
+        ],
+        strategies: &[
+            "Check for None before using the result",
+            "Use dir(obj) or the docs to find the right attribute name",
+            "Fix the code that produced the wrong object",
+        ],
+        pitfalls: &[
+            "hasattr() checks spread through the code hide which types are really expected",
+            "Methods that modify in place (list.sort()) return None; don't chain them",
+        ],
+        example: Some(Example {
+            kind: "AttributeError",
+            language: Language::Python,
+            before: "match = re.search(r\"\\d+\", text)\nnumber = match.group()",
+            after: "match = re.search(r\"\\d+\", text)\nnumber = match.group() if match else None",
+        }),
+    },
+    Doc {
+        kind: "ValueError",
+        causes: &[
+            "Text that isn't a number is converted with int() or float()",
+            "Unpacking a sequence with a different number of items",
+            "A date string in another format than the parser expects",
+        ],
+        strategies: &[
+            "Validate input before converting it",
+            "Catch ValueError around the conversion and report what was wrong",
+            "Strip whitespace and units from numbers read from files",
+        ],
+        pitfalls: &[
+            "A bare except: around the conversion swallows unrelated errors too",
+            "Falling back to 0 silently turns bad input into wrong results",
+        ],
+        example: Some(Example {
+            kind: "ValueError",
+            language: Language::Python,
+            before: "age = int(input(\"Age: \"))",
+            after: "try:\n    age = int(input(\"Age: \"))\nexcept ValueError:\n    print(\"Please enter a number\")",
+        }),
+    },
+    Doc {
+        kind: "MissingEnvVar",
+        causes: &[
+            "The variable is only set in one shell, or in a .env file that isn't loaded",
+            "The name differs between the code and the deployment settings",
+        ],
+        strategies: &[
+            "Set it in the shell or in a .env file loaded at startup",
+            "Read all settings at startup and fail with a clear message when one is missing",
+            "Use os.environ[\"NAME\"] for required settings, so a missing one fails right away",
+        ],
+        pitfalls: &[
+            "os.getenv() returns None silently, so the failure appears much later",
+            "Committing .env files with real secrets to version control",
+        ],
+        example: Some(Example {
+            kind: "MissingEnvVar",
+            language: Language::Python,
+            before: "url = f\"{os.getenv('API_URL')}/users\"",
+            after: "api_url = os.environ[\"API_URL\"]  # fails at startup if unset\nurl = f\"{api_url}/users\"",
+        }),
+    },
+    Doc {
+        kind: "RequestsError",
+        causes: &[
+            "The server is down, slow or unreachable",
+            "A wrong URL, scheme or port",
+            "Proxy, firewall or certificate problems",
+        ],
+        strategies: &[
+            "Always pass timeout= so a hanging server can't block forever",
+            "Catch requests.exceptions.RequestException and report or retry",
+            "Call response.raise_for_status() to turn HTTP errors into exceptions",
+        ],
+        pitfalls: &[
+            "verify=False hides certificate problems and opens the door to attacks",
+            "Retrying without a limit or backoff hammers a server that is already struggling",
+        ],
+        example: Some(Example {
+            kind: "RequestsError",
+            language: Language::Python,
+            before: "data = requests.get(url).json()",
+            after: "response = requests.get(url, timeout=10)\nresponse.raise_for_status()\ndata = response.json()",
+        }),
+    },
+    Doc {
+        kind: "UnclosedTemplateBlock",
+        causes: &[
+            "A {% for %} or {% if %} without its {% endfor %} or {% endif %}",
+            "A typo in the end tag, like {% endfro %}",
+            "An EJS <% if (...) { %> without the closing <% } %>",
+        ],
+        strategies: &[
+            "Indent template blocks like code so each end tag lines up with its start",
+            "Work from the innermost block outwards when several are nested",
+        ],
+        pitfalls: &[
+            "The error points at the end of the file, far from the block left open",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "UndefinedTemplateVariable",
+        causes: &[
+            "The view doesn't pass the variable to the template",
+            "The variable was renamed in the view but not in the template",
+        ],
+        strategies: &[
+            "Pass the variable in the render call",
+            "Give it a default in the template, e.g. {{ name | default(\"\") }}",
+        ],
+        pitfalls: &[
+            "Defaults hide views that forgot to pass real data",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "DeriveError",
+        causes: &[
+            "The derive macro isn't in scope, e.g. serde without its derive feature",
+            "A field's type doesn't implement the derived trait",
+        ],
+        strategies: &[
+            "Enable the derive feature: serde = { version = \"1\", features = [\"derive\"] }",
+            "Derive the same trait on the field's type",
+            "Implement the trait by hand when a field can't have it",
+        ],
+        pitfalls: &[
+            "Deriving Clone or Debug on everything can expose secrets in logs",
+        ],
+        example: Some(Example {
+            kind: "DeriveError",
+            language: Language::Rust,
+            before: "struct Point { x: i32 }\n\n#[derive(Debug)]\nstruct Line { start: Point }",
+            after: "#[derive(Debug)]\nstruct Point { x: i32 }\n\n#[derive(Debug)]\nstruct Line { start: Point }",
+        }),
+    },
+    Doc {
+        kind: "FormatError",
+        causes: &[
+            "More or fewer arguments than {} placeholders",
+            "{} used for a type that only implements Debug",
+        ],
+        strategies: &[
+            "Use {:?} for Debug output",
+            "Name the arguments inline, e.g. println!(\"{name}\"), so they can't get out of step",
+        ],
+        pitfalls: &[
+            "Implementing Display just to satisfy {} for a type that has no natural text form",
+        ],
+        example: Some(Example {
+            kind: "FormatError",
+            language: Language::Rust,
+            before: "println!(\"{}\", items);",
+            after: "println!(\"{:?}\", items);",
+        }),
+    },
+    Doc {
+        kind: "MissingImport",
+        causes: &[
+            "A class outside java.lang and the current package is used without an import",
+            "The class name is misspelled or in another package than expected",
+        ],
+        strategies: &[
+            "Add the import below the package line",
+            "Let the IDE organize imports",
+        ],
+        pitfalls: &[
+            "Wildcard imports (java.util.*) can clash when two packages have the same class name",
+        ],
+        example: Some(Example {
+            kind: "MissingImport",
+            language: Language::Java,
+            before: "public class App {\n    List<String> names = new ArrayList<>();\n}",
+            after: "import java.util.ArrayList;\nimport java.util.List;\n\npublic class App {\n    List<String> names = new ArrayList<>();\n}",
+        }),
+    },
+    Doc {
+        kind: "NullPointer",
+        causes: &[
+            "A field or variable was never assigned",
+            "Map.get or a lookup method returned null for a missing entry",
+            "Unboxing a null Integer or Boolean",
+        ],
+        strategies: &[
+            "Initialize fields in the constructor",
+            "Use getOrDefault or Optional for values that may be missing",
+            "Check for null at the boundary where data enters the program",
+        ],
+        pitfalls: &[
+            "Catching NullPointerException hides where the null came from",
+            "Null checks everywhere instead of one at the source",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "PortInUse",
+        causes: &[
+            "An earlier copy of the server is still running",
+            "Another program uses the same default port",
+        ],
+        strategies: &[
+            "Stop the other process: find it with 'lsof -i :PORT' or 'netstat -ano'",
+            "Make the port configurable and pick a free one",
+        ],
+        pitfalls: &[
+            "Killing processes by name can stop unrelated programs",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "Vulnerability",
+        causes: &[
+            "A direct or indirect dependency has a published advisory",
+        ],
+        strategies: &[
+            "Update to a patched version: cargo update -p <crate> or npm audit fix",
+            "Update the package that pulls in the vulnerable one",
+            "Replace the dependency when no fix is released",
+        ],
+        pitfalls: &[
+            "npm audit fix --force can install breaking major versions",
+            "Ignoring advisories because the code isn't called directly; it may be later",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "MissingLicenseHeader",
+        causes: &[
+            "A new file was created without the header",
+        ],
+        strategies: &[
+            "Copy the header from another file of the project",
+            "Use an editor template so new files start with it",
+        ],
+        pitfalls: &[
+            "Putting the header before a shebang line breaks the script",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "PolicyViolation",
+        causes: &[
+            "The code breaks one of the [policy] rules in .essentialscode.toml",
+        ],
+        strategies: &[
+            "Change the code to follow the rule",
+            "Discuss and change the rule if it no longer fits the project",
+        ],
+        pitfalls: &[
+            "Turning a rule off for everyone to get one change through",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "Unformatted",
+        causes: &[
+            "Tabs and spaces mixed for indentation",
+            "Lines longer than scan.max_line_length",
+        ],
+        strategies: &[
+            "Run the project's formatter on the file",
+            "Set the editor to format on save",
+        ],
+        pitfalls: &[
+            "Formatting whole files in a feature change makes the diff hard to review",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "AssertionFailed",
+        causes: &[
+            "The code under test changed its behaviour",
+            "The expected value in the test is out of date",
+            "The test depends on order, time or shared state",
+        ],
+        strategies: &[
+            "Read the compared values and decide which side is wrong",
+            "Run the test alone to see if it depends on other tests",
+        ],
+        pitfalls: &[
+            "Updating the expected value to whatever the code returns now",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "RustVersion",
+        causes: &[
+            "Code written for a newer edition in a crate without edition set, which means 2015",
+            "A dependency whose latest release needs a newer rustc than the one installed",
+            "A feature that is still unstable on the stable toolchain",
+        ],
+        strategies: &[
+            "Set edition in Cargo.toml after running cargo fix --edition",
+            "Run rustup update, or pin a version with rustup override set",
+            "Pin the dependency to an older release with cargo update --precise",
+        ],
+        pitfalls: &[
+            "Bumping the edition without cargo fix --edition, leaving code the new edition reads differently",
+            "Raising rust-version without checking CI still builds on it",
+        ],
+        example: None,
+    },
+    Doc {
+        kind: "Custom",
+        causes: &[
+            "A message from a framework or tool ess doesn't know, matched by your own rules",
+        ],
+        strategies: &[
+            "Follow the fix text the rule gives",
+            "Improve the rule's fix text when it's unclear",
+        ],
+        pitfalls: &[
+            "Regexes that match too broadly attach the fix to unrelated errors",
+        ],
+        example: None,
+    },
+];
+
+/// Look up the knowledge entry for an error kind
+pub fn lookup(kind: &str) -> Option<&'static Entry> {
+    ENTRIES.iter().find(|e| e.kind == kind)
+}
+
+/// Every knowledge entry
+pub fn entries() -> &'static [Entry] {
+    ENTRIES
+}
+
+/// The `ess docs` write-up for an error kind
+pub fn docs(kind: &str) -> Option<&'static Doc> {
+    DOCS.iter().find(|d| d.kind == kind)
+}
+
+/// The snippet for an error kind in `language`. There is no fallback to
+/// another language, so a JavaScript error never shows Python code.
+pub fn example(kind: &str, language: &Language) -> Option<&'static Example> {
+    EXAMPLES
+        .iter()
+        .find(|e| e.kind == kind && e.language == *language)
+}
+
+/// Every snippet for an error kind, whatever the language
+pub fn examples(kind: &str) -> impl Iterator<Item = &'static Example> + '_ {
+    EXAMPLES.iter().filter(move |e| e.kind == kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ErrorType;
+
+    #[test]
+    fn test_lookup_known_kind() {
+        let entry = lookup("KeyError").unwrap();
+        assert!(entry.fix.contains(".get("));
+    }
+
+    #[test]
+    fn test_example_matches_language() {
+        let js = example("TypeError", &Language::JavaScript).unwrap();
+        assert!(js.after.contains("?."));
+        let python = example("TypeError", &Language::Python).unwrap();
+        assert!(python.after.contains("str("));
+
+        assert!(example("TypeError", &Language::Rust).is_none());
+        assert!(example("ModuleNotFound", &Language::Python).is_none());
+    }
+
+    #[test]
+    fn test_every_example_has_entry() {
+        for e in EXAMPLES {
+            assert!(lookup(e.kind).is_some(), "no entry for example {}", e.kind);
+            assert_ne!(e.before, e.after);
+        }
+    }
+
+    #[test]
+    fn test_every_entry_has_docs() {
+        for entry in ENTRIES {
+            let doc = docs(entry.kind).unwrap_or_else(|| panic!("no docs for {}", entry.kind));
+            assert!(!doc.causes.is_empty() && !doc.strategies.is_empty());
+            if let Some(example) = &doc.example {
+                assert_eq!(example.kind, entry.kind);
+                assert_ne!(example.before, example.after);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_unknown_kind() {
+        assert!(lookup("Unknown").is_none());
+        assert!(lookup("NotAKind").is_none());
+    }
+
+    #[test]
+    fn test_every_error_type_has_entry() {
+        let types = [
+            ErrorType::MissingInclude(String::new()),
+            ErrorType::MissingSemicolon,
+            ErrorType::UndeclaredVariable(String::new()),
+            ErrorType::SyntaxError(String::new()),
+            ErrorType::IndentationError,
+            ErrorType::ImportError(String::new()),
+            ErrorType::TypeError(String::new()),
+            ErrorType::ModuleNotFound(String::new()),
+            ErrorType::BorrowError(String::new()),
+            ErrorType::KeyError(String::new()),
+            ErrorType::AttributeError(String::new()),
+            ErrorType::ValueError(String::new()),
+            ErrorType::MissingEnvVar(String::new()),
+            ErrorType::RequestsError(String::new()),
+            ErrorType::UnclosedTemplateBlock(String::new()),
+            ErrorType::UndefinedTemplateVariable(String::new()),
+            ErrorType::DeriveError(String::new()),
+            ErrorType::FormatError(String::new()),
+            ErrorType::Vulnerability(String::new()),
+            ErrorType::MissingLicenseHeader(String::new()),
+            ErrorType::PolicyViolation(String::new()),
+            ErrorType::Unformatted(String::new()),
+            ErrorType::AssertionFailed(String::new()),
+            ErrorType::RustVersion(String::new()),
+            ErrorType::Custom(crate::parser::CustomFix {
+                title: String::new(),
+                fix: String::new(),
+                insert: None,
+            }),
+        ];
+
+        for t in types {
+            assert!(lookup(t.name()).is_some(), "missing entry for {}", t.name());
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::history::{History, HistoryEntry};
+use crate::knowledge::{self, Entry};
+use crate::scanner::detect_language_from_str;
+use crate::ui;
+use anyhow::Result;
+use std::io::BufRead;
+
+/// A flash-card built from past errors of one kind
+#[derive(Debug)]
+struct Card {
+    entry: &'static Entry,
+    language: String,
+    example: String,
+    seen: usize,
+}
+
+pub fn run(count: usize, explain: bool) -> Result<()> {
+    ui::print_section("Learn From Your Errors");
+
+    let entries = match History::open_default() {
+        Some(history) => history.entries()?,
+        None => Vec::new(),
+    };
+
+    let cards = build_cards(&entries, count);
+    if cards.is_empty() {
+        println!();
+        ui::print_warning("No past errors to learn from yet");
+        ui::print_hint("Analyze some errors with 'ess bug \"<error>\"' first");
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+
+    for (i, card) in cards.iter().enumerate() {
+        ui::print_section(&format!("Card {}/{}", i + 1, cards.len()));
+        println!();
+        ui::print_info(&format!(
+            "{} - seen {} time{}",
+            card.language,
+            card.seen,
+            if card.seen == 1 { "" } else { "s" }
+        ));
+        ui::print_error(&card.example);
+        println!();
+        println!("  What does {} mean? What's the fix?", card.entry.kind);
+        println!();
+
+        ui::print_prompt("Press Enter to reveal (q to quit): ");
+        let mut answer = String::new();
+        // End of input quits too, rather than revealing every card
+        if input.read_line(&mut answer)? == 0 || answer.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+
+        println!();
+        ui::print_info(card.entry.meaning);
+        let language = detect_language_from_str(&card.language);
+        if let Some(example) = knowledge::example(card.entry.kind, &language) {
+            ui::print_diff(example.before, example.after);
+        }
+        ui::print_fix_instruction(card.entry.fix);
+        if explain {
+            ui::print_hint(card.entry.explanation);
+        }
+    }
+
+    println!();
+    Ok(())
+}
+
+/// One card per known error kind, most frequent kinds first
+fn build_cards(entries: &[HistoryEntry], count: usize) -> Vec<Card> {
+    let mut cards: Vec<Card> = Vec::new();
+
+    // Newest entries first so each card shows the latest example
+    for entry in entries.iter().rev() {
+        if let Some(card) = cards.iter_mut().find(|c| c.entry.kind == entry.kind) {
+            card.seen += 1;
+            continue;
+        }
+
+        if let Some(known) = knowledge::lookup(&entry.kind) {
+            cards.push(Card {
+                entry: known,
+                language: entry.language.clone(),
+                example: entry.message.clone(),
+                seen: 1,
+            });
+        }
+    }
+
+    cards.sort_by(|a, b| b.seen.cmp(&a.seen).then(a.entry.kind.cmp(b.entry.kind)));
+    cards.truncate(count);
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: &str, message: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: 0,
+            language: "Python".to_string(),
+            kind: kind.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_build_cards_empty() {
+        assert!(build_cards(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_build_cards_groups_by_kind() {
+        let entries = vec![
+            entry("KeyError", "KeyError: 'a'"),
+            entry("TypeError", "TypeError: bad operand"),
+            entry("KeyError", "KeyError: 'b'"),
+        ];
+
+        let cards = build_cards(&entries, 10);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].entry.kind, "KeyError");
+        assert_eq!(cards[0].seen, 2);
+        // Latest example wins
+        assert_eq!(cards[0].example, "KeyError: 'b'");
+    }
+
+    #[test]
+    fn test_build_cards_skips_unknown_kinds() {
+        let entries = vec![entry("Unknown", "something odd")];
+        assert!(build_cards(&entries, 10).is_empty());
+    }
+
+    #[test]
+    fn test_build_cards_respects_count() {
+        let entries = vec![
+            entry("KeyError", "a"),
+            entry("TypeError", "b"),
+            entry("ValueError", "c"),
+        ];
+        assert_eq!(build_cards(&entries, 2).len(), 2);
+    }
+}
@@ -0,0 +1,132 @@
+//! EssentialsCode as a library: parse error messages, scan projects and
+//! get fixes as data, without running the `ess` binary.
+//!
+//! ```no_run
+//! use essentials_code::{config::Config, parse_error, scan_project, suggest};
+//! use std::path::Path;
+//!
+//! let root = Path::new(".");
+//! let summary = scan_project(root, &Config::default()).unwrap();
+//! for finding in &summary.findings {
+//!     if let Some(error) = &finding.parsed {
+//!         let suggestion = suggest(error, root);
+//!         println!("{}: {:?}", finding.message, suggestion.fix);
+//!     }
+//! }
+//!
+//! let error = parse_error("main.cpp:3:5: error: 'cout' is not a member of 'std'");
+//! assert!(error.is_some());
+//! ```
+pub mod apply;
+pub mod audit;
+pub mod capabilities;
+pub mod clipboard;
+pub mod clusters;
+pub mod collisions;
+pub mod compare;
+pub mod config;
+pub mod container;
+pub mod crash;
+pub mod daemon;
+pub mod diff;
+pub mod docs;
+pub mod doctor;
+pub mod download;
+pub mod encoding;
+pub mod finding;
+pub mod fixer;
+pub mod format;
+pub mod git;
+pub mod health;
+pub mod highlight;
+pub mod history;
+pub mod hooks;
+pub mod knowledge;
+pub mod learn;
+pub mod locale;
+pub mod lockfiles;
+pub mod logs;
+pub mod markdown;
+pub mod metrics;
+pub mod msrv;
+pub mod onboard;
+pub mod outdated;
+pub mod parser;
+pub mod patterns;
+pub mod pick;
+pub mod plugins;
+pub mod policy;
+pub mod ports;
+pub mod precommit;
+pub mod rdjson;
+pub mod recent;
+pub mod redact;
+pub mod regexes;
+pub mod replay;
+pub mod report;
+pub mod retry;
+pub mod rules;
+pub mod sarif;
+pub mod scanner;
+pub mod scans;
+pub mod scripts;
+pub mod sfc;
+pub mod share;
+pub mod state;
+pub mod status;
+pub mod testsuite;
+pub mod timings;
+pub mod tools;
+pub mod ui;
+pub mod unparsed;
+pub mod update;
+pub mod usage;
+pub mod walk;
+pub mod wasm;
+pub mod why;
+
+pub use apply::{Edit, Fix};
+pub use finding::{Finding, Severity};
+pub use fixer::{suggest, Suggestion};
+pub use parser::{parse_error, parse_errors, ErrorType, Language, ParsedError};
+pub use scanner::{ScanOptions, ScanSummary};
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Scan a project without printing anything. The findings are in the
+/// returned summary, in `output.sort` order.
+pub fn scan_project(path: &Path, config: &config::Config) -> Result<ScanSummary> {
+    let options = ScanOptions {
+        quiet: true,
+        ..Default::default()
+    };
+    scanner::scan_project(path, config, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_project_returns_findings() {
+        let temp_dir = std::env::temp_dir().join("ess_test_lib_scan");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("big.js"), "var a;\nvar b;\nvar c;\n").unwrap();
+
+        let mut config = config::Config::default();
+        config.policy.max_file_lines = Some(2);
+        config.policy.require_use_strict = true;
+        let summary = scan_project(&temp_dir, &config).unwrap();
+
+        let rules: Vec<&str> = summary.findings.iter().map(|f| f.source.as_str()).collect();
+        assert_eq!(rules, vec!["policy/use-strict", "policy/max-file-length"]);
+        // Findings carry their fixes
+        assert_eq!(summary.findings[0].fixes[0].description, "Add 'use strict'");
+        assert!(summary.findings[1].fixes.is_empty());
+        assert_eq!(summary.total_errors(), 2);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::regexes;
+use regex::Captures;
+use std::borrow::Cow;
+
+/// Compiler output with gcc's German, French and Polish phrasing put back
+/// into the English the parsers know. Messages without a translation
+/// here keep their wording, but their location and severity still parse.
+/// English output comes back as it is.
+pub fn to_english(text: &str) -> Cow<'_, str> {
+    // Every translation needs a quoted name or a localized word, so
+    // plain ASCII output is English already
+    if text.is_ascii() && !regexes::LOCALIZED_SEVERITY.is_match(text) {
+        return Cow::Borrowed(text);
+    }
+
+    let text = regexes::LOCALIZED_SEVERITY.replace_all(text, |cap: &Captures| {
+        let severity = if cap.get(2).is_some() {
+            "fatal error"
+        } else if cap.get(3).is_some() {
+            "error"
+        } else if cap.get(4).is_some() {
+            "warning"
+        } else {
+            "note"
+        };
+        format!("{}{}:", &cap[1], severity)
+    });
+    let text = regexes::LOCALIZED_IN_FUNCTION.replace_all(&text, "$1: In function ");
+    let text =
+        regexes::LOCALIZED_UNDECLARED.replace_all(&text, "'$1' was not declared in this scope");
+    let text = regexes::LOCALIZED_NOT_MEMBER.replace_all(&text, "'$1' is not a member of '$2'");
+    let text = regexes::LOCALIZED_EXPECTED.replace_all(&text, "expected '$1' before '$2'");
+    Cow::Owned(text.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_error, parse_errors, ErrorType};
+
+    /// gcc output in each locale, and what the parser should make of it
+    const CORPUS: &[(&str, &str)] = &[
+        (
+            "de",
+            "main.cpp: In Funktion »int main()«:\n\
+            main.cpp:4:5: Fehler: »cout« ist kein Element von »std«\n\
+            main.cpp:5:5: Fehler: »total« wurde in diesem Gültigkeitsbereich nicht definiert\n\
+            main.cpp:6:14: Fehler: »;« vor »return« erwartet\n",
+        ),
+        (
+            "fr",
+            "main.cpp: Dans la fonction « int main() » :\n\
+            main.cpp:4:5: erreur : « cout » n'est pas membre de « std »\n\
+            main.cpp:5:5: erreur : « total » n'a pas été déclaré dans cette portée\n\
+            main.cpp:6:14: erreur : « ; » attendu avant « return »\n",
+        ),
+        (
+            "pl",
+            "main.cpp: W funkcji ‘int main()’:\n\
+            main.cpp:4:5: błąd: ‘cout’ nie jest składową ‘std’\n\
+            main.cpp:5:5: błąd: ‘total’ nie został zadeklarowany w tym zakresie\n\
+            main.cpp:6:14: błąd: oczekiwano ‘;’ przed ‘return’\n",
+        ),
+    ];
+
+    #[test]
+    fn test_localized_gcc_output_parses() {
+        for (locale, output) in CORPUS {
+            let english = to_english(output);
+            let errors = parse_errors(&english);
+            let found: Vec<(Option<u32>, &ErrorType)> =
+                errors.iter().map(|e| (e.line, &e.error_type)).collect();
+            assert_eq!(
+                found,
+                vec![
+                    (Some(4), &ErrorType::MissingInclude("iostream".to_string())),
+                    (Some(5), &ErrorType::UndeclaredVariable("total".to_string())),
+                    (Some(6), &ErrorType::MissingSemicolon),
+                ],
+                "{}: {}",
+                locale,
+                english
+            );
+            assert!(english.starts_with("main.cpp: In function "), "{}", locale);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_translates() {
+        let error = parse_error(
+            "main.cpp:5:5: Fehler: »total« wurde in diesem Gültigkeitsbereich nicht definiert",
+        )
+        .unwrap();
+        assert_eq!(
+            error.error_type,
+            ErrorType::UndeclaredVariable("total".to_string())
+        );
+        assert_eq!(error.message, "'total' was not declared in this scope");
+    }
+
+    #[test]
+    fn test_severity_and_untranslated_messages() {
+        assert_eq!(
+            to_english("a.cpp:1:10: schwerwiegender Fehler: foo.h: Datei nicht gefunden"),
+            "a.cpp:1:10: fatal error: foo.h: Datei nicht gefunden"
+        );
+        assert_eq!(
+            to_english("a.cpp:2:9: Warnung: unbenutzte Variable »y«"),
+            "a.cpp:2:9: warning: unbenutzte Variable »y«"
+        );
+        let english = "main.cpp:3:5: error: 'x' was not declared in this scope";
+        assert!(matches!(to_english(english), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,576 @@
+//! Lockfiles that a merge left broken or that no longer match their
+//! manifest. Build tools report these as parse errors in a file nobody
+//! edits by hand, or as `npm ci` refusing to run, so they are found and
+//! explained before the tools run.
+
+use crate::audit::line_where;
+use crate::finding::{Finding, Severity};
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Rule IDs, the findings' source
+pub const CONFLICT: &str = "lockfile/conflict";
+pub const CORRUPT: &str = "lockfile/corrupt";
+pub const MISMATCH: &str = "lockfile/mismatch";
+
+/// Dependency tables of a Cargo.toml, also under `[target.'cfg'.*]`
+const CARGO_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Dependency fields of a package.json
+const NPM_TABLES: &[&str] = &["dependencies", "devDependencies", "optionalDependencies"];
+
+/// Problems with the lockfiles in `root`
+pub fn check(root: &Path) -> Vec<Finding> {
+    let mut findings = cargo(root);
+    findings.extend(npm(root));
+    findings.extend(yarn(root));
+    findings.extend(pnpm(root));
+    findings
+}
+
+/// The file still has merge conflict markers
+pub fn has_conflicts(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| conflict(&content).is_some())
+}
+
+/// Merge conflicts left in a lockfile: the line of the first marker and
+/// the lines between markers
+struct Conflict {
+    line: u32,
+    hunks: Vec<String>,
+}
+
+fn conflict(content: &str) -> Option<Conflict> {
+    let mut first = None;
+    let mut hunks = Vec::new();
+    let mut inside = false;
+    for (i, line) in content.lines().enumerate() {
+        if line.starts_with("<<<<<<<") {
+            first.get_or_insert(i as u32 + 1);
+            inside = true;
+        } else if line.starts_with(">>>>>>>") {
+            inside = false;
+        } else if inside && !line.starts_with("=======") && !line.starts_with("|||||||") {
+            hunks.push(line.to_string());
+        }
+    }
+    first.map(|line| Conflict { line, hunks })
+}
+
+fn conflict_finding(lock: &Path, conflict: &Conflict, advice: &str) -> Finding {
+    let name = file_name(lock);
+    let message = format!(
+        "{} has unresolved merge conflicts, so every build fails to read it. {}",
+        name, advice
+    );
+    Finding::new(lock, Severity::Error, &message, CONFLICT).at(Some(conflict.line), None)
+}
+
+fn corrupt_finding(lock: &Path, error: &str, command: &str) -> Finding {
+    let message = format!(
+        "{} can't be read ({}). Regenerate it: {}",
+        file_name(lock),
+        error.lines().next().unwrap_or(error).trim(),
+        command
+    );
+    Finding::new(lock, Severity::Error, &message, CORRUPT)
+}
+
+fn mismatch_finding(manifest: &Path, line: Option<u32>, message: String) -> Finding {
+    Finding::new(manifest, Severity::Warning, &message, MISMATCH).at(line, None)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or(String::new(), |n| n.to_string_lossy().to_string())
+}
+
+/// `cargo update -p` for each crate
+fn cargo_update(names: &BTreeSet<String>) -> String {
+    let packages: Vec<String> = names.iter().map(|n| format!("-p {}", n)).collect();
+    format!("cargo update {}", packages.join(" "))
+}
+
+/// Cargo.lock against the root Cargo.toml
+fn cargo(root: &Path) -> Vec<Finding> {
+    let lock_path = root.join("Cargo.lock");
+    let Ok(lock) = std::fs::read_to_string(&lock_path) else {
+        return Vec::new();
+    };
+    if let Some(conflict) = conflict(&lock) {
+        let names: BTreeSet<String> = conflict
+            .hunks
+            .iter()
+            .filter_map(|line| toml_string(line, "name"))
+            .collect();
+        let advice = if names.is_empty() {
+            "Keep one side with 'git checkout --ours Cargo.lock', then run \
+            'cargo update --workspace'"
+                .to_string()
+        } else {
+            format!(
+                "Keep one side with 'git checkout --ours Cargo.lock', then bring \
+                the crates in conflict up to date: {}",
+                cargo_update(&names)
+            )
+        };
+        return vec![conflict_finding(&lock_path, &conflict, &advice)];
+    }
+    let lock: toml::Value = match toml::from_str(&lock) {
+        Ok(lock) => lock,
+        Err(e) => {
+            return vec![corrupt_finding(
+                &lock_path,
+                &e.to_string(),
+                "delete it and run 'cargo generate-lockfile'",
+            )]
+        }
+    };
+
+    let mut locked: BTreeMap<&str, Vec<Version>> = BTreeMap::new();
+    for package in lock
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let name = package.get("name").and_then(|n| n.as_str());
+        let version = package
+            .get("version")
+            .and_then(|v| v.as_str())
+            .and_then(|v| Version::parse(v).ok());
+        if let (Some(name), Some(version)) = (name, version) {
+            locked.entry(name).or_default().push(version);
+        }
+    }
+
+    let manifest_path = root.join("Cargo.toml");
+    let Ok(manifest_text) = std::fs::read_to_string(&manifest_path) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = toml::from_str::<toml::Value>(&manifest_text) else {
+        return Vec::new();
+    };
+    let mut findings = Vec::new();
+    for (key, package, requirement) in cargo_dependencies(&manifest) {
+        // Not resolved yet: cargo adds it on the next build
+        let Some(versions) = locked.get(package.as_str()) else {
+            continue;
+        };
+        let Ok(req) = VersionReq::parse(&requirement) else {
+            continue;
+        };
+        if versions.iter().any(|v| req.matches(v)) {
+            continue;
+        }
+        let locked_versions: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+        let message = format!(
+            "Cargo.toml asks for {} \"{}\", but Cargo.lock has {}; builds with --locked \
+            fail. Update the lockfile: cargo update -p {}",
+            package,
+            requirement,
+            locked_versions.join(", "),
+            package
+        );
+        let line = line_where(&manifest_text, |line, _| toml_key(line) == Some(&key));
+        findings.push(mismatch_finding(&manifest_path, line, message));
+    }
+    findings
+}
+
+/// Dependencies in a Cargo.toml with a version requirement, as the key
+/// they are declared under, the package name and the requirement
+fn cargo_dependencies(manifest: &toml::Value) -> Vec<(String, String, String)> {
+    let mut tables: Vec<&toml::Value> = CARGO_TABLES
+        .iter()
+        .filter_map(|name| manifest.get(name))
+        .collect();
+    for target in manifest
+        .get("target")
+        .and_then(|t| t.as_table())
+        .into_iter()
+        .flat_map(|t| t.values())
+    {
+        tables.extend(CARGO_TABLES.iter().filter_map(|name| target.get(name)));
+    }
+    tables.extend(
+        manifest
+            .get("workspace")
+            .and_then(|w| w.get("dependencies")),
+    );
+
+    let mut dependencies = Vec::new();
+    for (key, spec) in tables.into_iter().filter_map(|t| t.as_table()).flatten() {
+        let (package, requirement) = match spec {
+            toml::Value::String(version) => (key.as_str(), version.as_str()),
+            toml::Value::Table(table) => {
+                let Some(version) = table.get("version").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                let package = table.get("package").and_then(|p| p.as_str());
+                (package.unwrap_or(key), version)
+            }
+            _ => continue,
+        };
+        dependencies.push((key.clone(), package.to_string(), requirement.to_string()));
+    }
+    dependencies
+}
+
+/// `value` of a `key = "value"` TOML line
+fn toml_string(line: &str, key: &str) -> Option<String> {
+    let (k, value) = line.split_once('=')?;
+    (k.trim() == key).then(|| value.trim().trim_matches('"').to_string())
+}
+
+/// The key a TOML line sets, e.g. `serde` of `serde = { version = "1" }`
+fn toml_key(line: &str) -> Option<&str> {
+    let key = line.split(['=', '.']).next()?.trim();
+    (!key.is_empty() && !key.starts_with(['[', '#'])).then_some(key)
+}
+
+/// Dependencies in a package.json, with the range asked for
+fn npm_dependencies(manifest: &serde_json::Value) -> Vec<(String, String)> {
+    NPM_TABLES
+        .iter()
+        .filter_map(|table| manifest.get(table)?.as_object())
+        .flatten()
+        .filter_map(|(name, range)| Some((name.clone(), range.as_str()?.to_string())))
+        .collect()
+}
+
+/// Line declaring a dependency in a package.json
+fn npm_line(manifest: &str, name: &str) -> Option<u32> {
+    let key = format!("\"{}\"", name);
+    line_where(manifest, |line, _| line.starts_with(&key))
+}
+
+fn read_package_json(root: &Path) -> Option<(String, serde_json::Value)> {
+    let text = std::fs::read_to_string(root.join("package.json")).ok()?;
+    let value = serde_json::from_str(&text).ok()?;
+    Some((text, value))
+}
+
+/// package-lock.json against package.json
+fn npm(root: &Path) -> Vec<Finding> {
+    let lock_path = root.join("package-lock.json");
+    let Ok(lock) = std::fs::read_to_string(&lock_path) else {
+        return Vec::new();
+    };
+    let command = "npm install --package-lock-only";
+    if let Some(conflict) = conflict(&lock) {
+        let advice = format!(
+            "npm resolves them itself: run '{}' and commit the result",
+            command
+        );
+        return vec![conflict_finding(&lock_path, &conflict, &advice)];
+    }
+    let lock: serde_json::Value = match serde_json::from_str(&lock) {
+        Ok(lock) => lock,
+        Err(e) => {
+            return vec![corrupt_finding(
+                &lock_path,
+                &e.to_string(),
+                &format!("delete it and run '{}'", command),
+            )]
+        }
+    };
+    let Some((manifest_text, manifest)) = read_package_json(root) else {
+        return Vec::new();
+    };
+
+    let manifest_path = root.join("package.json");
+    let mut findings = Vec::new();
+    for (name, range) in npm_dependencies(&manifest) {
+        // lockfileVersion 2 and 3 list packages by path, 1 by name
+        let entry = lock
+            .get("packages")
+            .and_then(|p| p.get(format!("node_modules/{}", name)))
+            .or_else(|| lock.get("dependencies").and_then(|d| d.get(&name)));
+        let locked = entry
+            .and_then(|e| e.get("version"))
+            .and_then(|v| v.as_str());
+        let message = match locked {
+            None => format!(
+                "{} is in package.json but not in package-lock.json; 'npm ci' refuses \
+                to install. Update the lockfile: {}",
+                name, command
+            ),
+            Some(version) if !npm_satisfies(&range, version) => format!(
+                "package.json asks for {} \"{}\", but package-lock.json has {}; 'npm ci' \
+                refuses to install. Update the lockfile: {}",
+                name, range, version, command
+            ),
+            Some(_) => continue,
+        };
+        findings.push(mismatch_finding(
+            &manifest_path,
+            npm_line(&manifest_text, &name),
+            message,
+        ));
+    }
+    findings
+}
+
+/// yarn.lock against package.json. Its entries are keyed by the ranges
+/// package.json asks for, so a range with no entry is out of date.
+fn yarn(root: &Path) -> Vec<Finding> {
+    let lock_path = root.join("yarn.lock");
+    let Ok(lock) = std::fs::read_to_string(&lock_path) else {
+        return Vec::new();
+    };
+    if let Some(conflict) = conflict(&lock) {
+        let advice = "yarn resolves them itself: run 'yarn install' and commit the result";
+        return vec![conflict_finding(&lock_path, &conflict, advice)];
+    }
+    let locked = yarn_entries(&lock);
+    if locked.is_empty() {
+        return Vec::new();
+    }
+    let Some((manifest_text, manifest)) = read_package_json(root) else {
+        return Vec::new();
+    };
+
+    let manifest_path = root.join("package.json");
+    npm_dependencies(&manifest)
+        .into_iter()
+        .filter(|(name, range)| !locked.contains(&(name.clone(), range.clone())))
+        .map(|(name, range)| {
+            let message = format!(
+                "yarn.lock has no entry for {}@{} from package.json; 'yarn install \
+                --frozen-lockfile' fails. Update the lockfile: yarn install",
+                name, range
+            );
+            mismatch_finding(&manifest_path, npm_line(&manifest_text, &name), message)
+        })
+        .collect()
+}
+
+/// The (name, range) pairs yarn.lock has entries for, from headers like
+/// `"@babel/core@^7.0.0", "@babel/core@^7.1.0":`
+fn yarn_entries(lock: &str) -> BTreeSet<(String, String)> {
+    lock.lines()
+        .filter(|line| !line.starts_with([' ', '#']) && line.ends_with(':'))
+        .flat_map(|line| line.trim_end_matches(':').split(", "))
+        .filter_map(|spec| {
+            let spec = spec.trim().trim_matches('"');
+            // The name may be scoped: the range starts at the last '@'
+            let at = spec.rfind('@').filter(|&at| at > 0)?;
+            let range = &spec[at + 1..];
+            // Yarn 2+ writes the protocol: `lodash@npm:^4.17.0`
+            let range = range.strip_prefix("npm:").unwrap_or(range);
+            Some((spec[..at].to_string(), range.to_string()))
+        })
+        .collect()
+}
+
+/// pnpm-lock.yaml, for conflicts only
+fn pnpm(root: &Path) -> Vec<Finding> {
+    let lock_path = root.join("pnpm-lock.yaml");
+    let Ok(lock) = std::fs::read_to_string(&lock_path) else {
+        return Vec::new();
+    };
+    conflict(&lock)
+        .map(|conflict| {
+            let advice = "pnpm resolves them itself: run 'pnpm install' and commit the result";
+            conflict_finding(&lock_path, &conflict, advice)
+        })
+        .into_iter()
+        .collect()
+}
+
+/// `version` is in the npm `range`. Ranges that aren't versions (tags,
+/// URLs, `file:` and `workspace:` specs) are taken to match.
+fn npm_satisfies(range: &str, version: &str) -> bool {
+    let Ok(version) = Version::parse(version) else {
+        return true;
+    };
+    match npm_requirement(range) {
+        Some(alternatives) => alternatives.iter().any(|req| req.matches(&version)),
+        None => true,
+    }
+}
+
+/// An npm range as the cargo requirements it allows, one per `||`
+/// alternative; None for a range that allows anything or isn't semver
+fn npm_requirement(range: &str) -> Option<Vec<VersionReq>> {
+    range
+        .split("||")
+        .map(|alternative| {
+            let alternative = alternative.trim();
+            if matches!(alternative, "" | "*" | "x" | "X" | "latest") {
+                return None;
+            }
+            let comparators: Vec<String> = match alternative.split_once(" - ") {
+                Some((low, high)) => vec![
+                    format!(">={}", low.trim().trim_start_matches('v')),
+                    format!("<={}", high.trim().trim_start_matches('v')),
+                ],
+                None => alternative.split_whitespace().map(npm_comparator).collect(),
+            };
+            VersionReq::parse(&comparators.join(", ")).ok()
+        })
+        .collect()
+}
+
+/// One npm comparator in cargo's syntax: a bare version is exact in npm
+/// but a caret requirement in cargo, and `x` is a wildcard
+fn npm_comparator(comparator: &str) -> String {
+    let op_len = comparator
+        .find(|c: char| c.is_ascii_alphanumeric() && c != 'v' || c == '*')
+        .unwrap_or(comparator.len());
+    let (op, version) = comparator.split_at(op_len);
+    let op = op.trim_end_matches('v');
+    let version = version
+        .split('.')
+        .map(|part| if matches!(part, "x" | "X") { "*" } else { part })
+        .collect::<Vec<_>>()
+        .join(".");
+    match op {
+        "" | "=" if version.contains('*') => version,
+        "" => format!("={}", version),
+        op => format!("{}{}", op, version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_lockfile() {
+        let temp_dir = std::env::temp_dir().join("ess_test_lockfiles_cargo");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\n\
+            serde = \"1.0.150\"\nrand = { version = \"0.8\" }\nlocal = { path = \"local\" }\n\n\
+            [target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("Cargo.lock"),
+            "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.100\"\n\n\
+            [[package]]\nname = \"rand\"\nversion = \"0.8.5\"\n\n\
+            [[package]]\nname = \"libc\"\nversion = \"0.1.12\"\n",
+        )
+        .unwrap();
+        let mismatched = check(&temp_dir);
+
+        std::fs::write(
+            temp_dir.join("Cargo.lock"),
+            "version = 3\n\n<<<<<<< HEAD\n[[package]]\nname = \"serde\"\n\
+            version = \"1.0.100\"\n=======\n[[package]]\nname = \"serde\"\n\
+            version = \"1.0.150\"\n>>>>>>> feature\n",
+        )
+        .unwrap();
+        let conflicted = check(&temp_dir);
+        let blocks_build = has_conflicts(&temp_dir.join("Cargo.lock"));
+
+        std::fs::write(temp_dir.join("Cargo.lock"), "[[package]\nname = ").unwrap();
+        let corrupt = check(&temp_dir);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(mismatched.len(), 2, "{:?}", mismatched);
+        assert_eq!(mismatched[0].source, MISMATCH);
+        assert_eq!(mismatched[0].line, Some(6));
+        assert!(mismatched[0].message.contains("serde \"1.0.150\""));
+        assert!(mismatched[0].message.contains("cargo update -p serde"));
+        assert_eq!(mismatched[1].line, Some(11));
+        assert!(mismatched[1].message.contains("cargo update -p libc"));
+
+        assert_eq!(conflicted.len(), 1);
+        assert_eq!(conflicted[0].source, CONFLICT);
+        assert_eq!(conflicted[0].severity, Severity::Error);
+        assert_eq!(conflicted[0].line, Some(3));
+        assert!(conflicted[0].message.contains("cargo update -p serde"));
+        assert!(blocks_build);
+
+        assert_eq!(corrupt.len(), 1);
+        assert_eq!(corrupt[0].source, CORRUPT);
+        assert!(corrupt[0].message.contains("cargo generate-lockfile"));
+    }
+
+    #[test]
+    fn test_npm_and_yarn_lockfiles() {
+        let temp_dir = std::env::temp_dir().join("ess_test_lockfiles_npm");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("package.json"),
+            r#"{
+  "name": "app",
+  "dependencies": {
+    "lodash": "^4.17.0",
+    "@scope/ui": "~2.1.0",
+    "left-pad": "1.3.0",
+    "local": "file:../local"
+  },
+  "devDependencies": {
+    "jest": "29.x"
+  }
+}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("package-lock.json"),
+            r#"{"lockfileVersion": 3, "packages": {
+                "node_modules/lodash": {"version": "4.17.21"},
+                "node_modules/@scope/ui": {"version": "2.2.0"},
+                "node_modules/local": {"version": "0.0.1"},
+                "node_modules/jest": {"version": "29.7.0"}
+            }}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("yarn.lock"),
+            "# yarn lockfile v1\n\n\"@scope/ui@~2.1.0\":\n  version \"2.1.3\"\n\n\
+            lodash@^4.17.0, lodash@^4.0.0:\n  version \"4.17.21\"\n\n\
+            left-pad@1.3.0:\n  version \"1.3.0\"\n\njest@29.x:\n  version \"29.7.0\"\n\n\
+            \"local@file:../local\":\n  version \"0.0.1\"\n",
+        )
+        .unwrap();
+        let findings = check(&temp_dir);
+
+        std::fs::write(
+            temp_dir.join("yarn.lock"),
+            "<<<<<<< ours\nlodash@^4.17.0:\n=======\nlodash@^4.0.0:\n>>>>>>> theirs\n",
+        )
+        .unwrap();
+        let conflicted = yarn(&temp_dir);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(findings.len(), 2, "{:?}", messages);
+        assert!(messages[0].starts_with("package.json asks for @scope/ui \"~2.1.0\""));
+        assert_eq!(findings[0].line, Some(5));
+        assert!(messages[1].starts_with("left-pad is in package.json but not in"));
+        assert!(messages[1].contains("npm install --package-lock-only"));
+
+        assert_eq!(conflicted.len(), 1);
+        assert_eq!(conflicted[0].line, Some(1));
+        assert!(conflicted[0].message.contains("yarn install"));
+    }
+
+    #[test]
+    fn test_npm_ranges() {
+        assert!(npm_satisfies("^1.2.0", "1.9.0"));
+        assert!(!npm_satisfies("^1.2.0", "2.0.0"));
+        assert!(npm_satisfies("1.2.3", "1.2.3"));
+        assert!(!npm_satisfies("1.2.3", "1.2.4"));
+        assert!(npm_satisfies("1.x", "1.4.0"));
+        assert!(npm_satisfies(">=1.0.0 <2.0.0", "1.5.0"));
+        assert!(!npm_satisfies(">=1.0.0 <2.0.0", "2.0.0"));
+        assert!(npm_satisfies("1.0.0 - 1.5.0", "1.5.0"));
+        assert!(npm_satisfies("^1.0.0 || ^2.0.0", "2.3.0"));
+        assert!(npm_satisfies("v1.2.3", "1.2.3"));
+        assert!(npm_satisfies("latest", "9.9.9"));
+        assert!(npm_satisfies("github:user/repo", "1.0.0"));
+    }
+}
@@ -0,0 +1,298 @@
+//! Build logs too big to analyze in one go, e.g. a CI log of several
+//! megabytes: read a chunk at a time, with a cap on the errors kept from
+//! each chunk, and shown a page at a time
+
+use crate::fixer;
+use crate::parser::{parse_error, split_errors, ParsedError};
+use crate::regexes;
+use crate::rules::Registry;
+use crate::ui;
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Logs from this size on are analyzed in chunks
+pub const LARGE_LOG_BYTES: u64 = 1 << 20;
+
+/// Errors kept from each chunk unless `--per-chunk` says otherwise
+pub const DEFAULT_PER_CHUNK: usize = 50;
+
+/// Size a chunk is cut at, at the start of the next error
+const CHUNK_BYTES: usize = 256 << 10;
+
+/// Errors shown before asking whether to go on
+const PAGE_SIZE: usize = 10;
+
+/// The file is big enough to analyze in chunks
+pub fn is_large(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.len() >= LARGE_LOG_BYTES)
+}
+
+/// What the chunks of a log held
+#[derive(Debug, Default)]
+pub struct Analysis {
+    /// Each error with its own text, in log order
+    pub errors: Vec<(String, ParsedError)>,
+    pub chunks: usize,
+    /// Errors left out because their chunk had `per_chunk` already
+    pub capped: usize,
+    /// Errors left out because the same one was kept before
+    pub repeated: usize,
+}
+
+/// Reads a log a chunk at a time. A chunk ends at the first error after
+/// `size` bytes, so no error is cut in two, or at twice `size` for
+/// output with no errors to cut at.
+struct Chunks<R> {
+    input: R,
+    size: usize,
+    /// First line of the next chunk
+    carry: Option<String>,
+    /// Bytes read so far
+    read: u64,
+}
+
+impl<R: BufRead> Chunks<R> {
+    fn next_chunk(&mut self) -> Result<Option<String>> {
+        let mut chunk = self.carry.take().unwrap_or_default();
+        let mut bytes = Vec::new();
+        loop {
+            bytes.clear();
+            let read = self.input.read_until(b'\n', &mut bytes)?;
+            if read == 0 {
+                break;
+            }
+            self.read += read as u64;
+            let line = String::from_utf8_lossy(&bytes);
+            let line = regexes::ANSI_COLOR.replace_all(&line, "");
+            let full = chunk.len() >= self.size
+                && (regexes::ERROR_START.is_match(&line) || chunk.len() >= self.size * 2);
+            if full {
+                self.carry = Some(line.to_string());
+                return Ok(Some(chunk));
+            }
+            chunk.push_str(&line);
+        }
+        Ok(Some(chunk).filter(|c| !c.is_empty()))
+    }
+}
+
+/// Find the errors in `input`, `total` bytes long, a chunk at a time.
+/// At most `per_chunk` errors are kept from each chunk (0 for all), and
+/// an error repeated later in the log is kept once.
+fn analyze(
+    input: impl BufRead,
+    total: u64,
+    chunk_size: usize,
+    per_chunk: usize,
+    rules: &Registry,
+) -> Result<Analysis> {
+    let mut chunks = Chunks {
+        input,
+        size: chunk_size,
+        carry: None,
+        read: 0,
+    };
+    let mut analysis = Analysis::default();
+    let mut seen = HashSet::new();
+
+    while let Some(chunk) = chunks.next_chunk()? {
+        analysis.chunks += 1;
+        ui::print_info(&format!(
+            "Chunk {}: {} of {} read",
+            analysis.chunks,
+            megabytes(chunks.read),
+            megabytes(total)
+        ));
+        let mut kept = 0;
+        for text in split_errors(&chunk) {
+            let Some(parsed) = rules.resolve(&text, parse_error(&text)) else {
+                continue;
+            };
+            let key = (
+                parsed.file.clone(),
+                parsed.line,
+                parsed.column,
+                parsed.message.clone(),
+            );
+            if !seen.insert(key) {
+                analysis.repeated += 1;
+            } else if per_chunk > 0 && kept >= per_chunk {
+                analysis.capped += 1;
+            } else {
+                kept += 1;
+                analysis.errors.push((text, parsed));
+            }
+        }
+    }
+    Ok(analysis)
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1 << 20) as f64)
+}
+
+/// Analyze the log at `path` in chunks and explain what was found, a
+/// page at a time when `interactive`. Returns the errors kept.
+pub fn analyze_file(
+    path: &Path,
+    rules: &Registry,
+    per_chunk: usize,
+    interactive: bool,
+) -> Result<Vec<(String, ParsedError)>> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Could not read {}: {}", path.display(), e))?;
+    let total = file.metadata()?.len();
+    ui::print_info(&format!(
+        "{} is {}; analyzing it in chunks",
+        path.display(),
+        megabytes(total)
+    ));
+    let analysis = analyze(BufReader::new(file), total, CHUNK_BYTES, per_chunk, rules)?;
+
+    if analysis.errors.is_empty() {
+        ui::print_warning(&format!("No errors recognized in {}", path.display()));
+        ui::print_hint("Try 'ess list' to see supported error types");
+        return Ok(analysis.errors);
+    }
+    let shown = show_pages(
+        &analysis.errors,
+        rules,
+        interactive.then(|| std::io::stdin().lock()),
+    )?;
+
+    ui::print_blank_line();
+    ui::print_info(&format!(
+        "{} errors in {} chunks",
+        analysis.errors.len(),
+        analysis.chunks
+    ));
+    if analysis.repeated > 0 {
+        ui::print_info(&format!(
+            "{} repeats of errors above left out",
+            analysis.repeated
+        ));
+    }
+    if analysis.capped > 0 {
+        ui::print_warning(&format!(
+            "{} more errors left out, over {} per chunk",
+            analysis.capped, per_chunk
+        ));
+        ui::print_hint("Use --per-chunk 0 to keep every error");
+    }
+    if shown < analysis.errors.len() {
+        ui::print_hint("Use --save to keep them all and see the rest with 'ess replay'");
+    }
+    Ok(analysis.errors)
+}
+
+/// Explain `errors` a page at a time, asking on `input` before each
+/// page after the first. Without input, they are all shown. Returns how
+/// many were.
+fn show_pages(
+    errors: &[(String, ParsedError)],
+    rules: &Registry,
+    mut input: Option<impl BufRead>,
+) -> Result<usize> {
+    for (page, errors_on_page) in errors.chunks(PAGE_SIZE).enumerate() {
+        let shown = page * PAGE_SIZE;
+        if let Some(input) = input.as_mut().filter(|_| page > 0) {
+            ui::print_blank_line();
+            ui::print_prompt(&format!(
+                "Shown {} of {} errors. Enter for more, q to stop: ",
+                shown,
+                errors.len()
+            ));
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 || answer.trim().eq_ignore_ascii_case("q") {
+                ui::print_blank_line();
+                return Ok(shown);
+            }
+        }
+        for (i, (text, parsed)) in errors_on_page.iter().enumerate() {
+            fixer::explain_numbered(shown + i + 1, errors.len(), text, parsed, rules);
+        }
+    }
+    Ok(errors.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(errors: usize) -> String {
+        let mut log = String::new();
+        for i in 0..errors {
+            log.push_str(&format!("[{:04}] compiling module {}\n", i, i));
+            log.push_str(&format!(
+                "\x1b[1mmain.cpp:{}:5: error: 'x' was not declared in this scope\x1b[0m\n",
+                i + 1
+            ));
+            log.push_str("    x = 1;\n      ^\n");
+        }
+        log
+    }
+
+    #[test]
+    fn test_chunks_end_at_an_error() {
+        let log = log(20);
+        let mut chunks = Chunks {
+            input: log.as_bytes(),
+            size: 200,
+            carry: None,
+            read: 0,
+        };
+        let mut all = Vec::new();
+        while let Some(chunk) = chunks.next_chunk().unwrap() {
+            all.push(chunk);
+        }
+
+        assert!(all.len() > 1);
+        assert!(all[1..].iter().all(|c| c.starts_with("main.cpp:")));
+        assert!(!all.concat().contains('\x1b'));
+        assert_eq!(
+            all.concat().len(),
+            regexes::ANSI_COLOR.replace_all(&log, "").len()
+        );
+        assert_eq!(chunks.read, log.len() as u64);
+    }
+
+    #[test]
+    fn test_analyze_caps_and_dedups() {
+        let mut text = log(30);
+        // The same errors again, as a retried CI step prints them
+        text.push_str(&log(30));
+        let rules = Registry::new();
+
+        let all = analyze(text.as_bytes(), text.len() as u64, 400, 0, &rules).unwrap();
+        assert_eq!(all.errors.len(), 30);
+        assert_eq!(all.repeated, 30);
+        assert_eq!(all.capped, 0);
+        assert!(all.chunks > 2);
+        assert_eq!(all.errors[0].1.line, Some(1));
+
+        let capped = analyze(text.as_bytes(), text.len() as u64, 400, 2, &rules).unwrap();
+        assert!(capped.errors.len() < 30);
+        assert_eq!(capped.errors.len() + capped.capped + capped.repeated, 60);
+    }
+
+    #[test]
+    fn test_show_pages_stops_on_q() {
+        let errors: Vec<(String, ParsedError)> = log(25)
+            .lines()
+            .filter_map(|line| {
+                let line = regexes::ANSI_COLOR.replace_all(line, "");
+                parse_error(&line).map(|parsed| (line.to_string(), parsed))
+            })
+            .collect();
+        let rules = Registry::new();
+
+        assert_eq!(errors.len(), 25);
+        let stopped = show_pages(&errors, &rules, Some("\nq\n".as_bytes())).unwrap();
+        let all = show_pages(&errors, &rules, None::<&[u8]>).unwrap();
+
+        assert_eq!(stopped, 20);
+        assert_eq!(all, 25);
+    }
+}
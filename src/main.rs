@@ -1,14 +1,17 @@
 /// Made by Kubusieq | Jakubeq33
 /// Thanks for using EssentialsCode!
-mod config;
-mod fixer;
-mod parser;
-mod scanner;
-mod ui;
+use essentials_code::{
+    apply, capabilities, clipboard, compare, config, crash, daemon, docs, doctor, download, fixer,
+    git, health, history, hooks, knowledge, learn, logs, metrics, onboard, patterns, pick, plugins,
+    precommit, rdjson, recent, redact, replay, report, retry, rules, sarif, scanner, scans,
+    scripts, share, status, ui, update, usage, walk, wasm, why,
+};
+use essentials_code::{ParsedError, Severity};
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(
@@ -17,10 +20,90 @@ use std::path::PathBuf;
     version,
     about = "EssentialsCode - Smart error fixer for developers",
     long_about = None,
+    after_help = "Exit status, the same for every command:\n  \
+        0  nothing found\n  \
+        1  findings were reported\n  \
+        2  usage error: bad arguments, an invalid config file, or a scan, file or \
+        revision that isn't there\n  \
+        3  a tool or the environment failed, a check was skipped with --fail-on-missing-tools, \
+        or ess crashed",
 )]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// Don't use the network: no log downloads, dependency audits or
+    /// outdated checks
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Don't offer the first-run setup when there is no config
+    #[arg(long, global = true)]
+    pub no_interactive: bool,
+}
+
+/// Output switches available on every command
+#[derive(Args)]
+pub struct OutputArgs {
+    /// Don't show the banner (overrides output.show_banner)
+    #[arg(long, global = true)]
+    no_banner: bool,
+
+    /// Don't show hints (overrides output.show_hints)
+    #[arg(long, global = true)]
+    no_hints: bool,
+
+    /// Don't show before/after diffs (overrides output.show_diffs)
+    #[arg(long, global = true)]
+    no_diffs: bool,
+
+    /// Don't show section headers (overrides output.show_sections)
+    #[arg(long, global = true)]
+    no_sections: bool,
+
+    /// Cut fix instructions after N lines (overrides output.max_instruction_lines)
+    #[arg(long, global = true, value_name = "N")]
+    max_instruction_lines: Option<usize>,
+
+    /// Diff layout (overrides output.diff_style)
+    #[arg(long, global = true, value_enum)]
+    diff_style: Option<config::DiffStyle>,
+
+    /// Don't make file locations clickable (overrides output.hyperlinks)
+    #[arg(long, global = true)]
+    no_hyperlinks: bool,
+
+    /// Scan result layout (overrides output.view)
+    #[arg(long, global = true, value_enum)]
+    view: Option<config::OutputView>,
+
+    /// Order of scan results; "none" streams them as found (overrides output.sort)
+    #[arg(long, global = true, value_enum)]
+    sort: Option<config::SortOrder>,
+
+    /// Show only the totals, not each finding (same as --view summary)
+    #[arg(long, global = true, conflicts_with = "view")]
+    summary_only: bool,
+
+    /// What scans print, e.g. "sarif" for code scanning (overrides output.format)
+    #[arg(long, global = true, value_enum)]
+    format: Option<config::ReportFormat>,
+
+    /// Print only findings and totals: no banner, section headers, hints or diffs
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Redact secrets and personal details in terminal output too (overrides redact.terminal)
+    #[arg(long, global = true)]
+    redact: bool,
+
+    /// Bundle of settings to use: "quick", "full", "ci" or one from
+    /// [profiles.<name>] (overrides profile)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -32,23 +115,369 @@ pub enum Commands {
         #[arg(short, long, default_value = ".")]
         path: PathBuf,
 
+        /// Scan the project scanned most recently instead (see 'ess recent')
+        #[arg(long, conflicts_with = "path")]
+        last: bool,
+
         /// Specific language to check
         #[arg(short, long)]
         lang: Option<String>,
+
+        /// Maximum directory depth (overrides scan.max_depth)
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// Extra path or glob to ignore, e.g. "*.min.js" (repeatable)
+        #[arg(long = "ignore", value_name = "GLOB")]
+        ignore: Vec<String>,
+
+        /// Scan files .gitignore excludes too (overrides scan.respect_gitignore)
+        #[arg(long)]
+        no_gitignore: bool,
+
+        /// Files to check at once, 0 for one per CPU (overrides scan.jobs)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Tools to run at once, 0 for one per CPU (overrides scan.max_processes)
+        #[arg(long, value_name = "N")]
+        max_processes: Option<usize>,
+
+        /// Don't run language linters (overrides scan.run_linters)
+        #[arg(long)]
+        no_linters: bool,
+
+        /// Don't execute files to find runtime errors (overrides scan.run_files)
+        #[arg(long)]
+        no_run: bool,
+
+        /// Seconds a file may run before it's stopped (overrides scan.run_timeout)
+        #[arg(long, value_name = "SECONDS")]
+        run_timeout: Option<u64>,
+
+        /// Run the checks inside this container image, with the project
+        /// mounted read-only (overrides scan.docker_image)
+        #[arg(long, value_name = "IMAGE")]
+        in_docker: Option<String>,
+
+        /// Environment variable for the files run, repeatable (adds to [scan.env])
+        #[arg(long = "env", value_name = "KEY=VALUE", value_parser = parse_env)]
+        env: Vec<(String, String)>,
+
+        /// Show how long each tool and file took
+        #[arg(long)]
+        timings: bool,
+
+        /// Step through failing files one at a time after the scan
+        #[arg(long)]
+        walk: bool,
+
+        /// List the error lines in tool output the parser didn't recognize
+        #[arg(long)]
+        show_unparsed: bool,
+
+        /// Exit with code 3 if a check was skipped because its tool is missing
+        /// (overrides scan.fail_on_missing_tools)
+        #[arg(long)]
+        fail_on_missing_tools: bool,
+
+        /// Also compile C++ headers on their own (overrides scan.check_headers)
+        #[arg(long)]
+        headers: bool,
+
+        /// Also check code blocks in Markdown files (overrides scan.check_docs)
+        #[arg(long)]
+        docs: bool,
+
+        /// Also audit dependencies for known vulnerabilities (overrides scan.audit)
+        #[arg(long)]
+        audit: bool,
+
+        /// Also warn about dependencies a major version behind (overrides scan.outdated)
+        #[arg(long)]
+        outdated: bool,
+
+        /// Also flag unformatted files (overrides scan.check_formatting)
+        #[arg(long)]
+        formatting: bool,
+
+        /// Also run the project's tests and report failing ones (overrides scan.run_tests)
+        #[arg(long)]
+        with_tests: bool,
+
+        /// Rust feature set to check, repeatable: "all", "none" or a list like
+        /// "none,serde" (overrides rust.features)
+        #[arg(long = "features", value_name = "SET")]
+        features: Vec<String>,
+
+        /// Target triple to check Rust and C++ for, e.g. "aarch64-linux-gnu"
+        /// (overrides rust.target and cpp.target)
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Only check files with uncommitted changes, staged or not, and
+        /// untracked ones
+        #[arg(long)]
+        changed: bool,
+
+        /// Only check files changed since this git revision, e.g. "origin/main"
+        #[arg(long, value_name = "REV", conflicts_with = "changed")]
+        since: Option<String>,
+
+        /// Exit with code 1 only for findings this serious: "warning" for
+        /// any finding, "error" to let warnings pass
+        #[arg(long, value_enum, value_name = "SEVERITY", default_value = "warning")]
+        fail_on: Severity,
+
+        /// Ask a running 'ess daemon' for the results instead of scanning
+        /// here. It scans with its own environment (PATH, virtualenv) and
+        /// reuses results while no project file changed, even if installed
+        /// packages did
+        #[arg(long)]
+        daemon: bool,
+
+        /// Also save the results, to show again with 'ess replay'
+        #[arg(long, value_name = "PATH")]
+        save: Option<PathBuf>,
+
+        /// Also write a Markdown summary for a pull request or wiki page,
+        /// with secrets redacted
+        #[arg(long, value_name = "PATH")]
+        report_md: Option<PathBuf>,
     },
 
+    /// Audit dependencies for known vulnerabilities with cargo audit and npm audit
+    #[command(name = "audit")]
+    Audit {
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Re-check only the files that failed in the last scan
+    #[command(name = "retry")]
+    Retry {
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        /// Show how long each tool and file took
+        #[arg(long)]
+        timings: bool,
+    },
+
+    /// Scan two git revisions the same way and show the findings the
+    /// second adds and fixes, e.g. 'ess compare main feature-x'
+    #[command(name = "compare")]
+    Compare {
+        /// Revision to compare against, e.g. "main"
+        base: String,
+
+        /// Revision to check, e.g. a feature branch
+        head: String,
+
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Show which findings are new, fixed or unchanged between two scans:
+    /// ids from 'ess history', files saved with --save, or directories
+    #[command(name = "diff")]
+    Diff {
+        /// The scan to compare against, e.g. 12
+        a: String,
+
+        /// The scan to check, e.g. 13
+        b: String,
+
+        /// Project whose history the ids are from
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        /// List the findings both scans have too
+        #[arg(long)]
+        unchanged: bool,
+    },
+
+    /// Search the findings of a scan as you type, then look at one, open
+    /// it in your editor or apply its fix
+    #[command(name = "pick")]
+    Pick {
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        /// Pick from the last scan 'ess history' has instead of scanning again
+        #[arg(long)]
+        previous: bool,
+    },
+
+    /// Install or remove a git pre-commit hook that scans changed files
+    #[command(name = "hook", subcommand)]
+    Hook(HookCommand),
+
     /// Analyze a specific error message
     #[command(name = "bug", visible_alias = "fix")]
     Bug {
-        /// The error message to analyze
+        /// The error message to analyze, or "-" to read it from stdin
         #[arg(trailing_var_arg = true, num_args = 1..)]
         error: Vec<String>,
+
+        /// Read the errors from a file instead, e.g. a build log ("-" for stdin)
+        #[arg(short, long, value_name = "PATH", conflicts_with = "error")]
+        file: Option<PathBuf>,
+
+        /// Read the errors from the system clipboard, keeping their line breaks
+        #[arg(long, conflicts_with_all = ["error", "file"])]
+        clipboard: bool,
+
+        /// Download the errors from a CI log link (raw, GitHub file, gist or pastebin)
+        #[arg(long, value_name = "LINK", conflicts_with_all = ["error", "file", "clipboard"])]
+        url: Option<String>,
+
+        /// Also write the diagnosis to a Markdown file to share, with secrets redacted
+        #[arg(long, value_name = "PATH")]
+        share: Option<PathBuf>,
+
+        /// Also save the analysis, to show again with 'ess replay'
+        #[arg(long, value_name = "PATH")]
+        save: Option<PathBuf>,
+
+        /// Apply the automatic fixes ess has for the errors, e.g. a Cargo.toml
+        /// edition change
+        #[arg(long)]
+        apply: bool,
+
+        /// Most errors kept from each chunk of a large --file log, 0 for all
+        #[arg(long, value_name = "N", default_value_t = logs::DEFAULT_PER_CHUNK)]
+        per_chunk: usize,
+    },
+
+    /// Show a scan or bug analysis saved with --save again, without
+    /// running any tools
+    #[command(name = "replay")]
+    Replay {
+        /// File written by --save
+        file: PathBuf,
+    },
+
+    /// List this project's past scans, or show one again
+    #[command(name = "history")]
+    History {
+        #[command(subcommand)]
+        command: Option<HistoryCommand>,
+
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".", global = true)]
+        path: PathBuf,
+
+        /// Number of scans to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
     },
 
     /// List supported error patterns
     #[command(name = "list")]
     List,
 
+    /// Summarize the last scan and check whether it is stale
+    #[command(name = "status")]
+    Status {
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Generate an SVG health badge from the last scan
+    #[command(name = "badge")]
+    Badge {
+        /// Path to the project directory
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+
+        /// Where to write the badge
+        #[arg(short, long, default_value = "badge.svg")]
+        output: PathBuf,
+    },
+
+    /// Practice with flash-cards built from your past errors
+    #[command(name = "learn")]
+    Learn {
+        /// Maximum number of cards
+        #[arg(short, long, default_value_t = 10)]
+        count: usize,
+
+        /// Show a longer explanation with each answer
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Show or reset the counters kept on this machine (never uploaded)
+    #[command(name = "metrics", subcommand)]
+    Metrics(MetricsCommand),
+
+    /// List the projects scanned most recently
+    #[command(name = "recent")]
+    Recent,
+
+    /// Keep file hashes, found tools and scan results in memory, so
+    /// 'find-bug --daemon' runs after the first answer fast
+    #[command(name = "daemon")]
+    Daemon {
+        /// Stop the running daemon
+        #[arg(long)]
+        stop: bool,
+    },
+
+    /// Replace ess with the newest release, after checking its checksum
+    ///
+    /// The checksum is downloaded from the same release as the binary, so
+    /// it catches a corrupt download, not a tampered release. Releases
+    /// aren't signed.
+    #[command(name = "self-update")]
+    SelfUpdate {
+        /// Only report whether a newer version exists
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Check which compilers and interpreters are available
+    #[command(name = "doctor")]
+    Doctor {
+        /// Check ess itself instead: every bundled error pattern compiles
+        /// and matches its samples
+        #[arg(long)]
+        self_test: bool,
+    },
+
+    /// List the languages, rules and output formats this version supports
+    #[command(name = "capabilities")]
+    Capabilities {
+        /// Print JSON for IDE plugins and CI templates
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the long-form documentation of an error kind: causes, ways to
+    /// fix it, pitfalls and examples
+    #[command(name = "docs")]
+    Docs {
+        /// Rule ID or error kind, e.g. PY-KEYERROR or BorrowError
+        rule: String,
+
+        /// Open the page in a browser instead
+        #[arg(long)]
+        web: bool,
+    },
+
+    /// Explain why a command does or doesn't run: where PATH was searched,
+    /// similar programs and how to install it
+    Why {
+        /// Command to look for, e.g. "node"
+        command: String,
+    },
+
     /// Initialize a configuration file
     #[command(name = "init")]
     Init {
@@ -58,33 +487,705 @@ pub enum Commands {
     },
 }
 
-fn main() -> Result<()> {
+#[derive(Subcommand)]
+pub enum HookCommand {
+    /// Write .git/hooks/pre-commit, which runs 'ess find-bug --changed'
+    /// and stops commits that add errors
+    Install {
+        /// Replace a pre-commit hook ess didn't install
+        #[arg(long)]
+        force: bool,
+
+        /// Add the hook to .pre-commit-config.yaml for the pre-commit
+        /// framework (pre-commit.com) instead
+        #[arg(long)]
+        framework: bool,
+    },
+    /// Remove the pre-commit hook ess installed
+    Uninstall,
+}
+
+#[derive(Subcommand)]
+pub enum MetricsCommand {
+    /// Show scans run, errors found by type and fixes applied
+    Show,
+    /// Start counting from zero
+    Reset,
+}
+
+#[derive(Subcommand)]
+pub enum HistoryCommand {
+    /// Show the report of a past scan again, without running any tools
+    Show {
+        /// Scan id, as 'ess history' lists it
+        id: i64,
+    },
+}
+
+impl Commands {
+    /// Project directory the command works on, if any
+    fn project_path(&self) -> Option<&Path> {
+        match self {
+            Commands::FindBug { path, .. }
+            | Commands::Audit { path }
+            | Commands::Retry { path, .. }
+            | Commands::Compare { path, .. }
+            | Commands::Diff { path, .. }
+            | Commands::Pick { path, .. }
+            | Commands::Status { path }
+            | Commands::History { path, .. }
+            | Commands::Badge { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Exit codes, listed in the `--help` text
+const EXIT_CLEAN: i32 = 0;
+const EXIT_FINDINGS: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+/// A tool or the environment failed, a check was skipped with
+/// `fail_on_missing_tools`, or ess panicked
+const EXIT_FAILURE: i32 = 3;
+
+fn main() {
+    crash::install(EXIT_FAILURE);
+    // clap exits with EXIT_USAGE by itself on bad arguments
     let cli = Cli::parse();
+    let code = match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            if usage::is_usage_error(&e) {
+                EXIT_USAGE
+            } else {
+                EXIT_FAILURE
+            }
+        }
+    };
+    std::process::exit(code);
+}
+
+/// Apply the fixes for errors `ess bug` analyzed, whose paths are relative
+/// to the working directory, as far as `[fix]` allows
+fn apply_fixes(errors: &[ParsedError], policy: &config::FixConfig) -> Result<()> {
+    let mut fixes = Vec::new();
+    for error in errors {
+        let rule = error.error_type.name();
+        let Some(fix) = apply::suggest_fix(error, Path::new(".")) else {
+            continue;
+        };
+        let allowed = match apply::fix_policy(policy, rule) {
+            apply::FixPolicy::Never => apply::permitted(policy, rule, &fix),
+            apply::FixPolicy::Confirm => apply::confirm(&fix, &mut std::io::stdin().lock())?,
+            apply::FixPolicy::Auto | apply::FixPolicy::Default => true,
+        };
+        if allowed {
+            fixes.push(fix);
+        }
+    }
+    let plan = apply::plan(fixes);
+    if plan.fixes.is_empty() {
+        ui::print_info("No automatic fix for these errors");
+        return Ok(());
+    }
+    ui::print_blank_line();
+    for fix in plan.merged() {
+        apply::apply_fix(&fix)?;
+        ui::print_success(&format!("{}: {}", fix.file.display(), fix.description));
+    }
+    for conflict in &plan.conflicts {
+        ui::print_warning(&format!(
+            "Skipped '{}': it changes the same lines as '{}'",
+            conflict.fix.description, conflict.with
+        ));
+    }
+    Ok(())
+}
+
+/// Print a scan as a report for other tools, if one was asked for
+fn print_report(
+    format: config::ReportFormat,
+    path: &Path,
+    summary: &scanner::ScanSummary,
+    redactor: &redact::Redactor,
+) {
+    let root = scanner::normalize_path(path);
+    let report = match format {
+        config::ReportFormat::Text => return,
+        config::ReportFormat::Grep => {
+            print!(
+                "{}",
+                redactor.apply(&report::grep(&summary.findings, &root))
+            );
+            return;
+        }
+        config::ReportFormat::Sarif => sarif::log(&summary.findings, &root),
+        config::ReportFormat::Json => report::json(&summary.findings, &root),
+        config::ReportFormat::Rdjson => rdjson::log(&summary.findings, &root),
+    };
+    println!(
+        "{}",
+        redactor.apply(&serde_json::to_string_pretty(&report).unwrap_or_default())
+    );
+}
 
-    ui::print_banner();
+/// Exit code for a scan that fails on findings as serious as `fail_on`
+fn scan_exit_code(summary: &scanner::ScanSummary, fail_on: Severity) -> i32 {
+    let failing = summary
+        .findings
+        .iter()
+        .any(|f| f.severity == Severity::Error || fail_on == Severity::Warning);
+    if failing {
+        EXIT_FINDINGS
+    } else {
+        EXIT_CLEAN
+    }
+}
 
-    match cli.command {
-        Commands::FindBug { path, lang } => {
-            scanner::scan_project(&path, lang.as_deref())?;
+/// Show a saved session again, with the report `--format` asks for
+fn replay_session(
+    session: &replay::Session,
+    config: &config::Config,
+    report: bool,
+    redactor: &redact::Redactor,
+) -> Result<i32> {
+    match &session.analysis {
+        replay::Analysis::Scan { root, summary } => {
+            session.show(config, report)?;
+            print_report(config.output.format, root, summary, redactor);
+            Ok(scan_exit_code(summary, Severity::Warning))
+        }
+        replay::Analysis::Bug { .. } => {
+            session.show(config, false)?;
+            Ok(EXIT_CLEAN)
         }
-        Commands::Bug { error } => {
-            let error_text = error.join(" ");
-            if error_text.trim().is_empty() {
+    }
+}
+
+fn run(mut cli: Cli) -> Result<i32> {
+    if let Commands::FindBug {
+        path, last: true, ..
+    } = &mut cli.command
+    {
+        match recent::last_project() {
+            Some(last) => *path = last,
+            None => {
+                eprintln!("Error: No recently scanned project to scan again");
+                return Ok(EXIT_USAGE);
+            }
+        }
+    }
+
+    if let Commands::FindBug { path, .. } = &cli.command {
+        let reports = cli.output.quiet || cli.output.format.is_some();
+        if !cli.no_interactive && !reports && onboard::should_offer(path) {
+            onboard::run(path)?;
+        }
+    }
+
+    // Before loading the config, so a broken one doesn't stand in the way
+    // of writing a new one
+    if let Commands::Init { global } = cli.command {
+        init_config(global)?;
+        return Ok(EXIT_CLEAN);
+    }
+
+    let config = config::Config::load(Some(cli.command.project_path().unwrap_or(Path::new("."))))
+        .and_then(|config| {
+            // Catch bad [[patterns]] before any command runs
+            patterns::Patterns::new(&config.patterns)?;
+            Ok(config)
+        })
+        .and_then(|mut config| {
+            if let Some(profile) = cli.output.profile.clone().or(config.profile.clone()) {
+                config.apply_profile(&profile)?;
+            }
+            Ok(config)
+        });
+    let mut config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            return Ok(EXIT_USAGE);
+        }
+    };
+    config.output.apply_overrides(&config::OutputOverrides {
+        no_banner: cli.output.no_banner || cli.output.quiet,
+        no_hints: cli.output.no_hints || cli.output.quiet,
+        no_diffs: cli.output.no_diffs || cli.output.quiet,
+        no_sections: cli.output.no_sections || cli.output.quiet,
+        max_instruction_lines: cli.output.max_instruction_lines,
+        diff_style: cli.output.diff_style,
+        no_hyperlinks: cli.output.no_hyperlinks,
+        view: cli.output.view.or(cli
+            .output
+            .summary_only
+            .then_some(config::OutputView::Summary)),
+        sort: cli.output.sort,
+        format: cli.output.format,
+    });
+    ui::configure(&config.output);
+    let redactor = redact::Redactor::new(&config.redact)?;
+    if config.redact.terminal || cli.output.redact {
+        ui::redact(Some(redactor.clone()));
+    }
+
+    // Reports for other tools are printed on their own
+    let report = config.output.format != config::ReportFormat::Text
+        || matches!(cli.command, Commands::Capabilities { json: true });
+    if config.output.show_banner && !report {
+        ui::print_banner();
+    }
+
+    let code = match cli.command {
+        Commands::FindBug {
+            path,
+            last: _,
+            lang,
+            max_depth,
+            ignore,
+            no_gitignore,
+            jobs,
+            max_processes,
+            no_linters,
+            no_run,
+            run_timeout,
+            in_docker,
+            env,
+            timings,
+            walk,
+            show_unparsed,
+            fail_on_missing_tools,
+            headers,
+            docs,
+            audit,
+            outdated,
+            formatting,
+            with_tests,
+            features,
+            target,
+            changed,
+            since,
+            fail_on,
+            daemon: use_daemon,
+            save,
+            report_md,
+        } => {
+            config.scan.apply_overrides(&config::ScanOverrides {
+                max_depth,
+                ignore,
+                no_gitignore,
+                jobs,
+                max_processes,
+                no_linters,
+                no_run,
+                run_timeout,
+                docker_image: in_docker,
+                env,
+                fail_on_missing_tools,
+                check_headers: headers,
+                check_docs: docs,
+                audit,
+                outdated,
+                check_formatting: formatting,
+                run_tests: with_tests,
+            });
+            if cli.offline {
+                config.scan.audit = false;
+                config.scan.outdated = false;
+            }
+            config.rust.apply_overrides(&config::RustOverrides {
+                features,
+                target: target.clone(),
+            });
+            config.cpp.apply_overrides(&config::CppOverrides { target });
+            let options = scanner::ScanOptions {
+                lang,
+                timings,
+                quiet: report,
+                show_unparsed,
+                changed_since: since.or(changed.then(|| "HEAD".to_string())),
+                ..Default::default()
+            };
+            // Timings are of tools run here, and the daemon scans whole projects
+            let root = scanner::normalize_path(&path);
+            let answer = if !use_daemon || timings || options.changed_since.is_some() {
+                None
+            } else {
+                daemon::scan(&root, &config, options.lang.clone())
+            };
+            let summary = match answer {
+                Some(Ok((summary, cached))) => {
+                    scanner::show_summary(&root, &config, &options, &summary)?;
+                    if cached && !report {
+                        ui::print_hint(
+                            "From ess daemon: no file changed since it last scanned (drop --daemon to scan again)",
+                        );
+                    }
+                    summary
+                }
+                Some(Err(e)) => {
+                    ui::print_warning(&format!("ess daemon could not scan: {:#}", e));
+                    scanner::scan_project(&path, &config, &options)?
+                }
+                None => scanner::scan_project(&path, &config, &options)?,
+            };
+            print_report(config.output.format, &path, &summary, &redactor);
+            // A partial scan would look like progress next to a full one
+            if !options.is_partial() {
+                scans::record_scan(&root, &summary);
+            }
+            if let Some(save_path) = &save {
+                replay::Session::scan(&root, &summary).save(save_path)?;
+                if !report {
+                    ui::print_success(&format!("Results saved to {}", save_path.display()));
+                }
+            }
+            if let Some(md_path) = &report_md {
+                let doc = share::scan_report(&root, &summary.findings, &redactor);
+                std::fs::write(md_path, doc)
+                    .map_err(|e| anyhow::anyhow!("Could not write {}: {}", md_path.display(), e))?;
+                if !report {
+                    ui::print_success(&format!("Report written to {}", md_path.display()));
+                }
+            }
+            hooks::post_scan(&config.hooks, &root, &summary, save.as_deref())?;
+            if walk && !report {
+                walk::run(&path, &config, &summary)?;
+            }
+            if config.scan.fail_on_missing_tools && !summary.skipped.is_empty() {
+                EXIT_FAILURE
+            } else {
+                scan_exit_code(&summary, fail_on)
+            }
+        }
+        Commands::Audit { path } => {
+            if cli.offline {
+                ui::print_error("ess audit looks up advisories online, which --offline forbids");
+                return Ok(EXIT_USAGE);
+            }
+            config.scan.audit = true;
+            let summary = scanner::scan_project(
+                &path,
+                &config,
+                &scanner::ScanOptions {
+                    audit_only: true,
+                    quiet: report,
+                    ..Default::default()
+                },
+            )?;
+            print_report(config.output.format, &path, &summary, &redactor);
+            scan_exit_code(&summary, Severity::Warning)
+        }
+        Commands::Retry { path, timings } => {
+            if retry::run(&path, &config, timings)? > 0 {
+                EXIT_FINDINGS
+            } else {
+                EXIT_CLEAN
+            }
+        }
+        Commands::Diff {
+            a,
+            b,
+            path,
+            unchanged,
+        } => {
+            if compare::diff(&path, &config, &a, &b, unchanged)?
+                .added
+                .is_empty()
+            {
+                EXIT_CLEAN
+            } else {
+                EXIT_FINDINGS
+            }
+        }
+        Commands::Pick { path, previous } => {
+            let interactive = !cli.no_interactive
+                && std::io::stdin().is_terminal()
+                && std::io::stdout().is_terminal();
+            if !interactive {
+                ui::print_error("ess pick needs a terminal to type in");
+                ui::print_hint(
+                    "To filter findings in a script: ess find-bug --format grep | grep ...",
+                );
+                return Ok(EXIT_USAGE);
+            }
+            pick::run(&path, &config, previous)?;
+            EXIT_CLEAN
+        }
+        Commands::Compare { base, head, path } => {
+            if compare::run(&path, &config, &base, &head)?.added.is_empty() {
+                EXIT_CLEAN
+            } else {
+                EXIT_FINDINGS
+            }
+        }
+        Commands::Hook(HookCommand::Install {
+            framework: true, ..
+        }) => {
+            let root = git::repository(Path::new("."))
+                .map(|(root, _)| root)
+                .unwrap_or_else(|| PathBuf::from("."));
+            match precommit::install_framework(&root)? {
+                precommit::FrameworkInstall::Created(config) => {
+                    ui::print_success(&format!("Wrote {}", config.display()));
+                    ui::print_hint("Run 'pre-commit install' to have git use it");
+                }
+                precommit::FrameworkInstall::AlreadyThere(config) => {
+                    ui::print_info(&format!("{} already runs ess", config.display()));
+                }
+                precommit::FrameworkInstall::AddByHand(config) => {
+                    ui::print_info(&format!("Add this to {}:", config.display()));
+                    println!("\n{}", precommit::FRAMEWORK_SNIPPET);
+                }
+            }
+            EXIT_CLEAN
+        }
+        Commands::Hook(HookCommand::Install { force, .. }) => {
+            let root = scanner::normalize_path(Path::new("."));
+            let hook = precommit::install(&precommit::hooks_dir(&root)?, force)?;
+            ui::print_success(&format!("Installed {}", hook.display()));
+            ui::print_hint(&format!(
+                "Commits now run '{}'; skip it once with 'git commit --no-verify'",
+                precommit::COMMAND
+            ));
+            EXIT_CLEAN
+        }
+        Commands::Hook(HookCommand::Uninstall) => {
+            let hooks_dir = precommit::hooks_dir(&scanner::normalize_path(Path::new(".")))?;
+            if precommit::uninstall(&hooks_dir)? {
+                ui::print_success("Removed the pre-commit hook");
+            } else {
+                ui::print_info("No pre-commit hook to remove");
+            }
+            EXIT_CLEAN
+        }
+        Commands::Bug {
+            error,
+            file,
+            clipboard,
+            url,
+            share,
+            save,
+            apply,
+            per_chunk,
+        } => {
+            if url.is_some() && cli.offline {
+                ui::print_error("--url downloads the log, which --offline forbids");
+                ui::print_hint("Save the log and use --file instead");
+                return Ok(EXIT_USAGE);
+            }
+            let stdin = file.as_deref() == Some(Path::new("-"))
+                || error == ["-"]
+                // Piped in with no message given
+                || (error.is_empty()
+                    && file.is_none()
+                    && url.is_none()
+                    && !std::io::stdin().is_terminal());
+            // Multi-megabyte logs are read a chunk at a time instead
+            let large_log = file
+                .as_deref()
+                .filter(|path| !stdin && logs::is_large(path));
+            let error_text = match (&file, &url) {
+                _ if large_log.is_some() => String::new(),
+                _ if clipboard => clipboard::read()?,
+                (_, Some(url)) => download::fetch(url)?,
+                _ if stdin => fixer::read_error_text(&mut std::io::stdin().lock())?,
+                (Some(path), _) => match std::fs::File::open(path) {
+                    Ok(mut log) => fixer::read_error_text(&mut log)?,
+                    Err(e) => {
+                        eprintln!("Error: Could not read {}: {}", path.display(), e);
+                        return Ok(EXIT_USAGE);
+                    }
+                },
+                (None, None) => error.join(" "),
+            };
+            if large_log.is_none() && error_text.trim().is_empty() {
                 ui::print_error("Please provide an error message");
-                ui::print_hint("Usage: ess bug \"<paste your error here>\"");
-                return Ok(());
+                ui::print_hint(
+                    "Usage: ess bug \"<paste your error here>\", ess bug --file build.log, \
+                    ess bug --clipboard, ess bug --url <link>, or pipe it in: make 2>&1 | ess bug -",
+                );
+                return Ok(EXIT_USAGE);
+            }
+            let mut rules = rules::Registry::new();
+            rules.register(patterns::Patterns::new(&config.patterns)?);
+            rules.register(scripts::Scripts::load()?);
+            rules.register(wasm::WasmRules::new(&config.wasm_rules, Path::new("."))?);
+            if config.scan.plugins {
+                rules.register(plugins::Plugins::new(plugins::discover()));
+            }
+            let (error_text, analyzed) = match large_log {
+                Some(path) => {
+                    let interactive = !cli.no_interactive
+                        && std::io::stdin().is_terminal()
+                        && std::io::stdout().is_terminal();
+                    let analyzed = logs::analyze_file(path, &rules, per_chunk, interactive)?;
+                    // The log is too big to keep; the errors' own text stands in for it
+                    let text = analyzed
+                        .iter()
+                        .map(|(text, _)| text.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    (text, analyzed)
+                }
+                None => {
+                    let analyzed = fixer::analyze_errors(&error_text, &rules)?;
+                    (error_text, analyzed)
+                }
+            };
+            let errors: Vec<_> = analyzed.iter().map(|(_, parsed)| parsed.clone()).collect();
+            if let Some(save_path) = &save {
+                replay::Session::bug(&error_text, &analyzed).save(save_path)?;
+                ui::print_success(&format!("Analysis saved to {}", save_path.display()));
             }
-            fixer::analyze_error(&error_text)?;
+            if let Some(share_path) = &share {
+                let doc = share::diagnosis(&error_text, &errors, Path::new("."), &redactor);
+                std::fs::write(share_path, doc).map_err(|e| {
+                    anyhow::anyhow!("Could not write {}: {}", share_path.display(), e)
+                })?;
+                ui::print_success(&format!(
+                    "Diagnosis saved to {}, with secrets redacted",
+                    share_path.display()
+                ));
+            }
+            if apply {
+                apply_fixes(&errors, &config.fix)?;
+            }
+            for parsed in &errors {
+                history::record_error(parsed);
+            }
+            metrics::record_bugs_analyzed(errors.len().max(1));
+            if errors.is_empty() {
+                EXIT_CLEAN
+            } else {
+                EXIT_FINDINGS
+            }
+        }
+        Commands::Replay { file } => {
+            let session = replay::Session::load(&file)?;
+            replay_session(&session, &config, report, &redactor)?
+        }
+        Commands::History {
+            command: None,
+            path,
+            limit,
+        } => {
+            scans::run(&path, limit)?;
+            EXIT_CLEAN
+        }
+        Commands::History {
+            command: Some(HistoryCommand::Show { id }),
+            path,
+            ..
+        } => {
+            let session = scans::session(&scanner::normalize_path(&path), id)?;
+            replay_session(&session, &config, report, &redactor)?
         }
         Commands::List => {
-            ui::print_supported_patterns();
+            ui::print_supported_patterns(&config.patterns);
+            EXIT_CLEAN
         }
-        Commands::Init { global } => {
-            init_config(global)?;
+        Commands::Status { path } => {
+            status::run(&path, &config.scan)?;
+            EXIT_CLEAN
         }
-    }
+        Commands::Badge { path, output } => {
+            health::write_badge(&path, &output)?;
+            EXIT_CLEAN
+        }
+        Commands::Learn { count, explain } => {
+            learn::run(count, explain)?;
+            EXIT_CLEAN
+        }
+        Commands::Metrics(MetricsCommand::Show) => {
+            metrics::show()?;
+            EXIT_CLEAN
+        }
+        Commands::Metrics(MetricsCommand::Reset) => {
+            metrics::reset()?;
+            EXIT_CLEAN
+        }
+        Commands::Recent => {
+            recent::run()?;
+            EXIT_CLEAN
+        }
+        Commands::Daemon { stop: true } => {
+            if daemon::stop()? {
+                ui::print_success("ess daemon stopped");
+            } else {
+                ui::print_info("ess daemon is not running");
+            }
+            EXIT_CLEAN
+        }
+        Commands::Daemon { stop: false } => {
+            daemon::serve()?;
+            EXIT_CLEAN
+        }
+        Commands::Doctor { self_test: true } => {
+            if doctor::self_test() > 0 {
+                EXIT_FAILURE
+            } else {
+                EXIT_CLEAN
+            }
+        }
+        Commands::Doctor { self_test: false } => {
+            doctor::run(&config)?;
+            EXIT_CLEAN
+        }
+        Commands::SelfUpdate { .. } if cli.offline => {
+            ui::print_error("Updating needs the network; run it without --offline");
+            EXIT_USAGE
+        }
+        Commands::SelfUpdate { check } => {
+            update::run(check)?;
+            EXIT_CLEAN
+        }
+        Commands::Capabilities { json: true } => {
+            let capabilities = capabilities::describe();
+            println!("{}", serde_json::to_string_pretty(&capabilities)?);
+            EXIT_CLEAN
+        }
+        Commands::Capabilities { json: false } => {
+            capabilities::show();
+            EXIT_CLEAN
+        }
+        Commands::Docs { rule, web } => match docs::resolve(&rule) {
+            Some(entry) if web => {
+                docs::open_web(entry)?;
+                EXIT_CLEAN
+            }
+            Some(entry) => {
+                docs::show(entry);
+                EXIT_CLEAN
+            }
+            None => {
+                ui::print_error(&format!("No documentation for '{}'", rule));
+                let kinds: Vec<&str> = knowledge::entries().iter().map(|e| e.kind).collect();
+                ui::print_hint(&format!("Known error kinds: {}", kinds.join(", ")));
+                EXIT_USAGE
+            }
+        },
+        Commands::Why { command } => {
+            if why::run(&command) {
+                EXIT_CLEAN
+            } else {
+                EXIT_FAILURE
+            }
+        }
+        Commands::Init { .. } => unreachable!("handled before the config is loaded"),
+    };
 
-    Ok(())
+    Ok(code)
+}
+
+/// A `--env KEY=VALUE` argument
+fn parse_env(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
 }
 
 fn init_config(global: bool) -> Result<()> {
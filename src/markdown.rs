@@ -0,0 +1,225 @@
+use crate::parser::Language;
+use std::path::Path;
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        matches!(
+            ext.to_string_lossy().to_lowercase().as_str(),
+            "md" | "markdown"
+        )
+    })
+}
+
+/// A fenced code block in a Markdown file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    pub language: Language,
+    /// 1-based line of the block's first line of code
+    pub line: u32,
+    pub code: String,
+}
+
+impl CodeBlock {
+    /// Extension for the block on its own
+    pub fn extension(&self) -> &'static str {
+        match self.language {
+            Language::Python => "py",
+            Language::Rust => "rs",
+            _ => "mjs",
+        }
+    }
+
+    /// The code at the same lines as in the Markdown file. Rust examples
+    /// are treated the way rustdoc treats them: `# ` lines are shown code,
+    /// and examples without `fn main` are wrapped in one, opened on the
+    /// fence line.
+    pub fn aligned(&self) -> String {
+        let padding = "\n".repeat(self.line.saturating_sub(1) as usize);
+        if self.language != Language::Rust {
+            return format!("{}{}", padding, self.code);
+        }
+
+        let code: String = self
+            .code
+            .lines()
+            .map(|line| match line.trim_start().strip_prefix('#') {
+                Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                    format!("{}\n", rest.strip_prefix(' ').unwrap_or(rest))
+                }
+                _ => format!("{}\n", line),
+            })
+            .collect();
+        if code.contains("fn main") {
+            return format!("{}{}", padding, code);
+        }
+        let padding = padding.get(1..).unwrap_or_default();
+        format!("{}fn main() {{\n{}}}\n", padding, code)
+    }
+}
+
+/// Language of a fence's info string (`python`, `rust,no_run`, ...), if
+/// its examples can be checked
+fn fence_language(info: &str) -> Option<Language> {
+    let mut words = info
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase);
+
+    match words.next()?.as_str() {
+        "python" | "py" | "python3" => Some(Language::Python),
+        "javascript" | "js" | "mjs" => Some(Language::JavaScript),
+        // Blocks rustdoc doesn't compile aren't expected to compile here
+        "rust" | "rs"
+            if !words.any(|w| matches!(w.as_str(), "ignore" | "compile_fail" | "text")) =>
+        {
+            Some(Language::Rust)
+        }
+        _ => None,
+    }
+}
+
+/// Fenced code blocks in languages that can be checked. Unclosed fences and
+/// interactive Python sessions (`>>>`) are left out.
+pub fn code_blocks(source: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // Fence characters, indent, language and first code line of the open block
+    let mut open: Option<(String, usize, Option<Language>, u32)> = None;
+    let mut code = String::new();
+
+    for (i, line) in source.lines().enumerate() {
+        // Only spaces and tabs indent, so `indent` is a byte count of ASCII
+        // that `line` can be sliced at
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        let indent = line.len() - trimmed.len();
+
+        match &open {
+            None => {
+                let fence_char = match trimmed.chars().next() {
+                    Some(c @ ('`' | '~')) => c,
+                    _ => continue,
+                };
+                let fence_len = trimmed.chars().take_while(|&c| c == fence_char).count();
+                if fence_len >= 3 {
+                    let fence = trimmed[..fence_len].to_string();
+                    let language = fence_language(&trimmed[fence_len..]);
+                    open = Some((fence, indent, language, i as u32 + 2));
+                    code.clear();
+                }
+            }
+            Some((fence, fence_indent, language, start)) => {
+                let closes = trimmed.starts_with(fence.as_str())
+                    && trimmed
+                        .trim_start_matches(fence.chars().next().unwrap_or('`'))
+                        .trim()
+                        .is_empty();
+                if !closes {
+                    let strip = indent.min(*fence_indent);
+                    code.push_str(&line[strip..]);
+                    code.push('\n');
+                    continue;
+                }
+
+                let interactive = code.trim_start().starts_with(">>>");
+                if let Some(language) = language.as_ref().filter(|_| !interactive) {
+                    if !code.trim().is_empty() {
+                        blocks.push(CodeBlock {
+                            language: language.clone(),
+                            line: *start,
+                            code: std::mem::take(&mut code),
+                        });
+                    }
+                }
+                open = None;
+            }
+        }
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_blocks() {
+        let source = r#"# Example
+
+```python
+print("hi")
+```
+
+```bash
+ls -la
+```
+
+```rust,ignore
+let x = ;
+```
+
+~~~js
+const a = 1;
+~~~
+
+```python
+>>> 1 + 1
+2
+```
+
+```py
+unclosed(
+"#;
+        let blocks = code_blocks(source);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].language, Language::Python);
+        assert_eq!(blocks[0].line, 4);
+        assert_eq!(blocks[0].code, "print(\"hi\")\n");
+        assert_eq!(blocks[1].language, Language::JavaScript);
+        assert_eq!(blocks[1].line, 16);
+    }
+
+    #[test]
+    fn test_indented_block_is_dedented() {
+        let source = "1. Run:\n\n   ```python\n   if True:\n       pass\n   ```\n";
+        let blocks = code_blocks(source);
+
+        assert_eq!(blocks[0].code, "if True:\n    pass\n");
+    }
+
+    #[test]
+    fn test_unicode_whitespace_is_code() {
+        let source = " ```python\n\u{a0}x = 1\n ```\n";
+        let blocks = code_blocks(source);
+
+        assert_eq!(blocks[0].code, "\u{a0}x = 1\n");
+    }
+
+    #[test]
+    fn test_fence_language() {
+        assert_eq!(fence_language("python"), Some(Language::Python));
+        assert_eq!(fence_language("rust,no_run"), Some(Language::Rust));
+        assert_eq!(fence_language("rust,compile_fail"), None);
+        assert_eq!(
+            fence_language("js title=\"app.js\""),
+            Some(Language::JavaScript)
+        );
+        assert_eq!(fence_language(""), None);
+    }
+
+    #[test]
+    fn test_rust_examples_are_wrapped_in_main() {
+        let block = CodeBlock {
+            language: Language::Rust,
+            line: 3,
+            code: "# use std::fmt;\nlet x = 1;\n".to_string(),
+        };
+        let aligned = block.aligned();
+        let lines: Vec<&str> = aligned.lines().collect();
+
+        assert_eq!(lines[1], "fn main() {");
+        assert_eq!(lines[2], "use std::fmt;");
+        assert_eq!(lines[3], "let x = 1;");
+        assert_eq!(lines[4], "}");
+    }
+}
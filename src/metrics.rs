@@ -0,0 +1,223 @@
+use crate::config::Config;
+use crate::finding::Finding;
+use crate::history;
+use crate::status::format_age;
+use crate::ui;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Metrics file name (in the data directory)
+const METRICS_FILE_NAME: &str = "metrics.json";
+
+/// Error kinds listed by `ess metrics show`
+const MAX_LISTED_KINDS: usize = 10;
+
+/// Counters kept on this machine only. Nothing is ever sent anywhere.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Metrics {
+    /// Seconds since the Unix epoch when counting started
+    #[serde(default)]
+    pub since: u64,
+    /// Full project scans
+    #[serde(default)]
+    pub scans: u64,
+    /// Findings per rule, e.g. "SyntaxError" or "policy/no-print"
+    #[serde(default)]
+    pub errors: BTreeMap<String, u64>,
+    /// Error messages explained with `ess bug`
+    #[serde(default)]
+    pub bugs_analyzed: u64,
+    /// Automatic fixes that passed verification
+    #[serde(default)]
+    pub fixes_applied: u64,
+    /// Automatic fixes undone because the error stayed or new ones appeared
+    #[serde(default)]
+    pub fixes_rolled_back: u64,
+}
+
+impl Metrics {
+    pub fn total_errors(&self) -> u64 {
+        self.errors.values().sum()
+    }
+
+    /// Error kinds, most frequent first
+    pub fn top_errors(&self) -> Vec<(&str, u64)> {
+        let mut kinds: Vec<(&str, u64)> = self
+            .errors
+            .iter()
+            .map(|(kind, count)| (kind.as_str(), *count))
+            .collect();
+        kinds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        kinds
+    }
+}
+
+/// Where the counters are kept
+pub struct MetricsStore {
+    path: PathBuf,
+}
+
+impl MetricsStore {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Counters in the user's data directory
+    pub fn open_default() -> Option<Self> {
+        Config::data_dir().map(|dir| Self::new(&dir.join(METRICS_FILE_NAME)))
+    }
+
+    /// The stored counters. A missing or corrupt file starts from zero.
+    pub fn load(&self) -> Metrics {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Change the counters and save them
+    pub fn update(&self, change: impl FnOnce(&mut Metrics)) -> Result<()> {
+        let mut metrics = self.load();
+        if metrics.since == 0 {
+            metrics.since = history::now();
+        }
+        change(&mut metrics);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&metrics)?)?;
+        Ok(())
+    }
+
+    pub fn reset(&self) -> Result<()> {
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Change the default counters, ignoring failures
+fn update(change: impl FnOnce(&mut Metrics)) {
+    if let Some(store) = MetricsStore::open_default() {
+        let _ = store.update(change);
+    }
+}
+
+/// Count a full scan and what it found
+pub fn record_scan(findings: &[Finding]) {
+    update(|metrics| {
+        metrics.scans += 1;
+        for finding in findings {
+            *metrics
+                .errors
+                .entry(finding.rule().to_string())
+                .or_default() += 1;
+        }
+    });
+}
+
+/// Count error messages explained with `ess bug`
+pub fn record_bugs_analyzed(count: usize) {
+    update(|metrics| metrics.bugs_analyzed += count as u64);
+}
+
+/// Count fixes after verification: kept ones and rolled back ones
+pub fn record_fixes(count: usize, kept: bool) {
+    update(|metrics| {
+        if kept {
+            metrics.fixes_applied += count as u64;
+        } else {
+            metrics.fixes_rolled_back += count as u64;
+        }
+    });
+}
+
+/// `ess metrics show`
+pub fn show() -> Result<()> {
+    ui::print_section("Metrics");
+
+    let metrics = MetricsStore::open_default()
+        .map(|store| store.load())
+        .unwrap_or_default();
+    if metrics == Metrics::default() {
+        ui::print_warning("Nothing counted yet");
+        ui::print_hint("Counters start with your next 'ess find-bug' or 'ess bug'");
+        return Ok(());
+    }
+
+    ui::print_info(&format!(
+        "Counting since {} ago (kept on this machine, never uploaded)",
+        format_age(history::now().saturating_sub(metrics.since))
+    ));
+    ui::print_info(&format!("Scans: {}", metrics.scans));
+    ui::print_info(&format!("Errors found: {}", metrics.total_errors()));
+    ui::print_info(&format!("Errors explained: {}", metrics.bugs_analyzed));
+    ui::print_info(&format!(
+        "Fixes applied: {} ({} rolled back)",
+        metrics.fixes_applied, metrics.fixes_rolled_back
+    ));
+
+    let top = metrics.top_errors();
+    if !top.is_empty() {
+        println!();
+        ui::print_info("Most common errors:");
+        for (kind, count) in top.iter().take(MAX_LISTED_KINDS) {
+            println!("    {:>5}  {}", count, kind);
+        }
+    }
+
+    Ok(())
+}
+
+/// `ess metrics reset`
+pub fn reset() -> Result<()> {
+    if let Some(store) = MetricsStore::open_default() {
+        store.reset()?;
+    }
+    ui::print_success("Metrics reset");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_and_reset() {
+        let temp_dir = std::env::temp_dir().join("ess_test_metrics");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let store = MetricsStore::new(&temp_dir.join(METRICS_FILE_NAME));
+        assert_eq!(store.load(), Metrics::default());
+
+        store
+            .update(|m| {
+                m.scans += 1;
+                m.errors.insert("SyntaxError".to_string(), 2);
+                m.errors.insert("KeyError".to_string(), 5);
+            })
+            .unwrap();
+        store.update(|m| m.fixes_applied += 1).unwrap();
+
+        let metrics = store.load();
+        assert_eq!(metrics.scans, 1);
+        assert_eq!(metrics.fixes_applied, 1);
+        assert_eq!(metrics.total_errors(), 7);
+        assert!(metrics.since > 0);
+        assert_eq!(
+            metrics.top_errors(),
+            vec![("KeyError", 5), ("SyntaxError", 2)]
+        );
+
+        store.reset().unwrap();
+        assert_eq!(store.load(), Metrics::default());
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
@@ -0,0 +1,331 @@
+//! The edition and minimum Rust version (MSRV) a crate's Cargo.toml asks
+//! for, and what to change when the code or a dependency needs more:
+//! the manifest, or the toolchain
+
+use crate::apply::{Edit, Fix};
+use crate::update;
+use std::path::{Path, PathBuf};
+
+/// What a `RustVersion` error needs, parsed from its text
+#[derive(Debug, Clone, PartialEq)]
+pub enum Requirement {
+    Edition(String),
+    Rustc(String),
+    /// Nightly Rust, with the feature to enable when known
+    Nightly(Option<String>),
+}
+
+impl Requirement {
+    pub fn parse(text: &str) -> Option<Self> {
+        let (kind, value) = text.split_once(' ').unwrap_or((text, ""));
+        match kind {
+            "edition" => Some(Requirement::Edition(value.to_string())),
+            "rustc" => Some(Requirement::Rustc(value.to_string())),
+            "nightly" => Some(Requirement::Nightly(
+                Some(value.to_string()).filter(|v| !v.is_empty()),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The oldest Rust that knows an edition
+fn edition_rustc(edition: &str) -> Option<&'static str> {
+    match edition {
+        "2018" => Some("1.31"),
+        "2021" => Some("1.56"),
+        "2024" => Some("1.85"),
+        _ => None,
+    }
+}
+
+/// A crate's Cargo.toml
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub path: PathBuf,
+    content: String,
+    /// `package.edition`, when set in this file
+    pub edition: Option<String>,
+    /// `package.rust-version`, when set in this file
+    pub rust_version: Option<String>,
+    /// Keys taken from the workspace, e.g. `edition.workspace = true`
+    inherited: Vec<String>,
+}
+
+impl Manifest {
+    /// The manifest of the crate `file` is in: the nearest Cargo.toml
+    /// above it, else the one in `root`
+    pub fn find(root: &Path, file: &str) -> Option<Self> {
+        let start = root.join(file);
+        start
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join("Cargo.toml"))
+            .chain(std::iter::once(root.join("Cargo.toml")))
+            .find_map(|path| Self::read(&path))
+    }
+
+    pub fn read(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let value: toml::Value = toml::from_str(&content).ok()?;
+        let package = value.get("package")?;
+        // `{ workspace = true }` is set somewhere else
+        let key = |name: &str| package.get(name)?.as_str().map(str::to_string);
+        let inherited = ["edition", "rust-version"]
+            .iter()
+            .filter(|name| package.get(**name).is_some_and(|v| !v.is_str()))
+            .map(|name| name.to_string())
+            .collect();
+        Some(Self {
+            path: path.to_path_buf(),
+            edition: key("edition"),
+            rust_version: key("rust-version"),
+            inherited,
+            content,
+        })
+    }
+
+    /// The edition in effect: 2015 when none is set
+    pub fn edition(&self) -> &str {
+        self.edition.as_deref().unwrap_or("2015")
+    }
+
+    /// The manifest with `key = "value"` in `[package]`, replacing the
+    /// line that sets it or added right under the header
+    fn with_package_key(&self, key: &str, value: &str) -> String {
+        let setting = format!("{} = \"{}\"", key, value);
+        let mut lines: Vec<String> = self
+            .content
+            .split_inclusive('\n')
+            .map(str::to_string)
+            .collect();
+        let Some(header) = lines.iter().position(|l| l.trim() == "[package]") else {
+            return self.content.clone();
+        };
+        let end = lines[header + 1..]
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .map_or(lines.len(), |i| header + 1 + i);
+        let existing = (header + 1..end).find(|&i| {
+            lines[i]
+                .trim_start()
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        });
+
+        let line_ending = |line: &str| {
+            let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            if ending.is_empty() { "\n" } else { ending }.to_string()
+        };
+        match existing {
+            Some(i) => {
+                let indent = &lines[i][..lines[i].len() - lines[i].trim_start().len()];
+                lines[i] = format!("{}{}{}", indent, setting, line_ending(&lines[i]));
+            }
+            None => {
+                if !lines[header].ends_with('\n') {
+                    lines[header].push('\n');
+                }
+                let ending = line_ending(&lines[header]);
+                lines.insert(header + 1, format!("{}{}", setting, ending));
+            }
+        }
+        lines.concat()
+    }
+}
+
+/// The manifest change a requirement asks for, if the manifest is what's
+/// behind: an older edition, or a rust-version below what's needed
+pub fn fix(manifest: &Manifest, requirement: &str) -> Option<Fix> {
+    let (key, value, description) = match Requirement::parse(requirement)? {
+        Requirement::Edition(edition) if manifest.edition() < edition.as_str() => {
+            let description = format!("Set edition = \"{}\"", edition);
+            ("edition", edition, description)
+        }
+        Requirement::Rustc(version)
+            if manifest
+                .rust_version
+                .as_deref()
+                .is_some_and(|current| update::is_newer(&version, current)) =>
+        {
+            let description = format!("Raise rust-version to \"{}\"", version);
+            ("rust-version", version, description)
+        }
+        _ => return None,
+    };
+    // The workspace's manifest sets it for every member
+    if manifest.inherited.iter().any(|k| k == key) {
+        return None;
+    }
+    Some(Fix {
+        file: manifest.path.clone(),
+        description,
+        edits: vec![Edit::ReplaceAll {
+            text: manifest.with_package_key(key, &value),
+        }],
+    })
+}
+
+/// How to resolve a requirement, given the crate's manifest if found
+pub fn advice(requirement: &str, manifest: Option<&Manifest>) -> String {
+    let manifest_name = manifest.map_or("Cargo.toml".to_string(), |m| {
+        m.path.to_string_lossy().to_string()
+    });
+    match Requirement::parse(requirement) {
+        Some(Requirement::Edition(edition)) => {
+            let rustc = edition_rustc(&edition).unwrap_or("a newer version");
+            match manifest {
+                Some(m) if m.edition() >= edition.as_str() => format!(
+                    "{} already asks for edition {}, which needs Rust {} or newer. \
+                    This toolchain is older; update it:\n\n  rustup update\n\n\
+                    or give this project a toolchain of its own:\n\n  rustup override set {}",
+                    manifest_name, edition, rustc, rustc
+                ),
+                _ => format!(
+                    "This code needs edition {}, but {} uses {}. Set it under [package]:\n\n  \
+                    edition = \"{}\"\n\n\
+                    Run 'cargo fix --edition' first, so code the new edition changes is \
+                    migrated. 'ess bug --apply' edits the manifest. Edition {} needs Rust {} \
+                    or newer.",
+                    edition,
+                    manifest_name,
+                    manifest.map_or("an older one", |m| m.edition()),
+                    edition,
+                    edition,
+                    rustc
+                ),
+            }
+        }
+        Some(Requirement::Rustc(version)) => {
+            let mut advice = format!(
+                "This needs rustc {} or newer. Update the toolchain:\n\n  rustup update\n\n\
+                or pin this project to that version:\n\n  rustup override set {}\n\n\
+                To stay on this toolchain instead, keep the dependency on an older \
+                release: cargo update <package> --precise <older version>",
+                version, version
+            );
+            if let Some(current) = manifest
+                .and_then(|m| m.rust_version.as_deref())
+                .filter(|current| update::is_newer(&version, current))
+            {
+                advice.push_str(&format!(
+                    "\n\n{} says rust-version = \"{}\", which no longer holds. Raise it:\n\n  \
+                    rust-version = \"{}\"\n\n'ess bug --apply' edits the manifest.",
+                    manifest_name, current, version
+                ));
+            }
+            advice
+        }
+        Some(Requirement::Nightly(feature)) => {
+            let mut advice = format!(
+                "This uses an unstable feature{}, which only nightly Rust has. If it has \
+                been stabilized since this toolchain came out, 'rustup update' is enough. \
+                Otherwise use nightly for this project:\n\n  rustup override set nightly",
+                feature
+                    .as_ref()
+                    .map_or(String::new(), |f| format!(" ({})", f))
+            );
+            if let Some(feature) = feature {
+                advice.push_str(&format!(
+                    "\n\nand enable it at the top of main.rs or lib.rs:\n\n  #![feature({})]",
+                    feature
+                ));
+            }
+            advice
+        }
+        None => format!(
+            "Check the edition and rust-version in {} against 'rustc --version'.",
+            manifest_name
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::apply_edits;
+
+    fn manifest(content: &str) -> Manifest {
+        let value: toml::Value = toml::from_str(content).unwrap();
+        let key = |name: &str| value["package"].get(name)?.as_str().map(str::to_string);
+        Manifest {
+            path: PathBuf::from("Cargo.toml"),
+            edition: key("edition"),
+            rust_version: key("rust-version"),
+            inherited: Vec::new(),
+            content: content.to_string(),
+        }
+    }
+
+    fn fixed(manifest: &Manifest, requirement: &str) -> Option<String> {
+        let fix = fix(manifest, requirement)?;
+        Some(apply_edits(&manifest.content, &fix.edits))
+    }
+
+    #[test]
+    fn test_fix_edition() {
+        let old = manifest(
+            "[package]\nname = \"app\"\nedition = \"2018\"\n\n[dependencies]\nedition = \"x\"\n",
+        );
+        assert_eq!(
+            fixed(&old, "edition 2021").as_deref(),
+            Some(
+                "[package]\nname = \"app\"\nedition = \"2021\"\n\n[dependencies]\nedition = \"x\"\n"
+            )
+        );
+        let unset = manifest("[package]\r\nname = \"app\"\r\n");
+        assert_eq!(
+            fixed(&unset, "edition 2018").as_deref(),
+            Some("[package]\r\nedition = \"2018\"\r\nname = \"app\"\r\n")
+        );
+        // The manifest is right; the toolchain is too old
+        let current = manifest("[package]\nname = \"app\"\nedition = \"2024\"\n");
+        assert_eq!(fixed(&current, "edition 2024"), None);
+        assert!(advice("edition 2024", Some(&current)).contains("rustup override set 1.85"));
+    }
+
+    #[test]
+    fn test_fix_rust_version() {
+        let old = manifest("[package]\nname = \"app\"\nrust-version = \"1.70\"\n");
+        assert_eq!(
+            fixed(&old, "rustc 1.74").as_deref(),
+            Some("[package]\nname = \"app\"\nrust-version = \"1.74\"\n")
+        );
+        assert!(advice("rustc 1.74", Some(&old)).contains("rust-version = \"1.70\""));
+        assert_eq!(fixed(&old, "rustc 1.65"), None);
+        let unset = manifest("[package]\nname = \"app\"\n");
+        assert_eq!(fixed(&unset, "rustc 1.74"), None);
+        assert_eq!(fixed(&unset, "nightly let_chains"), None);
+    }
+
+    #[test]
+    fn test_find_manifest() {
+        let temp_dir = std::env::temp_dir().join("ess_test_msrv_find");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(temp_dir.join("crates/core/src")).unwrap();
+        std::fs::write(
+            temp_dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/core\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("crates/core/Cargo.toml"),
+            "[package]\nname = \"core\"\nedition.workspace = true\nrust-version = \"1.75\"\n",
+        )
+        .unwrap();
+
+        let found = Manifest::find(&temp_dir, "crates/core/src/lib.rs");
+        let workspace = Manifest::find(&temp_dir, "Cargo.toml");
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let found = found.unwrap();
+        assert_eq!(found.path, temp_dir.join("crates/core/Cargo.toml"));
+        assert_eq!(found.edition, None);
+        assert_eq!(found.rust_version.as_deref(), Some("1.75"));
+        assert!(fix(&found, "edition 2021").is_none());
+        assert!(fix(&found, "rustc 1.80").is_some());
+        assert!(workspace.is_none());
+    }
+}
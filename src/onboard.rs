@@ -0,0 +1,192 @@
+//! First-run setup: the first time ess runs with no config anywhere, it
+//! offers a `.essentialscode.toml` tuned for the languages it finds and a
+//! pre-commit hook. It is offered once; `--no-interactive` skips it.
+
+use crate::config::{Config, ScanConfig};
+use crate::parser::Language;
+use crate::precommit;
+use crate::scanner;
+use crate::ui;
+use anyhow::Result;
+use std::io::{BufRead, IsTerminal};
+use std::path::Path;
+
+/// File in the data dir that records the setup was offered
+const OFFERED_FILE_NAME: &str = "onboarded";
+
+/// Directories each language's tools fill with files not worth scanning,
+/// on top of the usual ones
+fn extra_ignores(language: &Language) -> &'static [&'static str] {
+    match language {
+        Language::Python => &[".pytest_cache", ".mypy_cache", ".tox"],
+        Language::JavaScript | Language::TypeScript => &["coverage", ".nuxt", "out"],
+        Language::Java => &[".gradle"],
+        Language::CSharp => &["bin", "obj"],
+        Language::Cpp => &["cmake-build-debug", "cmake-build-release"],
+        Language::Rust | Language::Unknown => &[],
+    }
+}
+
+/// The name `[languages] enabled` takes
+fn config_name(language: &Language) -> String {
+    language.to_string().to_lowercase()
+}
+
+/// The setup hasn't been offered, there is no config to respect, and
+/// someone is there to answer
+pub fn should_offer(root: &Path) -> bool {
+    let offered = Config::data_dir().is_none_or(|dir| dir.join(OFFERED_FILE_NAME).exists());
+    let configured = Config::project_config_path(root).exists()
+        || Config::global_config_path().is_some_and(|path| path.exists());
+    !offered && !configured && std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Offer the setup for the project at `root`, asking on stdin. It won't
+/// be offered again, whatever the answers.
+pub fn run(root: &Path) -> Result<()> {
+    if let Some(dir) = Config::data_dir() {
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(OFFERED_FILE_NAME), "")?;
+    }
+    setup(root, &mut std::io::stdin().lock())?;
+    ui::print_hint(
+        "This setup won't be offered again; 'ess init' writes a config with every setting",
+    );
+    Ok(())
+}
+
+fn setup(root: &Path, input: &mut impl BufRead) -> Result<()> {
+    let languages = scanner::project_languages(root, &ScanConfig::default())?;
+    if languages.is_empty() {
+        return Ok(());
+    }
+
+    ui::print_section("Welcome to EssentialsCode");
+    ui::print_blank_line();
+    let names: Vec<String> = languages.iter().map(ToString::to_string).collect();
+    ui::print_info(&format!("Detected {}", names.join(" + ")));
+
+    let config_path = Config::project_config_path(root);
+    if ask(
+        input,
+        &format!(
+            "Create {} tuned for them? [Y/n] ",
+            config_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        ),
+        true,
+    )? {
+        std::fs::write(&config_path, project_config(&languages))?;
+        ui::print_success(&format!("Created {}", config_path.display()));
+    }
+
+    if let Ok(hooks_dir) = precommit::hooks_dir(root) {
+        if ask(
+            input,
+            "Install a pre-commit hook that checks changed files? [y/N] ",
+            false,
+        )? {
+            match precommit::install(&hooks_dir, false) {
+                Ok(hook) => ui::print_success(&format!("Installed {}", hook.display())),
+                Err(e) => ui::print_warning(&format!("{:#}", e)),
+            }
+        }
+    }
+    ui::print_blank_line();
+    Ok(())
+}
+
+/// Ask a yes/no question; an empty answer or closed input is `default`
+fn ask(input: &mut impl BufRead, question: &str, default: bool) -> Result<bool> {
+    ui::print_prompt(question);
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        ui::print_blank_line();
+        return Ok(default);
+    }
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "" => default,
+        answer => matches!(answer, "y" | "yes"),
+    })
+}
+
+/// A config for a project in `languages`: only those are checked, and
+/// their tools' output directories are skipped
+pub fn project_config(languages: &[Language]) -> String {
+    let mut ignore = ScanConfig::default().ignore;
+    for language in languages {
+        for dir in extra_ignores(language) {
+            if !ignore.iter().any(|d| d == dir) {
+                ignore.push(dir.to_string());
+            }
+        }
+    }
+    let quoted = |items: Vec<String>| {
+        items
+            .iter()
+            .map(|item| format!("\"{}\"", item))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    format!(
+        "# EssentialsCode configuration, written by the first-run setup.\n\
+        # Every setting is described in the file 'ess init' writes.\n\
+        \n\
+        [scan]\n\
+        ignore = [{}]\n\
+        \n\
+        [languages]\n\
+        # Only these are checked; remove the line to check every supported language\n\
+        enabled = [{}]\n",
+        quoted(ignore),
+        quoted(languages.iter().map(config_name).collect())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_config() {
+        let content = project_config(&[Language::Python, Language::TypeScript]);
+        let config: Config = toml::from_str(&content).unwrap();
+
+        assert_eq!(config.languages.enabled, vec!["python", "typescript"]);
+        assert!(config.is_language_enabled("py"));
+        assert!(!config.is_language_enabled("rust"));
+        let ignore = &config.scan.ignore;
+        assert!(ignore.contains(&"node_modules".to_string()));
+        assert!(ignore.contains(&".pytest_cache".to_string()));
+        assert!(ignore.contains(&"coverage".to_string()));
+        assert!(!ignore.contains(&"obj".to_string()));
+    }
+
+    #[test]
+    fn test_setup_writes_config() {
+        let temp_dir = std::env::temp_dir().join("ess_test_onboard");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("app.py"), "print('hi')\n").unwrap();
+        std::fs::write(temp_dir.join("web.ts"), "let a = 1;\n").unwrap();
+        let config_path = Config::project_config_path(&temp_dir);
+
+        setup(&temp_dir, &mut "n\n".as_bytes()).unwrap();
+        let declined = config_path.exists();
+        setup(&temp_dir, &mut "\n".as_bytes()).unwrap();
+        let written = std::fs::read_to_string(&config_path).unwrap_or_default();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert!(!declined);
+        assert!(
+            written.contains("enabled = [\"python\", \"typescript\"]"),
+            "{}",
+            written
+        );
+    }
+}
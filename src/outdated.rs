@@ -0,0 +1,305 @@
+use crate::audit::semver_prefix;
+use crate::finding::{Finding, Severity};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A direct dependency with a newer release than the one in use
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outdated {
+    pub name: String,
+    pub current: String,
+    pub latest: String,
+}
+
+impl Outdated {
+    /// The latest release is a new major version (minor for 0.x), so the
+    /// project won't pick it up by itself
+    pub fn is_far_behind(&self) -> bool {
+        semver_prefix(&self.current) != semver_prefix(&self.latest)
+    }
+}
+
+/// `cargo outdated --root-deps-only --format json` report
+#[derive(Deserialize)]
+struct CargoOutdated {
+    dependencies: Vec<CargoDependency>,
+}
+
+#[derive(Deserialize)]
+struct CargoDependency {
+    name: String,
+    project: String,
+    latest: String,
+}
+
+/// `npm outdated --json` entry
+#[derive(Deserialize)]
+struct NpmDependency {
+    current: Option<String>,
+    latest: String,
+}
+
+/// `pip list --outdated --format=json` entry
+#[derive(Deserialize)]
+struct PipPackage {
+    name: String,
+    version: String,
+    latest_version: String,
+}
+
+/// Outdated crates in a `cargo outdated` JSON report. "---" marks a crate
+/// that was removed or can't be resolved.
+pub fn cargo_outdated(output: &str) -> Vec<Outdated> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoOutdated>(line).ok())
+        .flat_map(|report| report.dependencies)
+        .filter(|d| d.project != "---" && d.latest != "---")
+        .map(|d| Outdated {
+            name: d.name,
+            current: d.project,
+            latest: d.latest,
+        })
+        .collect()
+}
+
+/// Outdated packages in an `npm outdated --json` report. Packages that
+/// aren't installed have no current version and are left out.
+pub fn npm_outdated(output: &str) -> Vec<Outdated> {
+    let report: BTreeMap<String, NpmDependency> = serde_json::from_str(output).unwrap_or_default();
+    report
+        .into_iter()
+        .filter_map(|(name, d)| {
+            Some(Outdated {
+                name,
+                current: d.current?,
+                latest: d.latest,
+            })
+        })
+        .collect()
+}
+
+/// Outdated packages in a `pip list --outdated` report, limited to the
+/// project's own requirements (pip lists the whole environment)
+pub fn pip_outdated(output: &str, requirements: &[String]) -> Vec<Outdated> {
+    let report: Vec<PipPackage> = serde_json::from_str(output).unwrap_or_default();
+    report
+        .into_iter()
+        .filter(|p| requirements.contains(&normalize_name(&p.name)))
+        .map(|p| Outdated {
+            name: p.name,
+            current: p.version,
+            latest: p.latest_version,
+        })
+        .collect()
+}
+
+/// Python package names compare case-insensitively, with `_` and `.` as `-`
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Name of a requirement like "requests[socks]>=2.0; python_version > '3'"
+fn requirement_name(requirement: &str) -> Option<String> {
+    let name: String = requirement
+        .trim()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    Some(normalize_name(&name)).filter(|n| !n.is_empty())
+}
+
+/// Direct Python dependencies, from `requirements.txt` and the
+/// `[project]` or `[tool.poetry]` dependencies in `pyproject.toml`
+pub fn python_requirements(root: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+
+    if let Ok(content) = std::fs::read_to_string(root.join("requirements.txt")) {
+        names.extend(
+            content
+                .lines()
+                .map(str::trim)
+                // Options like "-r other.txt" and "--index-url" aren't packages
+                .filter(|l| !l.starts_with('#') && !l.starts_with('-'))
+                .filter_map(requirement_name),
+        );
+    }
+
+    let pyproject = std::fs::read_to_string(root.join("pyproject.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml::Table>().ok());
+    if let Some(pyproject) = pyproject {
+        let project = pyproject
+            .get("project")
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_array());
+        names.extend(
+            project
+                .into_iter()
+                .flatten()
+                .filter_map(|d| d.as_str())
+                .filter_map(requirement_name),
+        );
+
+        let poetry = pyproject
+            .get("tool")
+            .and_then(|t| t.get("poetry"))
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_table());
+        names.extend(
+            poetry
+                .into_iter()
+                .flat_map(|d| d.keys())
+                .filter(|name| *name != "python")
+                .map(|name| normalize_name(name)),
+        );
+    }
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// 1-based line of the manifest that declares `name`, e.g. `serde = "1"`,
+/// `"lodash": "^4.0.0"` or `requests>=2.0`
+fn declaration_line(manifest: &str, name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    manifest
+        .lines()
+        .position(|line| {
+            let line = line.trim().to_lowercase();
+            let rest = line
+                .strip_prefix('"')
+                .and_then(|l| l.strip_prefix(name.as_str()))
+                .and_then(|l| l.strip_prefix('"'))
+                .or_else(|| line.strip_prefix(name.as_str()));
+            rest.is_some_and(|r| r.starts_with(|c: char| " =:<>!~;[.".contains(c)) || r.is_empty())
+        })
+        .map(|i| i as u32 + 1)
+}
+
+/// Findings for the dependencies that are far behind, located in the
+/// manifest that declares them. `upgrade` gives the command for one.
+pub fn findings(
+    manifest: &Path,
+    outdated: &[Outdated],
+    source: &str,
+    upgrade: impl Fn(&Outdated) -> String,
+) -> Vec<Finding> {
+    let content = std::fs::read_to_string(manifest).unwrap_or_default();
+    outdated
+        .iter()
+        .filter(|d| d.is_far_behind())
+        .map(|d| {
+            let message = format!(
+                "{} {} is behind {} - upgrade with '{}'",
+                d.name,
+                d.current,
+                d.latest,
+                upgrade(d)
+            );
+            Finding::new(manifest, Severity::Warning, &message, source)
+                .at(declaration_line(&content, &d.name), None)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outdated(name: &str, current: &str, latest: &str) -> Outdated {
+        Outdated {
+            name: name.to_string(),
+            current: current.to_string(),
+            latest: latest.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_is_far_behind() {
+        assert!(outdated("tokio", "0.2.25", "1.37.0").is_far_behind());
+        assert!(outdated("rand", "0.7.3", "0.8.5").is_far_behind());
+        assert!(!outdated("serde", "1.0.100", "1.0.197").is_far_behind());
+        assert!(!outdated("regex", "0.1.2", "0.1.9").is_far_behind());
+    }
+
+    #[test]
+    fn test_parse_reports() {
+        let cargo = r#"{"crate_name":"app","dependencies":[{"name":"tokio","project":"0.2.25","compat":"0.2.25","latest":"1.37.0","kind":"Normal","platform":null},{"name":"old","project":"1.0.0","compat":"---","latest":"---","kind":"Normal","platform":null}]}"#;
+        assert_eq!(
+            cargo_outdated(cargo),
+            vec![outdated("tokio", "0.2.25", "1.37.0")]
+        );
+
+        let npm = r#"{"lodash": {"current": "3.10.1", "wanted": "3.10.1", "latest": "4.17.21", "dependent": "app"},
+                      "react": {"wanted": "18.2.0", "latest": "18.2.0", "dependent": "app"}}"#;
+        assert_eq!(
+            npm_outdated(npm),
+            vec![outdated("lodash", "3.10.1", "4.17.21")]
+        );
+
+        let pip = r#"[{"name": "Flask", "version": "1.1.4", "latest_version": "3.0.3", "latest_filetype": "wheel"},
+                      {"name": "pip", "version": "23.0", "latest_version": "24.0", "latest_filetype": "wheel"}]"#;
+        assert_eq!(
+            pip_outdated(pip, &["flask".to_string()]),
+            vec![outdated("Flask", "1.1.4", "3.0.3")]
+        );
+    }
+
+    #[test]
+    fn test_python_requirements() {
+        let temp_dir = std::env::temp_dir().join("ess_test_python_requirements");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("requirements.txt"),
+            "# web\nFlask>=1.0\nrequests[socks]==2.25.0 ; python_version > '3'\n-r dev.txt\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("pyproject.toml"),
+            "[project]\nname = \"app\"\ndependencies = [\"Django_Rest.Framework~=3.0\"]\n\n[tool.poetry.dependencies]\npython = \"^3.10\"\nrich = \"^13\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            python_requirements(&temp_dir),
+            vec!["django-rest-framework", "flask", "requests", "rich"]
+        );
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_findings_point_at_declaration() {
+        let temp_dir = std::env::temp_dir().join("ess_test_outdated_findings");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let manifest = temp_dir.join("Cargo.toml");
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1\"\ntokio-util = \"0.2\"\ntokio = { version = \"0.2\" }\n",
+        )
+        .unwrap();
+
+        let list = vec![
+            outdated("serde", "1.0.100", "1.0.197"),
+            outdated("tokio", "0.2.25", "1.37.0"),
+        ];
+        let findings = findings(&manifest, &list, "cargo outdated", |d| {
+            format!("cargo add {}@{}", d.name, d.latest)
+        });
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[0].line, Some(7));
+        assert_eq!(
+            findings[0].message,
+            "tokio 0.2.25 is behind 1.37.0 - upgrade with 'cargo add tokio@1.37.0'"
+        );
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
@@ -1,16 +1,22 @@
-use regex::Regex;
+use crate::locale;
+use crate::regexes;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedError {
     pub file: String,
     pub line: Option<u32>,
     pub column: Option<u32>,
+    /// Last line and column of the offending code, when the tool marks a
+    /// range. Both are inclusive.
+    pub end_line: Option<u32>,
+    pub end_column: Option<u32>,
     pub message: String,
     pub error_type: ErrorType,
     pub language: Language,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ErrorType {
     MissingInclude(String),
     MissingSemicolon,
@@ -26,16 +32,164 @@ pub enum ErrorType {
     ValueError(String),
     MissingEnvVar(String),
     RequestsError(String),
+    /// A template block left open; holds its tag (`for`, `if`, or `{` for EJS)
+    UnclosedTemplateBlock(String),
+    /// A variable the template uses but was not given when rendering
+    UndefinedTemplateVariable(String),
+    /// A `#[derive]` that can't be resolved or expanded
+    DeriveError(String),
+    /// Arguments that don't match a `format!`/`println!` string
+    FormatError(String),
+    /// A Java class used without its import; holds the full name to
+    /// import when it is a well-known class, else the class name
+    MissingImport(String),
+    /// A Java NullPointerException; holds what was null, as the JVM says
+    NullPointer(String),
+    /// A server's port is taken by another process; holds the port when
+    /// the error says it
+    PortInUse(Option<u16>),
+    /// A dependency with a known vulnerability; holds how to upgrade it
+    Vulnerability(String),
+    /// A file without the `policy.license_header`; holds the header as
+    /// comments in the file's language
+    MissingLicenseHeader(String),
+    /// A `[policy]` rule the file breaks; holds the rule ID
+    PolicyViolation(String),
+    /// A file that looks unformatted; holds the formatter command that fixes it
+    Unformatted(String),
+    /// A test assertion that failed; holds what it compared
+    AssertionFailed(String),
+    /// Code or a package needs another Rust edition or a newer toolchain;
+    /// holds what: "edition 2021", "rustc 1.74", or "nightly" and the
+    /// feature, e.g. "nightly let_chains"
+    RustVersion(String),
+    /// An error matched by one of the project's `[[patterns]]`
+    Custom(CustomFix),
     Unknown(String),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A `[[patterns]]` match, with its templates filled in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomFix {
+    pub title: String,
+    pub fix: String,
+    /// Line an automatic fix inserts, and where
+    pub insert: Option<(Anchor, String)>,
+}
+
+/// Where a pattern's automatic fix inserts its line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    /// First line, below a `#!` line
+    TopOfFile,
+    /// Below the last import, include or `use`
+    #[default]
+    AfterImports,
+    /// Above the line the error points at, with its indentation
+    AtErrorLine,
+}
+
+impl ErrorType {
+    /// Short name of the error kind, e.g. "KeyError"
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorType::MissingInclude(_) => "MissingInclude",
+            ErrorType::MissingSemicolon => "MissingSemicolon",
+            ErrorType::UndeclaredVariable(_) => "UndeclaredVariable",
+            ErrorType::SyntaxError(_) => "SyntaxError",
+            ErrorType::IndentationError => "IndentationError",
+            ErrorType::ImportError(_) => "ImportError",
+            ErrorType::TypeError(_) => "TypeError",
+            ErrorType::ModuleNotFound(_) => "ModuleNotFound",
+            ErrorType::BorrowError(_) => "BorrowError",
+            ErrorType::KeyError(_) => "KeyError",
+            ErrorType::AttributeError(_) => "AttributeError",
+            ErrorType::ValueError(_) => "ValueError",
+            ErrorType::MissingEnvVar(_) => "MissingEnvVar",
+            ErrorType::RequestsError(_) => "RequestsError",
+            ErrorType::UnclosedTemplateBlock(_) => "UnclosedTemplateBlock",
+            ErrorType::UndefinedTemplateVariable(_) => "UndefinedTemplateVariable",
+            ErrorType::DeriveError(_) => "DeriveError",
+            ErrorType::FormatError(_) => "FormatError",
+            ErrorType::MissingImport(_) => "MissingImport",
+            ErrorType::NullPointer(_) => "NullPointer",
+            ErrorType::PortInUse(_) => "PortInUse",
+            ErrorType::Vulnerability(_) => "Vulnerability",
+            ErrorType::MissingLicenseHeader(_) => "MissingLicenseHeader",
+            ErrorType::PolicyViolation(_) => "PolicyViolation",
+            ErrorType::Unformatted(_) => "Unformatted",
+            ErrorType::AssertionFailed(_) => "AssertionFailed",
+            ErrorType::RustVersion(_) => "RustVersion",
+            ErrorType::Custom(_) => "Custom",
+            ErrorType::Unknown(_) => "Unknown",
+        }
+    }
+
+    /// Stable ID of the error kind, e.g. "PY-KEY-001"; none when unknown
+    pub fn id(&self) -> Option<&'static str> {
+        rule_id(self.name())
+    }
+}
+
+/// Stable ID of each error kind, by `ErrorType::name`. IDs never change
+/// or get reused, so `[rules] disabled` keeps meaning the same check.
+const RULE_IDS: &[(&str, &str)] = &[
+    ("MissingInclude", "CPP-INC-001"),
+    ("MissingSemicolon", "GEN-SYN-001"),
+    ("UndeclaredVariable", "GEN-NAME-001"),
+    ("SyntaxError", "GEN-SYN-002"),
+    ("IndentationError", "PY-SYN-001"),
+    ("ImportError", "GEN-DEP-001"),
+    ("TypeError", "GEN-TYPE-001"),
+    ("ModuleNotFound", "GEN-DEP-002"),
+    ("BorrowError", "RS-BORROW-001"),
+    ("KeyError", "PY-KEY-001"),
+    ("AttributeError", "PY-ATTR-001"),
+    ("ValueError", "PY-VAL-001"),
+    ("MissingEnvVar", "GEN-ENV-001"),
+    ("RequestsError", "PY-NET-001"),
+    ("UnclosedTemplateBlock", "TPL-SYN-001"),
+    ("UndefinedTemplateVariable", "TPL-VAR-001"),
+    ("DeriveError", "RS-MACRO-001"),
+    ("FormatError", "RS-MACRO-002"),
+    ("MissingImport", "JAVA-IMP-001"),
+    ("NullPointer", "JAVA-NULL-001"),
+    ("PortInUse", "GEN-PORT-001"),
+    ("Vulnerability", "DEP-VULN-001"),
+    ("MissingLicenseHeader", "POL-LIC-001"),
+    ("PolicyViolation", "POL-RULE-001"),
+    ("Unformatted", "GEN-FMT-001"),
+    ("AssertionFailed", "TEST-ASSERT-001"),
+    ("RustVersion", "RS-TOOL-001"),
+    ("Custom", "USER-PAT-001"),
+];
+
+/// Stable ID of an error kind, by `ErrorType::name`, e.g. "PY-KEY-001"
+pub fn rule_id(kind: &str) -> Option<&'static str> {
+    RULE_IDS
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, id)| *id)
+}
+
+/// The error kind a stable ID names, matched case-insensitively
+pub fn rule_kind(id: &str) -> Option<&'static str> {
+    RULE_IDS
+        .iter()
+        .find(|(_, rule)| rule.eq_ignore_ascii_case(id))
+        .map(|(name, _)| *name)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Language {
     Cpp,
     Python,
     JavaScript,
     TypeScript,
     Rust,
+    Java,
+    CSharp,
     Unknown,
 }
 
@@ -47,15 +201,69 @@ impl std::fmt::Display for Language {
             Language::JavaScript => write!(f, "JavaScript"),
             Language::TypeScript => write!(f, "TypeScript"),
             Language::Rust => write!(f, "Rust"),
+            Language::Java => write!(f, "Java"),
+            Language::CSharp => write!(f, "C#"),
             Language::Unknown => write!(f, "Unknown"),
         }
     }
 }
 
 pub fn parse_error(input: &str) -> Option<ParsedError> {
+    let input = &*locale::to_english(input);
+    let parsed = parse_language_error(input);
+    if !regexes::ADDRESS_IN_USE.is_match(input) {
+        return parsed;
+    }
+
+    // Every language reports a taken port its own way; it's one fix
+    let error_type = ErrorType::PortInUse(
+        regexes::LISTEN_PORT
+            .captures(input)
+            .and_then(|c| c[1].parse().ok()),
+    );
+    // A Python traceback's OSError isn't one the Python parser knows
+    let frame = regexes::PYTHON_FRAME.captures_iter(input).last();
+    Some(match parsed {
+        Some(parsed) => ParsedError {
+            error_type,
+            ..parsed
+        },
+        None => ParsedError {
+            file: frame.as_ref().map_or(String::new(), |f| f[1].to_string()),
+            line: frame.as_ref().and_then(|f| f[2].parse().ok()),
+            column: None,
+            end_line: None,
+            end_column: None,
+            message: input
+                .lines()
+                .find(|line| regexes::ADDRESS_IN_USE.is_match(line))
+                .unwrap_or(input)
+                .trim()
+                .to_string(),
+            error_type,
+            language: if frame.is_some() {
+                Language::Python
+            } else {
+                Language::Unknown
+            },
+        },
+    })
+}
+
+fn parse_language_error(input: &str) -> Option<ParsedError> {
+    // Before Python and JavaScript, whose tracebacks these errors come in
+    if let Some(err) = parse_template_error(input) {
+        return Some(err);
+    }
     if let Some(err) = parse_cpp_error(input) {
         return Some(err);
     }
+    if let Some(err) = parse_java_error(input) {
+        return Some(err);
+    }
+    if let Some(err) = parse_csharp_error(input) {
+        return Some(err);
+    }
     if let Some(err) = parse_python_error(input) {
         return Some(err);
     }
@@ -69,10 +277,73 @@ pub fn parse_error(input: &str) -> Option<ParsedError> {
     None
 }
 
+/// Every error in the input, e.g. a whole build log, in order
+pub fn parse_errors(input: &str) -> Vec<ParsedError> {
+    let errors: Vec<ParsedError> = split_errors(input)
+        .iter()
+        .filter_map(|text| parse_error(text))
+        .collect();
+    if errors.is_empty() {
+        return parse_error(input).into_iter().collect();
+    }
+    errors
+}
+
+/// Split output into one text per error: a compiler error with the notes
+/// that follow it, or a Python traceback. Warnings are left out, and
+/// input with a single error comes back whole.
+pub fn split_errors(input: &str) -> Vec<String> {
+    let input = &*locale::to_english(input);
+    let mut errors: Vec<String> = vec![String::new()];
+    let mut in_error = true;
+    // Chained exceptions are one error, told as several tracebacks
+    let mut chained = false;
+
+    for line in input.lines() {
+        if regexes::ERROR_END.is_match(line) {
+            in_error = false;
+        } else if regexes::ERROR_START.is_match(line) && !chained {
+            errors.push(line.to_string());
+            in_error = true;
+        } else if in_error {
+            let error = errors.last_mut().unwrap();
+            if !error.is_empty() {
+                error.push('\n');
+            }
+            error.push_str(line);
+        }
+        if !line.trim().is_empty() {
+            chained = line.contains("another exception occurred")
+                || line.contains("direct cause of the following exception");
+        }
+    }
+
+    // Text before the first error is kept only when it says something
+    errors.retain(|error| !error.trim().is_empty());
+    if errors.len() <= 1 {
+        return vec![input.to_string()];
+    }
+    errors
+}
+
 fn parse_cpp_error(input: &str) -> Option<ParsedError> {
-    let re = Regex::new(r"([^\s:]+\.(cpp|cc|cxx|c|h|hpp)):(\d+):(\d+): error: (.+)").ok()?;
+    if let Some(cap) = regexes::MSVC_ERROR.captures(input) {
+        let message = format!("{}: {}", &cap[4], &cap[5]);
+        let error_type = detect_cpp_error_type(&message, input);
+
+        return Some(ParsedError {
+            file: cap[1].to_string(),
+            line: cap[2].parse().ok(),
+            column: cap.get(3).and_then(|m| m.as_str().parse().ok()),
+            end_line: None,
+            end_column: None,
+            message,
+            error_type,
+            language: Language::Cpp,
+        });
+    }
 
-    if let Some(cap) = re.captures(input) {
+    if let Some(cap) = regexes::CPP_ERROR.captures(input) {
         let file = cap[1].to_string();
         let line: u32 = cap[3].parse().ok()?;
         let col: u32 = cap[4].parse().ok()?;
@@ -84,6 +355,8 @@ fn parse_cpp_error(input: &str) -> Option<ParsedError> {
             file,
             line: Some(line),
             column: Some(col),
+            end_line: None,
+            end_column: None,
             message,
             error_type,
             language: Language::Cpp,
@@ -93,15 +366,237 @@ fn parse_cpp_error(input: &str) -> Option<ParsedError> {
     None
 }
 
+fn parse_java_error(input: &str) -> Option<ParsedError> {
+    let Some(cap) = regexes::JAVAC_ERROR.captures(input) else {
+        return parse_java_exception(input);
+    };
+    let line: u32 = cap.get(2).or(cap.get(4))?.as_str().parse().ok()?;
+    // javac marks the column with a caret under the code line
+    let column = match cap.get(3).or(cap.get(5)) {
+        Some(column) => column.as_str().parse().ok(),
+        None => input[cap.get(0)?.end()..]
+            .lines()
+            .nth(2)
+            .filter(|marks| marks.trim() == "^")
+            .and_then(|marks| marks.find('^'))
+            .map(|i| i as u32 + 1),
+    };
+    let message = cap[6].trim().to_string();
+
+    let error_type = if message.starts_with("cannot find symbol") {
+        match regexes::JAVAC_SYMBOL.captures(input) {
+            Some(symbol) if &symbol[1] == "class" => ErrorType::MissingImport(
+                java_import(&symbol[2]).map_or(symbol[2].to_string(), String::from),
+            ),
+            Some(symbol) if &symbol[1] == "variable" => {
+                ErrorType::UndeclaredVariable(symbol[2].to_string())
+            }
+            _ => ErrorType::Unknown(message.clone()),
+        }
+    } else if message == "';' expected" {
+        ErrorType::MissingSemicolon
+    } else if let Some(package) = regexes::JAVAC_PACKAGE.captures(&message) {
+        ErrorType::ModuleNotFound(package[1].to_string())
+    } else if message.starts_with("incompatible types") {
+        ErrorType::TypeError(message.clone())
+    } else if message.ends_with("expected")
+        || message.starts_with("illegal start of")
+        || message.starts_with("unclosed")
+        || message.contains("reached end of file")
+    {
+        ErrorType::SyntaxError(message.clone())
+    } else {
+        ErrorType::Unknown(message.clone())
+    };
+
+    Some(ParsedError {
+        file: cap[1].to_string(),
+        line: Some(line),
+        column,
+        end_line: None,
+        end_column: None,
+        message,
+        error_type,
+        language: Language::Java,
+    })
+}
+
+/// A Java stack trace. With `Caused by:` chains the last exception is the
+/// root cause, so that one is reported, at its first frame.
+fn parse_java_exception(input: &str) -> Option<ParsedError> {
+    if !input.contains("Exception in thread") && !regexes::JAVA_FRAME.is_match(input) {
+        return None;
+    }
+    let cap = regexes::JAVA_EXCEPTION.captures_iter(input).last()?;
+    let name = &cap[2];
+    let details = cap.get(3).map_or("", |m| m.as_str()).trim().to_string();
+    let frame = regexes::JAVA_FRAME
+        .captures(&input[cap.get(0)?.end()..])
+        .or_else(|| regexes::JAVA_FRAME.captures(input));
+
+    let error_type = match name {
+        "NullPointerException" => ErrorType::NullPointer(details.clone()),
+        "ClassNotFoundException" | "NoClassDefFoundError" => {
+            ErrorType::ModuleNotFound(details.replace('/', "."))
+        }
+        _ => ErrorType::Unknown(format!("{}: {}", &cap[1], details)),
+    };
+
+    Some(ParsedError {
+        file: frame.as_ref().map_or(String::new(), |f| f[1].to_string()),
+        line: frame.as_ref().and_then(|f| f[2].parse().ok()),
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: if details.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}: {}", name, details)
+        },
+        error_type,
+        language: Language::Java,
+    })
+}
+
+/// Full name of a well-known JDK class, for its import
+pub fn java_import(class: &str) -> Option<&'static str> {
+    const CLASSES: &[&str] = &[
+        "java.util.ArrayList",
+        "java.util.Arrays",
+        "java.util.Collections",
+        "java.util.HashMap",
+        "java.util.HashSet",
+        "java.util.Iterator",
+        "java.util.LinkedList",
+        "java.util.List",
+        "java.util.Map",
+        "java.util.Objects",
+        "java.util.Optional",
+        "java.util.Random",
+        "java.util.Scanner",
+        "java.util.Set",
+        "java.util.TreeMap",
+        "java.util.stream.Collectors",
+        "java.util.stream.Stream",
+        "java.io.BufferedReader",
+        "java.io.File",
+        "java.io.IOException",
+        "java.io.InputStreamReader",
+        "java.nio.file.Files",
+        "java.nio.file.Path",
+        "java.nio.file.Paths",
+        "java.math.BigDecimal",
+        "java.math.BigInteger",
+        "java.time.Duration",
+        "java.time.LocalDate",
+        "java.time.LocalDateTime",
+    ];
+    CLASSES
+        .iter()
+        .find(|full| full.rsplit('.').next() == Some(class))
+        .copied()
+}
+
+/// An MSBuild error from the C# compiler, by its CS code
+fn parse_csharp_error(input: &str) -> Option<ParsedError> {
+    let cap = regexes::CSHARP_ERROR.captures(input)?;
+    let message = format!("{}: {}", &cap[4], cap[5].trim());
+
+    let error_type = match &cap[4] {
+        "CS0103" => match regexes::CSHARP_UNKNOWN_NAME.captures(&cap[5]) {
+            Some(name) => ErrorType::UndeclaredVariable(name[1].to_string()),
+            None => ErrorType::Unknown(message.clone()),
+        },
+        "CS0246" | "CS0234" => match regexes::CSHARP_UNKNOWN_TYPE.captures(&cap[5]) {
+            Some(name) => ErrorType::ModuleNotFound(match name.get(2) {
+                Some(namespace) => format!("{}.{}", namespace.as_str(), &name[1]),
+                None => csharp_namespace(&name[1])
+                    .map_or(name[1].to_string(), |ns| format!("{}.{}", ns, &name[1])),
+            }),
+            None => ErrorType::Unknown(message.clone()),
+        },
+        "CS1002" => ErrorType::MissingSemicolon,
+        "CS0029" | "CS0266" | "CS1503" => ErrorType::TypeError(message.clone()),
+        "CS1513" | "CS1026" | "CS1003" | "CS1525" | "CS1022" => {
+            ErrorType::SyntaxError(message.clone())
+        }
+        _ => ErrorType::Unknown(message.clone()),
+    };
+
+    Some(ParsedError {
+        file: cap[1].to_string(),
+        line: cap[2].parse().ok(),
+        column: cap[3].parse().ok(),
+        end_line: None,
+        end_column: None,
+        message,
+        error_type,
+        language: Language::CSharp,
+    })
+}
+
+/// Namespace of a well-known .NET type, for its `using`
+pub fn csharp_namespace(type_name: &str) -> Option<&'static str> {
+    const TYPES: &[(&str, &str)] = &[
+        ("List", "System.Collections.Generic"),
+        ("Dictionary", "System.Collections.Generic"),
+        ("HashSet", "System.Collections.Generic"),
+        ("Queue", "System.Collections.Generic"),
+        ("Stack", "System.Collections.Generic"),
+        ("IEnumerable", "System.Collections.Generic"),
+        ("StringBuilder", "System.Text"),
+        ("Encoding", "System.Text"),
+        ("Regex", "System.Text.RegularExpressions"),
+        ("JsonSerializer", "System.Text.Json"),
+        ("File", "System.IO"),
+        ("Path", "System.IO"),
+        ("Directory", "System.IO"),
+        ("StreamReader", "System.IO"),
+        ("StreamWriter", "System.IO"),
+        ("Task", "System.Threading.Tasks"),
+        ("Thread", "System.Threading"),
+        ("CancellationToken", "System.Threading"),
+        ("HttpClient", "System.Net.Http"),
+        ("Stopwatch", "System.Diagnostics"),
+        ("Debug", "System.Diagnostics"),
+        ("CultureInfo", "System.Globalization"),
+        ("JsonConvert", "Newtonsoft.Json"),
+        ("JObject", "Newtonsoft.Json.Linq"),
+    ];
+    TYPES
+        .iter()
+        .find(|(name, _)| *name == type_name)
+        .map(|(_, namespace)| *namespace)
+}
+
+/// NuGet package that provides a namespace outside the .NET SDK
+pub fn nuget_package(namespace: &str) -> Option<&'static str> {
+    const PACKAGES: &[(&str, &str)] = &[
+        ("Newtonsoft.Json", "Newtonsoft.Json"),
+        (
+            "Microsoft.EntityFrameworkCore",
+            "Microsoft.EntityFrameworkCore",
+        ),
+        ("Serilog", "Serilog"),
+        ("Dapper", "Dapper"),
+        ("AutoMapper", "AutoMapper"),
+        ("Moq", "Moq"),
+        ("Xunit", "xunit"),
+        ("NUnit.Framework", "NUnit"),
+        ("FluentAssertions", "FluentAssertions"),
+    ];
+    PACKAGES
+        .iter()
+        .find(|(prefix, _)| namespace == *prefix || namespace.starts_with(&format!("{}.", prefix)))
+        .map(|(_, package)| *package)
+}
+
 fn detect_cpp_error_type(message: &str, full: &str) -> ErrorType {
     let msg = message.to_lowercase();
 
     if msg.contains("is not a member of 'std'") || msg.contains("was not declared") {
-        let include_re = Regex::new(r"#include <([^>]+)>").ok();
-        if let Some(re) = include_re {
-            if let Some(cap) = re.captures(full) {
-                return ErrorType::MissingInclude(cap[1].to_string());
-            }
+        if let Some(cap) = regexes::CPP_INCLUDE_HINT.captures(full) {
+            return ErrorType::MissingInclude(cap[1].to_string());
         }
 
         if msg.contains("vector") {
@@ -121,34 +616,163 @@ fn detect_cpp_error_type(message: &str, full: &str) -> ErrorType {
         }
     }
 
-    if msg.contains("expected ';'") || msg.contains("expected ';' before") {
+    if msg.contains("expected ';'") || msg.contains("or ';' before") || msg.contains("missing ';'")
+    {
         return ErrorType::MissingSemicolon;
     }
 
-    let undecl_re =
-        Regex::new(r"'([^']+)' was not declared|use of undeclared identifier '([^']+)'").ok();
-    if let Some(re) = undecl_re {
-        if let Some(cap) = re.captures(&msg) {
-            let var = cap.get(1).or(cap.get(2)).map(|m| m.as_str().to_string());
-            if let Some(v) = var {
-                return ErrorType::UndeclaredVariable(v);
-            }
+    if let Some(cap) = regexes::CPP_UNDECLARED.captures(&msg) {
+        let var = cap
+            .get(1)
+            .or(cap.get(2))
+            .or(cap.get(3))
+            .map(|m| m.as_str().to_string());
+        if let Some(v) = var {
+            return ErrorType::UndeclaredVariable(v);
         }
     }
 
     ErrorType::Unknown(message.to_string())
 }
 
-fn parse_python_error(input: &str) -> Option<ParsedError> {
-    let file_re = Regex::new(r#"File "([^"]+\.py)", line (\d+)"#).ok()?;
-    let error_re = Regex::new(r"(SyntaxError|IndentationError|NameError|ImportError|TypeError|ModuleNotFoundError|KeyError|AttributeError|ValueError|requests\.exceptions\.\w+): (.+)").ok()?;
+/// Errors from compiling or rendering a Jinja2, Django or EJS template,
+/// located in the template rather than in the code that rendered it
+fn parse_template_error(input: &str) -> Option<ParsedError> {
+    parse_jinja_error(input)
+        .or_else(|| parse_django_error(input))
+        .or_else(|| parse_ejs_error(input))
+}
 
-    let requests_re = Regex::new(r"requests\.exceptions\.(\w+): (.+)").ok()?;
+fn parse_jinja_error(input: &str) -> Option<ParsedError> {
+    let cap = regexes::JINJA_ERROR.captures(input)?;
+    let error_name = &cap[1];
+    let details = cap[2].to_string();
+    // Jinja adds a frame for the template itself to the traceback
+    let frame = regexes::PYTHON_FRAME
+        .captures_iter(input)
+        .filter(|c| !c[1].ends_with(".py"))
+        .last();
+
+    let error_type = match error_name {
+        "TemplateSyntaxError" => match regexes::JINJA_UNCLOSED_BLOCK.captures(&details) {
+            Some(c) => ErrorType::UnclosedTemplateBlock(c[1].to_string()),
+            None => ErrorType::SyntaxError(details.clone()),
+        },
+        "UndefinedError" => match regexes::JINJA_UNDEFINED.captures(&details) {
+            Some(c) => ErrorType::UndefinedTemplateVariable(c[1].to_string()),
+            None => ErrorType::Unknown(details.clone()),
+        },
+        _ => ErrorType::Unknown(details.clone()),
+    };
+
+    Some(ParsedError {
+        file: frame
+            .as_ref()
+            .map(|c| c[1].to_string())
+            .unwrap_or_else(|| "unknown template".to_string()),
+        line: frame.and_then(|c| c[2].parse().ok()),
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: format!("{}: {}", error_name, details),
+        error_type,
+        language: Language::Python,
+    })
+}
+
+fn parse_django_error(input: &str) -> Option<ParsedError> {
+    let cap = regexes::DJANGO_ERROR.captures(input)?;
+    let error_name = &cap[1];
+    let details = cap[2].to_string();
+    let template = regexes::DJANGO_TEMPLATE.captures(input);
+
+    let error_type = if let Some(c) = regexes::DJANGO_UNCLOSED.captures(&details) {
+        ErrorType::UnclosedTemplateBlock(c[1].to_string())
+    } else if let Some(c) = regexes::DJANGO_LOOKUP.captures(&details) {
+        ErrorType::UndefinedTemplateVariable(c[1].to_string())
+    } else if error_name == "TemplateSyntaxError" {
+        ErrorType::SyntaxError(details.clone())
+    } else {
+        ErrorType::Unknown(details.clone())
+    };
+
+    Some(ParsedError {
+        file: template
+            .as_ref()
+            .map(|c| c[1].to_string())
+            .unwrap_or_else(|| "unknown template".to_string()),
+        line: template
+            .as_ref()
+            .map(|c| &c[2])
+            .or_else(|| {
+                regexes::DJANGO_LINE
+                    .captures(&details)
+                    .map(|c| c.get(1).unwrap().as_str())
+            })
+            .and_then(|l| l.parse().ok()),
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: format!("{}: {}", error_name, details),
+        error_type,
+        language: Language::Python,
+    })
+}
 
-    let file_cap = file_re.captures(input);
-    let error_cap = error_re.captures(input);
+fn parse_ejs_error(input: &str) -> Option<ParsedError> {
+    if let Some(cap) = regexes::EJS_COMPILE.captures(input) {
+        let details = cap[1].to_string();
+        let error_type = if details.contains("end of input") {
+            ErrorType::UnclosedTemplateBlock("{".to_string())
+        } else {
+            ErrorType::SyntaxError(details.clone())
+        };
 
-    if let Some(req_cap) = requests_re.captures(input) {
+        return Some(ParsedError {
+            file: cap[2].to_string(),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            message: format!("SyntaxError: {}", details),
+            error_type,
+            language: Language::JavaScript,
+        });
+    }
+
+    let cap = regexes::EJS_RENDER.captures(input)?;
+    let error_name = &cap[1];
+    let details = input[cap.get(0)?.end()..]
+        .lines()
+        .skip(1)
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !regexes::EJS_EXCERPT.is_match(l))
+        .unwrap_or_default()
+        .to_string();
+
+    let error_type = match regexes::EJS_UNDEFINED.captures(&details) {
+        Some(c) => ErrorType::UndefinedTemplateVariable(c[1].to_string()),
+        None if error_name == "TypeError" => ErrorType::TypeError(details.clone()),
+        None => ErrorType::Unknown(details.clone()),
+    };
+
+    Some(ParsedError {
+        file: cap[2].to_string(),
+        line: cap[3].parse().ok(),
+        column: None,
+        end_line: None,
+        end_column: None,
+        message: format!("{}: {}", error_name, details),
+        error_type,
+        language: Language::JavaScript,
+    })
+}
+
+fn parse_python_error(input: &str) -> Option<ParsedError> {
+    let file_cap = regexes::PYTHON_FRAME.captures(input);
+    let error_cap = regexes::PYTHON_ERROR.captures(input);
+
+    if let Some(req_cap) = regexes::REQUESTS_ERROR.captures(input) {
         let error_name = req_cap[1].to_string();
         let details = req_cap[2].to_string();
 
@@ -168,6 +792,8 @@ fn parse_python_error(input: &str) -> Option<ParsedError> {
             file,
             line,
             column: None,
+            end_line: None,
+            end_column: None,
             message: format!("requests.exceptions.{}: {}", error_name, details),
             error_type,
             language: Language::Python,
@@ -183,28 +809,14 @@ fn parse_python_error(input: &str) -> Option<ParsedError> {
         let error_type = match error_name {
             "SyntaxError" => ErrorType::SyntaxError(details.clone()),
             "IndentationError" => ErrorType::IndentationError,
-            "NameError" => {
-                let var_re = Regex::new(r"name '([^']+)' is not defined").ok();
-                if let Some(re) = var_re {
-                    if let Some(cap) = re.captures(&details) {
-                        ErrorType::UndeclaredVariable(cap[1].to_string())
-                    } else {
-                        ErrorType::Unknown(details.clone())
-                    }
-                } else {
-                    ErrorType::Unknown(details.clone())
-                }
-            }
+            "NameError" => match regexes::PYTHON_UNDEFINED_NAME.captures(&details) {
+                Some(cap) => ErrorType::UndeclaredVariable(cap[1].to_string()),
+                None => ErrorType::Unknown(details.clone()),
+            },
             "ImportError" | "ModuleNotFoundError" => {
-                let mod_re = Regex::new(r"No module named '([^']+)'").ok();
-                if let Some(re) = mod_re {
-                    if let Some(cap) = re.captures(&details) {
-                        ErrorType::ImportError(cap[1].to_string())
-                    } else {
-                        ErrorType::ImportError(details.clone())
-                    }
-                } else {
-                    ErrorType::ImportError(details.clone())
+                match regexes::PYTHON_NO_MODULE.captures(&details) {
+                    Some(cap) => ErrorType::ImportError(cap[1].to_string()),
+                    None => ErrorType::ImportError(details.clone()),
                 }
             }
             "TypeError" => ErrorType::TypeError(details.clone()),
@@ -214,10 +826,13 @@ fn parse_python_error(input: &str) -> Option<ParsedError> {
             _ => ErrorType::Unknown(details.clone()),
         };
 
+        let range = python_caret_range(input, fc.get(0)?.end(), &file, line);
         return Some(ParsedError {
             file,
             line: Some(line),
-            column: None,
+            column: range.map(|(start, _)| start),
+            end_line: range.map(|_| line),
+            end_column: range.map(|(_, end)| end),
             message: format!("{}: {}", error_name, details),
             error_type,
             language: Language::Python,
@@ -227,32 +842,127 @@ fn parse_python_error(input: &str) -> Option<ParsedError> {
     None
 }
 
-fn parse_js_error(input: &str) -> Option<ParsedError> {
-    let file_re = Regex::new(r"([^\s:]+\.(js|ts|jsx|tsx|mjs)):(\d+)(?::(\d+))?").ok()?;
-    let error_re = Regex::new(r"(SyntaxError|TypeError|ReferenceError): (.+)").ok()?;
+/// Columns of the `^` (or `~~~^^^`) marks Python prints under the code of
+/// a frame: for syntax errors, and for every frame since 3.11. `frame_end`
+/// is where the frame's `File "..."` text ends.
+fn python_caret_range(input: &str, frame_end: usize, file: &str, line: u32) -> Option<(u32, u32)> {
+    let mut lines = input[frame_end..].lines().skip(1);
+    let code = lines.next()?;
+    let marks = lines.next()?;
+    if marks.trim().is_empty() || !marks.trim().chars().all(|c| c == '^' || c == '~') {
+        return None;
+    }
+
+    let indent = |text: &str| text.chars().take_while(|c| c.is_whitespace()).count();
+    let start = indent(marks).checked_sub(indent(code))?;
+    let end = start + marks.trim().chars().count();
+    // The code is shown without its indentation
+    let offset = std::fs::read_to_string(file)
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .nth(line.checked_sub(1)? as usize)
+                .map(indent)
+        })
+        .unwrap_or(0);
+
+    Some(((offset + start + 1) as u32, (offset + end) as u32))
+}
+
+/// Last column of the `^^^` (rustc) or `~~~` (tsc --pretty) underline
+/// under the code line that follows a location. Spans over several lines
+/// are drawn differently and give None.
+fn underline_end(input: &str, location_end: usize, column: u32, mark: char) -> Option<u32> {
+    input[location_end..]
+        .lines()
+        .skip(1)
+        .take(4)
+        .find_map(|line| {
+            let marks = line.trim_start().trim_start_matches('|').trim_start();
+            let width = marks.chars().take_while(|c| *c == mark).count();
+            (width > 0).then(|| column + width as u32 - 1)
+        })
+}
+
+fn is_format_error(message: &str) -> bool {
+    [
+        "format string",
+        "formatting argument",
+        "argument never used",
+        "doesn't implement `std::fmt::Display`",
+        "doesn't implement `Debug`",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+/// The macro an error was expanded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroOrigin {
+    /// `add!` or `derive(Clone)`
+    pub name: String,
+    /// Where the macro is invoked
+    pub file: String,
+    pub line: u32,
+}
+
+/// Read rustc's "in this macro invocation" (or "in this derive macro
+/// expansion") note. rustc reports errors inside a macro's definition;
+/// the note marks the code that invoked it.
+pub fn macro_origin(input: &str) -> Option<MacroOrigin> {
+    let mut file = None;
+    let mut code: Option<(u32, &str)> = None;
+    for line in input.lines() {
+        if let Some(cap) = regexes::RUST_LOCATION.captures(line) {
+            file = Some(cap[1].to_string());
+        } else if let Some(cap) = regexes::RUST_CODE.captures(line) {
+            code = Some((cap[1].parse().ok()?, cap.get(2)?.as_str()));
+        } else if let Some(cap) = regexes::RUST_MACRO_MARKER.captures(line) {
+            // The dashes underline the invocation in the code line above
+            let (line, text) = code?;
+            let snippet: String = text
+                .chars()
+                .skip(cap[1].chars().count())
+                .take(cap[2].len())
+                .collect();
+            let name = if cap.get(3).is_some() {
+                format!("derive({})", snippet.trim())
+            } else {
+                regexes::MACRO_NAME
+                    .captures(&snippet)
+                    .map(|c| format!("{}!", &c[1]))
+                    .unwrap_or(snippet)
+            };
+
+            return Some(MacroOrigin {
+                name,
+                file: file?,
+                line,
+            });
+        }
+    }
 
-    let ts_re = Regex::new(r"([^\s(]+\.(ts|tsx))\((\d+),(\d+)\): error (TS\d+): (.+)").ok()?;
+    None
+}
 
-    if let Some(cap) = ts_re.captures(input) {
+fn parse_js_error(input: &str) -> Option<ParsedError> {
+    let ts_cap = regexes::TS_ERROR
+        .captures(input)
+        .or_else(|| regexes::TS_PRETTY_ERROR.captures(input));
+    if let Some(cap) = ts_cap {
         let file = cap[1].to_string();
         let line: u32 = cap[3].parse().ok()?;
         let col: u32 = cap[4].parse().ok()?;
+        let end_col = underline_end(input, cap.get(0)?.end(), col, '~');
         let code = &cap[5];
         let message = cap[6].to_string();
 
         let error_type = match code {
-            "TS2304" | "TS2552" => {
-                let var_re = Regex::new(r"Cannot find name '([^']+)'").ok();
-                if let Some(re) = var_re {
-                    if let Some(c) = re.captures(&message) {
-                        ErrorType::UndeclaredVariable(c[1].to_string())
-                    } else {
-                        ErrorType::Unknown(message.clone())
-                    }
-                } else {
-                    ErrorType::Unknown(message.clone())
-                }
-            }
+            "TS2304" | "TS2552" => match regexes::TS_CANNOT_FIND_NAME.captures(&message) {
+                Some(c) => ErrorType::UndeclaredVariable(c[1].to_string()),
+                None => ErrorType::Unknown(message.clone()),
+            },
             "TS2307" => ErrorType::ModuleNotFound(message.clone()),
             _ => ErrorType::Unknown(message.clone()),
         };
@@ -261,14 +971,16 @@ fn parse_js_error(input: &str) -> Option<ParsedError> {
             file,
             line: Some(line),
             column: Some(col),
+            end_line: end_col.map(|_| line),
+            end_column: end_col,
             message: format!("{}: {}", code, message),
             error_type,
             language: Language::TypeScript,
         });
     }
 
-    if let Some(file_cap) = file_re.captures(input) {
-        if let Some(error_cap) = error_re.captures(input) {
+    if let Some(file_cap) = regexes::JS_LOCATION.captures(input) {
+        if let Some(error_cap) = regexes::JS_ERROR.captures(input) {
             let file = file_cap[1].to_string();
             let ext = &file_cap[2];
             let line: u32 = file_cap[3].parse().ok()?;
@@ -285,18 +997,10 @@ fn parse_js_error(input: &str) -> Option<ParsedError> {
 
             let error_type = match error_name {
                 "SyntaxError" => ErrorType::SyntaxError(details.clone()),
-                "ReferenceError" => {
-                    let var_re = Regex::new(r"(\w+) is not defined").ok();
-                    if let Some(re) = var_re {
-                        if let Some(cap) = re.captures(&details) {
-                            ErrorType::UndeclaredVariable(cap[1].to_string())
-                        } else {
-                            ErrorType::Unknown(details.clone())
-                        }
-                    } else {
-                        ErrorType::Unknown(details.clone())
-                    }
-                }
+                "ReferenceError" => match regexes::JS_NOT_DEFINED.captures(&details) {
+                    Some(cap) => ErrorType::UndeclaredVariable(cap[1].to_string()),
+                    None => ErrorType::Unknown(details.clone()),
+                },
                 "TypeError" => ErrorType::TypeError(details.clone()),
                 _ => ErrorType::Unknown(details.clone()),
             };
@@ -305,6 +1009,8 @@ fn parse_js_error(input: &str) -> Option<ParsedError> {
                 file,
                 line: Some(line),
                 column: col,
+                end_line: None,
+                end_column: None,
                 message: format!("{}: {}", error_name, details),
                 error_type,
                 language,
@@ -315,29 +1021,60 @@ fn parse_js_error(input: &str) -> Option<ParsedError> {
     None
 }
 
-fn parse_rust_error(input: &str) -> Option<ParsedError> {
-    let error_re = Regex::new(r"error\[E\d+\]: (.+)").ok()?;
-    let loc_re = Regex::new(r"--> ([^:]+):(\d+):(\d+)").ok()?;
+/// What an error needs of the toolchain or the edition, for
+/// `ErrorType::RustVersion`
+fn rust_requirement(input: &str) -> Option<String> {
+    if let Some(cap) = regexes::RUST_REQUIRES_RUSTC.captures(input) {
+        return Some(format!("rustc {}", &cap[3]));
+    }
+    if let Some(cap) = regexes::RUST_EDITION_NEEDED.captures(input) {
+        let edition = cap.iter().skip(1).flatten().next()?;
+        return Some(format!("edition {}", edition.as_str()));
+    }
+    if let Some(cap) = regexes::RUST_OLD_EDITION.captures(input) {
+        let next = match &cap[1] {
+            "2015" => "2018",
+            "2018" => "2021",
+            _ => "2024",
+        };
+        return Some(format!("edition {}", next));
+    }
+    if !regexes::RUST_UNSTABLE.is_match(input) {
+        return None;
+    }
+    let feature = regexes::RUST_UNSTABLE
+        .captures_iter(input)
+        .find_map(|cap| cap.get(1).or(cap.get(2)));
+    Some(match feature {
+        Some(feature) => format!("nightly {}", feature.as_str()),
+        None => "nightly".to_string(),
+    })
+}
 
-    let error_cap = error_re.captures(input);
-    let loc_cap = loc_re.captures(input);
+fn parse_rust_error(input: &str) -> Option<ParsedError> {
+    let error_cap = regexes::RUST_ERROR.captures(input);
+    let loc_cap = regexes::RUST_ERROR_LOCATION.captures(input);
+    let requirement = rust_requirement(input);
 
-    if let (Some(ec), Some(lc)) = (error_cap, loc_cap) {
+    if let (Some(ec), Some(lc)) = (&error_cap, loc_cap) {
         let message = ec[1].to_string();
         let file = lc[1].to_string();
         let line: u32 = lc[2].parse().ok()?;
         let col: u32 = lc[3].parse().ok()?;
-
-        let error_type = if message.contains("cannot find") {
-            let var_re = Regex::new(r"cannot find (?:value|type) `([^`]+)`").ok();
-            if let Some(re) = var_re {
-                if let Some(cap) = re.captures(&message) {
-                    ErrorType::UndeclaredVariable(cap[1].to_string())
-                } else {
-                    ErrorType::Unknown(message.clone())
-                }
-            } else {
-                ErrorType::Unknown(message.clone())
+        let end_col = underline_end(input, lc.get(0)?.end(), col, '^');
+
+        let error_type = if let Some(requirement) = requirement {
+            ErrorType::RustVersion(requirement)
+        } else if message.contains("derive macro")
+            || (message.contains("trait bound") && input.contains("in this derive macro expansion"))
+        {
+            ErrorType::DeriveError(message.clone())
+        } else if is_format_error(&message) {
+            ErrorType::FormatError(message.clone())
+        } else if message.contains("cannot find") {
+            match regexes::RUST_CANNOT_FIND.captures(&message) {
+                Some(cap) => ErrorType::UndeclaredVariable(cap[1].to_string()),
+                None => ErrorType::Unknown(message.clone()),
             }
         } else if message.contains("borrow") {
             ErrorType::BorrowError(message.clone())
@@ -349,12 +1086,35 @@ fn parse_rust_error(input: &str) -> Option<ParsedError> {
             file,
             line: Some(line),
             column: Some(col),
+            end_line: end_col.map(|_| line),
+            end_column: end_col,
             message,
             error_type,
             language: Language::Rust,
         });
     }
 
+    // Cargo reports a toolchain too old for the manifest, without a line
+    if let (Some(ec), Some(requirement)) = (error_cap, requirement) {
+        let message = match regexes::RUST_REQUIRES_RUSTC.captures(input) {
+            Some(cap) => format!(
+                "{} {} requires rustc {} or newer",
+                &cap[1], &cap[2], &cap[3]
+            ),
+            None => ec[1].to_string(),
+        };
+        return Some(ParsedError {
+            file: "Cargo.toml".to_string(),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            message,
+            error_type: ErrorType::RustVersion(requirement),
+            language: Language::Rust,
+        });
+    }
+
     None
 }
 
@@ -364,6 +1124,59 @@ mod tests {
 
     // ==================== C++ Parser Tests ====================
 
+    #[test]
+    fn test_split_errors() {
+        let output = "\
+    Compiling app v0.1.0
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+warning: unused variable: `y`
+ --> src/main.rs:3:9
+error[E0599]: no method named `foo` found
+ --> src/main.rs:4:7
+error: aborting due to 2 previous errors
+";
+        let errors = split_errors(output);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], "Compiling app v0.1.0");
+        assert!(errors[1].ends_with("src/main.rs:2:5"));
+        assert!(errors[2].starts_with("error[E0599]"));
+
+        let single = "main.cpp:1:1: error: a\n  note: here";
+        assert_eq!(split_errors(single), vec![single.to_string()]);
+    }
+
+    #[test]
+    fn test_chained_traceback_is_one_error() {
+        let output = "\
+Traceback (most recent call last):
+  File \"app.py\", line 3, in <module>
+KeyError: 'a'
+
+During handling of the above exception, another exception occurred:
+
+Traceback (most recent call last):
+  File \"app.py\", line 5, in <module>
+NameError: name 'b' is not defined
+";
+        assert_eq!(split_errors(output).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let output = "\
+a.cpp:3:5: error: 'cout' is not a member of 'std'
+a.cpp:3:5: note: suggested alternative
+b.cpp:9:2: error: expected ';' before 'return'
+";
+        let errors = parse_errors(output);
+        let files: Vec<&str> = errors.iter().map(|e| e.file.as_str()).collect();
+        assert_eq!(files, vec!["a.cpp", "b.cpp"]);
+
+        assert_eq!(parse_errors("main.cpp:1:2: error: expected ';'").len(), 1);
+        assert!(parse_errors("nothing to see").is_empty());
+    }
+
     #[test]
     fn test_parse_cpp_missing_include() {
         let error = "main.cpp:5:10: error: 'vector' is not a member of 'std'";
@@ -388,6 +1201,31 @@ mod tests {
         assert_eq!(parsed.error_type, ErrorType::MissingSemicolon);
     }
 
+    #[test]
+    fn test_parse_cpp_missing_comma_or_semicolon() {
+        let error = "test.cpp:7:5: error: expected ',' or ';' before 'return'";
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.error_type, ErrorType::MissingSemicolon);
+    }
+
+    #[test]
+    fn test_parse_msvc_errors() {
+        let parsed =
+            parse_error(r"C:\src\main.cpp(8,12): error C2065: 'myVar': undeclared identifier")
+                .unwrap();
+        assert_eq!(parsed.language, Language::Cpp);
+        assert_eq!(parsed.file, r"C:\src\main.cpp");
+        assert_eq!((parsed.line, parsed.column), (Some(8), Some(12)));
+        assert!(matches!(parsed.error_type, ErrorType::UndeclaredVariable(ref v) if v == "myvar"));
+
+        let parsed =
+            parse_error("main.cpp(9): error C2143: syntax error: missing ';' before 'return'")
+                .unwrap();
+        assert_eq!(parsed.column, None);
+        assert_eq!(parsed.error_type, ErrorType::MissingSemicolon);
+    }
+
     #[test]
     fn test_parse_cpp_undeclared_variable() {
         let error = "main.cpp:8:12: error: 'myVar' was not declared in this scope";
@@ -413,9 +1251,45 @@ SyntaxError: unexpected EOF while parsing"#;
         assert_eq!(parsed.language, Language::Python);
         assert_eq!(parsed.file, "test.py");
         assert_eq!(parsed.line, Some(5));
+        assert_eq!((parsed.column, parsed.end_column), (Some(5), Some(5)));
         assert!(matches!(parsed.error_type, ErrorType::SyntaxError(_)));
     }
 
+    #[test]
+    fn test_parse_python_caret_range() {
+        let traceback = r#"Traceback (most recent call last):
+  File "ess_no_such_file.py", line 3, in <module>
+    print(config["debug"])
+          ~~~~~~^^^^^^^^^
+KeyError: 'debug'"#;
+        let parsed = parse_error(traceback).unwrap();
+        assert_eq!((parsed.column, parsed.end_column), (Some(7), Some(21)));
+
+        // Without carets (before Python 3.11) there is no column
+        let old = "Traceback (most recent call last):\n  File \"a.py\", line 3, in <module>\n    print(config['debug'])\nKeyError: 'debug'";
+        let parsed = parse_error(old).unwrap();
+        assert_eq!((parsed.column, parsed.end_column), (None, None));
+    }
+
+    #[test]
+    fn test_python_caret_range_adds_indentation() {
+        let temp_dir = std::env::temp_dir().join("ess_test_python_carets");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        let file = temp_dir.join("app.py");
+        std::fs::write(&file, "def main():\n    return total / count\n").unwrap();
+
+        let traceback = format!(
+            "  File \"{}\", line 2, in main\n    return total / count\n           ~~~~~~^~~~~~~\nZeroDivisionError: division by zero",
+            file.display()
+        );
+        let range = python_caret_range(&traceback, 0, &file.to_string_lossy(), 2);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(range, Some((12, 24)));
+    }
+
     #[test]
     fn test_parse_python_indentation_error() {
         let error = r#"File "script.py", line 10
@@ -553,6 +1427,23 @@ ValueError: invalid literal for int() with base 10: 'abc'"#;
         );
     }
 
+    #[test]
+    fn test_parse_typescript_pretty_range() {
+        let error = "\
+src/app.ts:3:13 - error TS2304: Cannot find name 'userName'.
+
+3 const greet = userName;
+              ~~~~~~~~
+";
+        let parsed = parse_error(error).unwrap();
+        assert_eq!(parsed.file, "src/app.ts");
+        assert_eq!((parsed.line, parsed.column), (Some(3), Some(13)));
+        assert_eq!((parsed.end_line, parsed.end_column), (Some(3), Some(20)));
+
+        let plain = parse_error("src/app.ts(3,13): error TS2304: Cannot find name 'x'.").unwrap();
+        assert_eq!((plain.end_line, plain.end_column), (None, None));
+    }
+
     #[test]
     fn test_parse_typescript_module_not_found() {
         let error = "index.ts(1,20): error TS2307: Cannot find module 'missing-package'";
@@ -563,6 +1454,173 @@ ValueError: invalid literal for int() with base 10: 'abc'"#;
         assert!(matches!(parsed.error_type, ErrorType::ModuleNotFound(_)));
     }
 
+    // ==================== Java Parser Tests ====================
+
+    #[test]
+    fn test_parse_javac_missing_import() {
+        let error = "src/App.java:5: error: cannot find symbol\n        List<String> names = new ArrayList<>();\n        ^\n  symbol:   class List\n  location: class App";
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.language, Language::Java);
+        assert_eq!(parsed.file, "src/App.java");
+        assert_eq!(parsed.line, Some(5));
+        assert_eq!(parsed.column, Some(9));
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::MissingImport("java.util.List".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_maven_javac_error() {
+        let error = "[ERROR] /p/src/main/java/App.java:[7,20] ';' expected";
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.file, "/p/src/main/java/App.java");
+        assert_eq!(parsed.line, Some(7));
+        assert_eq!(parsed.column, Some(20));
+        assert_eq!(parsed.error_type, ErrorType::MissingSemicolon);
+
+        let error = "App.java:3: error: package org.junit does not exist";
+        assert_eq!(
+            parse_error(error).unwrap().error_type,
+            ErrorType::ModuleNotFound("org.junit".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_javac_unknown_variable() {
+        let error = "App.java:9: error: cannot find symbol\n    total += count;\n             ^\n  symbol:   variable count\n  location: class App";
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.column, Some(14));
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UndeclaredVariable("count".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_java_caused_by_chain() {
+        let trace = "Exception in thread \"main\" java.lang.RuntimeException: load failed\n\
+            \tat com.app.Main.main(Main.java:12)\n\
+            Caused by: java.lang.NullPointerException: Cannot invoke \"String.length()\" because \"name\" is null\n\
+            \tat com.app.Users.load(Users.java:31)\n\
+            \tat com.app.Main.main(Main.java:10)\n\
+            \t... 1 more";
+        let parsed = parse_error(trace).unwrap();
+
+        assert_eq!(parsed.language, Language::Java);
+        assert_eq!(parsed.file, "Users.java");
+        assert_eq!(parsed.line, Some(31));
+        assert!(
+            matches!(parsed.error_type, ErrorType::NullPointer(ref d) if d.contains("\"name\" is null"))
+        );
+    }
+
+    #[test]
+    fn test_parse_java_class_not_found() {
+        let trace =
+            "Exception in thread \"main\" java.lang.NoClassDefFoundError: com/google/gson/Gson\n\
+            \tat com.app.Main.main(Main.java:4)";
+        let parsed = parse_error(trace).unwrap();
+
+        assert_eq!(parsed.file, "Main.java");
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::ModuleNotFound("com.google.gson.Gson".to_string())
+        );
+    }
+
+    #[test]
+    fn test_java_import() {
+        assert_eq!(java_import("ArrayList"), Some("java.util.ArrayList"));
+        assert_eq!(java_import("Widget"), None);
+    }
+
+    // ==================== C# Parser Tests ====================
+
+    #[test]
+    fn test_parse_csharp_undeclared_name() {
+        let error = "/p/Program.cs(12,17): error CS0103: The name 'total' does not exist in the current context [/p/App.csproj]";
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.language, Language::CSharp);
+        assert_eq!(parsed.file, "/p/Program.cs");
+        assert_eq!(parsed.line, Some(12));
+        assert_eq!(parsed.column, Some(17));
+        assert_eq!(
+            parsed.message,
+            "CS0103: The name 'total' does not exist in the current context"
+        );
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UndeclaredVariable("total".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_csharp_missing_type() {
+        let error = "Program.cs(3,7): error CS0246: The type or namespace name 'JsonConvert' could not be found (are you missing a using directive or an assembly reference?)";
+        assert_eq!(
+            parse_error(error).unwrap().error_type,
+            ErrorType::ModuleNotFound("Newtonsoft.Json.JsonConvert".to_string())
+        );
+
+        let error = "Program.cs(3,7): error CS0246: The type or namespace name 'Widget' could not be found (are you missing a using directive or an assembly reference?)";
+        assert_eq!(
+            parse_error(error).unwrap().error_type,
+            ErrorType::ModuleNotFound("Widget".to_string())
+        );
+
+        let error = "Program.cs(1,14): error CS0234: The type or namespace name 'Json' does not exist in the namespace 'Newtonsoft' (are you missing an assembly reference?)";
+        assert_eq!(
+            parse_error(error).unwrap().error_type,
+            ErrorType::ModuleNotFound("Newtonsoft.Json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_csharp_missing_semicolon() {
+        let error = "Program.cs(5,22): error CS1002: ; expected";
+        assert_eq!(
+            parse_error(error).unwrap().error_type,
+            ErrorType::MissingSemicolon
+        );
+    }
+
+    #[test]
+    fn test_nuget_package() {
+        assert_eq!(
+            nuget_package("Newtonsoft.Json.Linq"),
+            Some("Newtonsoft.Json")
+        );
+        assert_eq!(nuget_package("Newtonsoft"), None);
+        assert_eq!(nuget_package("System.Text"), None);
+    }
+
+    // ==================== Port In Use Tests ====================
+
+    #[test]
+    fn test_parse_port_in_use() {
+        let node = "node:events:491\n      throw er;\n      ^\n\nError: listen EADDRINUSE: address already in use :::3000\n    at Server.setupListenHandle [as _listen2] (node:net:1740:16)";
+        let parsed = parse_error(node).unwrap();
+        assert_eq!(parsed.error_type, ErrorType::PortInUse(Some(3000)));
+
+        let python = "Traceback (most recent call last):\n  File \"server.py\", line 8, in <module>\n    sock.bind((\"\", 8000))\nOSError: [Errno 98] Address already in use";
+        let parsed = parse_error(python).unwrap();
+        assert_eq!(parsed.language, Language::Python);
+        assert_eq!(parsed.file, "server.py");
+        assert_eq!(parsed.error_type, ErrorType::PortInUse(None));
+    }
+
+    #[test]
+    fn test_parse_port_in_use_without_language() {
+        let rust = "thread 'main' panicked at src/main.rs:9:10:\ncalled `Result::unwrap()` on an `Err` value: Os { code: 98, kind: AddrInUse, message: \"Address already in use\" }";
+        let parsed = parse_error(rust).unwrap();
+        assert_eq!(parsed.error_type, ErrorType::PortInUse(None));
+    }
+
     // ==================== Rust Parser Tests ====================
 
     #[test]
@@ -579,6 +1637,7 @@ ValueError: invalid literal for int() with base 10: 'abc'"#;
         assert_eq!(parsed.language, Language::Rust);
         assert_eq!(parsed.file, "src/main.rs");
         assert_eq!(parsed.line, Some(10));
+        assert_eq!((parsed.end_line, parsed.end_column), (Some(10), Some(17)));
         assert!(
             matches!(parsed.error_type, ErrorType::UndeclaredVariable(ref v) if v == "undefined_var")
         );
@@ -596,10 +1655,203 @@ ValueError: invalid literal for int() with base 10: 'abc'"#;
         assert!(result.is_some());
         let parsed = result.unwrap();
         assert!(matches!(parsed.error_type, ErrorType::BorrowError(_)));
+        // The `--` marks another borrow, not the error
+        assert_eq!(parsed.end_column, None);
+    }
+
+    #[test]
+    fn test_parse_rust_version_errors() {
+        let edition = parse_error(
+            "error[E0670]: `async fn` is not permitted in Rust 2015\n \
+            --> src/main.rs:1:1\n  |\n1 | async fn run() {}\n  | ^^^^^ to use `async fn`, switch to Rust 2018 or later\n  |\n  \
+            = help: pass `--edition 2024` to `rustc`\n  = note: for more on editions, read https://doc.rust-lang.org/edition-guide",
+        )
+        .unwrap();
+        assert_eq!(
+            edition.error_type,
+            ErrorType::RustVersion("edition 2024".to_string())
+        );
+        assert_eq!(edition.line, Some(1));
+
+        let old = parse_error(
+            "error[E0670]: `async fn` is not permitted in Rust 2015\n --> src/lib.rs:3:1",
+        )
+        .unwrap();
+        assert_eq!(
+            old.error_type,
+            ErrorType::RustVersion("edition 2018".to_string())
+        );
+
+        let unstable = parse_error(
+            "error[E0658]: `let` expressions in this position are unstable\n --> src/main.rs:4:8\n  |\n  \
+            = help: add `#![feature(let_chains)]` to the crate attributes to enable",
+        )
+        .unwrap();
+        assert_eq!(
+            unstable.error_type,
+            ErrorType::RustVersion("nightly let_chains".to_string())
+        );
+
+        // Cargo's errors are about the manifest, with no line
+        let rustc = parse_error(
+            "error: package `clap_lex v0.7.4` cannot be built because it requires rustc 1.74 or newer, \
+            while the currently active rustc version is 1.70.0",
+        )
+        .unwrap();
+        assert_eq!(rustc.file, "Cargo.toml");
+        assert_eq!(rustc.line, None);
+        assert_eq!(
+            rustc.error_type,
+            ErrorType::RustVersion("rustc 1.74".to_string())
+        );
+        assert_eq!(rustc.message, "clap_lex 0.7.4 requires rustc 1.74 or newer");
+        let newer_cargo = parse_error(
+            "error: rustc 1.70.0 is not supported by the following package:\n  clap_lex@0.7.4 requires rustc 1.74",
+        )
+        .unwrap();
+        assert_eq!(newer_cargo.error_type, rustc.error_type);
+
+        let cargo_edition = parse_error(
+            "error: failed to parse manifest at `/app/Cargo.toml`\n\nCaused by:\n  \
+            feature `edition2024` is required",
+        )
+        .unwrap();
+        assert_eq!(
+            cargo_edition.error_type,
+            ErrorType::RustVersion("edition 2024".to_string())
+        );
+    }
+
+    const MACRO_ERROR: &str = r#"error[E0425]: cannot find value `y` in this scope
+  --> src/main.rs:3:14
+   |
+ 3 |         $x + y
+   |              ^ not found in this scope
+...
+16 |     let z = add!(x);
+   |             ------- in this macro invocation
+   |
+   = note: this error originates in the macro `add` (in Nightly builds, run with -Z macro-backtrace for more info)"#;
+
+    const DERIVE_ERROR: &str = r#"error[E0277]: the trait bound `Bar: Clone` is not satisfied
+  --> src/main.rs:11:5
+   |
+ 9 | #[derive(Clone)]
+   |          ----- in this derive macro expansion
+10 | struct Foo {
+11 |     bar: Bar,
+   |     ^^^^^^^^ the trait `Clone` is not implemented for `Bar`"#;
+
+    #[test]
+    fn test_macro_origin() {
+        assert_eq!(
+            macro_origin(MACRO_ERROR),
+            Some(MacroOrigin {
+                name: "add!".to_string(),
+                file: "src/main.rs".to_string(),
+                line: 16,
+            })
+        );
+        assert_eq!(macro_origin(DERIVE_ERROR).unwrap().name, "derive(Clone)");
+        assert_eq!(macro_origin("error: expected `;`\n --> src/a.rs:1:5"), None);
+
+        // The error is still located in the macro's definition
+        let parsed = parse_error(MACRO_ERROR).unwrap();
+        assert_eq!(parsed.line, Some(3));
+    }
+
+    #[test]
+    fn test_parse_rust_macro_errors() {
+        let derive = parse_error(DERIVE_ERROR).unwrap();
+        assert!(matches!(derive.error_type, ErrorType::DeriveError(_)));
+
+        let missing = parse_error(
+            "error: cannot find derive macro `Serialize` in this scope\n --> src/lib.rs:1:10",
+        )
+        .unwrap();
+        assert!(matches!(missing.error_type, ErrorType::DeriveError(_)));
+
+        let format = parse_error(
+            "error: 2 positional arguments in format string, but there is 1 argument\n  --> src/main.rs:19:15",
+        )
+        .unwrap();
+        assert!(matches!(format.error_type, ErrorType::FormatError(_)));
     }
 
     // ==================== Edge Cases ====================
 
+    #[test]
+    fn test_parse_jinja_unclosed_block() {
+        let error = r#"Traceback (most recent call last):
+  File "/app/app.py", line 4, in <module>
+    env.get_template("list.html").render()
+  File "/venv/lib/python3.11/site-packages/jinja2/environment.py", line 942, in handle_exception
+    raise rewrite_traceback_stack(source=source)
+  File "templates/list.html", line 3, in template
+    <li>{{ u }}</li>
+jinja2.exceptions.TemplateSyntaxError: Unexpected end of template. Jinja was looking for the following tags: 'endfor' or 'else'. The innermost block that needs to be closed is 'for'."#;
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.file, "templates/list.html");
+        assert_eq!(parsed.line, Some(3));
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UnclosedTemplateBlock("for".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_jinja_undefined_variable() {
+        let error = r#"  File "templates/user.html", line 1, in top-level template code
+    <h1>{{ user.name }}</h1>
+  File "/venv/lib/python3.11/site-packages/jinja2/environment.py", line 490, in getattr
+    return getattr(obj, attribute)
+jinja2.exceptions.UndefinedError: 'user' is undefined"#;
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.file, "templates/user.html");
+        assert_eq!(parsed.line, Some(1));
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UndefinedTemplateVariable("user".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_django_unclosed_tag() {
+        let error = "django.template.exceptions.TemplateSyntaxError: Unclosed tag on line 3: 'if'. Looking for one of: elif, else, endif.";
+        let parsed = parse_error(error).unwrap();
+
+        assert_eq!(parsed.line, Some(3));
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UnclosedTemplateBlock("if".to_string())
+        );
+        assert_eq!(parsed.language, Language::Python);
+    }
+
+    #[test]
+    fn test_parse_ejs_errors() {
+        let render = "ReferenceError: /app/views/index.ejs:3\n    1| <h1>Users</h1>\n    2| <ul>\n >> 3|   <% users.forEach(u => { %>\n\nusers is not defined\n    at eval (eval at compile)";
+        let parsed = parse_error(render).unwrap();
+        assert_eq!(parsed.file, "/app/views/index.ejs");
+        assert_eq!(parsed.line, Some(3));
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UndefinedTemplateVariable("users".to_string())
+        );
+
+        let compile =
+            "SyntaxError: Unexpected end of input in /app/views/list.ejs while compiling ejs";
+        let parsed = parse_error(compile).unwrap();
+        assert_eq!(parsed.file, "/app/views/list.ejs");
+        assert_eq!(
+            parsed.error_type,
+            ErrorType::UnclosedTemplateBlock("{".to_string())
+        );
+        assert_eq!(parsed.language, Language::JavaScript);
+    }
+
     #[test]
     fn test_parse_unknown_error() {
         let error = "Some random text that is not an error";
@@ -633,4 +1885,18 @@ ValueError: invalid literal for int() with base 10: 'abc'"#;
         assert_eq!(ErrorType::IndentationError, ErrorType::IndentationError);
         assert_ne!(ErrorType::MissingSemicolon, ErrorType::IndentationError);
     }
+
+    #[test]
+    fn test_error_type_name() {
+        assert_eq!(ErrorType::KeyError("k".to_string()).name(), "KeyError");
+        assert_eq!(ErrorType::MissingSemicolon.name(), "MissingSemicolon");
+        assert_eq!(ErrorType::Unknown("x".to_string()).name(), "Unknown");
+        assert_eq!(
+            ErrorType::KeyError("k".to_string()).id(),
+            Some("PY-KEY-001")
+        );
+        assert_eq!(ErrorType::Unknown("x".to_string()).id(), None);
+        assert_eq!(rule_kind("py-key-001"), Some("KeyError"));
+        assert_eq!(rule_kind("KeyError"), None);
+    }
 }
@@ -0,0 +1,163 @@
+use crate::config::PatternConfig;
+use crate::fixer;
+use crate::parser::{CustomFix, ErrorType, Language, ParsedError};
+use crate::regexes;
+use crate::rules::Rule;
+use crate::scanner::detect_language_from_str;
+use anyhow::Result;
+use regex::{Captures, Regex};
+
+/// Compiled `[[patterns]]`
+#[derive(Default)]
+pub struct Patterns {
+    patterns: Vec<(Regex, PatternConfig)>,
+}
+
+impl Patterns {
+    pub fn new(configs: &[PatternConfig]) -> Result<Self> {
+        let patterns = configs
+            .iter()
+            .map(|config| {
+                let regex = Regex::new(&config.regex).map_err(|e| {
+                    anyhow::anyhow!("Invalid [[patterns]] regex '{}': {}", config.regex, e)
+                })?;
+                Ok((regex, config.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// The first pattern for `language` that matches `text`, with its
+    /// templates filled in. Errors of unknown language match any pattern.
+    pub fn match_error(&self, text: &str, language: &Language) -> Option<ErrorType> {
+        self.patterns.iter().find_map(|(regex, config)| {
+            let for_language = match &config.language {
+                Some(wanted) => {
+                    *language == Language::Unknown || detect_language_from_str(wanted) == *language
+                }
+                None => true,
+            };
+            if !for_language {
+                return None;
+            }
+            let captures = regex.captures(text)?;
+            Some(ErrorType::Custom(CustomFix {
+                title: config.title.clone(),
+                fix: render(&config.fix, &captures),
+                insert: config
+                    .insert
+                    .as_ref()
+                    .map(|insert| (config.anchor, render(insert, &captures))),
+            }))
+        })
+    }
+}
+
+impl Rule for Patterns {
+    fn kind(&self) -> &'static str {
+        "Custom"
+    }
+
+    fn recognize(&self, text: &str, language: &Language) -> Option<ErrorType> {
+        self.match_error(text, language)
+    }
+
+    fn fix(&self, error: &ParsedError) {
+        if let ErrorType::Custom(fix) = &error.error_type {
+            fixer::fix_custom(fix);
+        }
+    }
+}
+
+/// Fill `{name}` placeholders from named capture groups and `{1}` from
+/// numbered ones. Placeholders without a group are left as they are.
+pub fn render(template: &str, captures: &Captures) -> String {
+    regexes::PLACEHOLDER
+        .replace_all(template, |found: &Captures| {
+            let name = &found[1];
+            let group = match name.parse::<usize>() {
+                Ok(index) => captures.get(index),
+                Err(_) => captures.name(name),
+            };
+            group.map_or(found[0].to_string(), |g| g.as_str().to_string())
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Anchor;
+    use crate::rules::Registry;
+
+    fn pattern(regex: &str, language: Option<&str>) -> PatternConfig {
+        PatternConfig {
+            regex: regex.to_string(),
+            language: language.map(String::from),
+            title: "Missing setting".to_string(),
+            fix: "Add {var} to {file}, then restart ({2})".to_string(),
+            insert: Some("from settings import {var}".to_string()),
+            anchor: Anchor::AfterImports,
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let regex = Regex::new(r"setting (?P<var>\w+) missing in (?P<file>\S+)").unwrap();
+        let captures = regex.captures("setting DEBUG missing in app.toml").unwrap();
+
+        assert_eq!(
+            render("{var} / {file} / {1} / {module}", &captures),
+            "DEBUG / app.toml / DEBUG / {module}"
+        );
+    }
+
+    #[test]
+    fn test_match_error_fills_templates() {
+        let patterns = Patterns::new(&[pattern(
+            r"setting (?P<var>\w+) missing in (?P<file>\S+)",
+            Some("python"),
+        )])
+        .unwrap();
+
+        let matched = patterns
+            .match_error(
+                "SettingsError: setting DEBUG missing in app.toml",
+                &Language::Python,
+            )
+            .unwrap();
+        assert_eq!(
+            matched,
+            ErrorType::Custom(CustomFix {
+                title: "Missing setting".to_string(),
+                fix: "Add DEBUG to app.toml, then restart (app.toml)".to_string(),
+                insert: Some((
+                    Anchor::AfterImports,
+                    "from settings import DEBUG".to_string()
+                )),
+            })
+        );
+
+        assert!(patterns
+            .match_error("setting DEBUG missing in app.toml", &Language::Rust)
+            .is_none());
+        assert!(patterns
+            .match_error("setting DEBUG missing in app.toml", &Language::Unknown)
+            .is_some());
+    }
+
+    #[test]
+    fn test_registered_patterns_resolve_errors() {
+        let mut rules = Registry::new();
+        rules.register(Patterns::new(&[pattern(r"(?P<var>.+)", None)]).unwrap());
+
+        let resolved = rules.resolve("anything at all", None).unwrap();
+        assert!(matches!(resolved.error_type, ErrorType::Custom(_)));
+        assert_eq!(resolved.file, "");
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        assert!(Patterns::new(&[pattern("(unclosed", None)]).is_err());
+    }
+}
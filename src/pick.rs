@@ -0,0 +1,526 @@
+//! `ess pick`: the findings of a scan in a list narrowed down as you type,
+//! fzf-style. Picking one shows its diagnosis, with the choice to open it
+//! in an editor or apply its fix.
+
+use crate::apply::{self, Fix};
+use crate::config::{Config, FixConfig};
+use crate::finding::Finding;
+use crate::patterns::Patterns;
+use crate::replay::Analysis;
+use crate::report;
+use crate::rules::Registry;
+use crate::scanner::{self, ScanOptions};
+use crate::scans;
+use crate::ui;
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use unicode_width::UnicodeWidthStr;
+
+/// Findings listed under the query at most
+const MAX_ROWS: usize = 12;
+
+/// How well `query` matches `text`: each word of the query must appear in
+/// it, its characters in order. Runs of characters and characters at the
+/// start of a word score higher. The query is case-sensitive only when it
+/// has capitals.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let text: Vec<char> = text.chars().map(fold).collect();
+    query.split_whitespace().try_fold(0, |total, term| {
+        let term: Vec<char> = term.chars().map(fold).collect();
+        Some(total + term_score(&term, &text)?)
+    })
+}
+
+/// The best score of `term` in `text`, trying each place its first
+/// character appears
+fn term_score(term: &[char], text: &[char]) -> Option<i64> {
+    (0..text.len())
+        .filter(|&start| text[start] == term[0])
+        .filter_map(|start| match_from(term, text, start))
+        .max()
+}
+
+/// Score of `term` matched at the earliest places in `text` from `start`
+fn match_from(term: &[char], text: &[char], start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut from = start;
+    for &c in term {
+        let at = (from..text.len()).find(|&i| text[i] == c)?;
+        score += 1;
+        match previous {
+            Some(p) if p + 1 == at => score += 5,
+            Some(p) => score -= (at - p - 1).min(5) as i64,
+            None => {}
+        }
+        if at == 0 || !text[at - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(at);
+        from = at + 1;
+    }
+    Some(score)
+}
+
+/// Indexes of the `lines` that match `query`, best first
+fn filter(query: &str, lines: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| Some((score(query, line)?, i)))
+        .collect();
+    scored.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// A key the picker acts on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Char(char),
+    Backspace,
+    ClearQuery,
+    Up,
+    Down,
+    Enter,
+    Cancel,
+}
+
+/// The query, what it matches and which match is selected
+struct Picker<'a> {
+    lines: &'a [String],
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+    /// First match shown, so the selected one stays in view
+    offset: usize,
+}
+
+impl<'a> Picker<'a> {
+    fn new(lines: &'a [String], query: String) -> Self {
+        let matches = filter(&query, lines);
+        Self {
+            lines,
+            query,
+            matches,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    /// Act on a key. Returns Some when picking is over: the line picked,
+    /// or None when cancelled.
+    fn press(&mut self, key: Key, rows: usize) -> Option<Option<usize>> {
+        match key {
+            Key::Char(c) => self.set_query(format!("{}{}", self.query, c)),
+            Key::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            Key::ClearQuery => self.set_query(String::new()),
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => {
+                self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1))
+            }
+            Key::Enter => return self.matches.get(self.selected).map(|&i| Some(i)),
+            Key::Cancel => return Some(None),
+        }
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + rows {
+            self.offset = self.selected + 1 - rows;
+        }
+        None
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.matches = filter(&query, self.lines);
+        self.query = query;
+        self.selected = 0;
+        self.offset = 0;
+    }
+}
+
+/// Turns raw mode off when dropped, however picking ends
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Let the user pick one of `findings`, starting from `query`. Returns the
+/// index picked, or None when cancelled; `query` is left as it was typed.
+fn choose(findings: &[Finding], lines: &[String], query: &mut String) -> Result<Option<usize>> {
+    // Some terminals report no size at all
+    let (width, height) = terminal::size()
+        .ok()
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .unwrap_or((80, 24));
+    let rows = MAX_ROWS.min(usize::from(height).saturating_sub(2)).max(1);
+    let mut picker = Picker::new(lines, std::mem::take(query));
+    let mut out = std::io::stdout();
+
+    let _raw = RawMode::enable()?;
+    let picked = loop {
+        draw(&mut out, &picker, findings, rows, usize::from(width))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let key = match key.code {
+            KeyCode::Char('c' | 'g') if ctrl => Key::Cancel,
+            KeyCode::Char('u') if ctrl => Key::ClearQuery,
+            KeyCode::Char('p' | 'k') if ctrl => Key::Up,
+            KeyCode::Char('n' | 'j') if ctrl => Key::Down,
+            KeyCode::Char(c) if !ctrl => Key::Char(c),
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Esc => Key::Cancel,
+            _ => continue,
+        };
+        if let Some(picked) = picker.press(key, rows) {
+            break picked;
+        }
+    };
+    queue!(
+        out,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown)
+    )?;
+    out.flush()?;
+    *query = picker.query;
+    Ok(picked)
+}
+
+/// Draw the query line and the matches in view under it, and leave the
+/// cursor after the query
+fn draw(
+    out: &mut impl Write,
+    picker: &Picker,
+    findings: &[Finding],
+    rows: usize,
+    width: usize,
+) -> Result<()> {
+    queue!(
+        out,
+        cursor::MoveToColumn(0),
+        terminal::Clear(ClearType::FromCursorDown)
+    )?;
+    write!(
+        out,
+        "{}",
+        ui::picker_prompt(&picker.query, picker.matches.len(), picker.lines.len())
+    )?;
+    let shown: Vec<(usize, usize)> = picker
+        .matches
+        .iter()
+        .enumerate()
+        .skip(picker.offset)
+        .take(rows)
+        .map(|(n, &i)| (n, i))
+        .collect();
+    for &(n, i) in &shown {
+        let row = ui::picker_row(
+            findings[i].severity,
+            &picker.lines[i],
+            n == picker.selected,
+            width,
+        );
+        write!(out, "\r\n{}", row)?;
+    }
+    if !shown.is_empty() {
+        queue!(out, cursor::MoveUp(shown.len() as u16))?;
+    }
+    let column = 2 + picker.query.width();
+    queue!(out, cursor::MoveToColumn(column as u16))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// The command that opens `file` in `editor` (as $VISUAL or $EDITOR give
+/// it, arguments included), at the line for editors known to take one
+fn editor_command(
+    editor: &str,
+    file: &Path,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Vec<String> {
+    let mut command: Vec<String> = editor.split_whitespace().map(str::to_string).collect();
+    let program = command
+        .first()
+        .and_then(|p| Path::new(p).file_stem())
+        .map_or(String::new(), |s| s.to_string_lossy().to_lowercase());
+    let file = file.to_string_lossy().to_string();
+    let (Some(line), column) = (line, column.unwrap_or(1)) else {
+        command.push(file);
+        return command;
+    };
+    match program.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            command.push("--goto".to_string());
+            command.push(format!("{}:{}:{}", file, line, column));
+        }
+        "subl" | "zed" | "hx" | "helix" => command.push(format!("{}:{}:{}", file, line, column)),
+        "vi" | "vim" | "nvim" | "gvim" => {
+            command.push(format!("+{}", line));
+            command.push(file);
+        }
+        "nano" => {
+            command.push(format!("+{},{}", line, column));
+            command.push(file);
+        }
+        "emacs" | "emacsclient" | "micro" => {
+            command.push(format!("+{}:{}", line, column));
+            command.push(file);
+        }
+        _ => command.push(file),
+    }
+    command
+}
+
+/// Open the finding's file in the user's editor and wait for it to close
+fn open_in_editor(finding: &Finding) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    let command = editor_command(&editor, &finding.file, finding.line, finding.column);
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .status()
+        .map_err(|e| anyhow!("Could not start editor '{}': {}", editor, e))?;
+    if !status.success() {
+        ui::print_warning(&format!("{} exited with {}", editor, status));
+    }
+    Ok(())
+}
+
+/// Show a finding with its diagnosis and the diffs of its fixes
+fn show(finding: &Finding, root: &Path, rules: &Registry) {
+    let mut finding = finding.clone();
+    // Only a tool's first error comes with its output; the message is
+    // enough for the parser to explain the others
+    if finding.explain.is_none() && finding.parsed.is_some() {
+        finding.explain = Some(finding.message.clone());
+    }
+    report::print_streamed(&finding, root, rules);
+    for fix in apply::plan(finding.fixes.clone()).merged() {
+        if let Ok((before, after)) = apply::preview(&fix) {
+            ui::print_diff(&before, &after);
+        }
+    }
+}
+
+/// Apply a finding's fixes, as far as `[fix]` allows. Returns whether any
+/// was.
+fn apply_fixes(finding: &Finding, policy: &FixConfig) -> Result<bool> {
+    if finding.fixes.is_empty() {
+        ui::print_warning("No automatic fix for this finding");
+        return Ok(false);
+    }
+    let fixes: Vec<Fix> = finding
+        .fixes
+        .iter()
+        .filter(|fix| apply::permitted(policy, finding.rule(), fix))
+        .cloned()
+        .collect();
+    if fixes.is_empty() {
+        return Ok(false);
+    }
+    let plan = apply::plan(fixes);
+    for fix in plan.merged() {
+        apply::apply_fix(&fix)?;
+        ui::print_success(&format!("Applied: {}", fix.description));
+    }
+    for conflict in &plan.conflicts {
+        ui::print_warning(&format!(
+            "Skipped '{}': it changes the same lines as '{}'",
+            conflict.fix.description, conflict.with
+        ));
+    }
+    Ok(true)
+}
+
+/// The findings to pick from: a new scan of `path`, or the last one
+/// recorded in its history
+fn load(path: &Path, config: &Config, previous: bool) -> Result<(Vec<Finding>, PathBuf)> {
+    let root = scanner::normalize_path(path);
+    if previous {
+        let Some(session) = scans::latest(&root)? else {
+            bail!(
+                "No scans recorded in {}; run 'ess pick' without --previous to scan now",
+                root.display()
+            );
+        };
+        return match session.analysis {
+            Analysis::Scan { root, summary } => Ok((summary.findings, root)),
+            Analysis::Bug { .. } => bail!("The last recorded session is not a scan"),
+        };
+    }
+    ui::print_info(&format!("Scanning {}...", root.display()));
+    let summary = scanner::scan_project(
+        &root,
+        config,
+        &ScanOptions {
+            quiet: true,
+            ..Default::default()
+        },
+    )?;
+    Ok((summary.findings, root))
+}
+
+/// `ess pick`: pick findings of a scan of `path` until the user quits
+pub fn run(path: &Path, config: &Config, previous: bool) -> Result<()> {
+    let (mut findings, root) = load(path, config, previous)?;
+    let mut rules = Registry::new();
+    rules.register(Patterns::new(&config.patterns)?);
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let mut query = String::new();
+
+    loop {
+        if findings.is_empty() {
+            ui::print_no_errors();
+            return Ok(());
+        }
+        let lines: Vec<String> = findings
+            .iter()
+            .map(|f| report::grep_line(f, &root))
+            .collect();
+        let Some(picked) = choose(&findings, &lines, &mut query)? else {
+            return Ok(());
+        };
+
+        show(&findings[picked], &root, &rules);
+        ui::print_blank_line();
+        let fixable = !findings[picked].fixes.is_empty();
+        ui::print_prompt(&format!(
+            "e = open in editor, {}Enter = back to the list, q = quit: ",
+            if fixable { "a = apply fix, " } else { "" }
+        ));
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            println!();
+            return Ok(());
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "e" | "edit" => open_in_editor(&findings[picked])?,
+            // Applied fixes leave nothing to pick for this finding
+            "a" | "apply" if apply_fixes(&findings[picked], &config.fix)? => {
+                findings.remove(picked);
+            }
+            "q" | "quit" => return Ok(()),
+            _ => {}
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let text = "src/main.py:3:1: ImportError: No module named 'requests'";
+        assert!(score("", text).is_some());
+        assert!(score("main import", text).is_some());
+        assert!(score("mnpy", text).is_some());
+        assert!(score("import main", text).is_some());
+        assert_eq!(score("xyz", text), None);
+        // Capitals make the query case-sensitive
+        assert!(score("ImportError", text).is_some());
+        assert_eq!(score("IMPORT", text), None);
+
+        let lines = vec![
+            "src/app.rs:9: unused variable 'p_a_r_s_e'".to_string(),
+            "src/parse.rs:1: expected ';'".to_string(),
+            "src/other.rs:2: mismatched types".to_string(),
+        ];
+        // A run of characters at a word start beats scattered ones
+        assert_eq!(filter("parse", &lines), vec![1, 0]);
+        assert_eq!(filter("", &lines), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_picker_keys() {
+        let lines: Vec<String> = (0..20)
+            .map(|i| format!("file{}.py: error {}", i, i))
+            .collect();
+        let mut picker = Picker::new(&lines, String::new());
+
+        for _ in 0..7 {
+            assert_eq!(picker.press(Key::Down, 5), None);
+        }
+        assert_eq!((picker.selected, picker.offset), (7, 3));
+        assert_eq!(picker.press(Key::Enter, 5), Some(Some(7)));
+
+        for c in "file1".chars() {
+            picker.press(Key::Char(c), 5);
+        }
+        // file1.py and file10.py to file19.py
+        assert_eq!(picker.matches.len(), 11);
+        assert_eq!(picker.selected, 0);
+        picker.press(Key::Char('7'), 5);
+        assert_eq!(picker.press(Key::Enter, 5), Some(Some(17)));
+        picker.press(Key::Char('x'), 5);
+        assert_eq!(picker.press(Key::Enter, 5), None);
+        picker.press(Key::Backspace, 5);
+        picker.press(Key::ClearQuery, 5);
+        assert_eq!(picker.matches.len(), 20);
+        assert_eq!(picker.press(Key::Cancel, 5), Some(None));
+    }
+
+    #[test]
+    fn test_editor_command() {
+        let file = Path::new("/p/main.py");
+        assert_eq!(
+            editor_command("code --wait", file, Some(3), Some(7)),
+            vec!["code", "--wait", "--goto", "/p/main.py:3:7"]
+        );
+        assert_eq!(
+            editor_command("/usr/bin/nvim", file, Some(3), None),
+            vec!["/usr/bin/nvim", "+3", "/p/main.py"]
+        );
+        assert_eq!(
+            editor_command("nano", file, Some(3), Some(2)),
+            vec!["nano", "+3,2", "/p/main.py"]
+        );
+        assert_eq!(
+            editor_command("gedit", file, Some(3), Some(2)),
+            vec!["gedit", "/p/main.py"]
+        );
+        assert_eq!(
+            editor_command("vim", file, None, None),
+            vec!["vim", "/p/main.py"]
+        );
+    }
+}
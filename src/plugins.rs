@@ -0,0 +1,356 @@
+//! Plugins add languages and error kinds without changing ess. A plugin is
+//! any executable named `ess-plugin-<name>` on PATH, run with one argument:
+//!
+//! - `scan`: stdin is `{"version": 1, "root": "/abs/project", "files": [...]}`
+//!   with the files no built-in check understands, as absolute paths
+//! - `bug`: stdin is `{"version": 1, "text": "<error text>"}`
+//!
+//! and prints `{"diagnostics": [...]}`, each with a `message` and optionally
+//! `file` (relative to the root or absolute), `line`, `column`, `end_line`,
+//! `end_column`, `severity` ("error" or "warning"), `title` and `fix`.
+//! Printing nothing means it found nothing; the exit code isn't looked at.
+
+use crate::finding::{Finding, Severity};
+use crate::fixer;
+use crate::parser::{CustomFix, ErrorType, Language, ParsedError};
+use crate::rules::Rule;
+use crate::tools;
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+pub const PREFIX: &str = "ess-plugin-";
+
+/// How long `ess bug` waits for a plugin to answer
+const BUG_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An `ess-plugin-<name>` executable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Plugin {
+    /// Name it's reported under, e.g. "ess-plugin-go"
+    pub fn tool(&self) -> String {
+        format!("{}{}", PREFIX, self.name)
+    }
+
+    /// The plugin run in `mode` ("scan" or "bug")
+    pub fn command(&self, mode: &str) -> Command {
+        let mut cmd = Command::new(&self.path);
+        cmd.arg(mode);
+        cmd
+    }
+}
+
+/// Plugins on PATH, by name. When two directories have the same plugin,
+/// the first one wins, like the shell would pick.
+pub fn discover() -> Vec<Plugin> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    find_in(std::env::split_paths(&path))
+}
+
+/// Plugins in `dirs`, in PATH order
+pub fn find_in(dirs: impl Iterator<Item = PathBuf>) -> Vec<Plugin> {
+    let mut plugins: Vec<Plugin> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<Plugin> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_executable(path))
+            .filter_map(|path| {
+                let name = plugin_name(&path)?;
+                Some(Plugin { name, path })
+            })
+            .collect();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        for plugin in found {
+            if !plugins.iter().any(|p| p.name == plugin.name) {
+                plugins.push(plugin);
+            }
+        }
+    }
+    plugins
+}
+
+/// "go" for `ess-plugin-go`, or `ess-plugin-go.exe` on Windows
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_string_lossy();
+    let name = file_name.strip_prefix(PREFIX)?;
+    let name = if cfg!(windows) {
+        let (stem, extension) = name.rsplit_once('.')?;
+        ["exe", "bat", "cmd"]
+            .contains(&extension.to_lowercase().as_str())
+            .then_some(stem)?
+    } else {
+        name
+    };
+    Some(name.to_string()).filter(|name| !name.is_empty())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// What `scan` reads on stdin
+pub fn scan_request(root: &Path, files: &[PathBuf]) -> Vec<u8> {
+    json!({ "version": 1, "root": root, "files": files })
+        .to_string()
+        .into_bytes()
+}
+
+/// What `bug` reads on stdin
+pub fn bug_request(text: &str) -> Vec<u8> {
+    json!({ "version": 1, "text": text })
+        .to_string()
+        .into_bytes()
+}
+
+/// One problem a plugin reported
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Diagnostic {
+    pub message: String,
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub column: Option<u32>,
+    #[serde(default)]
+    pub end_line: Option<u32>,
+    #[serde(default)]
+    pub end_column: Option<u32>,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    /// Heading shown above the fix
+    #[serde(default)]
+    pub title: Option<String>,
+    /// How to fix it
+    #[serde(default)]
+    pub fix: Option<String>,
+}
+
+fn default_severity() -> Severity {
+    Severity::Error
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The diagnostics in a plugin's output
+pub fn parse_response(stdout: &str) -> Result<Vec<Diagnostic>> {
+    if stdout.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let response: Response = serde_json::from_str(stdout)?;
+    Ok(response.diagnostics)
+}
+
+impl Diagnostic {
+    /// Its fix as an error type ess can show, when it has one
+    pub fn error_type(&self, plugin: &str) -> Option<ErrorType> {
+        let fix = self.fix.clone()?;
+        Some(ErrorType::Custom(CustomFix {
+            title: self
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("{} error", plugin)),
+            fix,
+            insert: None,
+        }))
+    }
+
+    /// A finding in the project at `root`, reported by `tool`
+    pub fn finding(&self, root: &Path, plugin: &Plugin) -> Finding {
+        let file = self
+            .file
+            .as_deref()
+            .map_or_else(|| root.to_path_buf(), |file| root.join(file));
+        let parsed = ParsedError {
+            file: file.to_string_lossy().to_string(),
+            line: self.line,
+            column: self.column,
+            end_line: self.end_line,
+            end_column: self.end_column,
+            message: self.message.clone(),
+            error_type: self
+                .error_type(&plugin.name)
+                .unwrap_or_else(|| ErrorType::Unknown(self.message.clone())),
+            language: Language::Unknown,
+        };
+        Finding::new(&file, self.severity, &self.message, &plugin.tool())
+            .at(self.line, self.column)
+            .ending_at(self.end_line, self.end_column)
+            .with_parsed(Some(parsed), root)
+    }
+}
+
+/// The plugins as a rule, so `ess bug` asks them about errors nothing
+/// built in recognizes. The first one with a fix answers.
+pub struct Plugins(Vec<Plugin>);
+
+impl Plugins {
+    pub fn new(plugins: Vec<Plugin>) -> Self {
+        Self(plugins)
+    }
+}
+
+impl Rule for Plugins {
+    fn kind(&self) -> &'static str {
+        "Custom"
+    }
+
+    fn recognize(&self, text: &str, _language: &Language) -> Option<ErrorType> {
+        self.0.iter().find_map(|plugin| {
+            let finished = tools::output_within(
+                &mut plugin.command("bug"),
+                &bug_request(text),
+                Some(BUG_TIMEOUT),
+                None,
+            )
+            .ok()?;
+            let stdout = String::from_utf8_lossy(&finished.output.stdout);
+            parse_response(&stdout)
+                .ok()?
+                .iter()
+                .find_map(|diagnostic| diagnostic.error_type(&plugin.name))
+        })
+    }
+
+    fn fix(&self, error: &ParsedError) {
+        if let ErrorType::Custom(fix) = &error.error_type {
+            fixer::fix_custom(fix);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn write_plugin(dir: &Path, name: &str, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_plugins_on_path() {
+        let temp_dir = std::env::temp_dir().join("ess_test_plugins_find");
+        let (first, second) = (temp_dir.join("first"), temp_dir.join("second"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        let go = write_plugin(&first, "ess-plugin-go", "#!/bin/sh\n");
+        write_plugin(&second, "ess-plugin-go", "#!/bin/sh\n");
+        let zig = write_plugin(&second, "ess-plugin-zig", "#!/bin/sh\n");
+        // Not executable, or not a plugin
+        std::fs::write(first.join("ess-plugin-lua"), "").unwrap();
+        write_plugin(&first, "ess-other", "#!/bin/sh\n");
+
+        let plugins = find_in([first, second, temp_dir.join("missing")].into_iter());
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(
+            plugins,
+            vec![
+                Plugin {
+                    name: "go".to_string(),
+                    path: go
+                },
+                Plugin {
+                    name: "zig".to_string(),
+                    path: zig
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_to_finding() {
+        let diagnostics = parse_response(
+            r#"{"diagnostics": [
+                {"file": "main.go", "line": 3, "column": 2, "message": "undefined: x",
+                 "title": "Undefined name", "fix": "Declare x first"},
+                {"message": "go.mod is missing", "severity": "warning"}
+            ]}"#,
+        )
+        .unwrap();
+        let plugin = Plugin {
+            name: "go".to_string(),
+            path: PathBuf::from("/bin/ess-plugin-go"),
+        };
+        let root = Path::new("/p");
+
+        let first = diagnostics[0].finding(root, &plugin);
+        assert_eq!(first.file, PathBuf::from("/p/main.go"));
+        assert_eq!(first.position(), "3:2");
+        assert_eq!(first.source, "ess-plugin-go");
+        assert!(first.is_fixable());
+        assert!(matches!(
+            &first.parsed.as_ref().unwrap().error_type,
+            ErrorType::Custom(fix) if fix.title == "Undefined name" && fix.fix == "Declare x first"
+        ));
+
+        let second = diagnostics[1].finding(root, &plugin);
+        assert_eq!(second.file, PathBuf::from("/p"));
+        assert_eq!(second.severity, Severity::Warning);
+        assert!(!second.is_fixable());
+
+        assert!(parse_response("").unwrap().is_empty());
+        assert!(parse_response("not json").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugins_answer_ess_bug() {
+        let temp_dir = std::env::temp_dir().join("ess_test_plugins_bug");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = write_plugin(
+            &temp_dir,
+            "ess-plugin-go",
+            "#!/bin/sh\nif grep -q 'undefined: ' ; then\n\
+            echo '{\"diagnostics\": [{\"message\": \"undefined\", \"fix\": \"Declare it\"}]}'\nfi\n",
+        );
+        let plugins = Plugins::new(vec![Plugin {
+            name: "go".to_string(),
+            path,
+        }]);
+
+        let known = plugins.recognize("./main.go:3:2: undefined: x", &Language::Unknown);
+        let unknown = plugins.recognize("something else", &Language::Unknown);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert!(matches!(
+            known,
+            Some(ErrorType::Custom(fix)) if fix.title == "go error" && fix.fix == "Declare it"
+        ));
+        assert!(unknown.is_none());
+    }
+}
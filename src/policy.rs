@@ -0,0 +1,354 @@
+use crate::config::PolicyConfig;
+use crate::finding::{Finding, Severity};
+use crate::parser::{ErrorType, Language, ParsedError};
+use crate::regexes;
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Rule IDs, as used in `[policy.severity]`
+pub const LICENSE_HEADER: &str = "license-header";
+pub const NO_PRINT: &str = "no-print";
+pub const USE_STRICT: &str = "use-strict";
+pub const MAX_FILE_LENGTH: &str = "max-file-length";
+
+/// Lines at the top of a file searched for the license header
+const HEADER_LINES: usize = 10;
+
+/// Compiled `[policy]` rules
+pub struct Policy {
+    config: PolicyConfig,
+    no_print: GlobSet,
+}
+
+impl Policy {
+    pub fn new(config: &PolicyConfig) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &config.no_print {
+            // A plain directory like "src" covers everything under it
+            let pattern = pattern.trim_start_matches("./");
+            let pattern = if pattern.contains(['*', '?', '[', '{']) {
+                pattern.to_string()
+            } else {
+                format!("{}/**", pattern.trim_end_matches('/'))
+            };
+            let glob = GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    anyhow::anyhow!("Invalid policy.no_print pattern '{}': {}", pattern, e)
+                })?;
+            builder.add(glob);
+        }
+
+        Ok(Self {
+            config: config.clone(),
+            no_print: builder.build()?,
+        })
+    }
+
+    /// Whether any rule is turned on
+    pub fn is_enabled(&self) -> bool {
+        self.config.license_header.is_some()
+            || !self.config.no_print.is_empty()
+            || self.config.require_use_strict
+            || self.config.max_file_lines.is_some()
+    }
+
+    fn severity(&self, rule: &str) -> Severity {
+        self.config
+            .severity
+            .get(rule)
+            .copied()
+            .unwrap_or(Severity::Warning)
+    }
+
+    fn finding(
+        &self,
+        file: &Path,
+        line: Option<u32>,
+        column: Option<u32>,
+        message: &str,
+        error_type: ErrorType,
+        language: &Language,
+    ) -> Finding {
+        let rule = match &error_type {
+            ErrorType::PolicyViolation(rule) => rule.as_str(),
+            _ => LICENSE_HEADER,
+        };
+        let finding = Finding::new(
+            file,
+            self.severity(rule),
+            message,
+            &format!("policy/{}", rule),
+        )
+        .at(line, column)
+        .with_explanation(message);
+        let parsed = ParsedError {
+            file: file.to_string_lossy().to_string(),
+            line,
+            column,
+            end_line: None,
+            end_column: None,
+            message: message.to_string(),
+            error_type,
+            language: language.clone(),
+        };
+        Finding {
+            parsed: Some(parsed),
+            ..finding
+        }
+    }
+
+    /// Check one file against the rules. `relative` is its path from the
+    /// project root, which `policy.no_print` globs are matched against.
+    pub fn check(
+        &self,
+        file: &Path,
+        relative: &Path,
+        language: &Language,
+        content: &str,
+    ) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        if let Some(header) = &self.config.license_header {
+            let has_header = content
+                .lines()
+                .take(HEADER_LINES)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .contains(header.lines().next().unwrap_or(header).trim());
+            if !has_header {
+                findings.push(self.finding(
+                    file,
+                    Some(1),
+                    None,
+                    "Missing license header",
+                    ErrorType::MissingLicenseHeader(commented(header, language)),
+                    language,
+                ));
+            }
+        }
+
+        if self.no_print.is_match(relative) {
+            for (i, (line, column)) in print_calls(content, language).into_iter().enumerate() {
+                let mut finding = self.finding(
+                    file,
+                    Some(line),
+                    Some(column),
+                    "Debug output is not allowed here",
+                    ErrorType::PolicyViolation(NO_PRINT.to_string()),
+                    language,
+                );
+                // Like compiler errors, only the first one is explained
+                if i > 0 {
+                    finding.explain = None;
+                }
+                findings.push(finding);
+            }
+        }
+
+        if self.config.require_use_strict && needs_use_strict(file, language, content) {
+            findings.push(self.finding(
+                file,
+                Some(1),
+                None,
+                "Missing 'use strict'",
+                ErrorType::PolicyViolation(USE_STRICT.to_string()),
+                language,
+            ));
+        }
+
+        if let Some(max) = self.config.max_file_lines {
+            let lines = content.lines().count();
+            if lines > max {
+                findings.push(self.finding(
+                    file,
+                    Some(max as u32 + 1),
+                    None,
+                    &format!("File has {} lines, more than the {} allowed", lines, max),
+                    ErrorType::PolicyViolation(MAX_FILE_LENGTH.to_string()),
+                    language,
+                ));
+            }
+        }
+
+        findings
+    }
+}
+
+/// The header as line comments in the file's language
+fn commented(header: &str, language: &Language) -> String {
+    let prefix = match language {
+        Language::Python => "#",
+        _ => "//",
+    };
+    header
+        .lines()
+        .map(|line| match line.trim() {
+            "" => prefix.to_string(),
+            text => format!("{} {}", prefix, text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Positions (1-based line and column) of debug output calls, skipping
+/// comment lines
+fn print_calls(content: &str, language: &Language) -> Vec<(u32, u32)> {
+    let (re, comment) = match language {
+        Language::JavaScript | Language::TypeScript => (&regexes::JS_PRINT, "//"),
+        Language::Python => (&regexes::PYTHON_PRINT, "#"),
+        Language::Rust => (&regexes::RUST_PRINT, "//"),
+        Language::Cpp => (&regexes::CPP_PRINT, "//"),
+        Language::Java => (&regexes::JAVA_PRINT, "//"),
+        Language::CSharp => (&regexes::CSHARP_PRINT, "//"),
+        Language::Unknown => return Vec::new(),
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with(comment))
+        .filter_map(|(i, line)| {
+            let found = re.captures(line)?;
+            let start = found.get(1).unwrap_or(found.get(0)?).start();
+            Some((i as u32 + 1, line[..start].chars().count() as u32 + 1))
+        })
+        .collect()
+}
+
+/// A CommonJS script without a `'use strict'` directive. ES modules and
+/// TypeScript are strict already.
+fn needs_use_strict(file: &Path, language: &Language, content: &str) -> bool {
+    let is_script = *language == Language::JavaScript
+        && file
+            .extension()
+            .is_none_or(|ext| matches!(ext.to_string_lossy().as_ref(), "js" | "cjs"));
+    if !is_script {
+        return false;
+    }
+
+    let is_module = content.lines().any(|l| {
+        let l = l.trim_start();
+        l.starts_with("import ") || l.starts_with("export ")
+    });
+    let has_directive = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//") && !l.starts_with("#!"))
+        .take(1)
+        .any(|l| l.starts_with("'use strict'") || l.starts_with("\"use strict\""));
+
+    !is_module && !has_directive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn policy(config: PolicyConfig) -> Policy {
+        Policy::new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_license_header() {
+        let policy = policy(PolicyConfig {
+            license_header: Some("SPDX-License-Identifier: MIT".to_string()),
+            ..Default::default()
+        });
+        let file = Path::new("/p/app.py");
+
+        let findings = policy.check(file, Path::new("app.py"), &Language::Python, "x = 1\n");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].source, "policy/license-header");
+        assert_eq!(
+            findings[0].parsed.as_ref().unwrap().error_type,
+            ErrorType::MissingLicenseHeader("# SPDX-License-Identifier: MIT".to_string())
+        );
+
+        let with_header = "#!/usr/bin/env python3\n# SPDX-License-Identifier: MIT\nx = 1\n";
+        assert!(policy
+            .check(file, Path::new("app.py"), &Language::Python, with_header)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_no_print_only_in_configured_paths() {
+        let policy = policy(PolicyConfig {
+            no_print: vec!["src".to_string()],
+            ..Default::default()
+        });
+        let content = "// console.log(debug)\nconst a = 1; console.log(a);\nlogger.log(a);\n";
+
+        let findings = policy.check(
+            Path::new("/p/src/app.js"),
+            Path::new("src/app.js"),
+            &Language::JavaScript,
+            content,
+        );
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].position(), "2:14");
+
+        assert!(policy
+            .check(
+                Path::new("/p/scripts/build.js"),
+                Path::new("scripts/build.js"),
+                &Language::JavaScript,
+                content,
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn test_print_calls() {
+        let python = "print('a')\nself.print()\n    pprint(x)\n# print(x)\nif x: print(x)\n";
+        assert_eq!(print_calls(python, &Language::Python), vec![(1, 1), (5, 7)]);
+        assert_eq!(
+            print_calls("let x = dbg!(y);\n", &Language::Rust),
+            vec![(1, 9)]
+        );
+    }
+
+    #[test]
+    fn test_use_strict_and_max_file_length() {
+        let mut severity = BTreeMap::new();
+        severity.insert(MAX_FILE_LENGTH.to_string(), Severity::Error);
+        let policy = policy(PolicyConfig {
+            require_use_strict: true,
+            max_file_lines: Some(2),
+            severity,
+            ..Default::default()
+        });
+        let check = |name: &str, content: &str| {
+            policy.check(
+                &Path::new("/p").join(name),
+                Path::new(name),
+                &Language::JavaScript,
+                content,
+            )
+        };
+
+        let findings = check("a.js", "var a = 1;\nvar b = 2;\nvar c = 3;\n");
+        let rules: Vec<&str> = findings.iter().map(|f| f.source.as_str()).collect();
+        assert_eq!(rules, vec!["policy/use-strict", "policy/max-file-length"]);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[1].severity, Severity::Error);
+        assert_eq!(findings[1].line, Some(3));
+
+        assert!(check("b.js", "#!/usr/bin/env node\n'use strict';\n").is_empty());
+        assert!(check("c.js", "import fs from 'fs';\n").is_empty());
+        assert!(check("d.mjs", "const a = 1;\n").is_empty());
+    }
+
+    #[test]
+    fn test_is_enabled() {
+        assert!(!policy(PolicyConfig::default()).is_enabled());
+        assert!(policy(PolicyConfig {
+            max_file_lines: Some(500),
+            ..Default::default()
+        })
+        .is_enabled());
+    }
+}
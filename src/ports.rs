@@ -0,0 +1,201 @@
+use crate::ui;
+use std::io::IsTerminal;
+use std::process::Command;
+
+/// A process listening on a port
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holder {
+    pub pid: u32,
+    /// Program name, when the lookup tool reports it
+    pub name: Option<String>,
+}
+
+/// The command that lists what listens on `port` on this OS
+/// ("linux", "macos" or "windows")
+pub fn lookup_command(port: u16, os: &str) -> (&'static str, Vec<String>) {
+    match os {
+        "windows" => ("netstat", vec!["-ano".into(), "-p".into(), "tcp".into()]),
+        _ => (
+            "lsof",
+            vec![
+                "-nP".into(),
+                format!("-iTCP:{}", port),
+                "-sTCP:LISTEN".into(),
+            ],
+        ),
+    }
+}
+
+/// Processes in `lsof` output, skipping its header
+pub fn parse_lsof(output: &str) -> Vec<Holder> {
+    let mut holders: Vec<Holder> = output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let name = columns.next()?.to_string();
+            let pid = columns.next()?.parse().ok()?;
+            Some(Holder {
+                pid,
+                name: Some(name),
+            })
+        })
+        .collect();
+    // lsof lists a process once per socket, e.g. for IPv4 and IPv6
+    holders.dedup();
+    holders
+}
+
+/// Processes in `netstat -ano` output listening on `port`
+pub fn parse_netstat(output: &str, port: u16) -> Vec<Holder> {
+    let suffix = format!(":{}", port);
+    let mut holders: Vec<Holder> = output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            match columns.as_slice() {
+                [_, local, _, "LISTENING", pid] if local.ends_with(&suffix) => Some(Holder {
+                    pid: pid.parse().ok()?,
+                    name: None,
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+    holders.dedup();
+    holders
+}
+
+/// How to stop a process on this OS
+pub fn stop_command(pid: u32, os: &str) -> String {
+    match os {
+        "windows" => format!("taskkill /PID {} /F", pid),
+        _ => format!("kill {}", pid),
+    }
+}
+
+/// Processes listening on `port`, or None when the lookup tool can't run
+pub fn holders(port: u16) -> Option<Vec<Holder>> {
+    let os = std::env::consts::OS;
+    let (program, args) = lookup_command(port, os);
+    // lsof exits with 1 when nothing matches
+    let output = Command::new(program).args(&args).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(match os {
+        "windows" => parse_netstat(&stdout, port),
+        _ => parse_lsof(&stdout),
+    })
+}
+
+/// Show what holds `port` and how to free it. The lookup runs a system
+/// command, so it asks first, and only when someone can answer.
+pub fn diagnose(port: Option<u16>) {
+    let os = std::env::consts::OS;
+    let Some(port) = port else {
+        ui::print_fix_instruction(&format!(
+            "Another process is already listening on the port this program wants.\n\n\
+            Find it with:\n  {}\n\n\
+            Then stop it, or start this program on a different port.",
+            match os {
+                "windows" => "netstat -ano | findstr LISTENING",
+                _ => "lsof -nP -iTCP -sTCP:LISTEN",
+            }
+        ));
+        return;
+    };
+
+    let (program, args) = lookup_command(port, os);
+    let command = format!("{} {}", program, args.join(" "));
+    let asked = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let allowed = asked && {
+        ui::print_prompt(&format!(
+            "Run '{}' to find what holds port {}? [y/N] ",
+            command, port
+        ));
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).is_ok()
+            && matches!(answer.trim(), "y" | "Y" | "yes")
+    };
+
+    match allowed.then(|| holders(port)).flatten() {
+        Some(found) if !found.is_empty() => {
+            for holder in &found {
+                ui::print_info(&format!(
+                    "Port {} is held by {} (PID {})",
+                    port,
+                    holder.name.as_deref().unwrap_or("a process"),
+                    holder.pid
+                ));
+            }
+            ui::print_fix_instruction(&format!(
+                "Stop it if it's an old copy of this program:\n  {}\n\n\
+                Or change the port this program is configured to use.",
+                stop_command(found[0].pid, os)
+            ));
+        }
+        Some(_) => ui::print_fix_instruction(&format!(
+            "Nothing is listening on port {} any more - it was probably a process that has \
+            since exited. Run the program again.",
+            port
+        )),
+        None => {
+            if allowed {
+                ui::print_warning(&format!("Could not run '{}'", program));
+            }
+            ui::print_fix_instruction(&format!(
+                "Another process is already listening on port {}.\n\n\
+                Find it with:\n  {}\n\n\
+                Then stop it ({}), or change the port this program is configured to use.",
+                port,
+                command,
+                match os {
+                    "windows" => "taskkill /PID <PID> /F",
+                    _ => "kill <PID>",
+                }
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lsof() {
+        let output = "COMMAND   PID USER   FD   TYPE DEVICE SIZE/OFF NODE NAME\n\
+            node    4242 me     23u  IPv4 0x1234      0t0  TCP *:3000 (LISTEN)\n\
+            node    4242 me     24u  IPv6 0x5678      0t0  TCP *:3000 (LISTEN)\n";
+        assert_eq!(
+            parse_lsof(output),
+            vec![Holder {
+                pid: 4242,
+                name: Some("node".to_string())
+            }]
+        );
+        assert!(parse_lsof("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_netstat() {
+        let output = "\nActive Connections\n\n  Proto  Local Address          Foreign Address        State           PID\n\
+            \x20 TCP    0.0.0.0:3000           0.0.0.0:0              LISTENING       812\n\
+            \x20 TCP    0.0.0.0:30000          0.0.0.0:0              LISTENING       900\n\
+            \x20 TCP    127.0.0.1:3000         127.0.0.1:50123        ESTABLISHED     812\n";
+        assert_eq!(
+            parse_netstat(output, 3000),
+            vec![Holder {
+                pid: 812,
+                name: None
+            }]
+        );
+    }
+
+    #[test]
+    fn test_commands_per_os() {
+        assert_eq!(lookup_command(8000, "linux").1[1], "-iTCP:8000");
+        assert_eq!(lookup_command(8000, "windows").0, "netstat");
+        assert_eq!(stop_command(812, "windows"), "taskkill /PID 812 /F");
+        assert_eq!(stop_command(4242, "macos"), "kill 4242");
+    }
+}
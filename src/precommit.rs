@@ -0,0 +1,170 @@
+//! `ess hook install`: a git pre-commit hook that scans the changed files
+//! and stops the commit when one has an error
+
+use crate::git;
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+/// What the hook runs
+pub const COMMAND: &str = "ess find-bug --changed --quiet --fail-on error";
+
+/// Line that marks a hook as ours, so uninstall leaves others alone
+const MARKER: &str = "# Installed by 'ess hook install'";
+
+/// Config file of the pre-commit framework (pre-commit.com)
+pub const FRAMEWORK_CONFIG: &str = ".pre-commit-config.yaml";
+
+/// The hook as a pre-commit framework repo entry
+pub const FRAMEWORK_SNIPPET: &str = "\
+repos:
+  - repo: local
+    hooks:
+      - id: ess
+        name: ess find-bug
+        entry: ess find-bug --changed --quiet --fail-on error
+        language: system
+        pass_filenames: false
+";
+
+/// What `install_framework` did
+#[derive(Debug, PartialEq)]
+pub enum FrameworkInstall {
+    Created(PathBuf),
+    AlreadyThere(PathBuf),
+    /// The config exists without the hook; it is left for the user to
+    /// edit, since rewriting their YAML could lose comments
+    AddByHand(PathBuf),
+}
+
+/// Add the hook to the pre-commit framework's config in `root`
+pub fn install_framework(root: &Path) -> Result<FrameworkInstall> {
+    let config = root.join(FRAMEWORK_CONFIG);
+    match std::fs::read_to_string(&config) {
+        Ok(existing) if existing.contains("id: ess") => Ok(FrameworkInstall::AlreadyThere(config)),
+        Ok(_) => Ok(FrameworkInstall::AddByHand(config)),
+        Err(_) => {
+            std::fs::write(&config, FRAMEWORK_SNIPPET)?;
+            Ok(FrameworkInstall::Created(config))
+        }
+    }
+}
+
+fn script() -> String {
+    format!(
+        "#!/bin/sh\n\
+        {}; remove it with 'ess hook uninstall'\n\
+        if ! command -v ess >/dev/null 2>&1; then\n\
+        \x20   echo \"ess is not on PATH; skipping its pre-commit checks\" >&2\n\
+        \x20   exit 0\n\
+        fi\n\
+        exec {}\n",
+        MARKER, COMMAND
+    )
+}
+
+/// Where git looks for hooks in the repository `path` is in, following
+/// `core.hooksPath`
+pub fn hooks_dir(path: &Path) -> Result<PathBuf> {
+    match git::hooks_dir(path) {
+        Some(dir) => Ok(dir),
+        None => bail!("{} is not in a git repository", path.display()),
+    }
+}
+
+/// Write the pre-commit hook into `hooks_dir`. A hook that isn't ours is
+/// only replaced with `force`.
+pub fn install(hooks_dir: &Path, force: bool) -> Result<PathBuf> {
+    let hook = hooks_dir.join("pre-commit");
+    if let Ok(existing) = std::fs::read_to_string(&hook) {
+        if !existing.contains(MARKER) && !force {
+            bail!(
+                "{} already exists; add '{}' to it, or pass --force to replace it",
+                hook.display(),
+                COMMAND
+            );
+        }
+    }
+    std::fs::create_dir_all(hooks_dir)?;
+    std::fs::write(&hook, script())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook)
+}
+
+/// Remove the pre-commit hook from `hooks_dir` if ess installed it.
+/// False when there was none.
+pub fn uninstall(hooks_dir: &Path) -> Result<bool> {
+    let hook = hooks_dir.join("pre-commit");
+    let Ok(existing) = std::fs::read_to_string(&hook) else {
+        return Ok(false);
+    };
+    if !existing.contains(MARKER) {
+        bail!(
+            "{} wasn't installed by ess; leaving it alone",
+            hook.display()
+        );
+    }
+    std::fs::remove_file(&hook)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_and_uninstall() {
+        let temp_dir = std::env::temp_dir().join("ess_test_precommit");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let hook = temp_dir.join("pre-commit");
+
+        let installed = install(&temp_dir, false).unwrap();
+        let script = std::fs::read_to_string(&hook).unwrap();
+        let reinstalled = install(&temp_dir, false).is_ok();
+        let removed = uninstall(&temp_dir).unwrap();
+        let removed_again = uninstall(&temp_dir).unwrap();
+
+        std::fs::write(&hook, "#!/bin/sh\nmake lint\n").unwrap();
+        let kept = install(&temp_dir, false).err().unwrap().to_string();
+        let not_ours = uninstall(&temp_dir).is_err();
+        let forced = install(&temp_dir, true).is_ok();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(installed, hook);
+        assert!(script.starts_with("#!/bin/sh\n# Installed by 'ess hook install'"));
+        assert!(script.ends_with("exec ess find-bug --changed --quiet --fail-on error\n"));
+        assert!(reinstalled);
+        assert!(removed);
+        assert!(!removed_again);
+        assert!(kept.contains("already exists"), "{}", kept);
+        assert!(not_ours);
+        assert!(forced);
+    }
+
+    #[test]
+    fn test_install_framework() {
+        let temp_dir = std::env::temp_dir().join("ess_test_precommit_framework");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let config = temp_dir.join(FRAMEWORK_CONFIG);
+
+        let created = install_framework(&temp_dir).unwrap();
+        let again = install_framework(&temp_dir).unwrap();
+        std::fs::write(&config, "repos: []\n").unwrap();
+        let by_hand = install_framework(&temp_dir).unwrap();
+        let left = std::fs::read_to_string(&config).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(created, FrameworkInstall::Created(config.clone()));
+        assert_eq!(again, FrameworkInstall::AlreadyThere(config.clone()));
+        assert_eq!(by_hand, FrameworkInstall::AddByHand(config));
+        assert_eq!(left, "repos: []\n");
+    }
+}
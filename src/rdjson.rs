@@ -0,0 +1,168 @@
+use crate::apply::Edit;
+use crate::finding::{Finding, Severity};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Findings in reviewdog's Diagnostic Format (rdjson), for
+/// `reviewdog -f=rdjson` to post as review comments. Paths are relative
+/// to `root`, which should be where reviewdog runs.
+pub fn log(findings: &[Finding], root: &Path) -> Value {
+    json!({
+        "source": {
+            "name": "ess",
+            "url": env!("CARGO_PKG_REPOSITORY"),
+        },
+        "diagnostics": findings
+            .iter()
+            .map(|finding| diagnostic(finding, root))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn diagnostic(finding: &Finding, root: &Path) -> Value {
+    let mut location = json!({ "path": relative_path(&finding.file, root) });
+    if let Some(line) = finding.line {
+        let mut range = json!({ "start": position(line, finding.column) });
+        if finding.end_line.is_some() || finding.end_column.is_some() {
+            // rdjson end columns point one past the range, like SARIF's
+            range["end"] = position(
+                finding.end_line.unwrap_or(line),
+                finding.end_column.map(|c| c + 1),
+            );
+        }
+        location["range"] = range;
+    }
+
+    let mut diagnostic = json!({
+        "message": finding.message,
+        "location": location,
+        "severity": match finding.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+        },
+        "source": { "name": finding.source },
+        "code": { "value": finding.reported_rule() },
+    });
+    let suggestions: Vec<Value> = finding
+        .fixes
+        .iter()
+        .filter(|fix| fix.file == finding.file)
+        .flat_map(|fix| &fix.edits)
+        .filter_map(suggestion)
+        .collect();
+    if !suggestions.is_empty() {
+        diagnostic["suggestions"] = json!(suggestions);
+    }
+    diagnostic
+}
+
+fn position(line: u32, column: Option<u32>) -> Value {
+    match column {
+        Some(column) => json!({ "line": line, "column": column }),
+        None => json!({ "line": line }),
+    }
+}
+
+/// An edit as text inserted at an empty range, which reviewdog shows as a
+/// suggested change. Whole-file replacements are too big to suggest.
+fn suggestion(edit: &Edit) -> Option<Value> {
+    let (line, column, text) = match edit {
+        Edit::InsertLine { line, text } => (*line, 1, format!("{}\n", text)),
+        Edit::InsertAt { line, column, text } => (*line, *column, text.clone()),
+        Edit::ReplaceAll { .. } => return None,
+    };
+    let at = json!({ "line": line, "column": column });
+    Some(json!({
+        "range": { "start": at, "end": at },
+        "text": text,
+    }))
+}
+
+/// Path of a file under `root`, with `/` separators
+fn relative_path(file: &Path, root: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply::Fix;
+    use crate::parser::parse_error;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_log_maps_findings() {
+        let root = Path::new("/p");
+        let findings = vec![
+            Finding::new(
+                Path::new("/p/src/main.cpp"),
+                Severity::Error,
+                "'cout' is not a member of 'std'",
+                "g++",
+            )
+            .with_parsed(
+                parse_error("src/main.cpp:3:10: error: 'cout' is not a member of 'std'"),
+                root,
+            )
+            .with_fix(Some(Fix {
+                file: PathBuf::from("/p/src/main.cpp"),
+                description: "Add #include <iostream>".to_string(),
+                edits: vec![Edit::InsertLine {
+                    line: 1,
+                    text: "#include <iostream>".to_string(),
+                }],
+            })),
+            Finding::new(
+                Path::new("/p/app.js"),
+                Severity::Warning,
+                "Missing 'use strict'",
+                "policy/use-strict",
+            ),
+        ];
+        let log = log(&findings, root);
+
+        assert_eq!(log["source"]["name"], "ess");
+        let first = &log["diagnostics"][0];
+        assert_eq!(first["severity"], "ERROR");
+        assert_eq!(first["location"]["path"], "src/main.cpp");
+        assert_eq!(first["location"]["range"]["start"]["line"], 3);
+        assert_eq!(first["location"]["range"]["start"]["column"], 10);
+        assert!(first["location"]["range"]["end"].is_null());
+        assert_eq!(first["source"]["name"], "g++");
+        assert_eq!(first["code"]["value"], "CPP-INC-001");
+        let suggestion = &first["suggestions"][0];
+        assert_eq!(
+            suggestion["range"]["start"],
+            json!({"line": 1, "column": 1})
+        );
+        assert_eq!(suggestion["text"], "#include <iostream>\n");
+
+        let second = &log["diagnostics"][1];
+        assert_eq!(second["severity"], "WARNING");
+        assert!(second["location"]["range"].is_null());
+        assert_eq!(second["code"]["value"], "policy/use-strict");
+        assert!(second["suggestions"].is_null());
+    }
+
+    #[test]
+    fn test_range_end_is_exclusive() {
+        let finding = Finding::new(
+            Path::new("/p/src/main.rs"),
+            Severity::Error,
+            "mismatched types",
+            "cargo",
+        )
+        .at(Some(4), Some(9))
+        .ending_at(Some(4), Some(12));
+        let log = log(&[finding], Path::new("/p"));
+        let range = &log["diagnostics"][0]["location"]["range"];
+
+        assert_eq!(range["start"], json!({"line": 4, "column": 9}));
+        assert_eq!(range["end"], json!({"line": 4, "column": 13}));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::config::Config;
+use crate::history;
+use crate::status::format_age;
+use crate::ui;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Recent projects file name (in the data directory)
+const RECENT_FILE_NAME: &str = "recent.json";
+
+/// Number of projects remembered
+const MAX_RECENT: usize = 10;
+
+/// A project that was scanned
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentProject {
+    pub path: PathBuf,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// The most recently scanned projects, newest first
+pub struct Recent {
+    path: PathBuf,
+}
+
+impl Recent {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// List stored in the user's data directory
+    pub fn open_default() -> Option<Self> {
+        Config::data_dir().map(|dir| Self::new(&dir.join(RECENT_FILE_NAME)))
+    }
+
+    /// The remembered projects, newest first. A corrupt file counts as empty.
+    pub fn projects(&self) -> Vec<RecentProject> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Put a project at the top of the list
+    pub fn record(&self, project: &Path, timestamp: u64) -> Result<()> {
+        let mut projects = self.projects();
+        projects.retain(|p| p.path != project);
+        projects.insert(
+            0,
+            RecentProject {
+                path: project.to_path_buf(),
+                timestamp,
+            },
+        );
+        projects.truncate(MAX_RECENT);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&projects)?)?;
+        Ok(())
+    }
+}
+
+/// Remember a scanned project in the default list, ignoring failures
+pub fn record_project(project: &Path) {
+    if let Some(recent) = Recent::open_default() {
+        let _ = recent.record(project, history::now());
+    }
+}
+
+/// The most recently scanned project that still exists
+pub fn last_project() -> Option<PathBuf> {
+    Recent::open_default()?
+        .projects()
+        .into_iter()
+        .map(|p| p.path)
+        .find(|p| p.is_dir())
+}
+
+/// `ess recent`: list the remembered projects
+pub fn run() -> Result<()> {
+    ui::print_section("Recent Projects");
+
+    let projects = Recent::open_default()
+        .map(|r| r.projects())
+        .unwrap_or_default();
+    if projects.is_empty() {
+        ui::print_warning("No projects scanned yet");
+        ui::print_hint("Run 'ess find-bug' in a project to add it here");
+        return Ok(());
+    }
+
+    let now = history::now();
+    for (i, project) in projects.iter().enumerate() {
+        let age = format_age(now.saturating_sub(project.timestamp));
+        let line = format!("{}. {} ({} ago)", i + 1, project.path.display(), age);
+        if project.path.is_dir() {
+            ui::print_info(&line);
+        } else {
+            ui::print_warning(&format!("{} - no longer exists", line));
+        }
+    }
+    ui::print_hint("Run 'ess find-bug --last' to scan the first one again");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_moves_project_to_top() {
+        let temp_dir = std::env::temp_dir().join("ess_test_recent");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let recent = Recent::new(&temp_dir.join(RECENT_FILE_NAME));
+        assert!(recent.projects().is_empty());
+
+        recent.record(Path::new("/p/a"), 1).unwrap();
+        recent.record(Path::new("/p/b"), 2).unwrap();
+        recent.record(Path::new("/p/a"), 3).unwrap();
+
+        let projects = recent.projects();
+        let paths: Vec<&Path> = projects.iter().map(|p| p.path.as_path()).collect();
+        assert_eq!(paths, vec![Path::new("/p/a"), Path::new("/p/b")]);
+        assert_eq!(projects[0].timestamp, 3);
+
+        for i in 0..MAX_RECENT + 2 {
+            recent
+                .record(&Path::new("/p").join(i.to_string()), 4)
+                .unwrap();
+        }
+        assert_eq!(recent.projects().len(), MAX_RECENT);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
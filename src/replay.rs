@@ -0,0 +1,182 @@
+//! Saved sessions: `--save results.json` on `find-bug` and `bug` writes
+//! down what was found, and `ess replay results.json` shows it again,
+//! fixes included, without running a single tool
+
+use crate::config::Config;
+use crate::fixer;
+use crate::history;
+use crate::parser::ParsedError;
+use crate::patterns::Patterns;
+use crate::rules::Registry;
+use crate::scanner::{self, ScanOptions, ScanSummary};
+use crate::status;
+use crate::ui;
+use crate::usage::UsageError;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Version of the file format, raised when older ess can't read it
+const FORMAT_VERSION: u32 = 1;
+
+/// A saved scan or bug analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    /// Seconds since the Unix epoch
+    pub saved: u64,
+    #[serde(flatten)]
+    pub analysis: Analysis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Analysis {
+    /// `ess find-bug`
+    Scan { root: PathBuf, summary: ScanSummary },
+    /// `ess bug`
+    Bug {
+        error_text: String,
+        errors: Vec<SavedError>,
+    },
+}
+
+/// An error `ess bug` recognized, and the part of the output it is from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedError {
+    pub text: String,
+    pub error: ParsedError,
+}
+
+impl Session {
+    pub fn scan(root: &Path, summary: &ScanSummary) -> Self {
+        Self::new(Analysis::Scan {
+            root: root.to_path_buf(),
+            summary: summary.clone(),
+        })
+    }
+
+    pub fn bug(error_text: &str, errors: &[(String, ParsedError)]) -> Self {
+        Self::new(Analysis::Bug {
+            error_text: error_text.to_string(),
+            errors: errors
+                .iter()
+                .map(|(text, error)| SavedError {
+                    text: text.clone(),
+                    error: error.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    fn new(analysis: Analysis) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            saved: history::now(),
+            analysis,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Could not write {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                UsageError(format!("No saved session at {}", path.display())).into()
+            } else {
+                anyhow!("Could not read {}: {}", path.display(), e)
+            }
+        })?;
+        Self::parse(&content, &path.display().to_string())
+    }
+
+    /// A session saved as `content`, named `origin` in errors
+    pub fn parse(content: &str, origin: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| anyhow!("{} is not a saved session: {}", origin, e))?;
+        let version = value["version"].as_u64().unwrap_or_default();
+        if version > u64::from(FORMAT_VERSION) {
+            bail!("{} was saved by a newer ess; update to replay it", origin);
+        }
+        serde_json::from_value(value)
+            .map_err(|e| anyhow!("{} is not a saved session: {}", origin, e))
+    }
+
+    /// Show the session the way it was shown when it was saved
+    pub fn show(&self, config: &Config, quiet: bool) -> Result<()> {
+        if !quiet {
+            ui::print_info(&format!(
+                "Replaying results saved {} ago; nothing is run again",
+                status::format_age(history::now().saturating_sub(self.saved))
+            ));
+        }
+        match &self.analysis {
+            Analysis::Scan { root, summary } => {
+                let options = ScanOptions {
+                    quiet,
+                    replay: true,
+                    ..Default::default()
+                };
+                scanner::show_summary(root, config, &options, summary)
+            }
+            Analysis::Bug { error_text, errors } => {
+                let _quiet = ui::quiet(quiet);
+                let mut rules = Registry::new();
+                rules.register(Patterns::new(&config.patterns)?);
+                let errors: Vec<(String, ParsedError)> = errors
+                    .iter()
+                    .map(|saved| (saved.text.clone(), saved.error.clone()))
+                    .collect();
+                fixer::show_errors(error_text, &errors, &rules);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_error;
+
+    #[test]
+    fn test_session_roundtrip() {
+        let temp_dir = std::env::temp_dir().join("ess_test_replay_roundtrip");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let text = "main.cpp:2:10: error: 'cout' is not a member of 'std'";
+        let errors = vec![(text.to_string(), parse_error(text).unwrap())];
+        let path = temp_dir.join("results.json");
+        Session::bug(text, &errors).save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap();
+
+        std::fs::write(&path, r#"{"version": 99, "kind": "bug"}"#).unwrap();
+        let newer = Session::load(&path).err().unwrap().to_string();
+        std::fs::write(&path, "{}").unwrap();
+        let invalid = Session::load(&path).err().unwrap().to_string();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(loaded.version, FORMAT_VERSION);
+        match loaded.analysis {
+            Analysis::Bug { error_text, errors } => {
+                assert_eq!(error_text, text);
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].error.error_type.name(), "MissingInclude");
+                assert_eq!(errors[0].error.line, Some(2));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(newer.contains("newer ess"), "{}", newer);
+        assert!(invalid.contains("is not a saved session"), "{}", invalid);
+    }
+
+    #[test]
+    fn test_missing_session_is_usage_error() {
+        let error = Session::load(Path::new("/nonexistent/ess_results.json")).unwrap_err();
+        assert!(crate::usage::is_usage_error(&error));
+    }
+}
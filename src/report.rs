@@ -0,0 +1,259 @@
+use crate::finding::{self, Finding, Related, Severity};
+use crate::fixer;
+use crate::rules::Registry;
+use crate::ui;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Path of a finding's file relative to the project root, for display
+fn display_path(file: &Path, root: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// `file:line:column` relative to the root, as far as it is known
+fn location(file: &Path, position: &str, root: &Path) -> String {
+    let path = display_path(file, root);
+    if position.is_empty() {
+        path
+    } else {
+        format!("{}:{}", path, position)
+    }
+}
+
+fn print_related(related: &[Related], root: &Path) {
+    for note in related {
+        ui::print_related(
+            &format!("{}:", location(&note.file, &note.position(), root)),
+            &note.message,
+        );
+    }
+}
+
+/// Show a finding on its own, followed by the explanation of its fix
+pub fn print_streamed(finding: &Finding, root: &Path, rules: &Registry) {
+    let location = location(&finding.file, &finding.position(), root);
+
+    if finding.severity == Severity::Error {
+        println!();
+    }
+    ui::print_finding(
+        finding.severity,
+        &format!("{}:", location),
+        &finding.message,
+        &finding.label(),
+    );
+    print_related(&finding.related, root);
+
+    if let Some(output) = &finding.explain {
+        println!();
+        fixer::explain(output, finding.parsed.as_ref(), rules);
+    }
+}
+
+/// Findings and totals as JSON, with paths relative to `root`
+pub fn json(findings: &[Finding], root: &Path) -> Value {
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    json!({
+        "errors": errors,
+        "warnings": findings.len() - errors,
+        "rules": finding::rule_counts(findings)
+            .iter()
+            .map(|c| json!({
+                "rule": c.rule,
+                "severity": c.severity,
+                "count": c.count,
+            }))
+            .collect::<Vec<_>>(),
+        "findings": findings
+            .iter()
+            .map(|f| json!({
+                "file": display_path(&f.file, root),
+                "line": f.line,
+                "column": f.column,
+                "end_line": f.end_line,
+                "end_column": f.end_column,
+                "severity": f.severity,
+                "rule": f.rule(),
+                "id": f.rule_id(),
+                "message": f.message,
+                "source": f.source,
+                "fixes": f.fixes.iter().map(|fix| &fix.description).collect::<Vec<_>>(),
+                "related": f.related.iter().map(|r| json!({
+                    "file": display_path(&r.file, root),
+                    "line": r.line,
+                    "column": r.column,
+                    "message": r.message,
+                })).collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// One `path:line:column: RULE: message` line per finding, paths relative
+/// to `root`. The line and column are left out when unknown, and
+/// multi-line messages are joined, so each finding stays on one line.
+pub fn grep(findings: &[Finding], root: &Path) -> String {
+    findings
+        .iter()
+        .map(|f| format!("{}\n", grep_line(f, root)))
+        .collect()
+}
+
+/// A finding as one `path:line:column: RULE: message` line
+pub fn grep_line(finding: &Finding, root: &Path) -> String {
+    let message = finding
+        .message
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "{}: {}: {}",
+        location(&finding.file, &finding.position(), root),
+        finding.reported_rule(),
+        message
+    )
+}
+
+/// Findings per file, in the order the files first appear
+fn group_by_file(findings: &[Finding]) -> Vec<(PathBuf, Vec<&Finding>)> {
+    let mut groups: Vec<(PathBuf, Vec<&Finding>)> = Vec::new();
+    for finding in findings {
+        match groups.iter_mut().find(|(file, _)| *file == finding.file) {
+            Some((_, group)) => group.push(finding),
+            None => groups.push((finding.file.clone(), vec![finding])),
+        }
+    }
+    groups
+}
+
+/// Show all findings under a header per file
+pub fn print_grouped(findings: &[Finding], root: &Path) {
+    if findings.is_empty() {
+        return;
+    }
+
+    ui::print_section("Findings by File");
+
+    for (file, group) in group_by_file(findings) {
+        let errors = group
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .count();
+        ui::print_file_header(
+            &file.to_string_lossy(),
+            &display_path(&file, root),
+            errors,
+            group.len() - errors,
+        );
+
+        let width = group.iter().map(|f| f.position().len()).max().unwrap_or(0);
+        for finding in group {
+            ui::print_grouped_finding(
+                finding.severity,
+                &finding.position(),
+                width,
+                &finding.message,
+                &finding.label(),
+            );
+            print_related(&finding.related, root);
+        }
+    }
+
+    if findings.iter().any(|f| f.explain.is_some()) {
+        println!();
+        ui::print_hint("Run with '--view stream' to see how to fix each error");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(file: &str, line: u32, message: &str) -> Finding {
+        Finding::new(Path::new(file), Severity::Error, message, "test").at(Some(line), None)
+    }
+
+    #[test]
+    fn test_group_by_file() {
+        let findings = vec![
+            finding("/p/b.py", 3, "first"),
+            finding("/p/a.py", 9, "second"),
+            finding("/p/b.py", 1, "third"),
+        ];
+        let groups = group_by_file(&findings);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, PathBuf::from("/p/b.py"));
+        let messages: Vec<&str> = groups[0].1.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages, vec!["first", "third"]);
+    }
+
+    #[test]
+    fn test_json() {
+        let findings = vec![
+            finding("/p/src/a.py", 3, "first").with_related(vec![Related {
+                file: PathBuf::from("/p/src/b.py"),
+                line: Some(1),
+                column: None,
+                message: "defined here".to_string(),
+            }]),
+            Finding::new(Path::new("/p/b.js"), Severity::Warning, "second", "eslint"),
+        ];
+        let report = json(&findings, Path::new("/p"));
+
+        assert_eq!(report["errors"], 1);
+        assert_eq!(report["warnings"], 1);
+        assert_eq!(
+            report["rules"],
+            json!([
+                { "rule": "test", "severity": "error", "count": 1 },
+                { "rule": "eslint", "severity": "warning", "count": 1 },
+            ])
+        );
+        assert_eq!(report["findings"][0]["file"], "src/a.py");
+        assert_eq!(report["findings"][0]["line"], 3);
+        assert_eq!(report["findings"][0]["severity"], "error");
+        assert_eq!(report["findings"][1]["rule"], "eslint");
+        assert!(report["findings"][1]["line"].is_null());
+
+        let related = &report["findings"][0]["related"][0];
+        assert_eq!(related["file"], "src/b.py");
+        assert_eq!(related["line"], 1);
+        assert_eq!(related["message"], "defined here");
+        assert_eq!(report["findings"][1]["related"], json!([]));
+    }
+
+    #[test]
+    fn test_grep() {
+        let findings = vec![
+            finding("/p/src/a.py", 3, "first\n  continued").at(Some(3), Some(7)),
+            finding("/p/b.py", 9, "second"),
+            Finding::new(Path::new("/p/c.js"), Severity::Warning, "third", "eslint"),
+        ];
+
+        assert_eq!(
+            grep(&findings, Path::new("/p")),
+            "src/a.py:3:7: test: first continued\n\
+            b.py:9: test: second\n\
+            c.js: eslint: third\n"
+        );
+    }
+
+    #[test]
+    fn test_display_path() {
+        assert_eq!(
+            display_path(Path::new("/p/src/a.py"), Path::new("/p")),
+            "src/a.py"
+        );
+        assert_eq!(
+            display_path(Path::new("/elsewhere/a.py"), Path::new("/p")),
+            "/elsewhere/a.py"
+        );
+    }
+}
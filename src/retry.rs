@@ -0,0 +1,102 @@
+use crate::config::Config;
+use crate::scanner::{self, ScanOptions};
+use crate::state::LastScan;
+use crate::ui;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Re-check only the files that had findings in the last scan. Returns how
+/// many still fail.
+pub fn run(path: &Path, config: &Config, timings: bool) -> Result<usize> {
+    ui::print_section("Retry");
+
+    let path = scanner::normalize_path(path);
+
+    let mut last = match LastScan::load(&path)? {
+        Some(last) => last,
+        None => {
+            ui::print_warning("This project has not been scanned yet");
+            ui::print_hint("Run 'ess find-bug' first, then 'ess retry' while fixing");
+            return Ok(0);
+        }
+    };
+
+    let files = existing_files(&path, &last.failing_files);
+    if files.is_empty() {
+        ui::print_success("Nothing to retry - the last scan had no failing files");
+        return Ok(0);
+    }
+
+    ui::print_info(&format!(
+        "Re-checking {} file{} from the last scan",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
+    ));
+
+    let before = files.len();
+    let summary = scanner::scan_project(
+        &path,
+        config,
+        &ScanOptions {
+            timings,
+            only_files: Some(files),
+            ..Default::default()
+        },
+    )?;
+
+    last.failing_files = summary
+        .failing_files
+        .iter()
+        .map(|f| f.to_string_lossy().to_string())
+        .collect();
+    last.save(&path)?;
+
+    let remaining = last.failing_files.len();
+    if remaining == 0 {
+        ui::print_success(&format!(
+            "{} file{} fixed - nothing left to retry",
+            before,
+            if before == 1 { "" } else { "s" }
+        ));
+        ui::print_hint("Run 'ess find-bug' for a full scan");
+    } else {
+        ui::print_info(&format!(
+            "{} fixed, {} still failing",
+            before.saturating_sub(remaining),
+            remaining
+        ));
+    }
+
+    Ok(remaining)
+}
+
+/// Absolute paths of the recorded files that still exist
+fn existing_files(root: &Path, files: &[String]) -> Vec<PathBuf> {
+    files
+        .iter()
+        .map(|f| root.join(f))
+        .filter(|f| f.is_file())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_existing_files_skips_deleted() {
+        let temp_dir = std::env::temp_dir().join("ess_retry_existing");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        std::fs::write(temp_dir.join("kept.py"), "x = 1").unwrap();
+
+        let files = existing_files(
+            &temp_dir,
+            &["kept.py".to_string(), "deleted.py".to_string()],
+        );
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(files, vec![temp_dir.join("kept.py")]);
+    }
+}
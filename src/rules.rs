@@ -0,0 +1,252 @@
+use crate::fixer;
+use crate::knowledge::{self, Entry};
+use crate::parser::{self, ErrorType, Language, ParsedError};
+use crate::scanner;
+use crate::ui;
+
+/// One kind of error: how to recognize it, what it means and how to fix
+/// it. A new kind of error is a new `Rule` in the registry.
+pub trait Rule: Send + Sync {
+    /// Name of the error kind it handles, as given by `ErrorType::name`
+    fn kind(&self) -> &'static str;
+
+    /// Recognize error text the language parsers don't understand
+    fn recognize(&self, _text: &str, _language: &Language) -> Option<ErrorType> {
+        None
+    }
+
+    /// Plain-language explanation of the error kind
+    fn explain(&self) -> Option<&'static Entry> {
+        knowledge::lookup(self.kind())
+    }
+
+    /// Show how to fix an error of this kind
+    fn fix(&self, error: &ParsedError);
+}
+
+/// A rule whose fix is a plain function, for the kinds the parsers know
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    kind: &'static str,
+    fix: fn(&ParsedError),
+}
+
+impl Builtin {
+    pub const fn new(kind: &'static str, fix: fn(&ParsedError)) -> Self {
+        Self { kind, fix }
+    }
+}
+
+impl Rule for Builtin {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn fix(&self, error: &ParsedError) {
+        (self.fix)(error)
+    }
+}
+
+/// The rules in use. Earlier rules win: the built-in ones first, then
+/// the ones registered from config.
+pub struct Registry {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// The built-in rules
+    pub fn new() -> Self {
+        Self {
+            rules: fixer::RULES
+                .iter()
+                .map(|rule| Box::new(*rule) as Box<dyn Rule>)
+                .collect(),
+        }
+    }
+
+    pub fn register(&mut self, rule: impl Rule + 'static) {
+        self.rules.push(Box::new(rule));
+    }
+
+    /// The rule that handles an error kind
+    pub fn rule(&self, kind: &str) -> Option<&dyn Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.kind() == kind)
+            .map(|rule| rule.as_ref())
+    }
+
+    /// The first rule that recognizes `text`
+    pub fn recognize(&self, text: &str, language: &Language) -> Option<ErrorType> {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.recognize(text, language))
+    }
+
+    /// Give an error the parsers didn't recognize the type a rule finds in
+    /// its text, keeping where it points
+    pub fn resolve(&self, text: &str, parsed: Option<ParsedError>) -> Option<ParsedError> {
+        let recognized = parsed
+            .as_ref()
+            .is_some_and(|p| !matches!(p.error_type, ErrorType::Unknown(_)));
+        if recognized {
+            return parsed;
+        }
+
+        let language = parsed
+            .as_ref()
+            .map_or(Language::Unknown, |p| p.language.clone());
+        match self.recognize(text, &language) {
+            Some(error_type) => Some(match parsed {
+                Some(parsed) => ParsedError {
+                    error_type,
+                    ..parsed
+                },
+                None => ParsedError {
+                    file: String::new(),
+                    line: None,
+                    column: None,
+                    end_line: None,
+                    end_column: None,
+                    message: text.trim().to_string(),
+                    error_type,
+                    language,
+                },
+            }),
+            None => parsed,
+        }
+    }
+
+    /// Show how to fix a parsed error
+    pub fn fix(&self, error: &ParsedError) {
+        match self.rule(error.error_type.name()) {
+            Some(rule) => rule.fix(error),
+            None => {
+                ui::print_warning(&format!("No fix known for {}", error.error_type.name()));
+                ui::print_hint("Check the error message and fix manually");
+            }
+        }
+    }
+}
+
+/// The stable ID of every built-in check and what it looks for, for
+/// `ess list`
+pub fn builtin_ids() -> Vec<(&'static str, String)> {
+    let kinds = fixer::RULES
+        .iter()
+        .filter_map(|rule| Some((parser::rule_id(rule.kind())?, rule.kind().to_string())));
+    let heuristics = scanner::PYTHON_HEURISTICS
+        .iter()
+        .map(|(id, _, warning)| (*id, warning.to_string()))
+        .chain(std::iter::once((
+            scanner::GETENV_IN_URL.0,
+            scanner::GETENV_IN_URL.1.to_string(),
+        )));
+    let common = fixer::COMMON_PATTERNS.iter().map(|(id, needles, _)| {
+        (
+            *id,
+            format!("Unparsed error text with '{}'", needles.join("' or '")),
+        )
+    });
+    kinds.chain(heuristics).chain(common).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::CustomFix;
+
+    /// Recognizes "deprecated: <name>" and has no fix to show
+    struct Deprecated;
+
+    impl Rule for Deprecated {
+        fn kind(&self) -> &'static str {
+            "Custom"
+        }
+
+        fn recognize(&self, text: &str, _language: &Language) -> Option<ErrorType> {
+            let name = text.trim().strip_prefix("deprecated: ")?;
+            Some(ErrorType::Custom(CustomFix {
+                title: "Deprecated API".to_string(),
+                fix: format!("Replace {}", name),
+                insert: None,
+            }))
+        }
+
+        fn fix(&self, _error: &ParsedError) {}
+    }
+
+    fn python_error(error_type: ErrorType) -> ParsedError {
+        ParsedError {
+            file: "app.py".to_string(),
+            line: Some(3),
+            column: None,
+            end_line: None,
+            end_column: None,
+            message: "message".to_string(),
+            error_type,
+            language: Language::Python,
+        }
+    }
+
+    #[test]
+    fn test_builtin_rules_have_knowledge() {
+        let registry = Registry::new();
+        for kind in ["KeyError", "BorrowError", "Custom"] {
+            let rule = registry.rule(kind).unwrap();
+            assert_eq!(rule.explain().unwrap().kind, kind);
+        }
+        assert!(registry.rule("NoSuchKind").is_none());
+    }
+
+    #[test]
+    fn test_builtin_rules_have_ids() {
+        for rule in fixer::RULES.iter().filter(|rule| rule.kind() != "Unknown") {
+            assert!(parser::rule_id(rule.kind()).is_some(), "{}", rule.kind());
+        }
+        let ids: Vec<&str> = builtin_ids().into_iter().map(|(id, _)| id).collect();
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ids.len());
+    }
+
+    #[test]
+    fn test_registered_rule_recognizes_text() {
+        let mut registry = Registry::new();
+        assert!(registry
+            .recognize("deprecated: os.popen2", &Language::Python)
+            .is_none());
+
+        registry.register(Deprecated);
+        let resolved = registry.resolve("deprecated: os.popen2", None).unwrap();
+        assert!(
+            matches!(resolved.error_type, ErrorType::Custom(ref f) if f.fix == "Replace os.popen2")
+        );
+        assert_eq!(resolved.file, "");
+
+        // The built-in Custom rule was there first, so it shows the fix
+        assert_eq!(registry.rule("Custom").unwrap().kind(), "Custom");
+    }
+
+    #[test]
+    fn test_resolve_keeps_recognized_errors() {
+        let mut registry = Registry::new();
+        registry.register(Deprecated);
+
+        let known = python_error(ErrorType::KeyError("a".to_string()));
+        let resolved = registry.resolve("deprecated: a", Some(known)).unwrap();
+        assert_eq!(resolved.error_type, ErrorType::KeyError("a".to_string()));
+
+        let unknown = python_error(ErrorType::Unknown("x".to_string()));
+        let resolved = registry.resolve("deprecated: a", Some(unknown)).unwrap();
+        assert!(matches!(resolved.error_type, ErrorType::Custom(_)));
+        assert_eq!(resolved.line, Some(3));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::finding::{Finding, Severity};
+use crate::knowledge;
+use crate::ui;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// SARIF version written, as accepted by GitHub code scanning
+const VERSION: &str = "2.1.0";
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Findings as a SARIF log with one run. Paths are relative to `root`,
+/// which consumers resolve through the `%SRCROOT%` base.
+pub fn log(findings: &[Finding], root: &Path) -> Value {
+    // By ID, with the name the knowledge base knows the rule by
+    let mut rules: Vec<(&str, &str)> = findings
+        .iter()
+        .map(|f| (f.reported_rule(), f.rule()))
+        .collect();
+    rules.sort();
+    rules.dedup_by_key(|(id, _)| *id);
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let rule_index = rules
+                .binary_search_by_key(&finding.reported_rule(), |(id, _)| id)
+                .unwrap_or(0);
+            result(finding, rule_index, root)
+        })
+        .collect();
+
+    json!({
+        "$schema": SCHEMA,
+        "version": VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "ess",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules.iter().map(|(id, name)| rule(id, name)).collect::<Vec<_>>(),
+                }
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": root_uri(root) }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// A rule, described from the knowledge base when ess knows the error kind
+fn rule(id: &str, name: &str) -> Value {
+    let mut rule = json!({ "id": id, "name": name });
+    if let Some(entry) = knowledge::lookup(name) {
+        rule["shortDescription"] = json!({ "text": entry.meaning });
+        rule["help"] = json!({ "text": entry.fix });
+    }
+    rule
+}
+
+fn result(finding: &Finding, rule_index: usize, root: &Path) -> Value {
+    let mut location = physical_location(&finding.file, finding.line, root);
+    if let Some(region) = location.get_mut("region") {
+        if let Some(column) = finding.column {
+            region["startColumn"] = json!(column);
+        }
+        if let Some(line) = finding.end_line {
+            region["endLine"] = json!(line);
+        }
+        // SARIF end columns point one past the region
+        if let Some(column) = finding.end_column {
+            region["endColumn"] = json!(column + 1);
+        }
+    }
+
+    let related: Vec<Value> = finding
+        .related
+        .iter()
+        .enumerate()
+        .map(|(id, note)| {
+            let mut location = physical_location(&note.file, note.line, root);
+            if let (Some(region), Some(column)) = (location.get_mut("region"), note.column) {
+                region["startColumn"] = json!(column);
+            }
+            json!({
+                "id": id,
+                "physicalLocation": location,
+                "message": { "text": note.message },
+            })
+        })
+        .collect();
+
+    let mut result = json!({
+        "ruleId": finding.reported_rule(),
+        "ruleIndex": rule_index,
+        "level": match finding.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        },
+        "message": { "text": finding.message },
+        "locations": [{ "physicalLocation": location }],
+        "properties": { "source": finding.source },
+    });
+    if !related.is_empty() {
+        result["relatedLocations"] = json!(related);
+    }
+    result
+}
+
+/// A file under `root`, and the line in it when known. SARIF regions
+/// need a start line.
+fn physical_location(file: &Path, line: Option<u32>, root: &Path) -> Value {
+    let mut location = json!({
+        "artifactLocation": {
+            "uri": relative_uri(file, root),
+            "uriBaseId": "%SRCROOT%",
+        }
+    });
+    if let Some(line) = line {
+        location["region"] = json!({ "startLine": line });
+    }
+    location
+}
+
+/// Path of a file under `root` as a URI reference, with `/` separators
+fn relative_uri(file: &Path, root: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    relative
+        .components()
+        .map(|c| ui::encode_path(&c.as_os_str().to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `file://` URI of the project root; base URIs must end with `/`
+fn root_uri(root: &Path) -> String {
+    let path = root.to_string_lossy().replace('\\', "/");
+    let path = ui::encode_path(path.trim_end_matches('/'));
+    if path.starts_with('/') {
+        format!("file://{}/", path)
+    } else {
+        format!("file:///{}/", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Related;
+    use crate::parser::parse_error;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_log_maps_findings() {
+        let root = Path::new("/p");
+        let findings = vec![
+            Finding::new(
+                Path::new("/p/src/main.cpp"),
+                Severity::Error,
+                "expected ';'",
+                "g++",
+            )
+            .with_parsed(
+                parse_error("src/main.cpp:10:5: error: expected ';' before 'return'"),
+                root,
+            ),
+            Finding::new(
+                Path::new("/p/app.js"),
+                Severity::Warning,
+                "Missing 'use strict'",
+                "policy/use-strict",
+            ),
+        ];
+        let log = log(&findings, root);
+        let run = &log["runs"][0];
+
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["originalUriBaseIds"]["%SRCROOT%"]["uri"], "file:///p/");
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let ids: Vec<&str> = rules.iter().map(|r| r["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["GEN-SYN-001", "policy/use-strict"]);
+        assert_eq!(rules[0]["name"], "MissingSemicolon");
+        assert!(rules[0]["shortDescription"]["text"].is_string());
+
+        let first = &run["results"][0];
+        assert_eq!(first["ruleId"], "GEN-SYN-001");
+        assert_eq!(first["level"], "error");
+        let location = &first["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/main.cpp");
+        assert_eq!(location["region"]["startLine"], 10);
+        assert_eq!(location["region"]["startColumn"], 5);
+        assert!(location["region"]["endColumn"].is_null());
+
+        let second = &run["results"][1];
+        assert_eq!(second["ruleIndex"], 1);
+        assert_eq!(second["level"], "warning");
+        assert!(second["locations"][0]["physicalLocation"]["region"].is_null());
+        assert!(second["relatedLocations"].is_null());
+    }
+
+    #[test]
+    fn test_region_end_is_exclusive() {
+        let root = Path::new("/p");
+        let finding = Finding::new(
+            Path::new("/p/src/main.rs"),
+            Severity::Error,
+            "cannot find value `y`",
+            "cargo check",
+        )
+        .at(Some(3), Some(14))
+        .ending_at(Some(3), Some(16));
+        let log = log(&[finding], root);
+
+        let region = &log["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 14);
+        assert_eq!(region["endLine"], 3);
+        assert_eq!(region["endColumn"], 17);
+    }
+
+    #[test]
+    fn test_related_locations() {
+        let root = Path::new("/p");
+        let finding = Finding::new(
+            Path::new("/p/src/main.rs"),
+            Severity::Error,
+            "cannot borrow `v` as mutable",
+            "cargo check",
+        )
+        .at(Some(4), Some(5))
+        .with_related(vec![Related {
+            file: PathBuf::from("/p/src/main.rs"),
+            line: Some(3),
+            column: Some(13),
+            message: "immutable borrow occurs here".to_string(),
+        }]);
+        let log = log(&[finding], root);
+
+        let related = &log["runs"][0]["results"][0]["relatedLocations"][0];
+        assert_eq!(related["id"], 0);
+        assert_eq!(related["message"]["text"], "immutable borrow occurs here");
+        let location = &related["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/main.rs");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["startColumn"], 13);
+    }
+
+    #[test]
+    fn test_root_uri() {
+        assert_eq!(root_uri(Path::new("/home/me/app")), "file:///home/me/app/");
+        assert_eq!(
+            root_uri(Path::new("C:\\Users\\me\\app")),
+            "file:///C:/Users/me/app/"
+        );
+        assert_eq!(
+            root_uri(Path::new("/home/me/my app#2")),
+            "file:///home/me/my%20app%232/"
+        );
+    }
+
+    #[test]
+    fn test_relative_uri_is_encoded() {
+        assert_eq!(
+            relative_uri(Path::new("/p/src/bug #1/main file.py"), Path::new("/p")),
+            "src/bug%20%231/main%20file.py"
+        );
+    }
+}
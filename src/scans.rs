@@ -0,0 +1,308 @@
+//! Scan history: every `ess find-bug` kept in `.ess/scans.db`, a SQLite
+//! database in the project, for `ess history` to list and
+//! `ess history show <id>` to show again
+
+use crate::finding::Severity;
+use crate::git;
+use crate::history;
+use crate::replay::Session;
+use crate::scanner::ScanSummary;
+use crate::state::{create_state_dir, state_dir};
+use crate::status::format_age;
+use crate::ui;
+use crate::usage::UsageError;
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+/// Database file name (in the project's state directory)
+const DB_FILE_NAME: &str = "scans.db";
+
+/// Scans kept per project; older ones are dropped
+const MAX_SCANS: usize = 200;
+
+/// One past scan, without its findings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanRecord {
+    pub id: i64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// HEAD commit at scan time, if the project is a git repository
+    pub commit: Option<String>,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Files with findings
+    pub files: usize,
+}
+
+/// The scan history of one project
+pub struct ScanDb {
+    conn: Connection,
+}
+
+impl ScanDb {
+    pub fn path(project_path: &Path) -> PathBuf {
+        state_dir(project_path).join(DB_FILE_NAME)
+    }
+
+    /// Open the project's history, creating it if needed
+    pub fn open(project_path: &Path) -> Result<Self> {
+        create_state_dir(project_path)?;
+        let path = Self::path(project_path);
+        let conn = Connection::open(&path)
+            .map_err(|e| anyhow!("Could not open {}: {}", path.display(), e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS scans (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                git_commit TEXT,
+                errors INTEGER NOT NULL,
+                warnings INTEGER NOT NULL,
+                files INTEGER NOT NULL,
+                session TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Store a scan with the session `ess history show` replays. Returns
+    /// its id.
+    pub fn record(&self, root: &Path, summary: &ScanSummary, commit: Option<&str>) -> Result<i64> {
+        let count = |severity| {
+            summary
+                .findings
+                .iter()
+                .filter(|f| f.severity == severity)
+                .count() as i64
+        };
+        let session = serde_json::to_string(&Session::scan(root, summary))?;
+        self.conn.execute(
+            "INSERT INTO scans (timestamp, git_commit, errors, warnings, files, session)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                history::now() as i64,
+                commit,
+                count(Severity::Error),
+                count(Severity::Warning),
+                summary.failing_files.len() as i64,
+                session,
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "DELETE FROM scans WHERE id <= ?1",
+            params![id - MAX_SCANS as i64],
+        )?;
+        Ok(id)
+    }
+
+    /// The most recent scans, newest first
+    pub fn list(&self, limit: usize) -> Result<Vec<ScanRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, timestamp, git_commit, errors, warnings, files
+             FROM scans ORDER BY id DESC LIMIT ?1",
+        )?;
+        let records = statement
+            .query_map(params![limit as i64], |row| {
+                Ok(ScanRecord {
+                    id: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u64,
+                    commit: row.get(2)?,
+                    errors: row.get::<_, i64>(3)? as usize,
+                    warnings: row.get::<_, i64>(4)? as usize,
+                    files: row.get::<_, i64>(5)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+
+    /// The saved session of a scan, if it is still kept
+    pub fn session(&self, id: i64) -> Result<Option<Session>> {
+        let content: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT session FROM scans WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        content
+            .map(|content| Session::parse(&content, &format!("Scan #{}", id)))
+            .transpose()
+    }
+}
+
+/// Add a scan to the project's history, warning on stderr, after any
+/// report, when it can't be stored
+pub fn record_scan(root: &Path, summary: &ScanSummary) {
+    let recorded = ScanDb::open(root).and_then(|db| {
+        let commit = git::head_commit(root);
+        db.record(root, summary, commit.as_deref())
+    });
+    if let Err(e) = recorded {
+        ui::eprint_warning(&format!("Could not add the scan to its history: {:#}", e));
+    }
+}
+
+/// `ess history`: list the project's past scans
+pub fn run(project_path: &Path, limit: usize) -> Result<()> {
+    ui::print_section("Scan History");
+
+    let records = if ScanDb::path(project_path).exists() {
+        ScanDb::open(project_path)?.list(limit)?
+    } else {
+        Vec::new()
+    };
+    if records.is_empty() {
+        ui::print_warning("No scans recorded yet");
+        ui::print_hint("Run 'ess find-bug' to record one");
+        return Ok(());
+    }
+
+    let now = history::now();
+    for (i, record) in records.iter().enumerate() {
+        let mut line = format!(
+            "#{} {} ago: {} errors, {} warnings in {} files",
+            record.id,
+            format_age(now.saturating_sub(record.timestamp)),
+            record.errors,
+            record.warnings,
+            record.files
+        );
+        // Records are newest first, so the next one is the scan before
+        if let Some(before) = records.get(i + 1) {
+            let change =
+                (record.errors + record.warnings) as i64 - (before.errors + before.warnings) as i64;
+            if change != 0 {
+                line.push_str(&format!(" ({:+})", change));
+            }
+        }
+        if let Some(commit) = &record.commit {
+            line.push_str(&format!(" at {}", &commit[..commit.len().min(7)]));
+        }
+        ui::print_info(&line);
+    }
+    ui::print_hint("Run 'ess history show <id>' to see a scan's report again");
+    Ok(())
+}
+
+/// The session of scan `id` for `ess history show`
+pub fn session(project_path: &Path, id: i64) -> Result<Session> {
+    if !ScanDb::path(project_path).exists() {
+        return Err(UsageError(format!(
+            "No scans recorded in {}; run 'ess find-bug' first",
+            project_path.display()
+        ))
+        .into());
+    }
+    ScanDb::open(project_path)?.session(id)?.ok_or_else(|| {
+        UsageError(format!(
+            "No scan #{} in the history; 'ess history' lists the ones kept",
+            id
+        ))
+        .into()
+    })
+}
+
+/// The session of the project's most recent scan, if any was recorded
+pub fn latest(project_path: &Path) -> Result<Option<Session>> {
+    if !ScanDb::path(project_path).exists() {
+        return Ok(None);
+    }
+    let db = ScanDb::open(project_path)?;
+    match db.list(1)?.first() {
+        Some(record) => db.session(record.id),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Finding;
+    use crate::replay::Analysis;
+    use crate::unparsed::ParseCoverage;
+
+    fn summary(findings: Vec<Finding>) -> ScanSummary {
+        ScanSummary {
+            languages: Vec::new(),
+            lines_of_code: 10,
+            failing_files: vec![PathBuf::from("main.py")],
+            findings,
+            skipped: Vec::new(),
+            parse_coverage: ParseCoverage::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_list_scans() {
+        let temp_dir = std::env::temp_dir().join("ess_test_scans_record");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let error = Finding::new(
+            &temp_dir.join("main.py"),
+            Severity::Error,
+            "invalid syntax",
+            "python",
+        );
+        let warning = Finding::new(
+            &temp_dir.join("main.py"),
+            Severity::Warning,
+            "unused import",
+            "ruff",
+        );
+
+        let db = ScanDb::open(&temp_dir).unwrap();
+        let first = db
+            .record(&temp_dir, &summary(vec![error.clone()]), Some("abc"))
+            .unwrap();
+        let second = db
+            .record(&temp_dir, &summary(vec![error, warning]), None)
+            .unwrap();
+        drop(db);
+        let db = ScanDb::open(&temp_dir).unwrap();
+        let records = db.list(10).unwrap();
+        let session = db.session(first).unwrap();
+        let missing = db.session(second + 1).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, second);
+        assert_eq!((records[0].errors, records[0].warnings), (1, 1));
+        assert_eq!(records[1].commit.as_deref(), Some("abc"));
+        assert_eq!(records[1].files, 1);
+        match session.unwrap().analysis {
+            Analysis::Scan { root, summary } => {
+                assert_eq!(root, temp_dir);
+                assert_eq!(summary.findings.len(), 1);
+                assert_eq!(summary.findings[0].message, "invalid syntax");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn test_old_scans_are_dropped() {
+        let temp_dir = std::env::temp_dir().join("ess_test_scans_prune");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let db = ScanDb::open(&temp_dir).unwrap();
+        let mut last = 0;
+        for _ in 0..MAX_SCANS + 3 {
+            last = db.record(&temp_dir, &summary(Vec::new()), None).unwrap();
+        }
+        let records = db.list(MAX_SCANS * 2).unwrap();
+        drop(db);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(records.len(), MAX_SCANS);
+        assert_eq!(records[0].id, last);
+        assert_eq!(records[MAX_SCANS - 1].id, last - MAX_SCANS as i64 + 1);
+    }
+}
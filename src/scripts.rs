@@ -0,0 +1,283 @@
+//! Rule scripts: `*.rhai` files in `~/.config/essentialscode/rules/` that
+//! fix errors ess doesn't recognize, with logic a `[[patterns]]` regex
+//! can't express. Each script defines `fix(error)`, where `error` has
+//! `file`, `line`, `column` (`()` when unknown), `message`, `text` (the
+//! whole error), `kind` and `language`, and returns the fix text,
+//! `#{ title: "...", fix: "..." }`, or nothing when the error isn't its:
+//!
+//! ```text
+//! fn fix(error) {
+//!     if error.message.starts_with("ImproperlyConfigured") {
+//!         let setting = error.message.split("'")[1];
+//!         return `Set ${setting} in settings.py, or export DJANGO_SETTINGS_MODULE`;
+//!     }
+//! }
+//! ```
+//!
+//! Scripts are tried in file name order, and can't touch files or the
+//! network.
+
+use crate::config::Config;
+use crate::fixer;
+use crate::parser::{self, CustomFix, ErrorType, Language, ParsedError};
+use crate::rules::Rule;
+use crate::ui;
+use anyhow::{anyhow, bail, Result};
+use rhai::{Dynamic, Engine, Map, Scope, AST, INT};
+use std::path::{Path, PathBuf};
+
+/// Steps a script may take per error, so a loop can't hang ess
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A compiled `*.rhai` script
+struct Script {
+    path: PathBuf,
+    ast: AST,
+}
+
+impl Script {
+    /// "django" for `django.rhai`
+    fn name(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// The rule scripts, asked in order about errors nothing before them
+/// recognized
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<Script>,
+}
+
+impl Scripts {
+    /// The scripts in `~/.config/essentialscode/rules/`
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Config::scripts_dir().unwrap_or_default())
+    }
+
+    /// The scripts in `dir`. A missing directory has none.
+    pub fn load_from(dir: &Path) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|e| e == "rhai"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        let scripts = paths
+            .into_iter()
+            .map(|path| {
+                let ast = engine
+                    .compile_file(path.clone())
+                    .map_err(|e| anyhow!("Invalid rule script {}: {}", path.display(), e))?;
+                if !ast
+                    .iter_functions()
+                    .any(|f| f.name == "fix" && f.params.len() == 1)
+                {
+                    bail!("Rule script {} doesn't define fix(error)", path.display());
+                }
+                Ok(Script { path, ast })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { engine, scripts })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// What a script makes of `error`
+    fn run(&self, script: &Script, error: &ParsedError, text: &str) -> Result<Option<CustomFix>> {
+        let answer: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &script.ast,
+                "fix",
+                (error_map(error, text),),
+            )
+            .map_err(|e| anyhow!("{}", e))?;
+
+        if answer.is_unit() {
+            return Ok(None);
+        }
+        if answer.is_string() {
+            return Ok(Some(CustomFix {
+                title: format!("Fix from {}.rhai", script.name()),
+                fix: answer.to_string(),
+                insert: None,
+            }));
+        }
+        let Some(map) = answer.clone().try_cast::<Map>() else {
+            bail!(
+                "fix() returned {}, not a string or #{{ title, fix }}",
+                answer.type_name()
+            );
+        };
+        let field = |name: &str| map.get(name).map(|value| value.to_string());
+        let fix = field("fix").ok_or_else(|| anyhow!("fix() returned a map without 'fix'"))?;
+        Ok(Some(CustomFix {
+            title: field("title").unwrap_or_else(|| format!("Fix from {}.rhai", script.name())),
+            fix,
+            insert: None,
+        }))
+    }
+}
+
+/// The error as scripts see it
+fn error_map(error: &ParsedError, text: &str) -> Map {
+    let number = |n: Option<u32>| n.map_or(Dynamic::UNIT, |n| Dynamic::from_int(INT::from(n)));
+    let mut map = Map::new();
+    map.insert("file".into(), error.file.clone().into());
+    map.insert("line".into(), number(error.line));
+    map.insert("column".into(), number(error.column));
+    map.insert("message".into(), error.message.clone().into());
+    map.insert("text".into(), text.to_string().into());
+    map.insert("kind".into(), error.error_type.name().into());
+    map.insert("language".into(), error.language.to_string().into());
+    map
+}
+
+impl Rule for Scripts {
+    fn kind(&self) -> &'static str {
+        "Custom"
+    }
+
+    fn recognize(&self, text: &str, language: &Language) -> Option<ErrorType> {
+        if self.scripts.is_empty() {
+            return None;
+        }
+        // Where the error points, even when its kind is unknown
+        let mut error = parser::parse_error(text).unwrap_or_else(|| ParsedError {
+            file: String::new(),
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            message: text.trim().to_string(),
+            error_type: ErrorType::Unknown(text.trim().to_string()),
+            language: Language::Unknown,
+        });
+        if error.language == Language::Unknown {
+            error.language = language.clone();
+        }
+
+        self.scripts
+            .iter()
+            .find_map(|script| match self.run(script, &error, text) {
+                Ok(fix) => fix.map(ErrorType::Custom),
+                Err(e) => {
+                    ui::print_warning(&format!(
+                        "Rule script {} failed: {}",
+                        script.path.display(),
+                        e
+                    ));
+                    None
+                }
+            })
+    }
+
+    fn fix(&self, error: &ParsedError) {
+        if let ErrorType::Custom(fix) = &error.error_type {
+            fixer::fix_custom(fix);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(error_type: Option<ErrorType>) -> Option<(String, String)> {
+        match error_type {
+            Some(ErrorType::Custom(fix)) => Some((fix.title, fix.fix)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_scripts_fix_unknown_errors() {
+        let temp_dir = std::env::temp_dir().join("ess_test_scripts_fix");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("a_django.rhai"),
+            r#"fn fix(error) {
+                if error.message.starts_with("ImproperlyConfigured") {
+                    let setting = error.message.split("'")[1];
+                    return `Set ${setting} in settings.py`;
+                }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("b_where.rhai"),
+            r#"fn fix(error) {
+                if error.line != () && error.text.contains("frobnicate") {
+                    #{ title: "Frobnicator", fix: `Check ${error.file} line ${error.line}` }
+                }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.join("c_loop.rhai"),
+            "fn fix(error) { if error.message == \"spin\" { loop {} } }",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.join("notes.txt"), "not a script").unwrap();
+
+        let scripts = Scripts::load_from(&temp_dir).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(
+            custom(scripts.recognize(
+                "ImproperlyConfigured: Requested setting 'DEBUG', but settings are not configured",
+                &Language::Python
+            )),
+            Some((
+                "Fix from a_django.rhai".to_string(),
+                "Set DEBUG in settings.py".to_string()
+            ))
+        );
+        assert_eq!(
+            custom(scripts.recognize("main.cpp:7:3: error: frobnicate failed", &Language::Unknown)),
+            Some((
+                "Frobnicator".to_string(),
+                "Check main.cpp line 7".to_string()
+            ))
+        );
+        assert!(scripts
+            .recognize("frobnicate failed", &Language::Unknown)
+            .is_none());
+        // Stopped, not hung
+        assert!(scripts.recognize("spin", &Language::Unknown).is_none());
+    }
+
+    #[test]
+    fn test_invalid_scripts_are_errors() {
+        let temp_dir = std::env::temp_dir().join("ess_test_scripts_invalid");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("broken.rhai"), "fn fix(error) {").unwrap();
+        let broken = Scripts::load_from(&temp_dir).err().unwrap().to_string();
+        std::fs::write(temp_dir.join("broken.rhai"), "fn other(error) {}").unwrap();
+        let no_fix = Scripts::load_from(&temp_dir).err().unwrap().to_string();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert!(broken.starts_with("Invalid rule script"), "{}", broken);
+        assert!(no_fix.ends_with("doesn't define fix(error)"), "{}", no_fix);
+        assert!(Scripts::load_from(&temp_dir).unwrap().is_empty());
+    }
+}
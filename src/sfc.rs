@@ -0,0 +1,120 @@
+use crate::regexes;
+use std::path::Path;
+
+/// Extensions of single-file components whose scripts get checked
+const EXTENSIONS: &[&str] = &["vue", "svelte"];
+
+/// A Vue or Svelte single-file component
+pub fn is_component(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// The code of one `<script>` block in a component
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptBlock {
+    /// Extension for the block on its own: "mjs", "ts" or "tsx"
+    pub extension: &'static str,
+    /// 1-based line and column in the component where the code starts
+    pub line: u32,
+    pub column: u32,
+    pub code: String,
+}
+
+impl ScriptBlock {
+    pub fn is_typescript(&self) -> bool {
+        self.extension != "mjs"
+    }
+
+    /// The code at the same line and column as in the component, so what a
+    /// tool reports about it points into the component as well
+    pub fn aligned(&self) -> String {
+        format!(
+            "{}{}{}",
+            "\n".repeat(self.line.saturating_sub(1) as usize),
+            " ".repeat(self.column.saturating_sub(1) as usize),
+            self.code
+        )
+    }
+}
+
+/// Inline `<script>` blocks of a component; `<script src="...">` and empty
+/// blocks are left out
+pub fn script_blocks(source: &str) -> Vec<ScriptBlock> {
+    regexes::SCRIPT_TAG
+        .captures_iter(source)
+        .filter(|cap| !regexes::SCRIPT_SRC.is_match(&cap[1]))
+        .filter(|cap| !cap[2].trim().is_empty())
+        .map(|cap| {
+            let extension = match regexes::SCRIPT_LANG
+                .captures(&cap[1])
+                .map(|lang| lang[1].to_lowercase())
+                .as_deref()
+            {
+                Some("ts" | "typescript") => "ts",
+                Some("tsx") => "tsx",
+                _ => "mjs",
+            };
+
+            let code = cap.get(2).unwrap();
+            let before = &source[..code.start()];
+            let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+            ScriptBlock {
+                extension,
+                line: before.matches('\n').count() as u32 + 1,
+                column: before[line_start..].chars().count() as u32 + 1,
+                code: code.as_str().to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vue_script_blocks() {
+        let source = r#"<template>
+  <p>{{ count }}</p>
+</template>
+
+<script>
+export default { name: "Counter" }
+</script>
+
+<script setup lang="ts">
+const count: number = 1
+</script>
+
+<script src="./external.js"></script>
+"#;
+        let blocks = script_blocks(source);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0].extension, blocks[0].line), ("mjs", 5));
+        assert_eq!((blocks[1].extension, blocks[1].line), ("ts", 9));
+        assert!(blocks[1].is_typescript());
+        assert!(blocks[1].code.contains("const count"));
+    }
+
+    #[test]
+    fn test_aligned_code_keeps_lines_and_columns() {
+        let source = "<h1>Hi</h1>\n<script lang='ts'>let a = 1;\nlet b = ;</script>\n";
+        let block = &script_blocks(source)[0];
+
+        assert_eq!((block.line, block.column), (2, 19));
+        let aligned = block.aligned();
+        let lines: Vec<&str> = aligned.lines().collect();
+        assert_eq!(lines[1].find("let a"), Some(18));
+        assert_eq!(lines[2], "let b = ;");
+    }
+
+    #[test]
+    fn test_is_component() {
+        assert!(is_component(Path::new("src/App.vue")));
+        assert!(is_component(Path::new("src/routes/+page.svelte")));
+        assert!(!is_component(Path::new("src/main.js")));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::health::HealthScore;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Per-project state directory name
+pub const STATE_DIR_NAME: &str = ".ess";
+
+/// Metadata of the most recent scan file name
+const LAST_SCAN_FILE_NAME: &str = "last_scan.json";
+
+/// Get the state directory for a project
+pub fn state_dir(project_path: &Path) -> PathBuf {
+    project_path.join(STATE_DIR_NAME)
+}
+
+/// Create the state directory, with a `.gitignore` that keeps all of it
+/// out of git
+pub fn create_state_dir(project_path: &Path) -> Result<PathBuf> {
+    let dir = state_dir(project_path);
+    std::fs::create_dir_all(&dir)?;
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(gitignore, "*\n")?;
+    }
+    Ok(dir)
+}
+
+/// What we remember about the last scan of a project
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LastScan {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// HEAD commit at scan time, if the project is a git repository
+    pub commit: Option<String>,
+    pub errors: usize,
+    pub languages: Vec<String>,
+    /// Errors that came with an automatic fix suggestion
+    #[serde(default)]
+    pub fixable: usize,
+    #[serde(default)]
+    pub lines_of_code: usize,
+    /// Error count of the scan before this one
+    #[serde(default)]
+    pub previous_errors: Option<usize>,
+    /// Files with findings, relative to the project root
+    #[serde(default)]
+    pub failing_files: Vec<String>,
+}
+
+impl LastScan {
+    pub fn path(project_path: &Path) -> PathBuf {
+        state_dir(project_path).join(LAST_SCAN_FILE_NAME)
+    }
+
+    /// Load the last scan metadata, if the project was scanned before
+    pub fn load(project_path: &Path) -> Result<Option<Self>> {
+        let path = Self::path(project_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn health(&self) -> HealthScore {
+        HealthScore::compute(
+            self.errors,
+            self.fixable,
+            self.lines_of_code,
+            self.previous_errors,
+        )
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<()> {
+        create_state_dir(project_path)?;
+        std::fs::write(
+            Self::path(project_path),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_returns_none() {
+        let temp_dir = std::env::temp_dir().join("ess_state_missing");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert!(LastScan::load(&temp_dir).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = std::env::temp_dir().join("ess_state_roundtrip");
+        let _ = std::fs::create_dir_all(&temp_dir);
+
+        let scan = LastScan {
+            timestamp: 1_700_000_000,
+            commit: Some("abc123".to_string()),
+            errors: 3,
+            languages: vec!["Python".to_string()],
+            fixable: 2,
+            lines_of_code: 1200,
+            previous_errors: Some(5),
+            failing_files: vec!["src/app.py".to_string()],
+        };
+        scan.save(&temp_dir).unwrap();
+        let loaded = LastScan::load(&temp_dir).unwrap();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(loaded, Some(scan));
+    }
+
+    #[test]
+    fn test_load_old_format() {
+        let temp_dir = std::env::temp_dir().join("ess_state_old_format");
+        let _ = std::fs::create_dir_all(state_dir(&temp_dir));
+        std::fs::write(
+            LastScan::path(&temp_dir),
+            r#"{"timestamp": 1, "commit": null, "errors": 0, "languages": []}"#,
+        )
+        .unwrap();
+
+        let loaded = LastScan::load(&temp_dir).unwrap().unwrap();
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(loaded.lines_of_code, 0);
+        assert_eq!(loaded.previous_errors, None);
+        assert!(loaded.failing_files.is_empty());
+    }
+
+    #[test]
+    fn test_state_dir_ignored_by_git() {
+        let temp_dir = std::env::temp_dir().join("ess_state_gitignore");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let dir = create_state_dir(&temp_dir).unwrap();
+        let gitignore = std::fs::read_to_string(dir.join(".gitignore"));
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(gitignore.unwrap(), "*\n");
+    }
+}
@@ -0,0 +1,213 @@
+use crate::config::{IgnoreRules, ScanConfig};
+use crate::git;
+use crate::history;
+use crate::scanner;
+use crate::state::LastScan;
+use crate::ui;
+use anyhow::Result;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Maximum number of changed files listed
+const MAX_LISTED_FILES: usize = 5;
+
+/// Changed files, or new commits, from which a whole re-scan is suggested
+/// rather than checking just the changes
+const STALE_FILES: usize = 20;
+const STALE_COMMITS: usize = 10;
+
+pub fn run(path: &Path, scan: &ScanConfig) -> Result<()> {
+    ui::print_section("Project Status");
+
+    let path = scanner::normalize_path(path);
+    ui::print_info(&format!("Path: {}", path.display()));
+
+    let last = match LastScan::load(&path)? {
+        Some(last) => last,
+        None => {
+            ui::print_warning("This project has not been scanned yet");
+            ui::print_hint("Run 'ess find-bug' to scan it");
+            return Ok(());
+        }
+    };
+
+    let age = history::now().saturating_sub(last.timestamp);
+    ui::print_info(&format!("Last scan: {} ago", format_age(age)));
+    if let Some(commit) = &last.commit {
+        ui::print_info(&format!("Commit: {}", &commit[..commit.len().min(10)]));
+    }
+    if !last.languages.is_empty() {
+        ui::print_info(&format!("Languages: {}", last.languages.join(", ")));
+    }
+
+    if last.errors == 0 {
+        ui::print_no_errors();
+    } else {
+        ui::print_errors_found(last.errors);
+        println!();
+    }
+
+    let health = last.health();
+    ui::print_health(health.score, health.grade, last.previous_errors.is_some());
+
+    let commits = last
+        .commit
+        .as_deref()
+        .and_then(|c| git::commits_since(&path, c))
+        .unwrap_or(0);
+    let changed = changed_source_files(&path, &last, scan)?;
+
+    if changed.is_empty() {
+        ui::print_success("Up to date - no source changes since the last scan");
+        return Ok(());
+    }
+
+    let mut summary = format!(
+        "{} source file{} changed since the last scan",
+        changed.len(),
+        if changed.len() == 1 { "" } else { "s" }
+    );
+    if commits > 0 {
+        summary.push_str(&format!(
+            " ({} new commit{})",
+            commits,
+            if commits == 1 { "" } else { "s" }
+        ));
+    }
+    ui::print_warning(&summary);
+
+    for file in changed.iter().take(MAX_LISTED_FILES) {
+        println!("    • {}", file);
+    }
+    if changed.len() > MAX_LISTED_FILES {
+        println!("    … and {} more", changed.len() - MAX_LISTED_FILES);
+    }
+
+    println!();
+    ui::print_hint(&rescan_hint(changed.len(), commits, last.commit.as_deref()));
+
+    Ok(())
+}
+
+/// What to run for up-to-date results: the changed files alone, unless so
+/// much changed that the last results say little
+fn rescan_hint(files: usize, commits: usize, commit: Option<&str>) -> String {
+    if files >= STALE_FILES || commits >= STALE_COMMITS {
+        return "Results are likely stale - run 'ess find-bug' to re-scan".to_string();
+    }
+    match commit {
+        Some(commit) if commits > 0 => format!(
+            "Run 'ess find-bug --since {}' to check the changed files",
+            &commit[..commit.len().min(10)]
+        ),
+        _ => "Run 'ess find-bug --changed' to check the changed files".to_string(),
+    }
+}
+
+/// Source files the scan would check that changed since it ran: from git
+/// if possible, otherwise by mtime
+fn changed_source_files(path: &Path, last: &LastScan, scan: &ScanConfig) -> Result<Vec<String>> {
+    let ignore = IgnoreRules::new(&scan.ignore)?;
+    let files = last
+        .commit
+        .as_deref()
+        .and_then(|c| git::changed_files_since(path, c))
+        .unwrap_or_else(|| modified_since(path, last.timestamp, scan, &ignore));
+
+    Ok(files
+        .into_iter()
+        .filter(|f| is_source_file(Path::new(f)) && !ignore.is_ignored(Path::new(f)))
+        .collect())
+}
+
+fn modified_since(
+    path: &Path,
+    timestamp: u64,
+    scan: &ScanConfig,
+    ignore: &IgnoreRules,
+) -> Vec<String> {
+    let since = UNIX_EPOCH + Duration::from_secs(timestamp);
+
+    scanner::walk(path, scan, ignore)
+        .filter(|file| {
+            file.metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .map(|m| m > since)
+                .unwrap_or(false)
+        })
+        .map(|file| {
+            file.strip_prefix(path)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect()
+}
+
+fn is_source_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| scanner::language_for_extension(&ext.to_string_lossy()))
+        .is_some()
+}
+
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(5), "5s");
+        assert_eq!(format_age(120), "2m");
+        assert_eq!(format_age(7200), "2h");
+        assert_eq!(format_age(3 * 86_400), "3d");
+    }
+
+    #[test]
+    fn test_rescan_hint() {
+        assert!(rescan_hint(2, 0, Some("abc")).contains("find-bug --changed"));
+        assert!(rescan_hint(2, 0, None).contains("find-bug --changed"));
+        assert!(
+            rescan_hint(2, 3, Some("0123456789abcdef")).contains("find-bug --since 0123456789'")
+        );
+        assert!(rescan_hint(STALE_FILES, 0, None).contains("'ess find-bug' to re-scan"));
+        assert!(rescan_hint(1, STALE_COMMITS, Some("abc")).contains("'ess find-bug' to re-scan"));
+    }
+
+    #[test]
+    fn test_is_source_file() {
+        assert!(is_source_file(Path::new("src/main.rs")));
+        assert!(is_source_file(Path::new("app.py")));
+        assert!(!is_source_file(Path::new("README.md")));
+        assert!(!is_source_file(Path::new(".ess/last_scan.json")));
+    }
+
+    #[test]
+    fn test_modified_since_detects_new_file() {
+        let temp_dir = std::env::temp_dir().join("ess_status_mtime");
+        let _ = std::fs::create_dir_all(&temp_dir);
+        let _ = std::fs::create_dir_all(temp_dir.join("node_modules"));
+        std::fs::write(temp_dir.join("new.py"), "print('hi')").unwrap();
+        std::fs::write(temp_dir.join("node_modules").join("dep.js"), "").unwrap();
+
+        let scan = ScanConfig::default();
+        let ignore = IgnoreRules::new(&scan.ignore).unwrap();
+        let recent = modified_since(&temp_dir, 0, &scan, &ignore);
+        let future = modified_since(&temp_dir, history::now() + 3600, &scan, &ignore);
+
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(recent, vec!["new.py".to_string()]);
+        assert!(future.is_empty());
+    }
+}
@@ -0,0 +1,762 @@
+use crate::finding::{Finding, Severity};
+use crate::parser::{parse_error, ErrorType, Language, ParsedError};
+use crate::regexes;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A test command a project can be checked with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runner {
+    Pytest,
+    Cargo,
+    Npm,
+}
+
+impl Runner {
+    /// Name findings are reported under
+    pub fn name(&self) -> &'static str {
+        match self {
+            Runner::Pytest => "pytest",
+            Runner::Cargo => "cargo test",
+            Runner::Npm => "npm test",
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        match self {
+            Runner::Pytest => Language::Python,
+            Runner::Cargo => Language::Rust,
+            Runner::Npm => Language::JavaScript,
+        }
+    }
+
+    /// The failed tests in the runner's output (stdout and stderr)
+    pub fn failures(&self, output: &str) -> Vec<Failure> {
+        match self {
+            Runner::Pytest => parse_pytest(output),
+            Runner::Cargo => parse_cargo_test(output),
+            // npm test runs whatever the script says; Jest and node --test
+            // are the usual ones
+            Runner::Npm => {
+                let mut failures = parse_jest(output);
+                failures.extend(parse_node_test(output));
+                failures
+            }
+        }
+    }
+}
+
+/// The test commands a project has: pytest when it's configured or there
+/// is a tests directory, cargo test for a Cargo.toml, and npm test when
+/// package.json has a test script other than npm's placeholder
+pub fn detect(root: &Path) -> Vec<Runner> {
+    let mut runners = Vec::new();
+
+    let has = |file: &str, text: &str| {
+        std::fs::read_to_string(root.join(file)).is_ok_and(|content| content.contains(text))
+    };
+    let pytest = root.join("pytest.ini").exists()
+        || root.join("conftest.py").exists()
+        || has("pyproject.toml", "[tool.pytest")
+        || has("setup.cfg", "[tool:pytest]")
+        || has("tox.ini", "[pytest]")
+        || ["tests", "test"]
+            .iter()
+            .any(|dir| has_python_files(&root.join(dir)));
+    if pytest {
+        runners.push(Runner::Pytest);
+    }
+
+    if root.join("Cargo.toml").exists() {
+        runners.push(Runner::Cargo);
+    }
+
+    let script = std::fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|package| package["scripts"]["test"].as_str().map(String::from));
+    if script.is_some_and(|script| !script.contains("no test specified")) {
+        runners.push(Runner::Npm);
+    }
+
+    runners
+}
+
+fn has_python_files(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.path().extension().is_some_and(|ext| ext == "py"))
+    })
+}
+
+/// A failed test
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// Test name, as the runner shows it
+    pub test: String,
+    /// Where it failed: the innermost frame in the project's own code
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// The exception, panic or assertion message
+    pub message: String,
+    /// What a failed assertion compared, e.g. "left: 3\nright: 4"
+    pub comparison: Option<String>,
+    /// The runner's output for the test
+    pub output: String,
+}
+
+impl Failure {
+    fn new(test: &str, output: &str) -> Self {
+        Self {
+            test: test.to_string(),
+            file: None,
+            line: None,
+            column: None,
+            message: String::new(),
+            comparison: None,
+            output: output.trim_end().to_string(),
+        }
+    }
+
+    /// What the parser makes of the failure: a failed assertion, or the
+    /// error the test ran into, as if the code had been run directly
+    pub fn parsed(&self, language: Language) -> ParsedError {
+        let file = self.file.clone().unwrap_or_default();
+        let error_type = match &self.comparison {
+            Some(comparison) => ErrorType::AssertionFailed(comparison.clone()),
+            None => {
+                // Put the location the way the language's own errors do
+                let text = match language {
+                    Language::Python => format!(
+                        "File \"{}\", line {}\n{}",
+                        file,
+                        self.line.unwrap_or(1),
+                        self.message
+                    ),
+                    Language::JavaScript | Language::TypeScript => format!(
+                        "{}\n    at {}:{}",
+                        self.message,
+                        file,
+                        self.line.unwrap_or(1)
+                    ),
+                    _ => self.message.clone(),
+                };
+                parse_error(&text)
+                    .map(|parsed| parsed.error_type)
+                    .unwrap_or_else(|| ErrorType::Unknown(self.message.clone()))
+            }
+        };
+        ParsedError {
+            file,
+            line: self.line,
+            column: self.column,
+            end_line: None,
+            end_column: None,
+            message: self.message.clone(),
+            error_type,
+            language,
+        }
+    }
+
+    /// The failure as a scan finding, at the failing line when it's in the
+    /// project
+    pub fn finding(&self, runner: Runner, root: &Path) -> Finding {
+        let file = match &self.file {
+            Some(file) => root.join(file),
+            None => root.to_path_buf(),
+        };
+        let message = format!("Test {} failed: {}", self.test, self.message);
+        Finding::new(&file, Severity::Error, &message, runner.name())
+            .at(self.line, self.column)
+            .with_parsed(Some(self.parsed(runner.language())), root)
+            .with_explanation(&self.output)
+    }
+}
+
+/// Frames in installed packages, the standard library or the runtime
+fn is_dependency(file: &str) -> bool {
+    [
+        "node_modules",
+        "site-packages",
+        "/rustc/",
+        ".cargo/registry",
+    ]
+    .iter()
+    .any(|dir| file.contains(dir))
+        || file.starts_with("node:")
+        || file.starts_with('<')
+}
+
+/// Failures in `pytest -q --tb=short` output: the FAILURES and ERRORS
+/// sections, one traceback per test
+pub fn parse_pytest(output: &str) -> Vec<Failure> {
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut in_report = false;
+    for line in output.lines() {
+        if line.starts_with("====") {
+            in_report = line.contains(" FAILURES ") || line.contains(" ERRORS ");
+            continue;
+        }
+        if !in_report {
+            continue;
+        }
+        if let Some(cap) = regexes::PYTEST_SECTION.captures(line) {
+            sections.push((cap[1].to_string(), Vec::new()));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line);
+        }
+    }
+
+    sections
+        .into_iter()
+        .map(|(test, lines)| {
+            let mut failure = Failure::new(&test, &lines.join("\n"));
+            if let Some(frame) = lines
+                .iter()
+                .rev()
+                .filter_map(|line| regexes::PYTEST_FRAME.captures(line))
+                .find(|cap| !is_dependency(&cap[1]))
+            {
+                failure.file = Some(frame[1].to_string());
+                failure.line = frame[2].parse().ok();
+            }
+
+            let errors: Vec<&str> = lines
+                .iter()
+                .filter_map(|line| line.strip_prefix('E'))
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect();
+            failure.message = errors
+                .first()
+                .or_else(|| lines.iter().rev().find(|line| !line.trim().is_empty()))
+                .map(|line| line.trim().to_string())
+                .unwrap_or_default();
+            if failure.message.starts_with("assert ")
+                || failure.message.starts_with("AssertionError")
+            {
+                failure.comparison = Some(errors.join("\n"));
+            }
+            failure
+        })
+        .collect()
+}
+
+/// Failures in `cargo test` output: each failed test's captured output,
+/// with the panic that failed it
+pub fn parse_cargo_test(output: &str) -> Vec<Failure> {
+    let mut blocks: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in output.lines() {
+        if let Some(cap) = regexes::CARGO_TEST_HEADER.captures(line) {
+            blocks.push((cap[1].to_string(), Vec::new()));
+        } else if line == "failures:" || line.starts_with("test result:") {
+            // The list of failed test names follows
+            if let Some((_, lines)) = blocks.last_mut() {
+                lines.push("");
+            }
+            blocks.push((String::new(), Vec::new()));
+        } else if let Some((_, lines)) = blocks.last_mut() {
+            lines.push(line);
+        }
+    }
+
+    blocks
+        .into_iter()
+        .filter(|(test, _)| !test.is_empty())
+        .map(|(test, lines)| {
+            let mut failure = Failure::new(&test, &lines.join("\n"));
+            let message = match lines
+                .iter()
+                .position(|line| regexes::RUST_PANIC.is_match(line))
+            {
+                Some(start) => panic_message(&lines[start..], &mut failure),
+                None => lines
+                    .iter()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| line.trim().to_string())
+                    .collect(),
+            };
+
+            failure.message = message.first().cloned().unwrap_or_default();
+            if failure.message.starts_with("assertion") {
+                // assert_eq! and assert_ne! give the values on lines of
+                // their own, assert! gives the condition
+                let values: Vec<String> = message[1..]
+                    .iter()
+                    .filter(|line| line.starts_with("left") || line.starts_with("right"))
+                    .map(|line| line.replace('`', "").trim_end_matches(',').to_string())
+                    .collect();
+                failure.comparison = Some(if values.is_empty() {
+                    failure.message.clone()
+                } else {
+                    values.join("\n")
+                });
+            }
+            failure
+        })
+        .collect()
+}
+
+/// The lines of a panic message, setting the failure's location from it
+fn panic_message(lines: &[&str], failure: &mut Failure) -> Vec<String> {
+    let Some(cap) = regexes::RUST_PANIC.captures(lines[0]) else {
+        return Vec::new();
+    };
+    let mut message = Vec::new();
+    let location = if cap.get(1).is_some() {
+        message.extend(
+            lines[1..]
+                .iter()
+                .take_while(|line| {
+                    !line.is_empty()
+                        && !line.starts_with("note:")
+                        && !line.starts_with("stack backtrace:")
+                })
+                .map(|line| line.trim().to_string()),
+        );
+        Some((cap[1].to_string(), cap[2].to_string(), cap[3].to_string()))
+    } else {
+        // Before Rust 1.73 the message is quoted, and the location follows it
+        let first = cap[4].to_string();
+        let mut location = None;
+        for line in std::iter::once(first.as_str()).chain(lines[1..].iter().copied()) {
+            if let Some(end) = regexes::RUST_OLD_PANIC_END.captures(line) {
+                message.push(end[1].trim().to_string());
+                location = Some((end[2].to_string(), end[3].to_string(), end[4].to_string()));
+                break;
+            }
+            message.push(line.trim().to_string());
+        }
+        location
+    };
+
+    if let Some((file, line, column)) = location.filter(|(file, _, _)| !is_dependency(file)) {
+        failure.file = Some(file);
+        failure.line = line.parse().ok();
+        failure.column = column.parse().ok();
+    }
+    message
+}
+
+/// Failures in Jest output: a `●` title, the message, and a stack trace
+pub fn parse_jest(output: &str) -> Vec<Failure> {
+    let mut blocks: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in output.lines() {
+        if let Some(cap) = regexes::JEST_HEADER.captures(line) {
+            blocks.push((cap[1].trim().to_string(), Vec::new()));
+        } else if line.starts_with("Test Suites:")
+            || line.starts_with("PASS ")
+            || line.starts_with("FAIL ")
+        {
+            blocks.push((String::new(), Vec::new()));
+        } else if let Some((_, lines)) = blocks.last_mut() {
+            lines.push(line);
+        }
+    }
+
+    let mut failures: Vec<Failure> = Vec::new();
+    for (test, lines) in blocks.into_iter().filter(|(test, _)| !test.is_empty()) {
+        let mut failure = Failure::new(&test, &lines.join("\n"));
+        failure.message = lines
+            .iter()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string();
+        set_first_frame(&mut failure, lines.iter().copied());
+
+        let values: Vec<&str> = lines
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| line.starts_with("Expected") || line.starts_with("Received"))
+            .collect();
+        if failure.message.starts_with("expect(") || !values.is_empty() {
+            failure.comparison = Some(if values.is_empty() {
+                failure.message.clone()
+            } else {
+                values.join("\n")
+            });
+        }
+        // Jest repeats failures in its summary when several files fail
+        if !failures.contains(&failure) {
+            failures.push(failure);
+        }
+    }
+    failures
+}
+
+/// Failures in TAP output from `node --test`: `not ok` lines with a YAML
+/// block of details. Tests that failed only because a subtest did are
+/// left out, as the subtest is reported.
+pub fn parse_node_test(output: &str) -> Vec<Failure> {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut failures = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(cap) = regexes::TAP_NOT_OK.captures(line) else {
+            continue;
+        };
+        let indent = cap[1].len();
+        let block: Vec<&str> = lines[i + 1..]
+            .iter()
+            .skip_while(|line| line.trim() == "---")
+            .take_while(|line| line.trim() != "..." && !regexes::TAP_NOT_OK.is_match(line))
+            .copied()
+            .collect();
+        let fields = yaml_fields(&block, indent + 2);
+        if fields.get("failureType").map(String::as_str) == Some("subtestsFailed") {
+            continue;
+        }
+
+        let mut failure = Failure::new(&cap[2], &block.join("\n"));
+        let error = fields.get("error").map(String::as_str).unwrap_or_default();
+        let first = error
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default();
+        failure.message = match fields.get("name") {
+            Some(name) => format!("{}: {}", name, first),
+            None => first.to_string(),
+        };
+        let stack = fields.get("stack").map(String::as_str).unwrap_or_default();
+        let location = fields
+            .get("location")
+            .map(String::as_str)
+            .unwrap_or_default();
+        set_first_frame(&mut failure, stack.lines().chain(std::iter::once(location)));
+
+        let is_assertion = fields.get("name").map(String::as_str) == Some("AssertionError")
+            || fields.get("code").map(String::as_str) == Some("ERR_ASSERTION");
+        if is_assertion {
+            failure.comparison = Some(match (fields.get("expected"), fields.get("actual")) {
+                (Some(expected), Some(actual)) => {
+                    format!("expected: {}\nactual: {}", expected, actual)
+                }
+                _ => error
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            });
+        }
+        failures.push(failure);
+    }
+    failures
+}
+
+/// Set the failure's location from the first stack frame in the project
+fn set_first_frame<'a>(failure: &mut Failure, lines: impl Iterator<Item = &'a str>) {
+    let frame = lines
+        .filter_map(|line| regexes::JS_STACK_FRAME.captures(line))
+        .find(|cap| !is_dependency(&cap[1]));
+    if let Some(frame) = frame {
+        failure.file = Some(frame[1].to_string());
+        failure.line = frame[2].parse().ok();
+        failure.column = frame[3].parse().ok();
+    }
+}
+
+/// The `key: value` fields of a TAP YAML block whose keys are indented by
+/// `indent`, with `|-` block values joined and quotes removed
+fn yaml_fields(lines: &[&str], indent: usize) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let mut block: Option<(String, Vec<String>)> = None;
+    for line in lines {
+        let depth = line.len() - line.trim_start().len();
+        if let Some((_, values)) = block.as_mut() {
+            if depth > indent || line.trim().is_empty() {
+                values.push(line.get(indent + 2..).unwrap_or("").to_string());
+                continue;
+            }
+        }
+        if let Some((key, values)) = block.take() {
+            fields.insert(key, values.join("\n"));
+        }
+
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.starts_with('|') || value.starts_with('>') {
+            block = Some((key.to_string(), Vec::new()));
+        } else {
+            let value = value
+                .strip_prefix('\'')
+                .and_then(|v| v.strip_suffix('\''))
+                .unwrap_or(value);
+            fields.insert(key.to_string(), value.to_string());
+        }
+    }
+    if let Some((key, values)) = block {
+        fields.insert(key, values.join("\n"));
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTEST: &str = "\
+F.E
+==================================== ERRORS ====================================
+______________________ ERROR collecting tests/test_io.py _______________________
+tests/test_io.py:1: in <module>
+    import missing_helper
+E   ModuleNotFoundError: No module named 'missing_helper'
+=================================== FAILURES ===================================
+___________________________________ test_add ___________________________________
+tests/test_math.py:5: in test_add
+    assert add(1, 2) == 4
+E   assert 3 == 4
+E    +  where 3 = add(1, 2)
+___________________________________ test_name __________________________________
+tests/test_math.py:8: in test_name
+    greet()
+app/math.py:6: in greet
+    return nme
+E   NameError: name 'nme' is not defined
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_add - assert 3 == 4
+2 failed, 1 passed, 1 error in 0.03s
+";
+
+    #[test]
+    fn test_parse_pytest() {
+        let failures = parse_pytest(PYTEST);
+        assert_eq!(failures.len(), 3);
+
+        assert_eq!(failures[0].test, "ERROR collecting tests/test_io.py");
+        assert_eq!(
+            failures[0].message,
+            "ModuleNotFoundError: No module named 'missing_helper'"
+        );
+
+        let assertion = &failures[1];
+        assert_eq!(assertion.test, "test_add");
+        assert_eq!(assertion.file.as_deref(), Some("tests/test_math.py"));
+        assert_eq!(assertion.line, Some(5));
+        assert_eq!(
+            assertion.comparison.as_deref(),
+            Some("assert 3 == 4\n+  where 3 = add(1, 2)")
+        );
+
+        // The innermost frame is where the error happened
+        let error = &failures[2];
+        assert_eq!(error.file.as_deref(), Some("app/math.py"));
+        assert_eq!(error.line, Some(6));
+        assert_eq!(error.comparison, None);
+        assert_eq!(
+            error.parsed(Language::Python).error_type,
+            ErrorType::UndeclaredVariable("nme".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_cargo_test() {
+        let output = "\
+running 3 tests
+test tests::it_adds ... FAILED
+
+failures:
+
+---- tests::it_adds stdout ----
+
+thread 'tests::it_adds' (1374) panicked at src/lib.rs:6:20:
+assertion `left == right` failed
+  left: 3
+ right: 4
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- tests::it_unwraps stdout ----
+thread 'tests::it_unwraps' panicked at 'called `Option::unwrap()` on a `None` value', src/lib.rs:8:51
+
+---- tests::it_checks stdout ----
+thread 'tests::it_checks' panicked at 'assertion failed: `(left == right)`
+  left: `1`,
+ right: `2`', tests/api.rs:3:5
+
+
+failures:
+    tests::it_adds
+    tests::it_unwraps
+    tests::it_checks
+
+test result: FAILED. 0 passed; 3 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let failures = parse_cargo_test(output);
+        assert_eq!(failures.len(), 3);
+
+        assert_eq!(failures[0].test, "tests::it_adds");
+        assert_eq!(failures[0].file.as_deref(), Some("src/lib.rs"));
+        assert_eq!((failures[0].line, failures[0].column), (Some(6), Some(20)));
+        assert_eq!(failures[0].message, "assertion `left == right` failed");
+        assert_eq!(failures[0].comparison.as_deref(), Some("left: 3\nright: 4"));
+
+        assert_eq!(
+            failures[1].message,
+            "called `Option::unwrap()` on a `None` value"
+        );
+        assert_eq!(failures[1].line, Some(8));
+        assert_eq!(failures[1].comparison, None);
+
+        assert_eq!(failures[2].file.as_deref(), Some("tests/api.rs"));
+        assert_eq!(failures[2].line, Some(3));
+        assert_eq!(failures[2].comparison.as_deref(), Some("left: 1\nright: 2"));
+    }
+
+    #[test]
+    fn test_parse_jest() {
+        let output = "\
+FAIL src/math.test.js
+  ● math › adds numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 4
+    Received: 3
+
+      3 | test('adds numbers', () => {
+    > 4 |   expect(add(1, 2)).toBe(4);
+        |                     ^
+
+      at Object.toBe (src/math.test.js:4:21)
+
+  ● math › greets
+
+    ReferenceError: nme is not defined
+
+      at greet (src/math.js:6:10)
+      at Object.<anonymous> (src/math.test.js:8:5)
+
+Test Suites: 1 failed, 1 total
+";
+        let failures = parse_jest(output);
+        assert_eq!(failures.len(), 2);
+
+        assert_eq!(failures[0].test, "math › adds numbers");
+        assert_eq!(failures[0].file.as_deref(), Some("src/math.test.js"));
+        assert_eq!((failures[0].line, failures[0].column), (Some(4), Some(21)));
+        assert_eq!(
+            failures[0].comparison.as_deref(),
+            Some("Expected: 4\nReceived: 3")
+        );
+
+        assert_eq!(failures[1].file.as_deref(), Some("src/math.js"));
+        assert_eq!(
+            failures[1].parsed(Language::JavaScript).error_type,
+            ErrorType::UndeclaredVariable("nme".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_node_test() {
+        let output = "\
+TAP version 13
+# Subtest: adds
+not ok 1 - adds
+  ---
+  duration_ms: 2.6
+  location: '/app/math.test.js:4:1'
+  failureType: 'testCodeFailure'
+  error: |-
+    Expected values to be strictly equal:
+    
+    3 !== 4
+    
+  code: 'ERR_ASSERTION'
+  name: 'AssertionError'
+  expected: 4
+  actual: 3
+  operator: 'strictEqual'
+  stack: |-
+    TestContext.<anonymous> (/app/math.test.js:5:10)
+    Test.runInAsyncScope (node:async_hooks:206:9)
+  ...
+# Subtest: math
+    not ok 1 - boom
+      ---
+      location: '/app/math.test.js:8:3'
+      failureType: 'testCodeFailure'
+      error: 'undefinedThing is not defined'
+      name: 'ReferenceError'
+      stack: |-
+        exports.boom (/app/math.js:2:37)
+        TestContext.<anonymous> (/app/math.test.js:9:5)
+      ...
+not ok 2 - math
+  ---
+  location: '/app/math.test.js:7:1'
+  failureType: 'subtestsFailed'
+  error: '1 subtest failed'
+  ...
+ok 3 - ok
+";
+        let failures = parse_node_test(output);
+        assert_eq!(failures.len(), 2);
+
+        assert_eq!(failures[0].test, "adds");
+        assert_eq!(failures[0].file.as_deref(), Some("/app/math.test.js"));
+        assert_eq!(failures[0].line, Some(5));
+        assert_eq!(
+            failures[0].message,
+            "AssertionError: Expected values to be strictly equal:"
+        );
+        assert_eq!(
+            failures[0].comparison.as_deref(),
+            Some("expected: 4\nactual: 3")
+        );
+
+        assert_eq!(failures[1].test, "boom");
+        assert_eq!(failures[1].file.as_deref(), Some("/app/math.js"));
+        assert_eq!(failures[1].line, Some(2));
+        assert_eq!(
+            failures[1].message,
+            "ReferenceError: undefinedThing is not defined"
+        );
+    }
+
+    #[test]
+    fn test_failure_finding() {
+        let failure = &parse_pytest(PYTEST)[1];
+        let root = Path::new("/project");
+        let finding = failure.finding(Runner::Pytest, root);
+
+        assert_eq!(finding.file, root.join("tests/test_math.py"));
+        assert_eq!(finding.line, Some(5));
+        assert_eq!(finding.source, "pytest");
+        assert_eq!(finding.message, "Test test_add failed: assert 3 == 4");
+        assert_eq!(
+            finding.parsed.unwrap().error_type,
+            ErrorType::AssertionFailed("assert 3 == 4\n+  where 3 = add(1, 2)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let temp_dir = std::env::temp_dir().join("ess_test_testsuite_detect");
+        std::fs::create_dir_all(temp_dir.join("tests")).unwrap();
+        std::fs::write(
+            temp_dir.join("package.json"),
+            r#"{"scripts": {"test": "echo \"Error: no test specified\" && exit 1"}}"#,
+        )
+        .unwrap();
+        assert!(detect(&temp_dir).is_empty());
+
+        std::fs::write(temp_dir.join("tests").join("test_app.py"), "").unwrap();
+        std::fs::write(temp_dir.join("Cargo.toml"), "[package]\n").unwrap();
+        std::fs::write(
+            temp_dir.join("package.json"),
+            r#"{"scripts": {"test": "jest"}}"#,
+        )
+        .unwrap();
+        let runners = detect(&temp_dir);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(runners, vec![Runner::Pytest, Runner::Cargo, Runner::Npm]);
+    }
+}
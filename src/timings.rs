@@ -0,0 +1,232 @@
+use crate::ui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Maximum number of files listed in the breakdown
+const MAX_LISTED_FILES: usize = 10;
+
+/// How many times slower than average a tool or file must be to stand out
+const SLOW_FACTOR: u32 = 2;
+
+/// One timed step of a scan
+#[derive(Debug, Clone)]
+struct Timing {
+    tool: String,
+    file: Option<PathBuf>,
+    duration: Duration,
+}
+
+/// Time spent per tool and per file during a scan
+#[derive(Debug, Default)]
+pub struct Timings {
+    entries: Vec<Timing>,
+}
+
+impl Timings {
+    pub fn record(&mut self, tool: &str, file: Option<&Path>, duration: Duration) {
+        self.entries.push(Timing {
+            tool: tool.to_string(),
+            file: file.map(Path::to_path_buf),
+            duration,
+        });
+    }
+
+    /// Total time spent in checks
+    pub fn total(&self) -> Duration {
+        self.entries.iter().map(|t| t.duration).sum()
+    }
+
+    /// (tool, runs, total time), slowest first
+    pub fn by_tool(&self) -> Vec<(String, usize, Duration)> {
+        let mut tools: HashMap<&str, (usize, Duration)> = HashMap::new();
+        for timing in &self.entries {
+            let entry = tools.entry(&timing.tool).or_default();
+            entry.0 += 1;
+            entry.1 += timing.duration;
+        }
+
+        let mut tools: Vec<_> = tools
+            .into_iter()
+            .map(|(tool, (runs, duration))| (tool.to_string(), runs, duration))
+            .collect();
+        tools.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        tools
+    }
+
+    /// (file, total time over all tools), slowest first
+    pub fn by_file(&self) -> Vec<(PathBuf, Duration)> {
+        let mut files: HashMap<&Path, Duration> = HashMap::new();
+        for timing in &self.entries {
+            if let Some(file) = &timing.file {
+                *files.entry(file).or_default() += timing.duration;
+            }
+        }
+
+        let mut files: Vec<_> = files
+            .into_iter()
+            .map(|(file, duration)| (file.to_path_buf(), duration))
+            .collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        files
+    }
+
+    /// Print the breakdown, with paths relative to `root`
+    pub fn print(&self, root: &Path, elapsed: Duration) {
+        ui::print_section("Timings");
+
+        ui::print_info(&format!(
+            "Scan took {} ({} in checks)",
+            format_duration(elapsed),
+            format_duration(self.total())
+        ));
+
+        if self.entries.is_empty() {
+            ui::print_hint("No checks were run");
+            return;
+        }
+
+        println!();
+        println!("  Per tool:");
+        let tools = self.by_tool();
+        let slow = slow_threshold(tools.iter().map(|t| t.2));
+        for (tool, runs, duration) in tools {
+            let label = format!(
+                "{} ({} run{})",
+                tool,
+                runs,
+                if runs == 1 { "" } else { "s" }
+            );
+            ui::print_timing(&format_duration(duration), &label, is_slow(duration, slow));
+        }
+
+        let files = self.by_file();
+        if files.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("  Slowest files:");
+        let slow = slow_threshold(files.iter().map(|f| f.1));
+        for (file, duration) in files.iter().take(MAX_LISTED_FILES) {
+            let label = file
+                .strip_prefix(root)
+                .unwrap_or(file)
+                .display()
+                .to_string();
+            ui::print_timing(
+                &format_duration(*duration),
+                &label,
+                is_slow(*duration, slow),
+            );
+        }
+        if files.len() > MAX_LISTED_FILES {
+            println!("    … and {} more", files.len() - MAX_LISTED_FILES);
+        }
+
+        if files.iter().any(|(_, d)| is_slow(*d, slow)) {
+            println!();
+            ui::print_hint(
+                "Skip slow paths with --ignore <glob> or scan.ignore in .essentialscode.toml",
+            );
+        }
+    }
+}
+
+/// Durations at or above this stand out from the rest; `None` if there is nothing to compare
+fn slow_threshold(durations: impl Iterator<Item = Duration>) -> Option<Duration> {
+    let durations: Vec<Duration> = durations.collect();
+    if durations.len() < 2 {
+        return None;
+    }
+
+    let mean = durations.iter().sum::<Duration>() / durations.len() as u32;
+    Some(mean * SLOW_FACTOR)
+}
+
+fn is_slow(duration: Duration, threshold: Option<Duration>) -> bool {
+    threshold.is_some_and(|t| !duration.is_zero() && duration >= t)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Timings {
+        let mut timings = Timings::default();
+        timings.record(
+            "python",
+            Some(Path::new("a.py")),
+            Duration::from_millis(100),
+        );
+        timings.record(
+            "pylint",
+            Some(Path::new("a.py")),
+            Duration::from_millis(900),
+        );
+        timings.record("python", Some(Path::new("b.py")), Duration::from_millis(50));
+        timings.record("cargo check", None, Duration::from_millis(400));
+        timings
+    }
+
+    #[test]
+    fn test_by_tool_sums_runs() {
+        let tools = sample().by_tool();
+        assert_eq!(
+            tools[0],
+            ("pylint".to_string(), 1, Duration::from_millis(900))
+        );
+        assert_eq!(tools[1].0, "cargo check");
+        assert_eq!(
+            tools[2],
+            ("python".to_string(), 2, Duration::from_millis(150))
+        );
+    }
+
+    #[test]
+    fn test_by_file_skips_project_wide_tools() {
+        let files = sample().by_file();
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            files[0],
+            (PathBuf::from("a.py"), Duration::from_millis(1000))
+        );
+        assert_eq!(files[1], (PathBuf::from("b.py"), Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn test_total() {
+        assert_eq!(sample().total(), Duration::from_millis(1450));
+        assert_eq!(Timings::default().total(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_slow_outliers() {
+        let ms = Duration::from_millis;
+        let durations = [ms(900), ms(50), ms(60), ms(40)];
+        let threshold = slow_threshold(durations.into_iter());
+
+        assert!(is_slow(ms(900), threshold));
+        assert!(!is_slow(ms(60), threshold));
+        // Nothing stands out when there is nothing to compare against
+        assert_eq!(slow_threshold([ms(900)].into_iter()), None);
+        // Equally slow files are not outliers
+        let even = slow_threshold([ms(100), ms(100)].into_iter());
+        assert!(!is_slow(ms(100), even));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(42)), "42ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.50s");
+    }
+}
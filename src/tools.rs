@@ -0,0 +1,640 @@
+use crate::config::CppConfig;
+use crate::encoding;
+use crate::locale;
+use crate::unparsed;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A program to run, with arguments it always needs (e.g. `py -3`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpreter {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Interpreter {
+    /// Parse a command line like "py -3" or "/usr/bin/python3.12"
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut parts = command.split_whitespace().map(String::from);
+        Some(Self {
+            program: parts.next()?,
+            args: parts.collect(),
+        })
+    }
+
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        cmd
+    }
+}
+
+impl std::fmt::Display for Interpreter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Python interpreters to try, in order: the configured one, then the usual
+/// names on Linux/macOS and the Windows launcher
+pub fn python_candidates(configured: Option<&str>) -> Vec<Interpreter> {
+    configured
+        .into_iter()
+        .chain(["python3", "python", "py -3"])
+        .filter_map(Interpreter::parse)
+        .collect()
+}
+
+/// First candidate that actually runs Python 3. A `python` that is only a
+/// shim or store stub fails `--version` and is passed over.
+pub fn resolve_python(configured: Option<&str>) -> Option<Interpreter> {
+    python_candidates(configured)
+        .into_iter()
+        .find(|python| version(&mut python.command()).is_some_and(|v| v.starts_with("Python 3")))
+}
+
+/// The Python interpreter for this run, resolved once
+pub fn python(configured: Option<&str>) -> Option<Interpreter> {
+    static PYTHON: OnceLock<Option<Interpreter>> = OnceLock::new();
+    PYTHON.get_or_init(|| resolve_python(configured)).clone()
+}
+
+/// C++ compiler families, which take different flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CppCompiler {
+    Gcc,
+    Clang,
+    /// Microsoft's cl.exe, from a Visual Studio developer prompt
+    Msvc,
+}
+
+impl CppCompiler {
+    pub fn program(self) -> &'static str {
+        match self {
+            CppCompiler::Gcc => "g++",
+            CppCompiler::Clang => "clang++",
+            CppCompiler::Msvc => "cl",
+        }
+    }
+
+    /// Family of a compiler from its program name, e.g. "aarch64-linux-gnu-g++"
+    pub fn from_program(program: &str) -> Self {
+        let name = Path::new(program)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.contains("clang") {
+            CppCompiler::Clang
+        } else if name == "cl" {
+            CppCompiler::Msvc
+        } else {
+            CppCompiler::Gcc
+        }
+    }
+
+    /// Flags for checking one file without compiling it
+    pub fn syntax_check_args(self) -> &'static [&'static str] {
+        match self {
+            // `-x c++` / `/TP` so files mapped to C++ in [files] compile
+            // whatever their extension
+            CppCompiler::Gcc | CppCompiler::Clang => {
+                &["-std=c++17", "-Wall", "-fsyntax-only", "-x", "c++"]
+            }
+            CppCompiler::Msvc => &[
+                "/nologo",
+                "/TP",
+                "/std:c++17",
+                "/W3",
+                "/EHsc",
+                "/Zs",
+                "/diagnostics:column",
+            ],
+        }
+    }
+
+    /// Flags that make the compiler treat the next file as a header, if it
+    /// has them; MSVC needs a source file that includes the header instead
+    pub fn header_args(self) -> Option<&'static [&'static str]> {
+        match self {
+            CppCompiler::Gcc | CppCompiler::Clang => Some(&["-x", "c++-header"]),
+            CppCompiler::Msvc => None,
+        }
+    }
+
+    pub fn include_arg(self, dir: &Path) -> String {
+        match self {
+            CppCompiler::Msvc => format!("/I{}", dir.display()),
+            _ => format!("-I{}", dir.display()),
+        }
+    }
+
+    /// Where the compiler writes its errors
+    pub fn diagnostics(self, output: &Output) -> String {
+        let bytes = match self {
+            CppCompiler::Msvc => &output.stdout,
+            _ => &output.stderr,
+        };
+        String::from_utf8_lossy(bytes).to_string()
+    }
+}
+
+/// A C++ compiler to run, set up for the configured target
+#[derive(Debug, Clone, PartialEq)]
+pub struct CppToolchain {
+    pub compiler: CppCompiler,
+    pub program: String,
+    /// Target selection and `cpp.flags`, passed before any other argument
+    pub args: Vec<String>,
+}
+
+impl CppToolchain {
+    fn new(compiler: CppCompiler, program: &str, args: Vec<String>) -> Self {
+        Self {
+            compiler,
+            program: program.to_string(),
+            args,
+        }
+    }
+
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        cmd
+    }
+
+    pub fn version(&self) -> Option<String> {
+        match self.compiler {
+            // cl has no --version; it prints its banner when run without arguments
+            CppCompiler::Msvc => {
+                let output = Command::new(&self.program).output().ok()?;
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .map(str::trim)
+                    .find(|l| !l.is_empty())
+                    .map(String::from)
+            }
+            _ => version(&mut Command::new(&self.program)),
+        }
+    }
+}
+
+impl std::fmt::Display for CppToolchain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compilers to try, in order. Without configuration that is g++, clang++
+/// and (on Windows) cl. A target picks its cross compiler `<target>-g++`,
+/// or clang++, which can target anything it was built for.
+pub fn cpp_candidates(config: &CppConfig) -> Vec<CppToolchain> {
+    let with_target = |target: &str| {
+        let mut args = vec![format!("--target={}", target)];
+        args.extend(config.flags.iter().cloned());
+        args
+    };
+
+    if let Some(program) = &config.compiler {
+        let compiler = CppCompiler::from_program(program);
+        let args = match (&config.target, compiler) {
+            (Some(target), CppCompiler::Clang) => with_target(target),
+            _ => config.flags.clone(),
+        };
+        return vec![CppToolchain::new(compiler, program, args)];
+    }
+
+    if let Some(target) = &config.target {
+        return vec![
+            CppToolchain::new(
+                CppCompiler::Gcc,
+                &format!("{}-g++", target),
+                config.flags.clone(),
+            ),
+            CppToolchain::new(CppCompiler::Clang, "clang++", with_target(target)),
+        ];
+    }
+
+    let mut compilers = vec![CppCompiler::Gcc, CppCompiler::Clang];
+    if cfg!(windows) {
+        compilers.push(CppCompiler::Msvc);
+    }
+    compilers
+        .into_iter()
+        .map(|c| CppToolchain::new(c, c.program(), config.flags.clone()))
+        .collect()
+}
+
+/// Names of the compilers to try, for messages like "g++ or clang++ not found"
+pub fn cpp_compiler_names(config: &CppConfig) -> String {
+    let names: Vec<String> = cpp_candidates(config)
+        .into_iter()
+        .map(|c| c.program)
+        .collect();
+    match names.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+/// The first C++ compiler candidate that runs. Finding it runs the
+/// candidates, so the answer is kept for the life of the process, which
+/// for `ess daemon` is many scans.
+pub fn cpp_compiler(config: &CppConfig) -> Option<CppToolchain> {
+    static FOUND: Mutex<BTreeMap<String, Option<CppToolchain>>> = Mutex::new(BTreeMap::new());
+    let key = format!("{:?}", config);
+    if let Some(found) = FOUND.lock().unwrap().get(&key) {
+        return found.clone();
+    }
+    let found = cpp_candidates(config)
+        .into_iter()
+        .find(|toolchain| toolchain.version().is_some());
+    FOUND.lock().unwrap().insert(key, found.clone());
+    found
+}
+
+/// First line of `<cmd> --version`, if the program runs
+pub fn version(cmd: &mut Command) -> Option<String> {
+    let output = cmd.arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Older Pythons print the version to stderr
+    let text = if output.stdout.is_empty() {
+        String::from_utf8_lossy(&output.stderr).to_string()
+    } else {
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(String::from)
+}
+
+/// Environment that makes tools print English messages in a known
+/// encoding, for `scan.english_output`
+pub const ENGLISH_ENV: &[(&str, &str)] = &[
+    // gcc, clang and other gettext programs
+    ("LC_ALL", "C"),
+    // MSVC
+    ("VSLANG", "1033"),
+    ("DOTNET_CLI_UI_LANGUAGE", "en"),
+    ("PYTHONIOENCODING", "utf-8"),
+];
+
+/// A process that ran to its end or was stopped
+pub struct Finished {
+    pub output: Output,
+    pub timed_out: bool,
+    /// Bytes of output dropped to stay within the cap
+    pub truncated: u64,
+    /// Error lines in the output that was dropped
+    pub dropped_error_lines: Vec<String>,
+}
+
+/// Run `cmd` like `Command::output`, killing it once `limit` has passed.
+/// A killed process's output is dropped, as processes it started may
+/// still hold the pipes open. Of output over `cap` bytes a stream, only
+/// the start and the end are kept. `input` is written to its stdin.
+pub fn output_within(
+    cmd: &mut Command,
+    input: &[u8],
+    limit: Option<Duration>,
+    cap: Option<usize>,
+) -> std::io::Result<Finished> {
+    let mut child = cmd
+        .stdin(if input.is_empty() {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_vec();
+        // A program that doesn't read all of it only closes the pipe
+        thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let stdout = read_pipe(child.stdout.take(), cap);
+    let stderr = read_pipe(child.stderr.take(), cap);
+
+    let status = match limit {
+        None => child.wait()?,
+        Some(limit) => {
+            let deadline = Instant::now() + limit;
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                if Instant::now() >= deadline {
+                    let _ = child.kill();
+                    let output = Output {
+                        status: child.wait()?,
+                        stdout: Vec::new(),
+                        stderr: Vec::new(),
+                    };
+                    return Ok(Finished {
+                        output,
+                        timed_out: true,
+                        truncated: 0,
+                        dropped_error_lines: Vec::new(),
+                    });
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(Finished {
+        truncated: stdout.dropped + stderr.dropped,
+        dropped_error_lines: [stdout.error_lines, stderr.error_lines].concat(),
+        output: Output {
+            status,
+            stdout: readable(stdout.bytes),
+            stderr: readable(stderr.bytes),
+        },
+        timed_out: false,
+    })
+}
+
+/// Output as UTF-8, with localized compiler messages in English
+fn readable(bytes: Vec<u8>) -> Vec<u8> {
+    let text = String::from_utf8(encoding::to_utf8(bytes)).unwrap_or_default();
+    match locale::to_english(&text) {
+        Cow::Borrowed(_) => text.into_bytes(),
+        Cow::Owned(english) => english.into_bytes(),
+    }
+}
+
+/// Error lines kept from the dropped part of one stream
+const MAX_DROPPED_ERROR_LINES: usize = 200;
+
+/// What was kept of a stream
+#[derive(Debug, Default)]
+struct Captured {
+    bytes: Vec<u8>,
+    dropped: u64,
+    error_lines: Vec<String>,
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>, cap: Option<usize>) -> JoinHandle<Captured> {
+    thread::spawn(move || match pipe {
+        Some(pipe) => capture(pipe, cap),
+        None => Captured::default(),
+    })
+}
+
+/// Read `pipe` to its end, keeping the first and last half of `cap` bytes.
+/// The middle is read and dropped as it goes, so a program printing
+/// gigabytes doesn't fill memory, and error lines in it are picked out on
+/// the way.
+fn capture(mut pipe: impl Read, cap: Option<usize>) -> Captured {
+    let mut captured = Captured::default();
+    let Some(cap) = cap else {
+        let _ = pipe.read_to_end(&mut captured.bytes);
+        return captured;
+    };
+
+    let (head, tail_cap) = (cap / 2, cap - cap / 2);
+    let mut tail = VecDeque::new();
+    // The start of the dropped line that is still being read
+    let mut line = Vec::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        let read = match pipe.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        let mut data = &chunk[..read];
+        if captured.bytes.len() < head {
+            let kept = (head - captured.bytes.len()).min(data.len());
+            captured.bytes.extend_from_slice(&data[..kept]);
+            data = &data[kept..];
+        }
+        tail.extend(data);
+        if tail.len() <= tail_cap {
+            continue;
+        }
+
+        let evicted: Vec<u8> = tail.drain(..tail.len() - tail_cap).collect();
+        captured.dropped += evicted.len() as u64;
+        let mut parts = evicted.split(|b| *b == b'\n').peekable();
+        while let Some(part) = parts.next() {
+            line.extend_from_slice(part);
+            // The last part has no newline yet, so the line goes on
+            if parts.peek().is_none() {
+                break;
+            }
+            if captured.error_lines.len() < MAX_DROPPED_ERROR_LINES {
+                let text = String::from_utf8_lossy(&line);
+                captured
+                    .error_lines
+                    .extend(unparsed::error_lines(&text).map(String::from));
+            }
+            line.clear();
+        }
+    }
+
+    if captured.dropped > 0 {
+        captured.bytes.extend_from_slice(
+            format!(
+                "\n[... {} bytes of output dropped by ess ...]\n",
+                captured.dropped
+            )
+            .as_bytes(),
+        );
+    }
+    captured.bytes.extend(tail);
+    captured
+}
+
+/// How to get a missing tool
+pub fn install_hint(tool: &str) -> &'static str {
+    match tool {
+        t if t.contains("-g++") => {
+            "Install the cross compiler for the target (e.g. 'apt install g++-aarch64-linux-gnu') or clang++"
+        }
+        t if t.starts_with("g++") || t == "clang++" || t == "cl" => {
+            "Install a C++ compiler: 'apt install g++', 'brew install gcc' or 'xcode-select --install'; MSYS2 or Visual Studio on Windows"
+        }
+        "python" => "Install Python 3 from https://www.python.org/downloads/ or with your package manager, or set languages.python",
+        "pylint" => "Install pylint with 'python3 -m pip install pylint'",
+        "pytest" => "Install pytest with 'python3 -m pip install pytest'",
+        "node" | "npx" | "npm" => "Install Node.js (includes npm and npx) from https://nodejs.org/",
+        "cargo" => "Install Rust with rustup: https://rustup.rs/",
+        t if t.ends_with(" target") => "Add the Rust target with 'rustup target add <target>'",
+        "cargo-audit" => "Install it with 'cargo install cargo-audit'",
+        "cargo-outdated" => "Install it with 'cargo install cargo-outdated'",
+        "pip" => "Install pip with 'python3 -m ensurepip --upgrade'",
+        "rustfmt" => "Install it with 'rustup component add rustfmt'",
+        "javac" => "Install a JDK (e.g. from https://adoptium.net/) and make sure its bin directory is on your PATH",
+        "mvn" => "Install Maven from https://maven.apache.org/ or with your package manager",
+        "gradle" => "Install Gradle from https://gradle.org/install/, or add the Gradle wrapper (gradlew) to the project",
+        "dotnet" => "Install the .NET SDK from https://dotnet.microsoft.com/download",
+        "a .csproj project" => "Create one with 'dotnet new console', or scan the directory that has it",
+        _ => "Install it and make sure it is on your PATH",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_within_stops_slow_processes() {
+        let started = Instant::now();
+        let slow = output_within(
+            Command::new("sleep").arg("5"),
+            &[],
+            Some(Duration::from_millis(100)),
+            None,
+        )
+        .unwrap();
+        assert!(slow.timed_out);
+        assert!(!slow.output.status.success());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let quick = output_within(
+            Command::new("echo").arg("hi"),
+            &[],
+            Some(Duration::from_secs(5)),
+            None,
+        )
+        .unwrap();
+        assert!(!quick.timed_out);
+        assert_eq!(quick.output.stdout, b"hi\n");
+
+        let echoed = output_within(&mut Command::new("cat"), b"from stdin", None, None).unwrap();
+        assert_eq!(echoed.output.stdout, b"from stdin");
+    }
+
+    #[test]
+    fn test_capture_keeps_start_and_end() {
+        let output = format!(
+            "first\n{}error: lost in the middle\n{}last\n",
+            "x\n".repeat(50),
+            "y\n".repeat(50)
+        );
+        let captured = capture(output.as_bytes(), Some(20));
+        let kept = String::from_utf8(captured.bytes).unwrap();
+
+        assert!(kept.starts_with("first\nx\nx"));
+        assert!(kept.ends_with("y\ny\nlast\n"));
+        assert!(kept.contains("bytes of output dropped by ess"));
+        assert_eq!(captured.dropped as usize, output.len() - 20);
+        assert_eq!(captured.error_lines, vec!["error: lost in the middle"]);
+
+        let whole = capture("short\n".as_bytes(), Some(20));
+        assert_eq!(whole.bytes, b"short\n");
+        assert_eq!(whole.dropped, 0);
+    }
+
+    #[test]
+    fn test_parse_interpreter() {
+        assert_eq!(
+            Interpreter::parse("py -3"),
+            Some(Interpreter {
+                program: "py".to_string(),
+                args: vec!["-3".to_string()],
+            })
+        );
+        assert_eq!(Interpreter::parse("  "), None);
+        assert_eq!(
+            Interpreter::parse("/usr/bin/python3.12")
+                .unwrap()
+                .to_string(),
+            "/usr/bin/python3.12"
+        );
+    }
+
+    #[test]
+    fn test_python_candidates_order() {
+        let names: Vec<String> = python_candidates(Some("/opt/py/bin/python"))
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["/opt/py/bin/python", "python3", "python", "py -3"]
+        );
+        assert_eq!(python_candidates(None).len(), 3);
+    }
+
+    #[test]
+    fn test_missing_configured_python_is_passed_over() {
+        let resolved = resolve_python(Some("ess-test-no-such-python"));
+        assert!(resolved.is_none_or(|p| p.program != "ess-test-no-such-python"));
+    }
+
+    #[test]
+    fn test_cpp_compiler_flags() {
+        assert!(CppCompiler::Gcc
+            .syntax_check_args()
+            .contains(&"-fsyntax-only"));
+        assert!(CppCompiler::Msvc.syntax_check_args().contains(&"/Zs"));
+        let expected = if cfg!(windows) {
+            "g++, clang++ or cl"
+        } else {
+            "g++ or clang++"
+        };
+        assert_eq!(cpp_compiler_names(&CppConfig::default()), expected);
+    }
+
+    #[test]
+    fn test_cpp_candidates_for_target() {
+        let config = CppConfig {
+            target: Some("aarch64-linux-gnu".to_string()),
+            flags: vec!["--sysroot=/opt/arm".to_string()],
+            ..Default::default()
+        };
+        let candidates: Vec<String> = cpp_candidates(&config)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            candidates,
+            vec![
+                "aarch64-linux-gnu-g++ --sysroot=/opt/arm",
+                "clang++ --target=aarch64-linux-gnu --sysroot=/opt/arm"
+            ]
+        );
+
+        let config = CppConfig {
+            compiler: Some("/opt/llvm/bin/clang++-17".to_string()),
+            target: Some("wasm32-wasi".to_string()),
+            ..Default::default()
+        };
+        let candidates = cpp_candidates(&config);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].compiler, CppCompiler::Clang);
+        assert_eq!(candidates[0].args, vec!["--target=wasm32-wasi"]);
+    }
+
+    #[test]
+    fn test_version_of_missing_tool() {
+        assert!(version(&mut Command::new("ess-test-no-such-tool")).is_none());
+    }
+}
@@ -1,4 +1,17 @@
+use crate::config::{DiffStyle, OutputConfig, PatternConfig};
+use crate::diff;
+use crate::finding::{RuleCount, Severity};
+use crate::highlight::{self, Token};
+use crate::parser::Language;
+use crate::redact::Redactor;
+use crate::regexes;
+use crate::rules;
 use owo_colors::OwoColorize;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use unicode_width::UnicodeWidthChar;
 
 const GRADIENT_START: (u8, u8, u8) = (255, 240, 181); // #FFF0B5
 const GRADIENT_END: (u8, u8, u8) = (134, 69, 199); // #8645C7
@@ -8,7 +21,137 @@ const WARNING: (u8, u8, u8) = (251, 191, 36); // Amber
 const INFO: (u8, u8, u8) = (147, 197, 253); // Blue
 const DIM: (u8, u8, u8) = (148, 163, 184); // Gray
 
+/// Tab stops used when showing source code
+const TAB_WIDTH: usize = 4;
+
+/// Tab stops gcc uses when counting columns
+const GCC_TAB_WIDTH: usize = 8;
+
+static SHOW_HINTS: AtomicBool = AtomicBool::new(true);
+static SHOW_DIFFS: AtomicBool = AtomicBool::new(true);
+static SHOW_SECTIONS: AtomicBool = AtomicBool::new(true);
+static MAX_INSTRUCTION_LINES: AtomicUsize = AtomicUsize::new(0);
+static SIDE_BY_SIDE: AtomicBool = AtomicBool::new(false);
+static HYPERLINKS: AtomicBool = AtomicBool::new(false);
+static HYPERLINK_URL: RwLock<Option<String>> = RwLock::new(None);
+static QUIET: AtomicBool = AtomicBool::new(false);
+static COLORS: AtomicBool = AtomicBool::new(true);
+static REDACTOR: RwLock<Option<Redactor>> = RwLock::new(None);
+static BANNER: RwLock<Option<String>> = RwLock::new(None);
+/// The line above and below section headers; None until configured
+static SECTION_RULE: RwLock<Option<String>> = RwLock::new(None);
+
+/// `println!` that prints nothing while output is turned off, and leaves
+/// out colors when they are
+macro_rules! say {
+    () => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!();
+        }
+    };
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!("{}", plain(format!($($arg)*)));
+        }
+    };
+}
+
+/// Text without color codes, when colors are off, and redacted when
+/// terminal output is
+fn plain(text: String) -> String {
+    let text = match REDACTOR.read().as_deref() {
+        Ok(Some(redactor)) => {
+            let redacted = redactor.apply(&text);
+            // Colors can split what should be taken out; such lines lose them
+            let bare = regexes::ANSI_COLOR.replace_all(&redacted, "");
+            match redactor.apply(&bare) {
+                again if again != bare => again,
+                _ => redacted,
+            }
+        }
+        _ => text,
+    };
+    if COLORS.load(Ordering::Relaxed) {
+        return text;
+    }
+    regexes::ANSI_COLOR.replace_all(&text, "").to_string()
+}
+
+/// Link target used when `output.hyperlink_url` is not set
+const DEFAULT_HYPERLINK_URL: &str = "file://{path}";
+
+/// Narrowest terminal that still gets side-by-side diffs
+const SIDE_BY_SIDE_MIN_WIDTH: usize = 100;
+
+/// Apply the `[output]` settings to everything printed from now on
+pub fn configure(output: &OutputConfig) {
+    // https://no-color.org
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    COLORS.store(output.colors && !no_color, Ordering::Relaxed);
+    SHOW_HINTS.store(output.show_hints, Ordering::Relaxed);
+    SHOW_DIFFS.store(output.show_diffs, Ordering::Relaxed);
+    SHOW_SECTIONS.store(output.show_sections, Ordering::Relaxed);
+    MAX_INSTRUCTION_LINES.store(output.max_instruction_lines, Ordering::Relaxed);
+    SIDE_BY_SIDE.store(
+        output.diff_style == DiffStyle::SideBySide,
+        Ordering::Relaxed,
+    );
+    HYPERLINKS.store(
+        output.hyperlinks && std::io::stdout().is_terminal(),
+        Ordering::Relaxed,
+    );
+    if let Ok(mut url) = HYPERLINK_URL.write() {
+        url.clone_from(&output.hyperlink_url);
+    }
+    if let Ok(mut banner) = BANNER.write() {
+        banner.clone_from(&output.banner);
+    }
+    if let Ok(mut rule) = SECTION_RULE.write() {
+        *rule = Some(section_rule(
+            &output.section_separator,
+            output.section_width,
+        ));
+    }
+}
+
+/// `separator` repeated to `width` characters
+fn section_rule(separator: &str, width: usize) -> String {
+    separator.chars().cycle().take(width).collect()
+}
+
+/// Redact everything printed from now on with `redactor`, or stop
+pub fn redact(redactor: Option<Redactor>) {
+    if let Ok(mut current) = REDACTOR.write() {
+        *current = redactor;
+    }
+}
+
+/// Turns output off until dropped, when `quiet` is set
+pub struct Quiet(bool);
+
+pub fn quiet(quiet: bool) -> Quiet {
+    Quiet(QUIET.fetch_or(quiet, Ordering::Relaxed))
+}
+
+impl Drop for Quiet {
+    fn drop(&mut self) {
+        QUIET.store(self.0, Ordering::Relaxed);
+    }
+}
+
+pub fn print_blank_line() {
+    say!();
+}
+
 pub fn print_banner() {
+    if let Ok(Some(custom)) = BANNER.read().as_deref() {
+        if !custom.is_empty() {
+            print_gradient(custom);
+            say!();
+        }
+        return;
+    }
+
     let banner = r#"
     ╔═══════════════════════════════════════════════════════════════╗
     ║                                                               ║
@@ -23,7 +166,7 @@ pub fn print_banner() {
 "#;
 
     print_gradient(banner);
-    println!();
+    say!();
 }
 
 pub fn print_gradient(text: &str) {
@@ -35,7 +178,7 @@ pub fn print_gradient(text: &str) {
         let r = lerp(GRADIENT_START.0, GRADIENT_END.0, t);
         let g = lerp(GRADIENT_START.1, GRADIENT_END.1, t);
         let b = lerp(GRADIENT_START.2, GRADIENT_END.2, t);
-        println!("{}", line.truecolor(r, g, b));
+        say!("{}", line.truecolor(r, g, b));
     }
 }
 
@@ -44,21 +187,31 @@ fn lerp(a: u8, b: u8, t: f32) -> u8 {
 }
 
 pub fn print_section(title: &str) {
-    println!();
-    let line = "─".repeat(60);
-    println!("{}", line.truecolor(DIM.0, DIM.1, DIM.2));
-    println!(
+    if !SHOW_SECTIONS.load(Ordering::Relaxed) {
+        return;
+    }
+
+    say!();
+    let line = match SECTION_RULE.read().as_deref() {
+        Ok(Some(rule)) => rule.clone(),
+        _ => "─".repeat(60),
+    };
+    if !line.is_empty() {
+        say!("{}", line.truecolor(DIM.0, DIM.1, DIM.2));
+    }
+    say!(
         "  {}",
         title
             .truecolor(GRADIENT_END.0, GRADIENT_END.1, GRADIENT_END.2)
             .bold()
     );
-    println!("{}", line.truecolor(DIM.0, DIM.1, DIM.2));
+    if !line.is_empty() {
+        say!("{}", line.truecolor(DIM.0, DIM.1, DIM.2));
+    }
 }
 
-#[allow(dead_code)]
 pub fn print_success(msg: &str) {
-    println!(
+    say!(
         "  {} {}",
         "✓".truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2).bold(),
         msg.truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2)
@@ -66,7 +219,7 @@ pub fn print_success(msg: &str) {
 }
 
 pub fn print_error(msg: &str) {
-    println!(
+    say!(
         "  {} {}",
         "✗".truecolor(ERROR.0, ERROR.1, ERROR.2).bold(),
         msg.truecolor(ERROR.0, ERROR.1, ERROR.2)
@@ -74,15 +227,30 @@ pub fn print_error(msg: &str) {
 }
 
 pub fn print_warning(msg: &str) {
-    println!(
+    say!(
         "  {} {}",
         "⚠".truecolor(WARNING.0, WARNING.1, WARNING.2).bold(),
         msg.truecolor(WARNING.0, WARNING.1, WARNING.2)
     );
 }
 
+/// A warning on stderr, for things that happen while stdout carries a
+/// report for another tool
+pub fn eprint_warning(msg: &str) {
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!(
+            "{}",
+            plain(format!(
+                "  {} {}",
+                "⚠".truecolor(WARNING.0, WARNING.1, WARNING.2).bold(),
+                msg.truecolor(WARNING.0, WARNING.1, WARNING.2)
+            ))
+        );
+    }
+}
+
 pub fn print_info(msg: &str) {
-    println!(
+    say!(
         "  {} {}",
         "→".truecolor(INFO.0, INFO.1, INFO.2).bold(),
         msg.truecolor(INFO.0, INFO.1, INFO.2)
@@ -90,132 +258,729 @@ pub fn print_info(msg: &str) {
 }
 
 pub fn print_hint(msg: &str) {
-    println!(
+    if !SHOW_HINTS.load(Ordering::Relaxed) {
+        return;
+    }
+
+    say!(
         "  {} {}",
         "💡".truecolor(DIM.0, DIM.1, DIM.2),
         msg.truecolor(DIM.0, DIM.1, DIM.2)
     );
 }
 
+pub fn print_prompt(msg: &str) {
+    use std::io::Write;
+
+    if QUIET.load(Ordering::Relaxed) {
+        return;
+    }
+    print!(
+        "{}",
+        plain(format!("  {}", msg.truecolor(INFO.0, INFO.1, INFO.2)))
+    );
+    let _ = std::io::stdout().flush();
+}
+
 pub fn print_file_location(file: &str, line: Option<u32>, col: Option<u32>) {
     let location = match (line, col) {
         (Some(l), Some(c)) => format!("{}:{}:{}", file, l, c),
         (Some(l), None) => format!("{}:{}", file, l),
         _ => file.to_string(),
     };
-    println!(
-        "  {} {}",
+    let location = location.truecolor(INFO.0, INFO.1, INFO.2).to_string();
+    let location = link_location(&location, file, line, col);
+
+    say!("  {} {}", "📄".truecolor(DIM.0, DIM.1, DIM.2), location);
+}
+
+/// Header above the findings of one file in the grouped view
+pub fn print_file_header(file: &str, display: &str, errors: usize, warnings: usize) {
+    let mut counts = Vec::new();
+    if errors > 0 {
+        counts.push(format!(
+            "{} error{}",
+            errors,
+            if errors == 1 { "" } else { "s" }
+        ));
+    }
+    if warnings > 0 {
+        counts.push(format!(
+            "{} warning{}",
+            warnings,
+            if warnings == 1 { "" } else { "s" }
+        ));
+    }
+
+    let name = display.truecolor(INFO.0, INFO.1, INFO.2).bold().to_string();
+    say!();
+    say!(
+        "  {} {}  {}",
         "📄".truecolor(DIM.0, DIM.1, DIM.2),
-        location.truecolor(INFO.0, INFO.1, INFO.2)
+        link_location(&name, file, None, None),
+        counts.join(", ").truecolor(DIM.0, DIM.1, DIM.2)
+    );
+}
+
+/// A finding reported as soon as a check found it
+pub fn print_finding(severity: Severity, location: &str, message: &str, source: &str) {
+    let (icon, color) = severity_style(severity);
+    say!(
+        "  {} {} {}  {}",
+        icon.truecolor(color.0, color.1, color.2).bold(),
+        location.truecolor(INFO.0, INFO.1, INFO.2),
+        message.truecolor(color.0, color.1, color.2),
+        source.truecolor(DIM.0, DIM.1, DIM.2)
     );
 }
 
-#[allow(dead_code)]
+/// A finding in `ess pick`'s list, cut to `width` columns. Returned
+/// rather than printed, as the picker draws in raw mode.
+pub fn picker_row(severity: Severity, text: &str, selected: bool, width: usize) -> String {
+    let (icon, color) = severity_style(severity);
+    let segment = diff::Segment {
+        text: text.to_string(),
+        emphasized: false,
+    };
+    let text: String = fit_segments(&[segment], width.saturating_sub(5))
+        .into_iter()
+        .map(|s| s.text)
+        .collect();
+    let text = text.trim_end();
+    plain(if selected {
+        format!(
+            "{} {} {}",
+            "›".truecolor(INFO.0, INFO.1, INFO.2).bold(),
+            icon.truecolor(color.0, color.1, color.2).bold(),
+            text.bold()
+        )
+    } else {
+        format!(
+            "  {} {}",
+            icon.truecolor(color.0, color.1, color.2),
+            text.truecolor(DIM.0, DIM.1, DIM.2)
+        )
+    })
+}
+
+/// The query line of `ess pick`, with how many findings match
+pub fn picker_prompt(query: &str, matches: usize, total: usize) -> String {
+    plain(format!(
+        "{} {}  {}",
+        ">".truecolor(INFO.0, INFO.1, INFO.2).bold(),
+        query,
+        format!("{}/{}", matches, total).truecolor(DIM.0, DIM.1, DIM.2)
+    ))
+}
+
+/// Another location of a finding, under it
+pub fn print_related(location: &str, message: &str) {
+    say!(
+        "        {} {} {}",
+        "↳".truecolor(DIM.0, DIM.1, DIM.2),
+        location.truecolor(INFO.0, INFO.1, INFO.2),
+        message.truecolor(DIM.0, DIM.1, DIM.2)
+    );
+}
+
+/// A finding under its file header; `position` is padded to `width`
+pub fn print_grouped_finding(
+    severity: Severity,
+    position: &str,
+    width: usize,
+    message: &str,
+    source: &str,
+) {
+    let (icon, color) = severity_style(severity);
+    say!(
+        "      {} {} {}  {}",
+        format!("{:>width$}", position, width = width).truecolor(DIM.0, DIM.1, DIM.2),
+        icon.truecolor(color.0, color.1, color.2).bold(),
+        message.truecolor(color.0, color.1, color.2),
+        source.truecolor(DIM.0, DIM.1, DIM.2)
+    );
+}
+
+fn severity_style(severity: Severity) -> (&'static str, (u8, u8, u8)) {
+    match severity {
+        Severity::Error => ("✗", ERROR),
+        Severity::Warning => ("⚠", WARNING),
+    }
+}
+
+/// Make already styled location text a hyperlink, if enabled
+fn link_location(text: &str, file: &str, line: Option<u32>, col: Option<u32>) -> String {
+    if !HYPERLINKS.load(Ordering::Relaxed) {
+        return text.to_string();
+    }
+
+    let template = HYPERLINK_URL.read().ok().and_then(|url| url.clone());
+    let url = hyperlink_url(
+        template.as_deref().unwrap_or(DEFAULT_HYPERLINK_URL),
+        file,
+        line,
+        col,
+    );
+    hyperlink(&url, text)
+}
+
+/// Wrap text in an OSC 8 terminal hyperlink
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// Fill in a link template for a file location
+fn hyperlink_url(template: &str, file: &str, line: Option<u32>, col: Option<u32>) -> String {
+    let path = std::path::absolute(Path::new(file)).unwrap_or_else(|_| file.into());
+    let mut path = path.to_string_lossy().replace('\\', "/");
+    // Windows drive paths become file:///C:/...
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+
+    template
+        .replace("{path}", &encode_path(&path))
+        .replace("{line}", &line.unwrap_or(1).to_string())
+        .replace("{column}", &col.unwrap_or(1).to_string())
+}
+
+/// Percent-encode the characters that would break a URL
+pub fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '#' => encoded.push_str("%23"),
+            '%' => encoded.push_str("%25"),
+            '?' => encoded.push_str("%3F"),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
 pub fn print_code_line(line_num: u32, code: &str, is_error: bool) {
     let num_str = format!("{:>4} │ ", line_num);
+    let code = expand_tabs(code);
     if is_error {
-        println!(
+        say!(
             "{}{}",
             num_str.truecolor(ERROR.0, ERROR.1, ERROR.2),
             code.truecolor(ERROR.0, ERROR.1, ERROR.2)
         );
     } else {
-        println!("{}{}", num_str.truecolor(DIM.0, DIM.1, DIM.2), code);
+        say!("{}{}", num_str.truecolor(DIM.0, DIM.1, DIM.2), code);
+    }
+}
+
+/// A code snippet with syntax highlighting, indented under `label`
+pub fn print_snippet(label: &str, code: &str, language: &Language) {
+    say!("  {}", label.truecolor(DIM.0, DIM.1, DIM.2));
+    for line in code.lines() {
+        let line = expand_tabs(line);
+        let highlighted: String = highlight::tokens(&line, language)
+            .into_iter()
+            .map(|(token, text)| match token {
+                Token::Plain => text.to_string(),
+                Token::Keyword => text.truecolor(INFO.0, INFO.1, INFO.2).bold().to_string(),
+                Token::String => text.truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2).to_string(),
+                Token::Comment => text.truecolor(DIM.0, DIM.1, DIM.2).italic().to_string(),
+                Token::Number => text.truecolor(WARNING.0, WARNING.1, WARNING.2).to_string(),
+            })
+            .collect();
+        say!("    {} {}", "│".truecolor(DIM.0, DIM.1, DIM.2), highlighted);
+    }
+    say!();
+}
+
+/// Underline the token at `column` of a line printed with `print_code_line`,
+/// or the range up to `end_column` when the tool marked one
+pub fn print_caret(code: &str, column: u32, end_column: Option<u32>) {
+    if let Some(marker) = caret_marker(code, column as usize, end_column.map(|c| c as usize)) {
+        say!(
+            "{}{}",
+            "     │ ".truecolor(DIM.0, DIM.1, DIM.2),
+            marker.truecolor(ERROR.0, ERROR.1, ERROR.2).bold()
+        );
+    }
+}
+
+/// Terminal width of a character at display column `at`
+fn char_width(c: char, at: usize) -> usize {
+    tab_aware_width(c, at, TAB_WIDTH)
+}
+
+fn tab_aware_width(c: char, at: usize, tab_width: usize) -> usize {
+    if c == '\t' {
+        tab_width - at % tab_width
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// Convert a 1-based gcc column (which expands tabs to 8 and counts wide
+/// characters twice) to a 1-based character column
+pub fn gcc_column_to_char(code: &str, column: u32) -> u32 {
+    let target = (column as usize).saturating_sub(1);
+    let mut at = 0;
+    for (i, c) in code.chars().enumerate() {
+        if at >= target {
+            return i as u32 + 1;
+        }
+        at += tab_aware_width(c, at, GCC_TAB_WIDTH);
+    }
+    code.chars().count() as u32 + 1
+}
+
+fn expand_tabs(code: &str) -> String {
+    let mut expanded = String::with_capacity(code.len());
+    let mut at = 0;
+    for c in code.chars() {
+        let width = char_width(c, at);
+        if c == '\t' {
+            expanded.extend(std::iter::repeat_n(' ', width));
+        } else {
+            expanded.push(c);
+        }
+        at += width;
+    }
+    expanded
+}
+
+/// Padding and a `^~~~` marker under the token starting at the 1-based
+/// character `column` (or under `column..=end_column`), accounting for tabs
+/// and wide characters
+fn caret_marker(code: &str, column: usize, end_column: Option<usize>) -> Option<String> {
+    let chars: Vec<char> = code.chars().collect();
+    let start = column.checked_sub(1)?;
+    if start > chars.len() {
+        return None;
+    }
+
+    let mut at = 0;
+    for c in &chars[..start] {
+        at += char_width(*c, at);
     }
+
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let token_width = match (chars.get(start), end_column) {
+        (Some(_), Some(end)) if end >= column => {
+            let mut width = 0;
+            for c in &chars[start..end.min(chars.len())] {
+                width += char_width(*c, at + width);
+            }
+            width.max(1)
+        }
+        (Some(c), _) if is_word(c) => chars[start..]
+            .iter()
+            .take_while(|c| is_word(c))
+            .map(|c| c.width().unwrap_or(0))
+            .sum::<usize>()
+            .max(1),
+        (Some(c), _) => char_width(*c, at).max(1),
+        (None, _) => 1,
+    };
+
+    Some(format!(
+        "{}^{}",
+        " ".repeat(at),
+        "~".repeat(token_width - 1)
+    ))
 }
 
 pub fn print_diff(before: &str, after: &str) {
+    if !SHOW_DIFFS.load(Ordering::Relaxed) {
+        return;
+    }
+
     print_section("Suggested Fix");
-    println!();
+    say!();
 
-    for line in before.lines() {
-        println!(
-            "  {} {}",
-            "-".truecolor(ERROR.0, ERROR.1, ERROR.2).bold(),
-            line.truecolor(ERROR.0, ERROR.1, ERROR.2)
-        );
+    let lines = diff::unified(before, after);
+
+    if SIDE_BY_SIDE.load(Ordering::Relaxed) {
+        if let Some(width) = terminal_width().filter(|w| *w >= SIDE_BY_SIDE_MIN_WIDTH) {
+            print_side_by_side(&lines, width);
+            return;
+        }
+    }
+
+    for line in &lines {
+        let (marker, rendered) = render_diff_line(line, usize::MAX);
+        say!("  {} {}", marker, rendered);
     }
 
-    println!();
+    say!();
+}
 
-    for line in after.lines() {
-        println!(
-            "  {} {}",
-            "+".truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2).bold(),
-            line.truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2)
+fn terminal_width() -> Option<usize> {
+    crossterm::terminal::size().ok().map(|(w, _)| w as usize)
+}
+
+fn print_side_by_side(lines: &[diff::Line], width: usize) {
+    // "  - " + left + " │ " + "+ " + right
+    let column = (width - 9) / 2;
+
+    for (left, right) in diff::side_by_side(lines) {
+        let (left_marker, left) = match &left {
+            Some(line) => render_diff_line(line, column),
+            None => (" ".to_string(), " ".repeat(column)),
+        };
+        let (right_marker, right) = match &right {
+            Some(line) => render_diff_line(line, column),
+            None => (" ".to_string(), " ".repeat(column)),
+        };
+
+        say!(
+            "  {} {} {} {} {}",
+            left_marker,
+            left,
+            "│".truecolor(DIM.0, DIM.1, DIM.2),
+            right_marker,
+            right
         );
     }
 
-    println!();
+    say!();
+}
+
+/// Colored marker and text of a diff line, fitted to `width` columns
+/// (`usize::MAX` leaves the text as is)
+fn render_diff_line(line: &diff::Line, width: usize) -> (String, String) {
+    let plain = |text: &str| diff::Segment {
+        text: text.to_string(),
+        emphasized: false,
+    };
+
+    let (marker, color, segments) = match line {
+        diff::Line::Equal(text) => (" ", DIM, vec![plain(text)]),
+        diff::Line::Removed(segments) => ("-", ERROR, segments.clone()),
+        diff::Line::Added(segments) => ("+", SUCCESS, segments.clone()),
+        diff::Line::Gap => (" ", DIM, vec![plain("⋮")]),
+    };
+    let segments = if width == usize::MAX {
+        segments
+    } else {
+        fit_segments(&segments, width)
+    };
+
+    let emphasize = !matches!(line, diff::Line::Equal(_) | diff::Line::Gap);
+    let text: String = segments
+        .iter()
+        .map(|s| {
+            if emphasize && s.emphasized {
+                s.text
+                    .truecolor(color.0, color.1, color.2)
+                    .bold()
+                    .underline()
+                    .to_string()
+            } else {
+                s.text.truecolor(color.0, color.1, color.2).to_string()
+            }
+        })
+        .collect();
+
+    (
+        marker
+            .truecolor(color.0, color.1, color.2)
+            .bold()
+            .to_string(),
+        text,
+    )
+}
+
+/// Expand tabs and pad or cut (with `…`) to exactly `width` columns
+#[cfg(test)]
+fn fit_to_width(text: &str, width: usize) -> String {
+    let segment = diff::Segment {
+        text: text.to_string(),
+        emphasized: false,
+    };
+    fit_segments(&[segment], width)
+        .into_iter()
+        .map(|s| s.text)
+        .collect()
+}
+
+/// Expand tabs and pad or cut (with `…`) segments to exactly `width` columns
+fn fit_segments(segments: &[diff::Segment], width: usize) -> Vec<diff::Segment> {
+    let expanded: Vec<(String, bool)> = segments
+        .iter()
+        .map(|s| (expand_tabs(&s.text), s.emphasized))
+        .collect();
+
+    let total: usize = expanded
+        .iter()
+        .flat_map(|(text, _)| text.chars())
+        .map(|c| c.width().unwrap_or(0))
+        .sum();
+    let limit = if total > width {
+        width.saturating_sub(1)
+    } else {
+        width
+    };
+
+    let mut fitted = Vec::new();
+    let mut used = 0;
+    'segments: for (text, emphasized) in expanded {
+        let mut part = String::new();
+        for c in text.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > limit {
+                fitted.push(diff::Segment {
+                    text: part,
+                    emphasized,
+                });
+                break 'segments;
+            }
+            part.push(c);
+            used += w;
+        }
+        fitted.push(diff::Segment {
+            text: part,
+            emphasized,
+        });
+    }
+
+    let mut tail = String::new();
+    if total > width {
+        tail.push('…');
+        used += 1;
+    }
+    tail.push_str(&" ".repeat(width.saturating_sub(used)));
+    fitted.push(diff::Segment {
+        text: tail,
+        emphasized: false,
+    });
+    fitted
 }
 
 pub fn print_fix_instruction(instruction: &str) {
     print_section("How to Fix");
-    println!();
-    for line in instruction.lines() {
-        println!("  {}", line.truecolor(255, 255, 255));
+    say!();
+
+    let (lines, hidden) = limit_lines(instruction, MAX_INSTRUCTION_LINES.load(Ordering::Relaxed));
+    for line in lines {
+        say!("  {}", line.truecolor(255, 255, 255));
+    }
+    if hidden > 0 {
+        say!(
+            "  {}",
+            format!(
+                "… {} more line{} (raise output.max_instruction_lines to see them)",
+                hidden,
+                if hidden == 1 { "" } else { "s" }
+            )
+            .truecolor(DIM.0, DIM.1, DIM.2)
+        );
+    }
+    say!();
+}
+
+/// The first `max` lines of `text` (all if `max` is 0) and how many were cut
+fn limit_lines(text: &str, max: usize) -> (Vec<&str>, usize) {
+    let lines: Vec<&str> = text.lines().collect();
+    if max == 0 || lines.len() <= max {
+        return (lines, 0);
     }
-    println!();
+
+    let hidden = lines.len() - max;
+    (lines[..max].to_vec(), hidden)
 }
 
-pub fn print_supported_patterns() {
+/// The built-in patterns, then the `[[patterns]]` from the config, which
+/// are tried on errors the built-in ones don't recognize
+pub fn print_supported_patterns(custom: &[PatternConfig]) {
     print_section("Supported Languages & Patterns");
-    println!();
+    say!();
 
-    println!(
+    say!(
         "  {}",
-        "C++ (g++/clang++)".truecolor(INFO.0, INFO.1, INFO.2).bold()
-    );
-    println!("    • Missing #include headers");
-    println!("    • Undeclared identifiers");
-    println!("    • Missing semicolons");
-    println!("    • Type mismatches");
-    println!();
-
-    println!("  {}", "Python".truecolor(INFO.0, INFO.1, INFO.2).bold());
-    println!("    • SyntaxError (missing colons, brackets)");
-    println!("    • IndentationError");
-    println!("    • NameError (undefined variables)");
-    println!("    • ImportError");
-    println!();
-
-    println!(
+        "C++ (g++/clang++/cl)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    say!("    • Missing #include headers");
+    say!("    • Undeclared identifiers");
+    say!("    • Missing semicolons");
+    say!("    • Type mismatches");
+    say!();
+
+    say!("  {}", "Python".truecolor(INFO.0, INFO.1, INFO.2).bold());
+    say!("    • SyntaxError (missing colons, brackets)");
+    say!("    • IndentationError");
+    say!("    • NameError (undefined variables)");
+    say!("    • ImportError");
+    say!();
+
+    say!(
         "  {}",
         "JavaScript/TypeScript"
             .truecolor(INFO.0, INFO.1, INFO.2)
             .bold()
     );
-    println!("    • SyntaxError (unexpected tokens)");
-    println!("    • ReferenceError");
-    println!("    • TypeError");
-    println!("    • Module not found");
-    println!();
+    say!("    • SyntaxError (unexpected tokens)");
+    say!("    • ReferenceError");
+    say!("    • TypeError");
+    say!("    • Module not found");
+    say!();
+
+    say!("  {}", "Rust".truecolor(INFO.0, INFO.1, INFO.2).bold());
+    say!("    • Missing use statements");
+    say!("    • Borrow checker errors");
+    say!("    • Type mismatches");
+    say!("    • Macro errors (derive, format!)");
+    say!();
+
+    say!(
+        "  {}",
+        "Templates (Jinja2/Django/EJS)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    say!("    • Unclosed {{% %}} and <% %> blocks");
+    say!("    • Undefined template variables");
+    say!();
+
+    say!(
+        "  {}",
+        "Dependencies (ess audit)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    say!("    • cargo audit advisories");
+    say!("    • npm audit advisories");
+    say!();
+
+    say!(
+        "  {}",
+        "Formatting (--formatting)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    say!("    • Mixed tab/space indentation");
+    say!("    • Lines over scan.max_line_length");
+    say!();
+
+    say!(
+        "  {}",
+        "Tests (--with-tests)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    say!("    • pytest, cargo test and npm test (Jest, node --test) failures");
+    say!("    • Failed assertions, with the values compared");
+    say!();
 
-    println!("  {}", "Rust".truecolor(INFO.0, INFO.1, INFO.2).bold());
-    println!("    • Missing use statements");
-    println!("    • Borrow checker errors");
-    println!("    • Type mismatches");
-    println!();
+    say!(
+        "  {}",
+        "Rule IDs ([rules] disabled)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    for (id, description) in rules::builtin_ids() {
+        say!(
+            "    • {}  {}",
+            format!("{:<15}", id).truecolor(DIM.0, DIM.1, DIM.2),
+            description
+        );
+    }
+    say!();
 
-    print_hint("More patterns coming soon!");
-    println!();
+    if custom.is_empty() {
+        print_hint("Teach ess your own errors with [[patterns]] in .essentialscode.toml");
+        say!();
+        return;
+    }
+    say!(
+        "  {}",
+        "Your patterns (.essentialscode.toml)"
+            .truecolor(INFO.0, INFO.1, INFO.2)
+            .bold()
+    );
+    for pattern in custom {
+        say!(
+            "    • {}{}  {}",
+            pattern.title,
+            pattern
+                .language
+                .as_ref()
+                .map(|language| format!(" ({})", language))
+                .unwrap_or_default(),
+            pattern.regex.truecolor(DIM.0, DIM.1, DIM.2)
+        );
+    }
+    say!();
 }
 
 pub fn print_no_errors() {
-    println!();
-    println!(
+    say!();
+    say!(
         "  {} {}",
         "✓".truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2).bold(),
         "No errors found!"
             .truecolor(SUCCESS.0, SUCCESS.1, SUCCESS.2)
             .bold()
     );
-    println!();
+    say!();
+}
+
+/// Checks that could not run, listed under the scan result
+pub fn print_skipped(skipped: &[String]) {
+    for check in skipped {
+        say!(
+            "  {} {} {}",
+            "⊘".truecolor(WARNING.0, WARNING.1, WARNING.2).bold(),
+            "Skipped (tool missing):".truecolor(WARNING.0, WARNING.1, WARNING.2),
+            check
+        );
+    }
+}
+
+pub fn print_health(score: u8, grade: char, has_baseline: bool) {
+    let color = match grade {
+        'A' | 'B' => SUCCESS,
+        'C' | 'D' => WARNING,
+        _ => ERROR,
+    };
+    say!(
+        "  {} Health: {} {}{}",
+        "♥".truecolor(color.0, color.1, color.2).bold(),
+        format!("{}/100", score)
+            .truecolor(color.0, color.1, color.2)
+            .bold(),
+        format!("(grade {})", grade).truecolor(color.0, color.1, color.2),
+        if has_baseline {
+            ""
+        } else {
+            " - first scan, no trend yet"
+        }
+        .truecolor(DIM.0, DIM.1, DIM.2)
+    );
+    say!();
+}
+
+pub fn print_timing(elapsed: &str, label: &str, slow: bool) {
+    if slow {
+        say!(
+            "  {} {:>8}  {}",
+            "⏱".truecolor(WARNING.0, WARNING.1, WARNING.2).bold(),
+            elapsed.truecolor(WARNING.0, WARNING.1, WARNING.2).bold(),
+            label.truecolor(WARNING.0, WARNING.1, WARNING.2)
+        );
+    } else {
+        say!(
+            "  {} {:>8}  {}",
+            "⏱".truecolor(DIM.0, DIM.1, DIM.2),
+            elapsed.truecolor(DIM.0, DIM.1, DIM.2),
+            label
+        );
+    }
 }
 
 pub fn print_errors_found(count: usize) {
-    println!();
-    println!(
+    say!();
+    say!(
         "  {} {} error{} found",
         "●".truecolor(ERROR.0, ERROR.1, ERROR.2).bold(),
         count
@@ -225,3 +990,158 @@ pub fn print_errors_found(count: usize) {
         if count == 1 { "" } else { "s" }
     );
 }
+
+/// Findings per rule, e.g. "KeyError ×4, pylint ×9", colored by severity
+pub fn print_rule_counts(counts: &[RuleCount]) {
+    if counts.is_empty() {
+        return;
+    }
+    let breakdown: Vec<String> = counts
+        .iter()
+        .map(|count| {
+            let color = match count.severity {
+                Severity::Error => ERROR,
+                Severity::Warning => WARNING,
+            };
+            format!(
+                "{} {}",
+                count.rule.truecolor(color.0, color.1, color.2),
+                format!("×{}", count.count).truecolor(DIM.0, DIM.1, DIM.2)
+            )
+        })
+        .collect();
+    say!("    {}", breakdown.join(", "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_rule() {
+        assert_eq!(section_rule("─", 3), "───");
+        assert_eq!(section_rule("=-", 5), "=-=-=");
+        assert_eq!(section_rule("", 60), "");
+        assert_eq!(section_rule("*", 0), "");
+    }
+
+    #[test]
+    fn test_caret_marker_simple() {
+        assert_eq!(
+            caret_marker("int x = foo(1);", 9, None).as_deref(),
+            Some("        ^~~")
+        );
+        assert_eq!(caret_marker("x = 1 +", 7, None).as_deref(), Some("      ^"));
+    }
+
+    #[test]
+    fn test_caret_marker_end_of_line() {
+        assert_eq!(
+            caret_marker("int x = 5", 10, None).as_deref(),
+            Some("         ^")
+        );
+        assert_eq!(caret_marker("int x = 5", 12, None), None);
+        assert_eq!(caret_marker("int x = 5", 0, None), None);
+    }
+
+    #[test]
+    fn test_caret_marker_tabs() {
+        // The tab expands to 4 columns, then "\tx" puts x at column 8
+        assert_eq!(expand_tabs("\tif\tx"), "    if  x");
+        assert_eq!(
+            caret_marker("\tif\tx", 5, None).as_deref(),
+            Some("        ^")
+        );
+    }
+
+    #[test]
+    fn test_caret_marker_multibyte() {
+        assert_eq!(
+            caret_marker("s = \"żółw\" + ok", 14, None).as_deref(),
+            Some("             ^~")
+        );
+        // Wide characters take two columns
+        assert_eq!(
+            caret_marker("名前 = x", 6, None).as_deref(),
+            Some("       ^")
+        );
+        assert_eq!(
+            caret_marker("x = 名前", 5, None).as_deref(),
+            Some("    ^~~~")
+        );
+    }
+
+    #[test]
+    fn test_caret_marker_range() {
+        let code = "print(config[\"debug\"])";
+        assert_eq!(
+            caret_marker(code, 7, Some(21)).as_deref(),
+            Some("      ^~~~~~~~~~~~~~~")
+        );
+        // A range past the end of the line stops at the end
+        assert_eq!(caret_marker("x = 1", 5, Some(9)).as_deref(), Some("    ^"));
+        // A range ending before it starts marks the token
+        assert_eq!(
+            caret_marker("x = foo", 5, Some(2)).as_deref(),
+            Some("    ^~~")
+        );
+    }
+
+    #[test]
+    fn test_gcc_column_to_char() {
+        assert_eq!(gcc_column_to_char("int x = y;", 9), 9);
+        assert_eq!(gcc_column_to_char("\tint y = z;", 17), 10);
+        assert_eq!(gcc_column_to_char("名前 = x", 8), 6);
+        assert_eq!(gcc_column_to_char("x", 5), 2);
+    }
+
+    #[test]
+    fn test_fit_to_width() {
+        assert_eq!(fit_to_width("abc", 5), "abc  ");
+        assert_eq!(fit_to_width("abcdef", 4), "abc…");
+        assert_eq!(fit_to_width("\tx", 6), "    x ");
+        // Wide characters never get split
+        assert_eq!(fit_to_width("名前です", 6), "名前… ");
+    }
+
+    #[test]
+    fn test_hyperlink_url() {
+        assert_eq!(
+            hyperlink_url("file://{path}", "/src/my app/main.rs", Some(3), Some(7)),
+            "file:///src/my%20app/main.rs"
+        );
+        assert_eq!(
+            hyperlink_url(
+                "vscode://file{path}:{line}:{column}",
+                "/src/main.rs",
+                Some(3),
+                None
+            ),
+            "vscode://file/src/main.rs:3:1"
+        );
+    }
+
+    #[test]
+    fn test_hyperlink_url_relative_path_is_absolute() {
+        let url = hyperlink_url("{path}", "src/main.rs", None, None);
+        assert!(url.starts_with('/'));
+        assert!(url.ends_with("/src/main.rs"));
+    }
+
+    #[test]
+    fn test_hyperlink_escape() {
+        assert_eq!(
+            hyperlink("file:///a.rs", "a.rs"),
+            "\x1b]8;;file:///a.rs\x1b\\a.rs\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_limit_lines() {
+        let text = "one\ntwo\nthree";
+
+        assert_eq!(limit_lines(text, 0), (vec!["one", "two", "three"], 0));
+        assert_eq!(limit_lines(text, 3), (vec!["one", "two", "three"], 0));
+        assert_eq!(limit_lines(text, 1), (vec!["one"], 2));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::finding::Finding;
+use crate::regexes;
+use crate::ui;
+use serde::{Deserialize, Serialize};
+
+/// An error-looking line of a tool's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorLine {
+    /// Tool or check that printed it, e.g. "g++" or "python run"
+    pub tool: String,
+    pub text: String,
+}
+
+/// How much of the error output of a scan's tools the parser understood
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParseCoverage {
+    /// Error-looking lines, each counted once
+    pub total: usize,
+    /// Lines no recognized finding came from, in the order they were printed
+    pub unparsed: Vec<ErrorLine>,
+}
+
+impl ParseCoverage {
+    /// Sort `lines` into those behind a finding the parser recognized and
+    /// the rest. A line is behind a finding when it contains the finding's
+    /// message or is part of the output the finding explains.
+    pub fn new(lines: &[ErrorLine], findings: &[Finding]) -> Self {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut coverage = Self::default();
+        for line in lines {
+            if seen.contains(&line.text.as_str()) {
+                continue;
+            }
+            seen.push(&line.text);
+            coverage.total += 1;
+
+            let recognized = findings.iter().filter(|f| f.is_fixable()).any(|finding| {
+                let message = finding.message.trim();
+                (!message.is_empty() && line.text.contains(message))
+                    || finding
+                        .explain
+                        .as_deref()
+                        .is_some_and(|output| output.contains(&line.text))
+            });
+            if !recognized {
+                coverage.unparsed.push(line.clone());
+            }
+        }
+        coverage
+    }
+
+    /// Say how many error lines weren't understood, and list them when
+    /// `show` is set
+    pub fn print(&self, show: bool) {
+        if self.unparsed.is_empty() {
+            return;
+        }
+        ui::print_info(&format!(
+            "{} of {} error line{} in tool output {} recognized by the parser{}",
+            self.unparsed.len(),
+            self.total,
+            if self.total == 1 { "" } else { "s" },
+            if self.unparsed.len() == 1 {
+                "wasn't"
+            } else {
+                "weren't"
+            },
+            if show {
+                ""
+            } else {
+                " (--show-unparsed lists them)"
+            }
+        ));
+        if !show {
+            return;
+        }
+
+        ui::print_section("Unparsed Error Lines");
+        for line in &self.unparsed {
+            println!("  [{}] {}", line.tool, line.text);
+        }
+        println!();
+        ui::print_hint(
+            "Match them with [[patterns]] in .essentialscode.toml, or report them so the \
+            parser can learn them",
+        );
+    }
+}
+
+/// The lines of `output` that look like an error message, trimmed.
+/// Summary lines like "error: aborting due to 2 previous errors" say
+/// nothing new, so they are left out.
+pub fn error_lines(output: &str) -> impl Iterator<Item = &str> {
+    output
+        .lines()
+        .filter(|line| regexes::ERROR_LINE.is_match(line) && !regexes::ERROR_END.is_match(line))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::Severity;
+    use crate::parser::parse_error;
+    use std::path::Path;
+
+    fn line(text: &str) -> ErrorLine {
+        ErrorLine {
+            tool: "g++".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_error_lines() {
+        let output = "main.cpp: In function 'int main()':\n\
+            main.cpp:4:5: error: 'cout' was not declared in this scope\n\
+            \x20   4 |     cout << 1;\n\
+            Traceback (most recent call last):\n\
+            NameError: name 'x' is not defined\n\
+            thread 'main' panicked at src/main.rs:2:5:\n\
+            error: aborting due to 1 previous error\n\
+            {\"level\":\"error\",\"message\":\"x\"}\n\
+            \x20 error: 'x is not defined'\n";
+        assert_eq!(
+            error_lines(output).collect::<Vec<_>>(),
+            vec![
+                "main.cpp:4:5: error: 'cout' was not declared in this scope",
+                "NameError: name 'x' is not defined",
+                "thread 'main' panicked at src/main.rs:2:5:",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coverage_counts_recognized_findings_only() {
+        let root = Path::new("/p");
+        let known = "main.cpp:4:5: error: 'cout' was not declared in this scope";
+        let unknown = "main.cpp:9:1: error: something the parser doesn't know";
+        let findings = vec![
+            Finding::new(
+                &root.join("main.cpp"),
+                Severity::Error,
+                "'cout' was not declared in this scope",
+                "g++",
+            )
+            .with_parsed(parse_error(known), root),
+            Finding::new(
+                &root.join("main.cpp"),
+                Severity::Error,
+                "something the parser doesn't know",
+                "g++",
+            )
+            .with_parsed(parse_error(unknown), root),
+        ];
+        let lines = vec![
+            line(known),
+            line(unknown),
+            line(unknown),
+            line("ld: error: undefined symbol: helper()"),
+        ];
+
+        let coverage = ParseCoverage::new(&lines, &findings);
+        assert_eq!(coverage.total, 3);
+        assert_eq!(
+            coverage.unparsed,
+            vec![line(unknown), line("ld: error: undefined symbol: helper()")]
+        );
+    }
+}
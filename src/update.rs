@@ -0,0 +1,245 @@
+//! `ess self-update`: replace this executable with the newest release.
+//! Releases carry one binary per platform, named `ess-<arch>-<os>`
+//! (`.exe` on Windows, e.g. `ess-x86_64-windows.exe`), and a `SHA256SUMS`
+//! file listing their checksums. A binary that isn't listed there, or
+//! doesn't match, is never installed. Both come from the same place, so
+//! this guards against corrupt downloads only; nothing is signed.
+
+use crate::download;
+use crate::ui;
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// The release feed: the newest release and its files
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/Jakubeq33/EssentialsCode/releases/latest";
+
+/// Release file with the SHA-256 of every binary
+const CHECKSUMS_NAME: &str = "SHA256SUMS";
+
+/// Largest release feed and checksum file read
+const MAX_FEED_BYTES: usize = 1 << 20;
+
+/// Largest binary downloaded
+const MAX_BINARY_BYTES: usize = 200 << 20;
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| anyhow!("Release {} has no {}", self.tag_name, name))
+    }
+}
+
+/// Name of the release binary for a platform, as in `std::env::consts`
+pub fn asset_name(arch: &str, os: &str) -> String {
+    let suffix = if os == "windows" { ".exe" } else { "" };
+    format!("ess-{}-{}{}", arch, os, suffix)
+}
+
+/// Numbers of a version like "v1.2.3" or "1.2.3-beta"; a pre-release
+/// suffix is left out
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|n| n.parse().unwrap_or(0))
+        .collect()
+}
+
+/// `latest` is a higher version than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    let (mut latest, mut current) = (version_numbers(latest), version_numbers(current));
+    let len = latest.len().max(current.len());
+    latest.resize(len, 0);
+    current.resize(len, 0);
+    latest > current
+}
+
+/// The checksum `sums` (`sha256sum` output) lists for the file `name`
+pub fn checksum_for(sums: &str, name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        // sha256sum marks binary mode with '*'
+        let file = file.trim_start().trim_start_matches('*');
+        (file == name).then(|| hash.to_lowercase())
+    })
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Write `bytes` over the executable at `target`. The new file is written
+/// next to it first, so a failed download or full disk leaves the old one.
+pub fn install(target: &Path, bytes: &[u8]) -> Result<()> {
+    let new = target.with_extension("new");
+    std::fs::write(&new, bytes).map_err(|e| anyhow!("Could not write {}: {}", new.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // A running .exe can't be replaced, only renamed
+    let old = target.with_extension("old");
+    if cfg!(windows) {
+        let _ = std::fs::remove_file(&old);
+        if let Err(e) = std::fs::rename(target, &old) {
+            let _ = std::fs::remove_file(&new);
+            bail!("Could not move {} aside: {}", target.display(), e);
+        }
+    }
+    std::fs::rename(&new, target).map_err(|e| {
+        let _ = std::fs::remove_file(&new);
+        // Put the old executable back, so ess is still installed
+        if cfg!(windows) {
+            let _ = std::fs::rename(&old, target);
+        }
+        anyhow!("Could not replace {}: {}", target.display(), e)
+    })
+}
+
+fn latest_release() -> Result<Release> {
+    let feed = download::bytes(LATEST_RELEASE_URL, MAX_FEED_BYTES)?;
+    serde_json::from_slice(&feed).map_err(|e| anyhow!("Unexpected release feed: {}", e))
+}
+
+/// Check for a newer release and, unless `check_only`, install it.
+/// Returns the newer version, if there is one.
+pub fn run(check_only: bool) -> Result<Option<String>> {
+    let current = env!("CARGO_PKG_VERSION");
+    ui::print_info("Checking for a newer release...");
+    let release = latest_release()?;
+    let latest = release.tag_name.trim_start_matches('v').to_string();
+    if !is_newer(&latest, current) {
+        ui::print_success(&format!("ess {} is the newest version", current));
+        return Ok(None);
+    }
+    if check_only {
+        ui::print_info(&format!(
+            "ess {} is available (you have {})",
+            latest, current
+        ));
+        ui::print_hint("Install it with 'ess self-update'");
+        return Ok(Some(latest));
+    }
+
+    let name = asset_name(std::env::consts::ARCH, std::env::consts::OS);
+    let binary = release.asset(&name)?;
+    let sums = release.asset(CHECKSUMS_NAME)?;
+    let sums = String::from_utf8_lossy(&download::bytes(
+        &sums.browser_download_url,
+        MAX_FEED_BYTES,
+    )?)
+    .to_string();
+    let Some(expected) = checksum_for(&sums, &name) else {
+        bail!(
+            "{} doesn't list {}; not installing it",
+            CHECKSUMS_NAME,
+            name
+        );
+    };
+
+    ui::print_info(&format!("Downloading {}...", name));
+    let bytes = download::bytes(&binary.browser_download_url, MAX_BINARY_BYTES)?;
+    let actual = sha256_hex(&bytes);
+    if actual != expected {
+        bail!(
+            "The checksum of {} is {}, but {} says {}; not installing it",
+            name,
+            actual,
+            CHECKSUMS_NAME,
+            expected
+        );
+    }
+
+    let target = current_exe()?;
+    install(&target, &bytes)?;
+    ui::print_success(&format!("Updated ess {} → {}", current, latest));
+    Ok(Some(latest))
+}
+
+/// This executable, with symlinks followed so a link in ~/bin stays one
+fn current_exe() -> Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    Ok(exe.canonicalize().unwrap_or(exe))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("v0.3.0", "0.2.0"));
+        assert!(is_newer("0.10.0", "0.9.1"));
+        assert!(is_newer("1.0", "0.9.9"));
+        assert!(!is_newer("v0.2.0", "0.2.0"));
+        assert!(!is_newer("0.2", "0.2.0"));
+        assert!(!is_newer("0.1.9", "0.2.0"));
+        assert!(!is_newer("0.2.0-beta", "0.2.0"));
+    }
+
+    #[test]
+    fn test_checksum_for() {
+        let sums = "AB12  ess-x86_64-linux\ncd34 *ess-x86_64-windows.exe\n";
+        assert_eq!(
+            checksum_for(sums, "ess-x86_64-linux").as_deref(),
+            Some("ab12")
+        );
+        assert_eq!(
+            checksum_for(sums, &asset_name("x86_64", "windows")).as_deref(),
+            Some("cd34")
+        );
+        assert_eq!(checksum_for(sums, "ess-aarch64-macos"), None);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_install_replaces_file() {
+        let temp_dir = std::env::temp_dir().join("ess_test_update_install");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let target = temp_dir.join("ess");
+        std::fs::write(&target, "old").unwrap();
+
+        install(&target, b"new").unwrap();
+        let content = std::fs::read_to_string(&target).unwrap();
+        let leftover = target.with_extension("new").exists();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        assert_eq!(content, "new");
+        assert!(!leftover);
+    }
+}
@@ -0,0 +1,36 @@
+//! Errors in what was asked for rather than in doing it: a scan id that
+//! isn't in the history, a file that isn't there, a revision git doesn't
+//! know. `ess` exits with its usage code for these, the same as clap does
+//! for bad arguments.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Whether `error`, or any error it wraps, is a usage error
+pub fn is_usage_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|e| e.is::<UsageError>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_usage_error_through_context() {
+        let error: anyhow::Result<()> = Err(UsageError("No scan #9".to_string()).into());
+        let error = error.context("Could not compare").unwrap_err();
+        assert!(is_usage_error(&error));
+        assert!(!is_usage_error(&anyhow::anyhow!("disk full")));
+    }
+}
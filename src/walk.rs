@@ -0,0 +1,252 @@
+use crate::apply::{self, Fix, FixPolicy, Verification};
+use crate::config::Config;
+use crate::finding::Finding;
+use crate::hooks;
+use crate::metrics;
+use crate::scanner::{self, ScanOptions, ScanSummary};
+use crate::state::LastScan;
+use crate::ui;
+use anyhow::Result;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// What to do next with the current file
+#[derive(Debug, PartialEq)]
+enum Action {
+    Recheck,
+    Apply,
+    Skip,
+    Quit,
+}
+
+fn parse_action(answer: &str) -> Action {
+    match answer.trim().to_lowercase().as_str() {
+        "a" | "apply" => Action::Apply,
+        "s" | "skip" => Action::Skip,
+        "q" | "quit" => Action::Quit,
+        _ => Action::Recheck,
+    }
+}
+
+/// Step through the failing files of a scan one at a time
+pub fn run(path: &Path, config: &Config, summary: &ScanSummary) -> Result<()> {
+    let root = scanner::normalize_path(path);
+    let files = &summary.failing_files;
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+
+    let mut fixed = 0;
+    let mut remaining: Vec<PathBuf> = Vec::new();
+
+    'files: for (i, file) in files.iter().enumerate() {
+        let mut attempt = 0;
+        // The re-check after a verified fix, so it isn't scanned twice
+        let mut verified: Option<ScanSummary> = None;
+
+        loop {
+            ui::print_section(&format!(
+                "Walkthrough {}/{}: {}",
+                i + 1,
+                files.len(),
+                file.display()
+            ));
+
+            let result = match verified.take() {
+                Some(result) => result,
+                None => check_file(&root, config, file)?,
+            };
+
+            if result.failing_files.is_empty() {
+                if attempt > 0 {
+                    fixed += 1;
+                    ui::print_success(&format!("{} is clean now", file.display()));
+                }
+                continue 'files;
+            }
+            attempt += 1;
+
+            let suggestions: Vec<(&Finding, Fix)> = result
+                .findings
+                .iter()
+                .flat_map(|finding| finding.fixes.iter().map(move |fix| (finding, fix.clone())))
+                .filter(|(finding, fix)| apply::permitted(&config.fix, finding.rule(), fix))
+                .collect();
+            let plan = apply::plan(suggestions.iter().map(|(_, fix)| fix.clone()).collect());
+            let mut already_applied: Vec<String> = result
+                .findings
+                .iter()
+                .filter(|finding| !suggestions.iter().any(|(f, _)| std::ptr::eq(*f, *finding)))
+                .filter_map(|finding| apply::already_applied(finding.parsed.as_ref()?, &root))
+                .collect();
+            already_applied.dedup();
+            let merged = plan.merged();
+
+            println!();
+            for fix in &merged {
+                if let Ok((before, after)) = apply::preview(fix) {
+                    ui::print_diff(&before, &after);
+                }
+            }
+            for fix in &plan.fixes {
+                ui::print_info(&format!("Auto-fix available: {}", fix.description));
+            }
+            for reason in &already_applied {
+                ui::print_info(&format!("No fix to apply: {}", reason));
+            }
+            for conflict in &plan.conflicts {
+                ui::print_warning(&format!(
+                    "Skipping '{}': it changes the same lines as '{}'",
+                    conflict.fix.description, conflict.with
+                ));
+            }
+            if !plan.conflicts.is_empty() {
+                ui::print_hint("Skipped fixes are suggested again after the re-check");
+            }
+            // Only the first time, so a fix that didn't work isn't retried
+            // without asking
+            let automatic = attempt == 1
+                && !plan.fixes.is_empty()
+                && suggestions.iter().all(|(finding, _)| {
+                    apply::fix_policy(&config.fix, finding.rule()) == FixPolicy::Auto
+                });
+            let action = if automatic {
+                ui::print_info("Applying automatically ([fix] auto)");
+                Action::Apply
+            } else {
+                if plan.fixes.is_empty() {
+                    ui::print_prompt("Enter = re-check after editing, s = skip, q = quit: ");
+                } else {
+                    ui::print_prompt(&format!(
+                        "Enter = re-check after editing, a = apply fix{}, s = skip, q = quit: ",
+                        if plan.fixes.len() == 1 { "" } else { "es" }
+                    ));
+                }
+
+                let mut answer = String::new();
+                if input.read_line(&mut answer)? == 0 {
+                    println!();
+                    remaining.extend(files[i..].iter().cloned());
+                    break 'files;
+                }
+                parse_action(&answer)
+            };
+
+            match action {
+                Action::Recheck => {}
+                Action::Apply if plan.fixes.is_empty() => {
+                    ui::print_warning("No automatic fix for this error")
+                }
+                Action::Apply => {
+                    let mut backups = Vec::new();
+                    for fix in &merged {
+                        backups.push(apply::apply_fix(fix)?);
+                        ui::print_success(&format!("Applied: {}", fix.description));
+                    }
+
+                    let targets: Vec<&Finding> = suggestions
+                        .iter()
+                        .filter(|(_, fix)| plan.fixes.contains(fix))
+                        .map(|(finding, _)| *finding)
+                        .collect();
+                    ui::print_info("Re-checking the file to verify the fix");
+                    let check = check_file(&root, config, file)?;
+                    let verification = apply::verify(&result.findings, &targets, &check.findings);
+                    if verification != Verification::Fixed {
+                        for backup in backups.iter().rev() {
+                            backup.restore()?;
+                        }
+                    }
+                    metrics::record_fixes(plan.fixes.len(), verification == Verification::Fixed);
+                    match verification {
+                        Verification::Fixed => {
+                            ui::print_success(if plan.fixes.len() == 1 {
+                                "Verified: the error is gone"
+                            } else {
+                                "Verified: the errors are gone"
+                            });
+                            hooks::post_fix(&config.hooks, &root, file, plan.fixes.len(), &check);
+                            verified = Some(check);
+                        }
+                        Verification::StillFailing => ui::print_warning(
+                            "Rolled back: the error is still reported after the fix",
+                        ),
+                        Verification::NewErrors(errors) => {
+                            ui::print_warning(&format!(
+                                "Rolled back: the fix caused {} new error{}",
+                                errors.len(),
+                                if errors.len() == 1 { "" } else { "s" }
+                            ));
+                            for error in &errors {
+                                ui::print_info(error);
+                            }
+                        }
+                    }
+                }
+                Action::Skip => {
+                    remaining.push(file.clone());
+                    continue 'files;
+                }
+                Action::Quit => {
+                    remaining.extend(files[i..].iter().cloned());
+                    break 'files;
+                }
+            }
+        }
+    }
+
+    ui::print_section("Walkthrough Done");
+    ui::print_info(&format!(
+        "{} of {} file{} fixed",
+        fixed,
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
+    ));
+    if !remaining.is_empty() {
+        ui::print_hint("Run 'ess retry' to re-check the remaining files");
+    }
+
+    save_remaining(&root, &remaining);
+    Ok(())
+}
+
+/// Scan just one file of the project
+fn check_file(root: &Path, config: &Config, file: &Path) -> Result<ScanSummary> {
+    scanner::scan_project(
+        root,
+        config,
+        &ScanOptions {
+            only_files: Some(vec![root.join(file)]),
+            ..Default::default()
+        },
+    )
+}
+
+/// Keep `ess retry` in sync with what is still failing
+fn save_remaining(root: &Path, remaining: &[PathBuf]) {
+    if let Ok(Some(mut last)) = LastScan::load(root) {
+        last.failing_files = remaining
+            .iter()
+            .map(|f| f.to_string_lossy().to_string())
+            .collect();
+        let _ = last.save(root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(parse_action("\n"), Action::Recheck);
+        assert_eq!(parse_action("a\n"), Action::Apply);
+        assert_eq!(parse_action("Apply"), Action::Apply);
+        assert_eq!(parse_action("s"), Action::Skip);
+        assert_eq!(parse_action(" Q "), Action::Quit);
+        assert_eq!(parse_action("whatever"), Action::Recheck);
+    }
+}
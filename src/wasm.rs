@@ -0,0 +1,270 @@
+use crate::config::WasmRuleConfig;
+use crate::fixer;
+use crate::parser::{CustomFix, ErrorType, Language, ParsedError};
+use crate::rules::Rule;
+use crate::ui;
+use anyhow::Result;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// What `match_error` returns, as JSON
+#[derive(Debug, Deserialize)]
+struct Answer {
+    title: String,
+    fix: String,
+}
+
+/// A loaded `[[wasm_rules]]` module
+struct WasmRule {
+    path: PathBuf,
+    language: Option<Language>,
+    #[cfg(feature = "wasm")]
+    module: wasmtime::Module,
+}
+
+/// The `[[wasm_rules]]` modules, asked in order about errors nothing
+/// before them recognized
+#[derive(Default)]
+pub struct WasmRules {
+    rules: Vec<WasmRule>,
+    #[cfg(feature = "wasm")]
+    engine: Option<wasmtime::Engine>,
+}
+
+impl WasmRules {
+    /// Compile the modules, with paths relative to `root`
+    #[cfg(feature = "wasm")]
+    pub fn new(configs: &[WasmRuleConfig], root: &Path) -> Result<Self> {
+        if configs.is_empty() {
+            return Ok(Self::default());
+        }
+        let engine = sandbox::engine()?;
+        let rules = configs
+            .iter()
+            .map(|config| {
+                let path = root.join(&config.path);
+                let module = sandbox::compile(&engine, &path).map_err(|e| {
+                    anyhow::anyhow!("Invalid [[wasm_rules]] module {}: {}", path.display(), e)
+                })?;
+                Ok(WasmRule {
+                    language: config
+                        .language
+                        .as_deref()
+                        .map(crate::scanner::detect_language_from_str),
+                    path,
+                    module,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            engine: Some(engine),
+        })
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    pub fn new(configs: &[WasmRuleConfig], root: &Path) -> Result<Self> {
+        match configs.first() {
+            Some(config) => anyhow::bail!(
+                "Can't load [[wasm_rules]] module {}: this ess was built without WebAssembly \
+                support (install it with 'cargo install essentials-code --features wasm')",
+                root.join(&config.path).display()
+            ),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn answer(&self, rule: &WasmRule, text: &str) -> Result<Option<Answer>> {
+        #[cfg(feature = "wasm")]
+        if let Some(engine) = &self.engine {
+            return sandbox::run(engine, &rule.module, text);
+        }
+        let _ = (rule, text);
+        Ok(None)
+    }
+}
+
+impl Rule for WasmRules {
+    fn kind(&self) -> &'static str {
+        "Custom"
+    }
+
+    fn recognize(&self, text: &str, language: &Language) -> Option<ErrorType> {
+        self.rules
+            .iter()
+            .filter(|rule| {
+                *language == Language::Unknown
+                    || rule
+                        .language
+                        .as_ref()
+                        .is_none_or(|wanted| wanted == language)
+            })
+            .find_map(|rule| match self.answer(rule, text) {
+                Ok(answer) => answer.map(|answer| {
+                    ErrorType::Custom(CustomFix {
+                        title: answer.title,
+                        fix: answer.fix,
+                        insert: None,
+                    })
+                }),
+                Err(e) => {
+                    ui::print_warning(&format!("WASM rule {} failed: {}", rule.path.display(), e));
+                    None
+                }
+            })
+    }
+
+    fn fix(&self, error: &ParsedError) {
+        if let ErrorType::Custom(fix) = &error.error_type {
+            fixer::fix_custom(fix);
+        }
+    }
+}
+
+/// Running modules with no way out: no imports, and limits on the
+/// instructions and memory one error may use
+#[cfg(feature = "wasm")]
+mod sandbox {
+    use super::Answer;
+    use anyhow::{anyhow, bail, Result};
+    use std::path::Path;
+    use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+    /// Instructions (roughly) a module may run per error
+    const FUEL: u64 = 100_000_000;
+    /// Bytes of linear memory a module may grow to
+    const MEMORY: usize = 64 << 20;
+    /// Bytes of JSON an answer may take
+    const MAX_ANSWER: usize = 1 << 20;
+
+    pub fn engine() -> Result<Engine> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        Ok(Engine::new(&config)?)
+    }
+
+    pub fn compile(engine: &Engine, path: &Path) -> Result<Module> {
+        let module = Module::from_file(engine, path)?;
+        if let Some(import) = module.imports().next() {
+            bail!(
+                "it imports {}::{}, but rules get no host functions",
+                import.module(),
+                import.name()
+            );
+        }
+        Ok(module)
+    }
+
+    pub fn run(engine: &Engine, module: &Module, text: &str) -> Result<Option<Answer>> {
+        let limits = StoreLimitsBuilder::new().memory_size(MEMORY).build();
+        let mut store: Store<StoreLimits> = Store::new(engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL)?;
+
+        let instance = Instance::new(&mut store, module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("it doesn't export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let match_error = instance.get_typed_func::<(i32, i32), i64>(&mut store, "match_error")?;
+
+        let len = i32::try_from(text.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, text.as_bytes())?;
+        let found = match_error.call(&mut store, (ptr, len))? as u64;
+        if found == 0 {
+            return Ok(None);
+        }
+
+        // Read in place: the length is the module's to claim, so nothing
+        // is allocated for it
+        let (start, len) = ((found >> 32) as usize, (found & 0xFFFF_FFFF) as usize);
+        if len > MAX_ANSWER {
+            bail!("its answer is {} bytes, over {}", len, MAX_ANSWER);
+        }
+        let json = start
+            .checked_add(len)
+            .and_then(|end| memory.data(&store).get(start..end))
+            .ok_or_else(|| anyhow!("its answer lies outside its memory"))?;
+        Ok(Some(serde_json::from_slice(json)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(path: &str) -> WasmRuleConfig {
+        WasmRuleConfig {
+            path: path.to_string(),
+            language: Some("python".to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn test_wasm_rules_need_the_feature() {
+        assert!(WasmRules::new(&[], Path::new("/p")).is_ok());
+        let error = WasmRules::new(&[rule("orm.wasm")], Path::new("/p"))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("/p/orm.wasm"));
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_rule_matches_in_sandbox() {
+        let answer = r#"{"title":"Stale ORM cache","fix":"Run manage.py clear_cache"}"#;
+        // Matches errors starting with "E", loops forever on "L", and
+        // claims a 4 GiB answer on "B"
+        let detector = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "match_error") (param $ptr i32) (param $len i32) (result i64)
+                    (if (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 76))
+                        (then (loop $forever (br $forever))))
+                    (if (result i64) (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 69))
+                        (then (i64.const {}))
+                        (else (if (result i64) (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 66))
+                            (then (i64.const 0xFFFFFFFF))
+                            (else (i64.const 0)))))))"#,
+            answer.replace('"', "\\\""),
+            answer.len()
+        );
+        let temp_dir = std::env::temp_dir().join("ess_test_wasm_rules");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("orm.wat"), detector).unwrap();
+        std::fs::write(
+            temp_dir.join("escape.wat"),
+            r#"(module (import "env" "open" (func)))"#,
+        )
+        .unwrap();
+
+        let rules = WasmRules::new(&[rule("orm.wat")], &temp_dir).unwrap();
+        let escape = WasmRules::new(&[rule("escape.wat")], &temp_dir);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let found = rules.recognize("Error: stale cache", &Language::Python);
+        assert!(matches!(
+            found,
+            Some(ErrorType::Custom(fix)) if fix.title == "Stale ORM cache"
+        ));
+        assert!(rules
+            .recognize("Error: stale cache", &Language::Rust)
+            .is_none());
+        assert!(rules.recognize("other", &Language::Python).is_none());
+        // Out of fuel, not stuck
+        assert!(rules.recognize("Loop", &Language::Python).is_none());
+        // A 4 GiB answer is refused, not allocated
+        assert!(rules.recognize("Big", &Language::Python).is_none());
+        assert!(escape
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("imports env::open"));
+    }
+}
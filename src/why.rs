@@ -0,0 +1,332 @@
+use crate::tools;
+use crate::ui;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// What a PATH search for a command found
+#[derive(Debug, Default)]
+pub struct Lookup {
+    /// The program that runs when the command is typed
+    pub found: Option<PathBuf>,
+    /// PATH directories, in search order, each once
+    pub searched: Vec<PathBuf>,
+    /// PATH directories that don't exist
+    pub missing: Vec<PathBuf>,
+    /// Programs with a differently-cased or versioned name, e.g. node18
+    /// or python3.11 for python
+    pub similar: Vec<PathBuf>,
+    /// The command, installed in a usual place that isn't on PATH
+    pub off_path: Vec<PathBuf>,
+}
+
+/// Search `path` (a PATH value) for `command` the way the shell does
+pub fn lookup(command: &str, path: &OsStr) -> Lookup {
+    let mut lookup = Lookup::default();
+    for dir in std::env::split_paths(path) {
+        if !dir.as_os_str().is_empty() && !lookup.searched.contains(&dir) {
+            lookup.searched.push(dir);
+        }
+    }
+
+    for dir in &lookup.searched {
+        if !dir.is_dir() {
+            lookup.missing.push(dir.clone());
+            continue;
+        }
+        if lookup.found.is_none() {
+            lookup.found = executable_in(dir, command);
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut similar: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|file| {
+                let name = program_name(file);
+                name != command && is_similar(&name, command) && is_executable(file)
+            })
+            .collect();
+        similar.sort();
+        lookup.similar.extend(similar);
+    }
+
+    if lookup.found.is_none() {
+        lookup.off_path = usual_dirs()
+            .into_iter()
+            .filter(|dir| !lookup.searched.contains(dir))
+            .filter_map(|dir| executable_in(&dir, command))
+            .collect();
+    }
+    lookup
+}
+
+/// `name` is `command` with other casing or a version after it
+/// (node18, node-18, python3.11), or the same base with another version
+pub fn is_similar(name: &str, command: &str) -> bool {
+    let name = name.to_lowercase();
+    let command = command.to_lowercase();
+    if name == command {
+        return true;
+    }
+    let base = unversioned(&command);
+    name.strip_prefix(base)
+        .is_some_and(|rest| !rest.is_empty() && is_version(rest))
+}
+
+/// A command without its version suffix: "python3.11" -> "python"
+fn unversioned(command: &str) -> &str {
+    let base = command.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '-');
+    if base.is_empty() {
+        command
+    } else {
+        base
+    }
+}
+
+fn is_version(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// File name as typed, without a Windows program extension
+fn program_name(file: &Path) -> String {
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if cfg!(windows) {
+        let lower = name.to_lowercase();
+        for ext in [".exe", ".cmd", ".bat", ".com"] {
+            if lower.ends_with(ext) {
+                return name[..name.len() - ext.len()].to_string();
+            }
+        }
+    }
+    name
+}
+
+fn executable_in(dir: &Path, command: &str) -> Option<PathBuf> {
+    let names = if cfg!(windows) && Path::new(command).extension().is_none() {
+        ["exe", "cmd", "bat", "com"]
+            .iter()
+            .map(|ext| format!("{}.{}", command, ext))
+            .collect()
+    } else {
+        vec![command.to_string()]
+    };
+    names
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|file| is_executable(file))
+}
+
+#[cfg(unix)]
+fn is_executable(file: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(file).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(file: &Path) -> bool {
+    file.is_file()
+}
+
+/// Where installers put programs without always adding them to PATH
+fn usual_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    let mut dirs: Vec<PathBuf> = [
+        "/usr/local/bin",
+        "/opt/homebrew/bin",
+        "/snap/bin",
+        "C:\\Program Files\\nodejs",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+    if let Some(home) = home {
+        for dir in [".cargo/bin", ".local/bin", ".npm-global/bin", ".deno/bin"] {
+            dirs.push(home.join(dir));
+        }
+    }
+    dirs
+}
+
+/// How to install a command on this OS ("linux", "macos" or "windows")
+pub fn install_instructions(command: &str, os: &str) -> String {
+    let base = unversioned(command);
+    let (apt, brew, winget) = match base {
+        "node" | "npm" | "npx" => ("nodejs npm", "node", "OpenJS.NodeJS.LTS"),
+        "python" | "pip" => ("python3 python3-pip", "python", "Python.Python.3.12"),
+        "g++" | "gcc" => ("g++", "gcc", "MSYS2.MSYS2"),
+        "clang" | "clang++" => ("clang", "llvm", "LLVM.LLVM"),
+        "javac" | "java" => ("default-jdk", "openjdk", "EclipseAdoptium.Temurin.21.JDK"),
+        "mvn" => ("maven", "maven", "Apache.Maven"),
+        "gradle" => ("gradle", "gradle", "Gradle.Gradle"),
+        "git" => ("git", "git", "Git.Git"),
+        "dotnet" => ("dotnet-sdk-8.0", "dotnet-sdk", "Microsoft.DotNet.SDK.8"),
+        "cargo" | "rustc" | "rustup" => {
+            return "Install Rust with rustup: https://rustup.rs/".to_string()
+        }
+        _ => return tools::install_hint(command).to_string(),
+    };
+    match os {
+        "macos" => format!("Install it with Homebrew: brew install {}", brew),
+        "windows" => format!("Install it with winget: winget install {}", winget),
+        _ => format!(
+            "Install it with your package manager, e.g. sudo apt install {}",
+            apt
+        ),
+    }
+}
+
+/// How to put a directory on PATH on this OS
+pub fn path_instructions(dir: &Path, os: &str) -> String {
+    match os {
+        "windows" => format!(
+            "Add it to PATH: setx PATH \"%PATH%;{}\", then open a new terminal",
+            dir.display()
+        ),
+        _ => format!(
+            "Add it to PATH in ~/.bashrc or ~/.zshrc: export PATH=\"{}:$PATH\", then open a new terminal",
+            dir.display()
+        ),
+    }
+}
+
+/// Explain why `command` does or doesn't run; returns whether it was found
+pub fn run(command: &str) -> bool {
+    ui::print_section(&format!("Why: {}", command));
+
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let lookup = lookup(command, &path);
+    let os = std::env::consts::OS;
+
+    if let Some(found) = &lookup.found {
+        ui::print_success(&format!("'{}' runs {}", command, found.display()));
+        if let Some(version) = tools::version(&mut std::process::Command::new(found)) {
+            ui::print_info(&format!("Version: {}", version));
+        }
+    } else {
+        ui::print_error(&format!(
+            "'{}' is not on PATH ({} director{} searched)",
+            command,
+            lookup.searched.len(),
+            if lookup.searched.len() == 1 {
+                "y"
+            } else {
+                "ies"
+            }
+        ));
+    }
+
+    println!();
+    ui::print_info("Searched, in order:");
+    for dir in &lookup.searched {
+        let note = if lookup.missing.contains(dir) {
+            "  (doesn't exist)"
+        } else {
+            ""
+        };
+        println!("    {}{}", dir.display(), note);
+    }
+
+    if !lookup.similar.is_empty() {
+        println!();
+        ui::print_info("Similar programs on PATH:");
+        for file in &lookup.similar {
+            println!("    {}", file.display());
+        }
+    }
+    if lookup.found.is_some() {
+        return true;
+    }
+
+    println!();
+    if let Some(file) = lookup.off_path.first() {
+        ui::print_warning(&format!(
+            "'{}' is installed at {}, but that directory isn't on PATH",
+            command,
+            file.display()
+        ));
+        if let Some(dir) = file.parent() {
+            ui::print_hint(&path_instructions(dir, os));
+        }
+    } else if let Some(file) = lookup.similar.first() {
+        ui::print_hint(&format!(
+            "Run '{}' instead, or point ess at it in your config",
+            program_name(file)
+        ));
+    } else {
+        ui::print_hint(&install_instructions(command, os));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_similar() {
+        assert!(is_similar("node18", "node"));
+        assert!(is_similar("node-18", "node"));
+        assert!(is_similar("python3.11", "python"));
+        assert!(is_similar("python3", "python3.11"));
+        assert!(is_similar("Node", "node"));
+        assert!(!is_similar("nodemon", "node"));
+        assert!(!is_similar("pythonw", "python"));
+    }
+
+    #[test]
+    fn test_install_instructions_per_os() {
+        assert_eq!(
+            install_instructions("node", "macos"),
+            "Install it with Homebrew: brew install node"
+        );
+        assert!(install_instructions("python3", "windows").contains("winget install Python"));
+        assert!(install_instructions("mvn", "linux").ends_with("sudo apt install maven"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lookup_searches_path_in_order() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = std::env::temp_dir().join("ess_test_why");
+        let (first, second) = (temp_dir.join("a"), temp_dir.join("b"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        for file in [
+            second.join("node18"),
+            second.join("tool"),
+            first.join("notes"),
+        ] {
+            std::fs::write(&file, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let gone = temp_dir.join("gone");
+        let path = std::env::join_paths([&first, &gone, &second]).unwrap();
+
+        let found = lookup("tool", &path);
+        assert_eq!(found.found, Some(second.join("tool")));
+        assert_eq!(
+            found.searched,
+            vec![first.clone(), gone.clone(), second.clone()]
+        );
+        assert_eq!(found.missing, vec![gone.clone()]);
+
+        let twice = std::env::join_paths([&first, &first]).unwrap();
+        assert_eq!(lookup("tool", &twice).searched, vec![first.clone()]);
+
+        let missing = lookup("node", &path);
+        assert!(missing.found.is_none());
+        assert_eq!(missing.similar, vec![second.join("node18")]);
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}